@@ -0,0 +1,23 @@
+// STAmount parser: any bytes either parse or fail cleanly, and parsed
+// native amounts encode back to the same value
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use lks_hook_sdk::amount::{self, Amount};
+use lks_hook_sdk::sim;
+
+fuzz_target!(|data: &[u8]| {
+    sim::reset();
+
+    match amount::parse(data) {
+        Ok(Amount::Native(drops)) => {
+            assert!(matches!(amount::parse(&amount::encode_native(drops)), Ok(Amount::Native(d)) if d == drops));
+        }
+        Ok(Amount::Issued { value, .. }) => {
+            amount::issued_value(&value, amount::LKS_DECIMALS);
+        }
+        Err(_) => {}
+    }
+
+    assert_eq!(sim::with(|host| host.guard_violation), None);
+});
@@ -0,0 +1,19 @@
+// Memo parser: arbitrary Memos array contents must never panic, read out of
+// bounds or exceed the parser's guard budgets
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use lks_hook_sdk::{memo, sim};
+
+fuzz_target!(|data: &[u8]| {
+    // Each parse stands for one hook execution with its own guard budgets
+    sim::reset();
+    let _ = memo::for_each_memo(data, |memo| {
+        assert!(memo.memo_type.len() + memo.data.len() + memo.format.len() <= data.len());
+    });
+    assert_eq!(sim::with(|host| host.guard_violation), None);
+
+    sim::reset();
+    let _ = memo::parse_directives(data);
+    assert_eq!(sim::with(|host| host.guard_violation), None);
+});
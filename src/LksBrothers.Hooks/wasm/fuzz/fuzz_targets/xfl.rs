@@ -0,0 +1,28 @@
+// XFL decoder: values decoded from amounts re-encode to the same bytes, and
+// arithmetic on any two decoded values never panics
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use lks_hook_sdk::xfl::Xfl;
+
+fuzz_target!(|data: [u8; 16]| {
+    let mut first = [0u8; 8];
+    let mut second = [0u8; 8];
+    first.copy_from_slice(&data[..8]);
+    second.copy_from_slice(&data[8..]);
+
+    let a = match Xfl::from_amount_value(&first) {
+        Some(a) => a,
+        None => return,
+    };
+    if !a.is_zero() {
+        assert_eq!(a.to_amount_value(), first);
+    }
+
+    if let Some(b) = Xfl::from_raw(u64::from_be_bytes(second)) {
+        let _ = a.checked_add(b);
+        let _ = a.checked_sub(b);
+        let _ = a.checked_mul(b);
+    }
+    let _ = a.to_int(6);
+});
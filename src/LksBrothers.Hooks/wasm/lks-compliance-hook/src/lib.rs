@@ -0,0 +1,260 @@
+// Compliance Hook for LKS COIN
+// Optional gate for deployments where only KYC-verified accounts may
+// transact: installed ahead of the zero-fee hook, it rejects transactions
+// from accounts without a valid KYC marker, so they never reach
+// sponsorship. The foundation maintains the markers with KYC admin commands
+// on Invoke transactions.
+//
+// Deployments that should still let unverified accounts transact, only
+// without sponsorship, set KYCONLY on the zero-fee hook instead.
+//
+// Accounts on the emergency denylist are rejected ahead of the KYC check.
+// Installed on the security admin account, the hook also keeps that list,
+// changed with denylist commands from SECADMIN.
+
+// Natively built with the sim feature (and for tests) the hook runs against
+// the SDK's simulated host instead of the Hooks runtime
+#![cfg_attr(not(any(test, feature = "sim")), no_std)]
+#![cfg_attr(not(any(test, feature = "sim")), no_main)]
+
+use lks_hook_sdk::admin::{is_foundation, is_foundation_transaction, read_otxn_param};
+use lks_hook_sdk::api::{accept, otxn_type};
+use lks_hook_sdk::error::{finish_with_error, HookError};
+use lks_hook_sdk::text::{self, Text};
+use lks_hook_sdk::{bytes, denylist, fields, kyc, log};
+
+const TX_TYPE_INVOKE: i32 = 99;
+
+#[cfg_attr(not(feature = "xahau"), no_mangle)]
+pub extern "C" fn hook() -> i64 {
+    let tx_type = unsafe { otxn_type() };
+
+    log::debug(log::EV_TX_TYPE, b"Processing transaction type", tx_type as u64, 0);
+
+    // KYC markers are managed with foundation-signed Invoke transactions and
+    // the denylist with ones signed by the security admin; any other Invoke
+    // is gated like everything else
+    let mut command = [0u8; kyc::COMMAND_LEN];
+    let mut deny_command = [0u8; denylist::COMMAND_LEN];
+    let (command_len, deny_command_len) = if tx_type == TX_TYPE_INVOKE {
+        (read_otxn_param(kyc::PARAM_COMMAND, &mut command),
+         read_otxn_param(denylist::PARAM_COMMAND, &mut deny_command))
+    } else {
+        (0, 0)
+    };
+
+    let result = if command_len > 0 {
+        apply_admin_command(bytes::head(&command, command_len))
+    } else if deny_command_len > 0 {
+        apply_denylist_command(bytes::head(&deny_command, deny_command_len))
+    } else if is_foundation_transaction() {
+        pass_through(b"Foundation transaction bypasses LKS compliance")
+    } else {
+        check_account()
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(err) => finish_with_error(err),
+    }
+}
+
+fn check_account() -> Result<(), HookError> {
+    let source = fields::read_account()?;
+    if denylist::is_banned(&source) {
+        return Err(HookError::AccountBanned);
+    }
+    if !kyc::is_verified(&source) {
+        return Err(HookError::KycRequired);
+    }
+
+    pass_through(b"KYC-verified account")
+}
+
+fn apply_admin_command(command: &[u8]) -> Result<(), HookError> {
+    let source = fields::read_account()?;
+    if !is_foundation(&source) {
+        return Err(HookError::Unauthorized);
+    }
+
+    let op = kyc::apply_command(command)?;
+
+    if log::enabled(log::Level::Info) {
+        let mut msg = Text::<{ text::MESSAGE_LEN }>::new();
+        msg.push(b"LKS KYC marker updated, account ").hex(bytes::head(bytes::tail(command, 1), 20));
+        log::info(log::EV_KYC, msg.as_bytes(), op as u64, 0);
+    }
+
+    pass_through(b"LKS admin command applied")
+}
+
+fn apply_denylist_command(command: &[u8]) -> Result<(), HookError> {
+    let source = fields::read_account()?;
+    if !denylist::is_security_admin(&source) {
+        return Err(HookError::Unauthorized);
+    }
+
+    let op = denylist::apply_command(command)?;
+
+    let account = bytes::tail(command, 1);
+    if log::enabled(log::Level::Warn) {
+        let mut msg = Text::<{ text::MESSAGE_LEN }>::new();
+        msg.push(b"LKS emergency denylist updated, account ").hex(bytes::head(account, 20));
+        log::warn(log::EV_DENYLIST_SET, msg.as_bytes(), op as u64, bytes::u64_at(account, 0).unwrap_or(0));
+    }
+
+    pass_through(b"LKS denylist command applied")
+}
+
+fn pass_through(msg: &[u8]) -> Result<(), HookError> {
+    unsafe {
+        accept(msg.as_ptr(), msg.len() as i32);
+    }
+
+    Ok(())
+}
+
+// Xahau calls hook() with an argument reserved for future use
+#[cfg(feature = "xahau")]
+#[export_name = "hook"]
+pub extern "C" fn xahau_hook(_reserved: u32) -> i64 {
+    hook()
+}
+
+// Required for no_main
+#[cfg(not(any(test, feature = "sim")))]
+#[no_mangle]
+pub extern "C" fn _start() {
+    // This function is required but not used in hooks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::admin::FOUNDATION_ACCOUNT;
+    use lks_hook_sdk::sim::{self, Outcome};
+
+    const USER: [u8; 20] = [0xAA; 20];
+    const SECURITY_ADMIN: [u8; 20] = [0x5E; 20];
+
+    fn transaction(tx_type: i32, account: &[u8; 20]) {
+        sim::reset();
+        let account = account.to_vec();
+        sim::with(|host| {
+            host.tx_type = tx_type;
+            host.ledger_seq = 1000;
+            host.fields.insert(fields::SF_ACCOUNT, account);
+        });
+    }
+
+    fn kyc_command(op: u8, expires: u32) -> std::vec::Vec<u8> {
+        let mut command = std::vec![op];
+        command.extend_from_slice(&USER);
+        command.extend_from_slice(&expires.to_be_bytes());
+        command
+    }
+
+    fn invoke_as_foundation(command: std::vec::Vec<u8>) {
+        sim::with(|host| {
+            host.tx_type = TX_TYPE_INVOKE;
+            host.fields.insert(fields::SF_ACCOUNT, FOUNDATION_ACCOUNT.as_bytes().to_vec());
+            host.otxn_params.insert(kyc::PARAM_COMMAND.to_vec(), command);
+        });
+        assert_eq!(sim::run(hook), 0);
+
+        sim::with(|host| {
+            host.tx_type = 0;
+            host.fields.insert(fields::SF_ACCOUNT, USER.to_vec());
+            host.otxn_params.clear();
+        });
+    }
+
+    #[test]
+    fn rejects_unverified_accounts() {
+        transaction(0, &USER);
+
+        assert_eq!(sim::run(hook), HookError::KycRequired.return_value());
+        assert!(matches!(sim::with(|host| host.outcome.clone()), Some(Outcome::Rejected(_))));
+    }
+
+    #[test]
+    fn accepts_accounts_verified_by_the_foundation() {
+        transaction(0, &USER);
+        invoke_as_foundation(kyc_command(kyc::OP_VERIFY, 0));
+        assert_eq!(sim::run(hook), 0);
+
+        invoke_as_foundation(kyc_command(kyc::OP_REVOKE, 0));
+        assert_eq!(sim::run(hook), HookError::KycRequired.return_value());
+    }
+
+    #[test]
+    fn markers_expire_at_their_ledger() {
+        transaction(0, &USER);
+        invoke_as_foundation(kyc_command(kyc::OP_VERIFY, 1001));
+        assert_eq!(sim::run(hook), 0);
+
+        sim::with(|host| host.ledger_seq = 1001);
+        assert_eq!(sim::run(hook), HookError::KycRequired.return_value());
+    }
+
+    #[test]
+    fn only_the_foundation_manages_markers() {
+        transaction(TX_TYPE_INVOKE, &USER);
+        let command = kyc_command(kyc::OP_VERIFY, 0);
+        sim::with(|host| host.otxn_params.insert(kyc::PARAM_COMMAND.to_vec(), command));
+
+        assert_eq!(sim::run(hook), HookError::Unauthorized.return_value());
+        assert!(sim::with(|host| host.state.is_empty()));
+    }
+
+    fn denylist_command(op: u8) -> std::vec::Vec<u8> {
+        let mut command = std::vec![op];
+        command.extend_from_slice(&USER);
+        command
+    }
+
+    fn invoke_as_security(command: std::vec::Vec<u8>) -> i64 {
+        sim::with(|host| {
+            host.tx_type = TX_TYPE_INVOKE;
+            host.fields.insert(fields::SF_ACCOUNT, SECURITY_ADMIN.to_vec());
+            host.otxn_params.insert(denylist::PARAM_COMMAND.to_vec(), command);
+        });
+        let result = sim::run(hook);
+
+        sim::with(|host| {
+            host.tx_type = 0;
+            host.fields.insert(fields::SF_ACCOUNT, USER.to_vec());
+            host.otxn_params.clear();
+        });
+        result
+    }
+
+    #[test]
+    fn rejects_denylisted_accounts_ahead_of_kyc() {
+        transaction(0, &USER);
+        sim::with(|host| host.hook_params.insert(denylist::PARAM_SECURITY_ADMIN.to_vec(), SECURITY_ADMIN.to_vec()));
+        invoke_as_foundation(kyc_command(kyc::OP_VERIFY, 0));
+
+        assert_eq!(invoke_as_security(denylist_command(denylist::OP_BAN)), 0);
+        assert_eq!(sim::run(hook), HookError::AccountBanned.return_value());
+        assert!(matches!(sim::with(|host| host.outcome.clone()), Some(Outcome::Rejected(_))));
+
+        assert_eq!(invoke_as_security(denylist_command(denylist::OP_LIFT)), 0);
+        assert_eq!(sim::run(hook), 0);
+    }
+
+    #[test]
+    fn only_the_security_admin_manages_the_denylist() {
+        transaction(0, &USER);
+        sim::with(|host| host.hook_params.insert(denylist::PARAM_SECURITY_ADMIN.to_vec(), SECURITY_ADMIN.to_vec()));
+
+        let command = denylist_command(denylist::OP_BAN);
+        sim::with(|host| {
+            host.tx_type = TX_TYPE_INVOKE;
+            host.fields.insert(fields::SF_ACCOUNT, FOUNDATION_ACCOUNT.as_bytes().to_vec());
+            host.otxn_params.insert(denylist::PARAM_COMMAND.to_vec(), command);
+        });
+        assert_eq!(sim::run(hook), HookError::Unauthorized.return_value());
+        assert!(sim::with(|host| host.state.is_empty()));
+    }
+}
@@ -0,0 +1,512 @@
+// Instruction budget benchmarks for the LKS hooks
+// Runs the compiled wasm of a hook in wasmi against the SDK's simulated host
+// and counts the instructions every code path executes, so we know how close
+// each path is to the hook budget. wasmi fuel is deterministic, so the same
+// wasm and transaction always measure the same.
+//
+// Report every path with
+//   cargo run -p lks-hook-bench --release
+// cargo test fails when a path exceeds its budget, and when a hook's wasm
+// outgrows its size budget, can still panic or has a loop without a guard.
+
+pub mod scenarios;
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use lks_hook_sdk::{api, sim};
+use wasmi::{Caller, CompilationMode, Config, Engine, Error, Extern, Linker, Module, Store};
+use wasmparser::{Operator, Parser, Payload, TypeRef};
+
+// Fuel given to every run; a hook still running when it is gone is stuck
+const FUEL_LIMIT: u64 = 100_000_000;
+
+const WASM_TARGET: &str = "wasm32-unknown-unknown";
+
+#[derive(Clone, Copy, Debug)]
+pub enum Entry {
+    Hook,
+    // The callback, with its `what` argument
+    Cbak(u32),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Measurement {
+    // Value the entry point returned
+    pub result: i64,
+    // wasmi fuel consumed, about one unit per executed instruction
+    pub instructions: u64,
+}
+
+// Compiled hook, ready to run against the current simulated host
+pub struct Runner {
+    engine: Engine,
+    module: Module,
+}
+
+impl Runner {
+    pub fn new(wasm: &[u8]) -> Result<Runner, Error> {
+        // Lazily compiled functions would charge their compilation to the
+        // first run calling them
+        let mut config = Config::default();
+        config.consume_fuel(true).compilation_mode(CompilationMode::Eager);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, wasm)?;
+
+        Ok(Runner { engine, module })
+    }
+
+    pub fn load(path: &Path) -> Result<Runner, Error> {
+        let wasm = std::fs::read(path)
+            .map_err(|err| Error::new(format!("reading {}: {err}", path.display())))?;
+        Runner::new(&wasm)
+    }
+
+    // Run an entry point once in a fresh instance. Hook state lives in the
+    // simulated host, so it carries over between runs like on ledger.
+    pub fn run(&self, entry: Entry) -> Result<Measurement, Error> {
+        let mut store = Store::new(&self.engine, ());
+        store.set_fuel(FUEL_LIMIT)?;
+        let instance = link(&self.engine)?.instantiate_and_start(&mut store, &self.module)?;
+
+        sim::begin();
+        let result = match entry {
+            Entry::Hook => instance.get_typed_func::<(), i64>(&store, "hook")?.call(&mut store, ())?,
+            Entry::Cbak(what) => instance.get_typed_func::<u32, i64>(&store, "cbak")?.call(&mut store, what)?,
+        };
+
+        Ok(Measurement { result, instructions: FUEL_LIMIT - store.get_fuel()? })
+    }
+}
+
+// Build a hook crate of the workspace for the ledger, with the given cargo
+// features, and return its wasm. The build uses its own target directory,
+// one per feature set, so it doesn't wait on the cargo invocation running
+// the benchmarks or overwrite a build with other features.
+pub fn build_hook(package: &str, artifact: &str, features: &[&str]) -> Result<PathBuf, Error> {
+    let workspace = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    let mut target_name = String::from("bench");
+    for feature in features {
+        target_name = format!("{target_name}-{feature}");
+    }
+    let target_dir = workspace.join("target").join(target_name);
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+
+    let status = Command::new(cargo)
+        .current_dir(&workspace)
+        .args(["build", "--release", "--target", WASM_TARGET, "-p", package])
+        .args(["--features", &features.join(",")])
+        .env("CARGO_TARGET_DIR", &target_dir)
+        .status()
+        .map_err(|err| Error::new(format!("running cargo: {err}")))?;
+    if !status.success() {
+        return Err(Error::new(format!("building {package} for {WASM_TARGET} failed")));
+    }
+
+    Ok(target_dir.join(WASM_TARGET).join("release").join(format!("{artifact}.wasm")))
+}
+
+// Indices of the functions with a loop whose body never calls `_g`. The
+// Hooks runtime rejects such a hook at SetHook, and the simulated host can't
+// notice them: they come from code the compiler emits for us, like memcmp
+// behind a slice `==` or the u128 division builtins, not from guarded_loop!.
+pub fn unguarded_loops(wasm: &[u8]) -> Result<Vec<u32>, Error> {
+    let parse = |err: wasmparser::BinaryReaderError| Error::new(format!("parsing wasm: {err}"));
+    let mut imported = 0;
+    let mut guard = None;
+    // Whether each function defined in the module guards all its loops
+    let mut functions = Vec::new();
+
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload.map_err(parse)? {
+            Payload::ImportSection(imports) => {
+                for import in imports {
+                    let import = import.map_err(parse)?;
+                    if let TypeRef::Func(_) = import.ty {
+                        if import.name == "_g" {
+                            guard = Some(imported);
+                        }
+                        imported += 1;
+                    }
+                }
+            }
+            Payload::CodeSectionEntry(body) => {
+                // Every open block, with whether it is a loop still missing its guard
+                let mut blocks = Vec::new();
+                let mut guarded = true;
+                let mut operators = body.get_operators_reader().map_err(parse)?;
+                while !operators.eof() {
+                    match operators.read().map_err(parse)? {
+                        Operator::Block { .. } | Operator::If { .. } | Operator::TryTable { .. } => blocks.push(false),
+                        Operator::Loop { .. } => blocks.push(true),
+                        Operator::Call { function_index } if Some(function_index) == guard => {
+                            blocks.iter_mut().for_each(|missing| *missing = false)
+                        }
+                        Operator::End => guarded &= !blocks.pop().unwrap_or(false),
+                        _ => {}
+                    }
+                }
+                functions.push(guarded);
+            }
+            _ => {}
+        }
+    }
+
+    Ok((imported..).zip(functions).filter(|&(_, guarded)| !guarded).map(|(index, _)| index).collect())
+}
+
+// Native pointer to `len` bytes of linear memory at `ptr`. Out of bounds
+// regions trap, as they do in the Hooks runtime.
+fn region(memory: &mut [u8], ptr: u32, len: i32) -> Result<*mut u8, Error> {
+    let start = ptr as usize;
+    match start.checked_add(len.max(0) as usize) {
+        Some(end) if end <= memory.len() => Ok(memory[start..].as_mut_ptr()),
+        _ => Err(Error::new("memory access out of bounds")),
+    }
+}
+
+fn memory<'a>(caller: &'a mut Caller<'_, ()>) -> Result<&'a mut [u8], Error> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| Error::new("hook exports no memory"))?;
+    Ok(memory.data_mut(caller))
+}
+
+// Provide the Hook API by forwarding every call to the simulated host with
+// pointers into the hook's linear memory
+fn link(engine: &Engine) -> Result<Linker<()>, Error> {
+    let mut linker = Linker::new(engine);
+
+    linker.func_wrap("env", "otxn_type", || unsafe { api::otxn_type() })?;
+    linker.func_wrap("env", "otxn_slot", |mut caller: Caller<'_, ()>, slot: i32, data: u32, len: i32| {
+        let memory = memory(&mut caller)?;
+        Ok(unsafe { api::otxn_slot(slot, region(memory, data, len)?, len) })
+    })?;
+    linker.func_wrap("env", "meta_slot", |mut caller: Caller<'_, ()>, slot: i32, data: u32, len: i32| {
+        let memory = memory(&mut caller)?;
+        Ok(unsafe { api::meta_slot(slot, region(memory, data, len)?, len) })
+    })?;
+    linker.func_wrap("env", "slot_set", |mut caller: Caller<'_, ()>, slot: i32, data: u32, len: i32| {
+        let memory = memory(&mut caller)?;
+        Ok(unsafe { api::slot_set(slot, region(memory, data, len)?, len) })
+    })?;
+    linker.func_wrap("env", "accept", |mut caller: Caller<'_, ()>, msg: u32, len: i32| {
+        let memory = memory(&mut caller)?;
+        Ok(unsafe { api::accept(region(memory, msg, len)?, len) })
+    })?;
+    linker.func_wrap("env", "reject", |mut caller: Caller<'_, ()>, msg: u32, len: i32| {
+        let memory = memory(&mut caller)?;
+        Ok(unsafe { api::reject(region(memory, msg, len)?, len) })
+    })?;
+    linker.func_wrap(
+        "env",
+        "trace",
+        |mut caller: Caller<'_, ()>, msg: u32, msg_len: i32, data: u32, data_len: i32, as_hex: i32| {
+            let memory = memory(&mut caller)?;
+            let msg = region(memory, msg, msg_len)?;
+            let data = region(memory, data, data_len)?;
+            Ok(unsafe { api::trace(msg, msg_len, data, data_len, as_hex) })
+        },
+    )?;
+    linker.func_wrap("env", "ledger_seq", || unsafe { api::ledger_seq() })?;
+    linker.func_wrap("env", "otxn_id", |mut caller: Caller<'_, ()>, data: u32, len: i32, flags: u32| {
+        let memory = memory(&mut caller)?;
+        Ok(unsafe { api::otxn_id(region(memory, data, len)?, len, flags) })
+    })?;
+    linker.func_wrap(
+        "env",
+        "util_keylet",
+        |mut caller: Caller<'_, ()>, data: u32, len: i32, keylet_type: u32, account: u32, account_len: i32,
+         sequence: u32| {
+            let memory = memory(&mut caller)?;
+            let data = region(memory, data, len)?;
+            let account = region(memory, account, account_len)?;
+            Ok(unsafe { api::util_keylet(data, len, keylet_type, account, account_len, sequence) })
+        },
+    )?;
+    linker.func_wrap(
+        "env",
+        "util_keylet_line",
+        |mut caller: Caller<'_, ()>, data: u32, len: i32, account: u32, issuer: u32, currency: u32| {
+            let memory = memory(&mut caller)?;
+            let data = region(memory, data, len)?;
+            let account = region(memory, account, 20)?;
+            let issuer = region(memory, issuer, 20)?;
+            let currency = region(memory, currency, 20)?;
+            Ok(unsafe { api::util_keylet_line(data, len, account, issuer, currency) })
+        },
+    )?;
+    linker.func_wrap(
+        "env",
+        "keylet_field",
+        |mut caller: Caller<'_, ()>, keylet: u32, keylet_len: i32, field: i32, data: u32, len: i32| {
+            let memory = memory(&mut caller)?;
+            let keylet = region(memory, keylet, keylet_len)?;
+            let data = region(memory, data, len)?;
+            Ok(unsafe { api::keylet_field(keylet, keylet_len, field, data, len) })
+        },
+    )?;
+    linker.func_wrap(
+        "env",
+        "util_verify",
+        |mut caller: Caller<'_, ()>, data: u32, data_len: i32, signature: u32, signature_len: i32, key: u32,
+         key_len: i32| {
+            let memory = memory(&mut caller)?;
+            let data = region(memory, data, data_len)?;
+            let signature = region(memory, signature, signature_len)?;
+            let key = region(memory, key, key_len)?;
+            Ok(unsafe { api::util_verify(data, data_len, signature, signature_len, key, key_len) })
+        },
+    )?;
+    linker.func_wrap("env", "util_sha512h", |mut caller: Caller<'_, ()>, hash: u32, hash_len: i32, data: u32,
+                                              data_len: i32| {
+        let memory = memory(&mut caller)?;
+        let hash = region(memory, hash, hash_len)?;
+        let data = region(memory, data, data_len)?;
+        Ok(unsafe { api::util_sha512h(hash, hash_len, data, data_len) })
+    })?;
+    linker.func_wrap("env", "etxn_reserve", |count: u32| unsafe { api::etxn_reserve(count) })?;
+    linker.func_wrap("env", "etxn_details", |mut caller: Caller<'_, ()>, data: u32, len: i32| {
+        let memory = memory(&mut caller)?;
+        Ok(unsafe { api::etxn_details(region(memory, data, len)?, len) })
+    })?;
+    linker.func_wrap("env", "etxn_fee_base", |mut caller: Caller<'_, ()>, tx: u32, tx_len: i32| {
+        let memory = memory(&mut caller)?;
+        Ok(unsafe { api::etxn_fee_base(region(memory, tx, tx_len)?, tx_len) })
+    })?;
+    linker.func_wrap(
+        "env",
+        "emit",
+        |mut caller: Caller<'_, ()>, hash: u32, hash_len: i32, tx: u32, tx_len: i32| {
+            let memory = memory(&mut caller)?;
+            let hash = region(memory, hash, hash_len)?;
+            let tx = region(memory, tx, tx_len)?;
+            Ok(unsafe { api::emit(hash, hash_len, tx, tx_len) })
+        },
+    )?;
+    linker.func_wrap("env", "hook_account", |mut caller: Caller<'_, ()>, account: u32| {
+        let memory = memory(&mut caller)?;
+        Ok(unsafe { api::hook_account(region(memory, account, 20)?) })
+    })?;
+    linker.func_wrap(
+        "env",
+        "hook_param",
+        |mut caller: Caller<'_, ()>, name: u32, name_len: i32, data: u32, len: i32| {
+            let memory = memory(&mut caller)?;
+            let name = region(memory, name, name_len)?;
+            let data = region(memory, data, len)?;
+            Ok(unsafe { api::hook_param(name, name_len, data, len) })
+        },
+    )?;
+    linker.func_wrap(
+        "env",
+        "otxn_param",
+        |mut caller: Caller<'_, ()>, name: u32, name_len: i32, data: u32, len: i32| {
+            let memory = memory(&mut caller)?;
+            let name = region(memory, name, name_len)?;
+            let data = region(memory, data, len)?;
+            Ok(unsafe { api::otxn_param(name, name_len, data, len) })
+        },
+    )?;
+    linker.func_wrap(
+        "env",
+        "state",
+        |mut caller: Caller<'_, ()>, key: u32, key_len: i32, data: u32, len: i32| {
+            let memory = memory(&mut caller)?;
+            let key = region(memory, key, key_len)?;
+            let data = region(memory, data, len)?;
+            Ok(unsafe { api::state(key, key_len, data, len) })
+        },
+    )?;
+    linker.func_wrap(
+        "env",
+        "state_set",
+        |mut caller: Caller<'_, ()>, key: u32, key_len: i32, data: u32, len: i32| {
+            let memory = memory(&mut caller)?;
+            let key = region(memory, key, key_len)?;
+            let data = region(memory, data, len)?;
+            Ok(unsafe { api::state_set(key, key_len, data, len) })
+        },
+    )?;
+    linker.func_wrap(
+        "env",
+        "state_foreign",
+        |mut caller: Caller<'_, ()>, data: u32, len: i32, key: u32, key_len: i32, namespace: u32,
+         namespace_len: i32, account: u32, account_len: i32| {
+            let memory = memory(&mut caller)?;
+            let data = region(memory, data, len)?;
+            let key = region(memory, key, key_len)?;
+            let namespace = region(memory, namespace, namespace_len)?;
+            let account = region(memory, account, account_len)?;
+            Ok(unsafe {
+                api::state_foreign(data, len, key, key_len, namespace, namespace_len, account, account_len)
+            })
+        },
+    )?;
+
+    // Exceeding a guard aborts the hook on ledger
+    linker.func_wrap("env", "_g", |id: u32, max_iterations: u32| {
+        let result = unsafe { api::_g(id, max_iterations) };
+        match sim::with(|host| host.guard_violation) {
+            Some((id, max_iterations)) => {
+                Err(Error::new(format!("guard {id:#x} exceeded {max_iterations} iterations")))
+            }
+            None => Ok(result),
+        }
+    })?;
+
+    Ok(linker)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scenarios::HOOKS;
+    use wasmi::{ExternType, ValType};
+
+    #[test]
+    fn every_path_stays_within_its_budget() {
+        for hook in HOOKS {
+            let runner = Runner::load(&build_hook(hook.package, hook.artifact, &[]).unwrap()).unwrap();
+
+            for scenario in hook.scenarios {
+                (scenario.setup)();
+                let measurement = runner.run(scenario.entry).unwrap_or_else(|err| panic!("{}: {err}", scenario.name));
+                assert_eq!(measurement.result, scenario.expected_result(), "{}", scenario.name);
+                assert!(
+                    measurement.instructions <= scenario.budget,
+                    "{}: {} instructions, budget {}",
+                    scenario.name,
+                    measurement.instructions,
+                    scenario.budget
+                );
+
+                // The same transaction always measures the same
+                (scenario.setup)();
+                assert_eq!(runner.run(scenario.entry).unwrap().instructions, measurement.instructions);
+            }
+        }
+    }
+
+    // A reachable panic shows up as an import of the function the SDK's panic
+    // handler calls, which the Hooks runtime doesn't provide
+    #[test]
+    fn hooks_are_panic_free_and_within_size_budget() {
+        for hook in HOOKS {
+            let wasm = std::fs::read(build_hook(hook.package, hook.artifact, &[]).unwrap()).unwrap();
+            assert!(wasm.len() <= hook.size_budget, "{}: {} bytes, budget {}", hook.package, wasm.len(), hook.size_budget);
+
+            let module = Module::new(&Engine::default(), &wasm[..]).unwrap();
+            for import in module.imports() {
+                assert_eq!(import.module(), "env", "{}", hook.package);
+                assert_ne!(import.name(), "panic_is_reachable", "{} can panic", hook.package);
+            }
+        }
+    }
+
+    // Either build of a hook is rejected at SetHook if a single loop in it
+    // goes unguarded
+    #[test]
+    fn hooks_guard_every_loop() {
+        for hook in HOOKS {
+            for features in [&[][..], &["xahau"]] {
+                let wasm = std::fs::read(build_hook(hook.package, hook.artifact, features).unwrap()).unwrap();
+                let unguarded = unguarded_loops(&wasm).unwrap();
+                assert!(unguarded.is_empty(), "{} {features:?}: unguarded loops in functions {unguarded:?}", hook.package);
+            }
+        }
+    }
+
+    #[test]
+    fn finds_unguarded_loops() {
+        let wasm = wat::parse_str(
+            r#"(module
+                (import "env" "_g" (func $g (param i32 i32) (result i32)))
+                (func $guarded (loop (drop (call $g (i32.const 1) (i32.const 2))) (br 0)))
+                (func $unguarded (block (loop (br 0))))
+                (func $nested (loop (drop (call $g (i32.const 1) (i32.const 2))) (loop (br 0)))))"#,
+        )
+        .unwrap();
+        assert_eq!(unguarded_loops(&wasm).unwrap(), [2, 3]);
+    }
+
+    // Hook API of the Xahau runtime as (name, parameters, results)
+    const XAHAU_API: &[(&str, &[ValType], &[ValType])] = {
+        use ValType::{I32, I64};
+        &[
+            ("accept", &[I32, I32, I64], &[I64]),
+            ("rollback", &[I32, I32, I64], &[I64]),
+            ("trace", &[I32, I32, I32, I32, I32], &[I64]),
+            ("ledger_seq", &[], &[I64]),
+            ("otxn_type", &[], &[I64]),
+            ("otxn_field", &[I32, I32, I32], &[I64]),
+            ("otxn_id", &[I32, I32, I32], &[I64]),
+            ("otxn_param", &[I32, I32, I32, I32], &[I64]),
+            ("hook_param", &[I32, I32, I32, I32], &[I64]),
+            ("hook_account", &[I32, I32], &[I64]),
+            ("otxn_slot", &[I32], &[I64]),
+            ("meta_slot", &[I32], &[I64]),
+            ("slot", &[I32, I32, I32], &[I64]),
+            ("slot_set", &[I32, I32, I32], &[I64]),
+            ("slot_subfield", &[I32, I32, I32], &[I64]),
+            ("slot_clear", &[I32], &[I64]),
+            ("util_keylet", &[I32, I32, I32, I32, I32, I32, I32, I32, I32], &[I64]),
+            ("util_verify", &[I32, I32, I32, I32, I32, I32], &[I64]),
+            ("util_sha512h", &[I32, I32, I32, I32], &[I64]),
+            ("etxn_reserve", &[I32], &[I64]),
+            ("etxn_details", &[I32, I32], &[I64]),
+            ("etxn_fee_base", &[I32, I32], &[I64]),
+            ("emit", &[I32, I32, I32, I32], &[I64]),
+            ("state", &[I32, I32, I32, I32], &[I64]),
+            ("state_set", &[I32, I32, I32, I32], &[I64]),
+            ("state_foreign", &[I32, I32, I32, I32, I32, I32, I32, I32], &[I64]),
+            ("_g", &[I32, I32], &[I32]),
+        ]
+    };
+
+    fn func_type(ty: &ExternType) -> (&[ValType], &[ValType]) {
+        match ty {
+            ExternType::Func(func) => (func.params(), func.results()),
+            _ => panic!("not a function: {ty:?}"),
+        }
+    }
+
+    // Built with the xahau feature a hook only imports Xahau functions, with
+    // their exact signatures, and exports the entry points Xahau calls
+    #[test]
+    fn hooks_build_against_the_xahau_api() {
+        for hook in HOOKS {
+            let wasm = std::fs::read(build_hook(hook.package, hook.artifact, &["xahau"]).unwrap()).unwrap();
+            let module = Module::new(&Engine::default(), &wasm[..]).unwrap();
+
+            for import in module.imports() {
+                let name = import.name();
+                let (_, params, results) = XAHAU_API
+                    .iter()
+                    .find(|(api, ..)| *api == name)
+                    .unwrap_or_else(|| panic!("{} imports {name}, which Xahau doesn't provide", hook.package));
+                assert_eq!(import.module(), "env", "{}", hook.package);
+                assert_eq!(func_type(import.ty()), (*params, *results), "{}: {name}", hook.package);
+            }
+
+            let mut entry_points = 0;
+            for export in module.exports() {
+                if matches!(export.name(), "hook" | "cbak") {
+                    assert_eq!(func_type(export.ty()), (&[ValType::I32][..], &[ValType::I64][..]), "{}", hook.package);
+                    entry_points += 1;
+                }
+            }
+            assert!(entry_points > 0, "{} exports no hook", hook.package);
+        }
+    }
+
+    #[test]
+    fn out_of_bounds_regions_trap() {
+        let mut memory = [0u8; 16];
+        assert!(region(&mut memory, 8, 8).is_ok());
+        assert!(region(&mut memory, 16, 0).is_ok());
+        assert!(region(&mut memory, 8, 9).is_err());
+        assert!(region(&mut memory, u32::MAX, 1).is_err());
+    }
+}
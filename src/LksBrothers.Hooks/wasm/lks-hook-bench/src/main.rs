@@ -0,0 +1,46 @@
+// Report the instructions every scenario executes against its budget
+
+use std::process::ExitCode;
+
+use lks_hook_bench::scenarios::HOOKS;
+use lks_hook_bench::{build_hook, Runner};
+
+fn main() -> ExitCode {
+    let mut over_budget = false;
+
+    for hook in HOOKS {
+        let runner = match build_hook(hook.package, hook.artifact, &[]).and_then(|path| Runner::load(&path)) {
+            Ok(runner) => runner,
+            Err(err) => {
+                eprintln!("{}: {err}", hook.package);
+                return ExitCode::FAILURE;
+            }
+        };
+
+        println!("{}", hook.package);
+        for scenario in hook.scenarios {
+            (scenario.setup)();
+            match runner.run(scenario.entry) {
+                Ok(measurement) => {
+                    let percent = measurement.instructions * 100 / scenario.budget;
+                    let flag = if measurement.instructions > scenario.budget { "  OVER BUDGET" } else { "" };
+                    over_budget |= measurement.instructions > scenario.budget;
+                    println!(
+                        "  {:<48} {:>9} / {:>9} ({percent:>3}%) returned {}{flag}",
+                        scenario.name, measurement.instructions, scenario.budget, measurement.result
+                    );
+                }
+                Err(err) => {
+                    over_budget = true;
+                    println!("  {:<48} trapped: {err}", scenario.name);
+                }
+            }
+        }
+    }
+
+    if over_budget {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
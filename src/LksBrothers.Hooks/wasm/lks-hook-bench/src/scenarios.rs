@@ -0,0 +1,423 @@
+// Representative transactions for every code path of the LKS hooks
+// Each scenario prepares the simulated host, names the error the hook must
+// finish with (if any) so a benchmark can't silently measure a different
+// path, and sets the path's instruction budget. Budgets leave headroom over
+// the current measurements; raise one only with the change that needs it.
+
+use lks_hook_sdk::account::AccountId;
+use lks_hook_sdk::admin::FOUNDATION_ACCOUNT;
+use lks_hook_sdk::amount;
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::fields;
+use lks_hook_sdk::kyc;
+use lks_hook_sdk::sim;
+use lks_hook_sdk::state;
+use lks_hook_sdk::xfl::Xfl;
+
+use crate::Entry;
+
+pub struct Scenario {
+    pub name: &'static str,
+    pub entry: Entry,
+    // Prepare the transaction, parameters and hook state
+    pub setup: fn(),
+    pub expected: Option<HookError>,
+    // Most instructions the path may execute
+    pub budget: u64,
+}
+
+impl Scenario {
+    // Value the hook returns on the measured path
+    pub fn expected_result(&self) -> i64 {
+        self.expected.map_or(0, HookError::return_value)
+    }
+}
+
+// A hook crate of the workspace and the paths measured on it
+pub struct Hook {
+    pub package: &'static str,
+    pub artifact: &'static str,
+    // Largest the release wasm may grow, in bytes
+    pub size_budget: usize,
+    pub scenarios: &'static [Scenario],
+}
+
+pub const HOOKS: &[Hook] = &[
+    Hook { package: "lks-zero-fee-hook", artifact: "zero_fee_hook", size_budget: 62_000, scenarios: ZERO_FEE },
+    Hook { package: "lks-compliance-hook", artifact: "compliance_hook", size_budget: 12_500, scenarios: COMPLIANCE },
+];
+
+// Transaction types
+const PAYMENT: i32 = 0;
+const ESCROW_CREATE: i32 = 1;
+const SET_REGULAR_KEY: i32 = 5;
+const OFFER_CREATE: i32 = 7;
+const TRUST_SET: i32 = 20;
+const INVOKE: i32 = 99;
+
+// Issuer compiled into the zero-fee hook
+const LKS_ISSUER: [u8; 20] = [
+    0x4C, 0x4B, 0x53, 0x00, 0x9A, 0xBC, 0xDE, 0xF0, 0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0, 0x12,
+    0x34, 0x56, 0x78,
+];
+
+const USER: [u8; 20] = [0xAA; 20];
+const MERCHANT: [u8; 20] = [0xBB; 20];
+
+const ZERO_FEE: &[Scenario] = &[
+    Scenario {
+        name: "native payment declined",
+        entry: Entry::Hook,
+        setup: native_payment,
+        expected: Some(HookError::WrongCurrency),
+        budget: 3_000,
+    },
+    Scenario {
+        name: "LKS payment sponsored",
+        entry: Entry::Hook,
+        setup: lks_payment,
+        expected: None,
+        budget: 17_000,
+    },
+    Scenario {
+        name: "LKS payment with memos and full tier tables",
+        entry: Entry::Hook,
+        setup: lks_payment_worst_case,
+        expected: None,
+        budget: 30_000,
+    },
+    Scenario {
+        name: "LKS dust payment declined",
+        entry: Entry::Hook,
+        setup: lks_dust_payment,
+        expected: Some(HookError::DustAmount),
+        budget: 4_000,
+    },
+    Scenario {
+        name: "LKS payment passed through while paused",
+        entry: Entry::Hook,
+        setup: paused_payment,
+        expected: Some(HookError::SponsorshipPaused),
+        budget: 2_000,
+    },
+    Scenario {
+        name: "LKS offer passed through in payments-only mode",
+        entry: Entry::Hook,
+        setup: payments_only_offer,
+        expected: Some(HookError::TxTypeNotSponsored),
+        budget: 2_000,
+    },
+    Scenario {
+        name: "LKS payment with partner voucher",
+        entry: Entry::Hook,
+        setup: vouchered_payment,
+        expected: None,
+        budget: 15_000,
+    },
+    Scenario {
+        name: "multi-signed LKS payment sponsored",
+        entry: Entry::Hook,
+        setup: multi_signed_payment,
+        expected: None,
+        budget: 21_000,
+    },
+    Scenario {
+        name: "LKS offer sponsored",
+        entry: Entry::Hook,
+        setup: lks_offer,
+        expected: None,
+        budget: 15_000,
+    },
+    Scenario {
+        name: "LKS maker offer sponsored",
+        entry: Entry::Hook,
+        setup: lks_maker_offer,
+        expected: None,
+        budget: 18_000,
+    },
+    Scenario {
+        name: "LKS trust line sponsored",
+        entry: Entry::Hook,
+        setup: lks_trust_set,
+        expected: None,
+        budget: 15_000,
+    },
+    Scenario {
+        name: "LKS escrow creation sponsored",
+        entry: Entry::Hook,
+        setup: lks_escrow_create,
+        expected: None,
+        budget: 16_000,
+    },
+    Scenario {
+        name: "LKS holder key rotation sponsored",
+        entry: Entry::Hook,
+        setup: holder_set_regular_key,
+        expected: None,
+        budget: 15_000,
+    },
+    Scenario {
+        name: "LKS payment with settlement emitted",
+        entry: Entry::Hook,
+        setup: settled_payment,
+        expected: None,
+        budget: 17_000,
+    },
+    Scenario {
+        name: "registry admin command",
+        entry: Entry::Hook,
+        setup: registry_invoke,
+        expected: None,
+        budget: 4_000,
+    },
+    Scenario {
+        name: "registry admin command naming an r-address",
+        entry: Entry::Hook,
+        setup: registry_address_invoke,
+        expected: None,
+        budget: 40_000,
+    },
+    Scenario {
+        name: "typed admin commands in parameter and memo",
+        entry: Entry::Hook,
+        setup: typed_admin_invoke,
+        expected: None,
+        budget: 6_000,
+    },
+    Scenario {
+        name: "settlement callback",
+        entry: Entry::Cbak(0),
+        setup: settlement_callback,
+        expected: None,
+        budget: 2_000,
+    },
+];
+
+const COMPLIANCE: &[Scenario] = &[
+    Scenario {
+        name: "unverified account rejected",
+        entry: Entry::Hook,
+        setup: unverified_transaction,
+        expected: Some(HookError::KycRequired),
+        budget: 2_000,
+    },
+    Scenario {
+        name: "KYC admin command",
+        entry: Entry::Hook,
+        setup: kyc_invoke,
+        expected: None,
+        budget: 2_000,
+    },
+];
+
+fn lks_amount_bytes(units: u64) -> Vec<u8> {
+    let mut amount = Xfl::from_int(units).to_amount_value().to_vec();
+    amount.extend_from_slice(&amount::currency_code(b"LKS"));
+    amount.extend_from_slice(&LKS_ISSUER);
+    amount
+}
+
+fn transaction(tx_type: i32, account: &[u8; 20]) {
+    sim::reset();
+    let account = account.to_vec();
+    sim::with(|host| {
+        host.tx_type = tx_type;
+        host.ledger_seq = 1000;
+        host.fields.insert(fields::SF_FEE, amount::encode_native(12).to_vec());
+        host.fields.insert(fields::SF_ACCOUNT, account);
+    });
+}
+
+fn set_field(field: fields::FieldId, value: Vec<u8>) {
+    sim::with(|host| host.fields.insert(field, value));
+}
+
+fn set_param(name: &[u8], value: Vec<u8>) {
+    sim::with(|host| host.hook_params.insert(name.to_vec(), value));
+}
+
+fn native_payment() {
+    transaction(PAYMENT, &USER);
+    set_field(fields::SF_DESTINATION, MERCHANT.to_vec());
+    set_field(fields::SF_AMOUNT, amount::encode_native(5_000).to_vec());
+}
+
+fn lks_payment() {
+    transaction(PAYMENT, &USER);
+    set_field(fields::SF_DESTINATION, MERCHANT.to_vec());
+    set_field(fields::SF_AMOUNT, lks_amount_bytes(25));
+}
+
+// Memos filling most of the 1 KB limit and eight-entry tier tables, the
+// most a sponsored payment decodes
+fn lks_payment_worst_case() {
+    lks_payment();
+
+    let mut memos = Vec::new();
+    for _ in 0..24 {
+        memos.extend_from_slice(&[0xEA, 0x7C, 10]);
+        memos.extend_from_slice(b"text/plain");
+        memos.extend_from_slice(&[0x7D, 16]);
+        memos.extend_from_slice(b"invoice 00000042");
+        memos.push(0xE1);
+    }
+    memos.push(0xF1);
+    set_field(fields::SF_MEMOS, memos);
+
+    let mut tiers = Vec::new();
+    for tier in 1..=8u64 {
+        tiers.extend_from_slice(&(tier * 100_000_000).to_be_bytes());
+        tiers.push(100);
+    }
+    set_param(b"TIERS", tiers.clone());
+    set_param(b"STKTIERS", tiers);
+}
+
+fn lks_dust_payment() {
+    lks_payment();
+    set_field(fields::SF_AMOUNT, {
+        let mut amount = Xfl::new(false, 1, -6).unwrap().to_amount_value().to_vec();
+        amount.extend_from_slice(&amount::currency_code(b"LKS"));
+        amount.extend_from_slice(&LKS_ISSUER);
+        amount
+    });
+}
+
+fn paused_payment() {
+    lks_payment();
+    let key = state::key(state::NS_PAUSE, &[]);
+    sim::with(|host| host.state.insert(key.to_vec(), vec![1]));
+}
+
+// Sponsorship limited to Payments with the TXTYPES allowlist
+fn payments_only_offer() {
+    lks_offer();
+    set_param(b"TXTYPES", (1u64 << PAYMENT).to_be_bytes().to_vec());
+}
+
+fn vouchered_payment() {
+    lks_payment();
+
+    let key = [0xED; 33];
+    let mut voucher = USER.to_vec();
+    voucher.extend_from_slice(&1200u32.to_be_bytes());
+    voucher.extend_from_slice(&1u64.to_be_bytes());
+    let mut message = b"LKSV".to_vec();
+    message.extend_from_slice(&voucher);
+    voucher.extend(sim::sign(&key, &message));
+
+    let mut memos = vec![0xEA, 0x7C, 11];
+    memos.extend_from_slice(b"lks/voucher");
+    memos.extend_from_slice(&[0x7D, voucher.len() as u8]);
+    memos.extend_from_slice(&voucher);
+    memos.extend_from_slice(&[0xE1, 0xF1]);
+    set_field(fields::SF_MEMOS, memos);
+    set_param(b"VOUCHKEY", key.to_vec());
+}
+
+fn multi_signed_payment() {
+    lks_payment();
+
+    let mut signers = Vec::new();
+    for signer in 0..8u8 {
+        signers.extend_from_slice(&[0xE0, 0x10, 0x81, 0x14]);
+        signers.extend_from_slice(&[signer; 20]);
+        signers.extend_from_slice(&[0x73, 0x21]);
+        signers.extend_from_slice(&[0x02; 33]);
+        signers.extend_from_slice(&[0x74, 0x03, 0x30, 0x01, 0x00]);
+        signers.push(0xE1);
+    }
+    signers.push(0xF1);
+    set_field(fields::SF_SIGNERS, signers);
+    set_field(fields::SF_FEE, amount::encode_native(12 * 9).to_vec());
+}
+
+fn lks_offer() {
+    transaction(OFFER_CREATE, &USER);
+    set_field(fields::SF_TAKER_GETS, lks_amount_bytes(25));
+    set_field(fields::SF_TAKER_PAYS, amount::encode_native(5_000).to_vec());
+}
+
+// Maker mode judging the offer on its flags and price
+fn lks_maker_offer() {
+    lks_offer();
+    set_field(fields::SF_FLAGS, 0x0001_0000u32.to_be_bytes().to_vec());
+    set_param(b"MAKERONLY", vec![1]);
+    set_param(b"MAKERREF", 100u64.to_be_bytes().to_vec());
+}
+
+fn lks_trust_set() {
+    transaction(TRUST_SET, &USER);
+    set_field(fields::SF_LIMIT_AMOUNT, lks_amount_bytes(1_000_000));
+}
+
+fn lks_escrow_create() {
+    transaction(ESCROW_CREATE, &USER);
+    set_field(fields::SF_DESTINATION, MERCHANT.to_vec());
+    set_field(fields::SF_AMOUNT, lks_amount_bytes(25));
+    set_field(fields::SF_SEQUENCE, 7u32.to_be_bytes().to_vec());
+}
+
+fn holder_set_regular_key() {
+    transaction(SET_REGULAR_KEY, &USER);
+    set_param(b"MAINT", vec![1]);
+
+    // USER sorts above the issuer, so its holdings show as negative
+    let mut balance = Xfl::from_int(25).negate().to_amount_value().to_vec();
+    balance.extend_from_slice(&amount::currency_code(b"LKS"));
+    balance.extend_from_slice(&[0; 20]);
+    let keylet = sim::line_keylet(&USER, &LKS_ISSUER, &amount::currency_code(b"LKS"));
+    sim::with(|host| host.ledger.entry(keylet).or_default().insert(fields::SF_BALANCE, balance));
+}
+
+fn settled_payment() {
+    lks_payment();
+    set_param(b"SETTLDST", MERCHANT.to_vec());
+}
+
+fn registry_invoke() {
+    transaction(INVOKE, FOUNDATION_ACCOUNT.as_bytes());
+    let mut command = vec![1, 0x01];
+    command.extend_from_slice(&USER);
+    sim::with(|host| host.otxn_params.insert(b"LKSREG".to_vec(), command));
+}
+
+fn registry_address_invoke() {
+    transaction(INVOKE, FOUNDATION_ACCOUNT.as_bytes());
+    let mut command = vec![1, 0x01];
+    command.extend_from_slice(AccountId::new(USER).to_r_address().as_bytes());
+    sim::with(|host| host.otxn_params.insert(b"LKSREG".to_vec(), command));
+}
+
+fn typed_admin_invoke() {
+    transaction(INVOKE, FOUNDATION_ACCOUNT.as_bytes());
+    sim::with(|host| host.otxn_params.insert(b"LKSCMD".to_vec(), vec![0x06, 1]));
+
+    let mut memos = vec![0xEA, 0x7C, 9];
+    memos.extend_from_slice(b"lks/admin");
+    memos.extend_from_slice(&[0x7D, 9, 0x05]);
+    memos.extend_from_slice(&5_000_000u64.to_be_bytes());
+    memos.extend_from_slice(&[0xE1, 0xF1]);
+    set_field(fields::SF_MEMOS, memos);
+}
+
+fn settlement_callback() {
+    sim::reset();
+    sim::with(|host| {
+        host.ledger_seq = 1000;
+        host.fields.insert(fields::SF_AMOUNT, amount::encode_native(12).to_vec());
+        host.meta.insert(fields::SF_TRANSACTION_RESULT, vec![fields::TES_SUCCESS]);
+    });
+}
+
+fn unverified_transaction() {
+    transaction(PAYMENT, &USER);
+}
+
+fn kyc_invoke() {
+    transaction(INVOKE, FOUNDATION_ACCOUNT.as_bytes());
+    let mut command = vec![kyc::OP_VERIFY];
+    command.extend_from_slice(&USER);
+    command.extend_from_slice(&0u32.to_be_bytes());
+    sim::with(|host| host.otxn_params.insert(kyc::PARAM_COMMAND.to_vec(), command));
+}
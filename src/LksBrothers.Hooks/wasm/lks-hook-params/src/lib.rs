@@ -0,0 +1,312 @@
+// SetHook parameter encoding for the LKS hooks
+// Turns a deployment config into the HookParameters of a SetHook
+// transaction, decodes the HookParameters of a deployed hook back into a
+// config, and checks a deployment against its config. Parameters are
+// encoded from the list each hook keeps of the parameters it reads
+// (config::PARAMS, zero_fee_hook::params::PARAMS) with the SDK's encoders,
+// so an encoding can't drift from what the hook reads.
+//
+//   cargo run -p lks-hook-params -- encode deploy.toml
+//   cargo run -p lks-hook-params -- decode hook-parameters.json
+//   cargo run -p lks-hook-params -- verify deploy.toml hook-parameters.json
+// add --hook compliance for the compliance hook.
+//
+// A config (TOML, or JSON for files not ending in .toml) maps parameter
+// names to values:
+//   MAXFEE = 2000                       integers
+//   XCUR = true                         flags
+//   SETTLDST = "r..."                   accounts, as r-address or 40 hex digits
+//   STKNS = "5A5A..."                   other bytes, in hex
+//   TIERS = [[1000000000, 100], [100000000000, 50]]   [bound, share] tiers
+// The foundation account and the sponsored currencies aren't parameters:
+// they are compiled in and changed with admin commands.
+
+use lks_hook_sdk::config::{self, Encoding, Param};
+use lks_hook_sdk::{bytes, codec};
+use serde_json::{json, Map, Value};
+use zero_fee_hook::params;
+
+// An encoded parameter: name and value bytes
+pub type Parameter = (Vec<u8>, Vec<u8>);
+
+// The parameters `hook` reads, None for hooks this tool doesn't know
+pub fn catalog(hook: &str) -> Option<Vec<&'static Param>> {
+    let own: &[Param] = match hook {
+        "zero-fee" => params::PARAMS,
+        "compliance" => &[],
+        _ => return None,
+    };
+    Some(config::PARAMS.iter().chain(own).collect())
+}
+
+pub fn read_config(path: &str) -> Result<Value, String> {
+    let text = std::fs::read_to_string(path).map_err(|err| format!("reading {path}: {err}"))?;
+    if path.ends_with(".toml") {
+        toml::from_str(&text).map_err(|err| format!("parsing {path}: {err}"))
+    } else {
+        serde_json::from_str(&text).map_err(|err| format!("parsing {path}: {err}"))
+    }
+}
+
+// Encode the parameters of `config`, in catalog order
+pub fn encode(config: &Value, catalog: &[&'static Param]) -> Result<Vec<Parameter>, String> {
+    let entries = config.as_object().ok_or("a config maps parameter names to values")?;
+    if let Some(name) = entries.keys().find(|name| find(catalog, name.as_bytes()).is_none()) {
+        return Err(format!("{name}: the hook reads no such parameter"));
+    }
+
+    let mut parameters = Vec::new();
+    for param in catalog {
+        let name = String::from_utf8_lossy(param.name);
+        if let Some(value) = entries.get(name.as_ref()) {
+            let encoded = encode_value(param.encoding, value).map_err(|err| format!("{name}: {err}"))?;
+            check(param, &encoded).map_err(|err| format!("{name}: {err}"))?;
+            parameters.push((param.name.to_vec(), encoded));
+        }
+    }
+
+    if parameters.len() > config::MAX_PARAMS {
+        return Err(format!("{} parameters, a hook takes at most {}", parameters.len(), config::MAX_PARAMS));
+    }
+    Ok(parameters)
+}
+
+fn encode_value(encoding: Encoding, value: &Value) -> Result<Vec<u8>, String> {
+    match encoding {
+        Encoding::Flag => value.as_bool().map(|on| config::encode_flag(on).to_vec()).ok_or("expected true or false".into()),
+        Encoding::U64 => value.as_u64().map(|value| config::encode_u64(value).to_vec()).ok_or("expected an unsigned integer".into()),
+        Encoding::Account => {
+            let text = value.as_str().unwrap_or_default().as_bytes();
+            codec::decode_hex_account(text)
+                .or_else(|| codec::decode_r_address(text))
+                .map(|account| account.to_vec())
+                .ok_or("expected an r-address or 40 hex digits".into())
+        }
+        Encoding::Records { len, .. } if len == params::TIER_LEN && value.is_array() => {
+            value.as_array().into_iter().flatten().try_fold(Vec::new(), |mut table, tier| {
+                match tier.as_array().map(Vec::as_slice) {
+                    Some([bound, share]) => {
+                        let bound = bound.as_u64().ok_or("tier bounds are unsigned integers")?;
+                        let share = share.as_u64().and_then(|share| u8::try_from(share).ok());
+                        let share = share.ok_or("tier shares are percentages")?;
+                        table.extend_from_slice(&params::encode_tier(bound, share));
+                        Ok(table)
+                    }
+                    _ => Err("expected [bound, share] tiers".to_string()),
+                }
+            })
+        }
+        Encoding::Bytes(_) | Encoding::Records { .. } => {
+            value.as_str().and_then(from_hex).ok_or("expected hex".into())
+        }
+    }
+}
+
+// Values the hook would read as unset are mistakes
+fn check(param: &Param, value: &[u8]) -> Result<(), String> {
+    if value.len() > config::MAX_PARAM_VALUE_LEN {
+        return Err(format!("{} bytes, parameters hold at most {}", value.len(), config::MAX_PARAM_VALUE_LEN));
+    }
+    if !param.encoding.fits(value) {
+        return Err(format!("{} bytes don't fit {:?}, so the hook would ignore them", value.len(), param.encoding));
+    }
+    Ok(())
+}
+
+// Decode parameters back into a config
+pub fn decode(parameters: &[Parameter], catalog: &[&'static Param]) -> Result<Value, String> {
+    let mut config = Map::new();
+    for (name, value) in parameters {
+        let text = String::from_utf8_lossy(name).into_owned();
+        let param = find(catalog, name).ok_or_else(|| format!("{text}: the hook reads no such parameter"))?;
+        check(param, value).map_err(|err| format!("{text}: {err}"))?;
+        config.insert(text, decode_value(param.encoding, value));
+    }
+    Ok(Value::Object(config))
+}
+
+fn decode_value(encoding: Encoding, value: &[u8]) -> Value {
+    match encoding {
+        Encoding::Flag => Value::from(value.first().is_some_and(|&on| on != 0)),
+        Encoding::U64 => Value::from(bytes::u64_at(value, 0).unwrap_or(0)),
+        Encoding::Account => match bytes::array(value, 0) {
+            Some(account) => {
+                let mut address = [0u8; codec::R_ADDRESS_MAX_LEN];
+                let len = codec::encode_r_address(&account, &mut address);
+                Value::from(String::from_utf8_lossy(bytes::head(&address, len)))
+            }
+            None => Value::from(to_hex(value)),
+        },
+        Encoding::Records { len, .. } if len == params::TIER_LEN => Value::from(
+            value.chunks(len).map(|tier| json!([bytes::u64_at(tier, 0).unwrap_or(0), tier[len - 1]])).collect::<Vec<_>>(),
+        ),
+        Encoding::Bytes(_) | Encoding::Records { .. } => Value::from(to_hex(value)),
+    }
+}
+
+fn find(catalog: &[&'static Param], name: &[u8]) -> Option<&'static Param> {
+    catalog.iter().copied().find(|param| param.name == name)
+}
+
+// The HookParameters array of a SetHook transaction
+pub fn to_hook_parameters(parameters: &[Parameter]) -> Value {
+    Value::from(
+        parameters
+            .iter()
+            .map(|(name, value)| {
+                json!({ "HookParameter": { "HookParameterName": to_hex(name), "HookParameterValue": to_hex(value) } })
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+// The parameters of every HookParameter in `value`: a HookParameters array,
+// a SetHook transaction or a Hook ledger object
+pub fn from_hook_parameters(value: &Value) -> Result<Vec<Parameter>, String> {
+    let mut parameters = Vec::new();
+    collect(value, &mut parameters)?;
+    Ok(parameters)
+}
+
+fn collect(value: &Value, parameters: &mut Vec<Parameter>) -> Result<(), String> {
+    match value {
+        Value::Object(object) => match object.get("HookParameter") {
+            Some(parameter) => {
+                let field = |field: &str| {
+                    let text = parameter[field].as_str().unwrap_or_default();
+                    from_hex(text).ok_or_else(|| format!("{field} {text} isn't hex"))
+                };
+                parameters.push((field("HookParameterName")?, field("HookParameterValue")?));
+                Ok(())
+            }
+            None => object.values().try_for_each(|value| collect(value, parameters)),
+        },
+        Value::Array(values) => values.iter().try_for_each(|value| collect(value, parameters)),
+        _ => Ok(()),
+    }
+}
+
+// Differences between the parameters a config encodes to and deployed ones,
+// one line each
+pub fn verify(expected: &[Parameter], deployed: &[Parameter]) -> Vec<String> {
+    let name = |name: &[u8]| String::from_utf8_lossy(name).into_owned();
+    let mut differences = Vec::new();
+    for (param, value) in expected {
+        match deployed.iter().find(|(deployed, _)| deployed == param) {
+            None => differences.push(format!("{}: missing", name(param))),
+            Some((_, deployed)) if deployed != value => {
+                differences.push(format!("{}: {} deployed, {} expected", name(param), to_hex(deployed), to_hex(value)))
+            }
+            Some(_) => {}
+        }
+    }
+    for (param, _) in deployed {
+        if !expected.iter().any(|(expected, _)| expected == param) {
+            differences.push(format!("{}: not in the config", name(param)));
+        }
+    }
+    differences
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02X}")).collect()
+}
+
+pub fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| text.get(i..i + 2).and_then(|digits| u8::from_str_radix(digits, 16).ok()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::account::AccountId;
+    use lks_hook_sdk::sim;
+
+    const DESTINATION: [u8; 20] = [0xAB; 20];
+
+    fn zero_fee() -> Vec<&'static Param> {
+        catalog("zero-fee").unwrap()
+    }
+
+    fn deploy_config() -> Value {
+        toml::from_str(&format!(
+            "MAXFEE = 2000\nXCUR = true\nSETTLDST = \"{}\"\nTIERS = [[1000, 100], [5000, 50]]\n",
+            AccountId::new(DESTINATION).to_r_address()
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn the_hook_reads_what_a_config_encodes() {
+        let parameters = encode(&deploy_config(), &zero_fee()).unwrap();
+        sim::reset();
+        sim::with(|host| host.hook_params.extend(parameters.iter().cloned()));
+
+        assert_eq!(config::u64_param(b"MAXFEE", 0), 2000);
+        assert!(config::flag(b"XCUR", false));
+        let mut destination = [0u8; 20];
+        assert_eq!(config::bytes_param(b"SETTLDST", &mut destination), 20);
+        assert_eq!(destination, DESTINATION);
+        let mut tiers = [0u8; 18];
+        assert_eq!(config::records_param(b"TIERS", 9, &mut tiers), 18);
+        assert_eq!(&tiers[9..], &params::encode_tier(5000, 50));
+    }
+
+    #[test]
+    fn decodes_hook_parameters_back_into_the_config() {
+        let parameters = encode(&deploy_config(), &zero_fee()).unwrap();
+        let set_hook = json!({ "TransactionType": "SetHook", "Hooks": [{ "Hook": {
+            "HookParameters": to_hook_parameters(&parameters),
+        } }] });
+
+        let deployed = from_hook_parameters(&set_hook).unwrap();
+        assert_eq!(deployed, parameters);
+        let config = decode(&deployed, &zero_fee()).unwrap();
+        assert_eq!(encode(&config, &zero_fee()).unwrap(), parameters);
+        assert!(verify(&parameters, &deployed).is_empty());
+
+        // Parameters come in catalog order
+        let names: Vec<&[u8]> = deployed.iter().map(|(name, _)| name.as_slice()).collect();
+        assert_eq!(names, [&b"XCUR"[..], b"MAXFEE", b"TIERS", b"SETTLDST"]);
+
+        let mut changed = deployed.clone();
+        changed[1].1 = config::encode_u64(3000).to_vec();
+        changed.remove(2);
+        changed.push((b"STRICT".to_vec(), vec![1]));
+        assert_eq!(verify(&parameters, &changed), [
+            "MAXFEE: 0000000000000BB8 deployed, 00000000000007D0 expected",
+            "TIERS: missing",
+            "STRICT: not in the config",
+        ]);
+    }
+
+    #[test]
+    fn rejects_what_the_hook_would_ignore() {
+        let encode = |config: Value| encode(&config, &zero_fee());
+
+        assert!(encode(json!({ "MAXFEES": 1 })).is_err());
+        assert!(encode(json!({ "MAXFEE": -1 })).is_err());
+        assert!(encode(json!({ "SETTLDST": "rNotAnAddress" })).is_err());
+        assert!(encode(json!({ "STKNS": "5A5A" })).is_err());
+        assert!(encode(json!({ "TIERS": vec![[1, 100]; 9] })).is_err());
+        assert!(encode(json!({ "TIERS": [[1, 300]] })).is_err());
+
+        // The compliance hook reads none of the zero-fee hook's parameters
+        assert!(super::encode(&json!({ "MAXFEE": 1 }), &catalog("compliance").unwrap()).is_err());
+        assert!(super::encode(&json!({ "FNDGRACE": 1 }), &catalog("compliance").unwrap()).is_ok());
+
+        let too_many: Map<String, Value> = params::PARAMS
+            .iter()
+            .filter(|param| param.encoding == Encoding::U64)
+            .map(|param| (String::from_utf8_lossy(param.name).into_owned(), json!(1)))
+            .collect();
+        assert!(too_many.len() > config::MAX_PARAMS);
+        assert!(encode(Value::Object(too_many)).is_err());
+    }
+}
@@ -0,0 +1,56 @@
+// Encode, decode or verify the SetHook parameters of an LKS hook
+
+use std::process::ExitCode;
+
+use lks_hook_params as params;
+
+const USAGE: &str = "usage: lks-hook-params (encode <config> | decode <parameters.json> | \
+                     verify <config> <parameters.json>) [--hook zero-fee|compliance]";
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// Whether the command succeeded
+fn run() -> Result<bool, String> {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let hook = match args.iter().position(|arg| arg == "--hook") {
+        Some(i) if i + 1 < args.len() => args.drain(i..i + 2).nth(1).unwrap_or_default(),
+        Some(_) => return Err(USAGE.into()),
+        None => "zero-fee".into(),
+    };
+    let catalog = params::catalog(&hook).ok_or_else(|| format!("unknown hook {hook}"))?;
+    let parameters = |path: &str| {
+        let text = std::fs::read_to_string(path).map_err(|err| format!("reading {path}: {err}"))?;
+        let value = serde_json::from_str(&text).map_err(|err| format!("parsing {path}: {err}"))?;
+        params::from_hook_parameters(&value)
+    };
+
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["encode", config] => {
+            let encoded = params::encode(&params::read_config(config)?, &catalog)?;
+            println!("{:#}", params::to_hook_parameters(&encoded));
+            Ok(true)
+        }
+        ["decode", deployed] => {
+            println!("{:#}", params::decode(&parameters(deployed)?, &catalog)?);
+            Ok(true)
+        }
+        ["verify", config, deployed] => {
+            let expected = params::encode(&params::read_config(config)?, &catalog)?;
+            let deployed = parameters(deployed)?;
+            params::decode(&deployed, &catalog)?;
+            let differences = params::verify(&expected, &deployed);
+            differences.iter().for_each(|difference| println!("{difference}"));
+            Ok(differences.is_empty())
+        }
+        _ => Err(USAGE.into()),
+    }
+}
@@ -0,0 +1,117 @@
+// Account ids for the LKS hooks
+// Accounts the hook trusts (foundation, issuers) are compared in constant
+// time: the comparison never exits early, so its timing doesn't reveal how
+// much of a candidate account matched. AccountId's == is that comparison.
+//
+// Built with the sim feature (and for tests) accounts also render as hex and
+// as classic r-addresses for debugging; the hook itself only parses them,
+// with codec.rs.
+
+use crate::bytes;
+
+pub const ACCOUNT_ID_LEN: usize = 20;
+
+#[derive(Clone, Copy, Eq)]
+pub struct AccountId([u8; ACCOUNT_ID_LEN]);
+
+impl AccountId {
+    pub const fn new(bytes: [u8; ACCOUNT_ID_LEN]) -> Self {
+        AccountId(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; ACCOUNT_ID_LEN] {
+        &self.0
+    }
+
+    // Constant-time equality against raw account bytes read from a
+    // transaction. The account is compared as three words so there is no
+    // loop (and no guard) involved.
+    pub fn matches(&self, other: &[u8; ACCOUNT_ID_LEN]) -> bool {
+        let a = &self.0;
+        let diff = (word(a, 0) ^ word(other, 0))
+            | (word(a, 8) ^ word(other, 8))
+            | (half_word(a) ^ half_word(other)) as u64;
+
+        core::hint::black_box(diff) == 0
+    }
+}
+
+impl PartialEq for AccountId {
+    fn eq(&self, other: &AccountId) -> bool {
+        self.matches(&other.0)
+    }
+}
+
+impl From<[u8; ACCOUNT_ID_LEN]> for AccountId {
+    fn from(bytes: [u8; ACCOUNT_ID_LEN]) -> Self {
+        AccountId(bytes)
+    }
+}
+
+fn word(account: &[u8; ACCOUNT_ID_LEN], start: usize) -> u64 {
+    u64::from_ne_bytes(bytes::array(account, start).unwrap_or_default())
+}
+
+fn half_word(account: &[u8; ACCOUNT_ID_LEN]) -> u32 {
+    u32::from_ne_bytes(bytes::array(account, 16).unwrap_or_default())
+}
+
+#[cfg(any(test, feature = "sim"))]
+mod debug {
+    use super::AccountId;
+    use crate::{bytes, codec};
+    use std::string::String;
+
+    impl AccountId {
+        pub fn to_hex(&self) -> String {
+            self.0.iter().map(|byte| std::format!("{byte:02X}")).collect()
+        }
+
+        pub fn to_r_address(&self) -> String {
+            let mut address = [0u8; codec::R_ADDRESS_MAX_LEN];
+            let len = codec::encode_r_address(&self.0, &mut address);
+            String::from_utf8_lossy(bytes::head(&address, len)).into_owned()
+        }
+
+        pub fn from_r_address(address: &str) -> Option<AccountId> {
+            codec::decode_r_address(address.as_bytes()).map(AccountId)
+        }
+    }
+
+    impl std::fmt::Debug for AccountId {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.to_r_address())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::account::ACCOUNT_ID_LEN;
+
+        #[test]
+        fn encodes_known_addresses() {
+            // The all-zero account is the well-known black hole address
+            let black_hole = AccountId::new([0; ACCOUNT_ID_LEN]);
+            assert_eq!(black_hole.to_r_address(), "rrrrrrrrrrrrrrrrrrrrrhoLvTp");
+            assert_eq!(AccountId::from_r_address("rrrrrrrrrrrrrrrrrrrrrhoLvTp"), Some(black_hole));
+            assert_eq!(AccountId::new([0x0A; ACCOUNT_ID_LEN]).to_hex(), "0A".repeat(20));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_every_byte() {
+        let account = AccountId::new([0x11; ACCOUNT_ID_LEN]);
+        for i in 0..ACCOUNT_ID_LEN {
+            let mut other = [0x11; ACCOUNT_ID_LEN];
+            other[i] ^= 0x80;
+            assert!(!account.matches(&other));
+        }
+        assert!(account.matches(&[0x11; ACCOUNT_ID_LEN]));
+    }
+}
@@ -0,0 +1,164 @@
+// Foundation administration shared by the LKS hooks
+// Every LKS hook is administered by the foundation: admin commands arrive as
+// parameters of foundation-signed Invoke transactions, and foundation
+// housekeeping is never subject to the hooks' own policies.
+//
+// The foundation account is compiled in, but can be rotated with an admin
+// command; the rotated account is kept in the hook's state and replaces the
+// compiled one. With a grace period set in FNDGRACE the new account is first
+// recorded as pending, and both accounts are accepted until the grace period
+// ends and the outgoing one retires, so commands already signed by the old
+// key still go through.
+//
+// Foundation entry: [account(20)], during a grace period followed by the
+// pending account and the ledger the outgoing one retires at:
+// [account(20)][pending(20)][retire ledger u32]
+
+use crate::account::{AccountId, ACCOUNT_ID_LEN};
+use crate::api::{hook_account, ledger_seq, otxn_param};
+use crate::entry::{Entry, Reader, Writer};
+use crate::error::HookError;
+use crate::{bytes, config, fields, log, state};
+
+// Foundation account (this would be configured)
+pub const FOUNDATION_ACCOUNT: AccountId = AccountId::new([
+    0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0,
+    0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0,
+    0x12, 0x34, 0x56, 0x78
+]);
+
+// Ledgers both the outgoing and the pending foundation account are accepted
+// after a rotation (u64); unset or zero hands over right away
+pub const PARAM_FOUNDATION_GRACE: &[u8] = b"FNDGRACE";
+
+// Rotation phases traced with EV_FOUNDATION
+pub const PHASE_PENDING: u64 = 1;
+pub const PHASE_OUTGOING: u64 = 2;
+pub const PHASE_RETIRED: u64 = 3;
+
+struct Foundation {
+    account: AccountId,
+    // Account taking over and the ledger it does, during a grace period
+    pending: Option<(AccountId, u32)>,
+}
+
+impl Entry for Foundation {
+    #[inline(always)]
+    fn encode(&self, w: &mut Writer) {
+        w.account(self.account.as_bytes());
+        if let Some((pending, retire)) = &self.pending {
+            w.account(pending.as_bytes()).u32(*retire);
+        }
+    }
+
+    #[inline(always)]
+    fn decode(r: &mut Reader) -> Option<Foundation> {
+        let account = AccountId::new(r.account()?);
+        if r.is_empty() {
+            return Some(Foundation { account, pending: None });
+        }
+
+        let pending = (AccountId::new(r.account()?), r.u32()?);
+        Some(Foundation { account, pending: Some(pending) })
+    }
+}
+
+// The foundation signs either from its configured account (or, during a
+// grace period, the pending one) or from the hook account itself, which
+// stays trusted so a lost foundation key can always be rotated away
+pub fn is_foundation(account: &[u8; 20]) -> bool {
+    let mut hook = [0u8; 20];
+    let hook_known = unsafe { hook_account(hook.as_mut_ptr()) } == 20;
+
+    let foundation = load_foundation();
+    let current = foundation.account.matches(account);
+    let pending = match foundation.pending {
+        Some((pending, retire)) => {
+            if current {
+                log::info(log::EV_FOUNDATION, b"LKS outgoing foundation account used", PHASE_OUTGOING,
+                          retire as u64);
+            }
+            pending.matches(account)
+        }
+        None => false,
+    };
+
+    current | pending | (hook_known & AccountId::new(hook).matches(account))
+}
+
+// The current foundation account: the rotated one if any, else the compiled
+// one. During a grace period that is still the outgoing account.
+pub fn foundation_account() -> AccountId {
+    load_foundation().account
+}
+
+// Hand the foundation role to `account`, after the grace period if one is
+// set. Rotating during a grace period replaces the pending account, and
+// rotating back to the outgoing one calls the rotation off. The zero account
+// is refused, as nobody can sign for it.
+pub fn rotate_foundation(account: &[u8; ACCOUNT_ID_LEN]) -> Result<(), HookError> {
+    if bytes::eq(account, &[0u8; ACCOUNT_ID_LEN]) {
+        return Err(HookError::AdminCommandInvalid);
+    }
+
+    let ledger = unsafe { ledger_seq() };
+    let grace = config::u64_param(PARAM_FOUNDATION_GRACE, 0);
+    let current = load_foundation().account;
+    if grace == 0 || current.matches(account) {
+        state::store_entry(&foundation_key(), &Foundation { account: AccountId::new(*account), pending: None })?;
+        log::info(log::EV_FOUNDATION, b"LKS foundation rotated", PHASE_RETIRED, ledger);
+        return Ok(());
+    }
+
+    let retire = ledger.saturating_add(grace).min(u32::MAX as u64);
+    let pending = Some((AccountId::new(*account), retire as u32));
+    state::store_entry(&foundation_key(), &Foundation { account: current, pending })?;
+
+    log::info(log::EV_FOUNDATION, b"LKS foundation rotation pending", PHASE_PENDING, retire);
+    Ok(())
+}
+
+// Read the foundation entry. Once the grace period is over the pending
+// account takes over for good, and the entry is rewritten to hold it alone.
+fn load_foundation() -> Foundation {
+    let foundation = state::load_entry(&foundation_key())
+        .unwrap_or(Foundation { account: FOUNDATION_ACCOUNT, pending: None });
+    let (account, (pending, retire)) = match foundation.pending {
+        Some(pending) => (foundation.account, pending),
+        None => return foundation,
+    };
+    let ledger = unsafe { ledger_seq() };
+    if ledger < retire as u64 {
+        return Foundation { account, pending: Some((pending, retire)) };
+    }
+
+    // The pending account already governs even if the entry can't be
+    // rewritten; the next read tries again
+    let retired = Foundation { account: pending, pending: None };
+    if state::store_entry(&foundation_key(), &retired).is_ok() {
+        log::info(log::EV_FOUNDATION, b"LKS outgoing foundation account retired", PHASE_RETIRED, ledger);
+    }
+    retired
+}
+
+fn foundation_key() -> [u8; state::KEY_LEN] {
+    state::key(state::NS_FOUNDATION, &[])
+}
+
+pub fn is_foundation_transaction() -> bool {
+    matches!(fields::read_account(), Ok(source) if is_foundation(&source))
+}
+
+// Read a parameter of the originating transaction into `out`, returning its
+// length (zero when missing or too long)
+pub fn read_otxn_param(name: &[u8], out: &mut [u8]) -> usize {
+    let result = unsafe {
+        otxn_param(name.as_ptr(), name.len() as i32, out.as_mut_ptr(), out.len() as i32)
+    };
+
+    if result <= 0 {
+        return 0;
+    }
+
+    (result as usize).min(out.len())
+}
@@ -0,0 +1,97 @@
+// STAmount parsing for the LKS hooks
+// Native amounts are 8 bytes; issued amounts are an 8-byte XFL value followed
+// by the 20-byte currency code and the 20-byte issuer account
+
+use crate::bytes;
+use crate::error::HookError;
+use crate::xfl::Xfl;
+
+pub const NATIVE_LEN: usize = 8;
+pub const ISSUED_LEN: usize = 48;
+
+// Offsets within issued amounts
+pub const CURRENCY_OFFSET: usize = 8;
+pub const ISSUER_OFFSET: usize = 28;
+
+// LKS values are handled as fixed-point integers with six decimals
+pub const LKS_DECIMALS: u32 = 6;
+
+// Top bit of the value marks an issued (non-native) amount
+const ISSUED_BIT: u8 = 0x80;
+
+// Native values: positive bit followed by 62 bits of drops
+const NATIVE_POSITIVE_BIT: u64 = 1 << 62;
+const NATIVE_DROPS_MASK: u64 = NATIVE_POSITIVE_BIT - 1;
+
+pub enum Amount {
+    // Drops of the native currency
+    Native(u64),
+    Issued {
+        value: [u8; 8],
+        currency: [u8; 20],
+        issuer: [u8; 20],
+    },
+}
+
+pub fn parse(data: &[u8]) -> Result<Amount, HookError> {
+    let value: [u8; 8] = bytes::array(data, 0).ok_or(HookError::AmountParseFailed)?;
+
+    if value[0] & ISSUED_BIT == 0 {
+        let raw = u64::from_be_bytes(value);
+
+        // Negative native amounts never appear in transactions
+        if data.len() != NATIVE_LEN || (raw & NATIVE_POSITIVE_BIT == 0 && raw != 0) {
+            return Err(HookError::AmountParseFailed);
+        }
+        return Ok(Amount::Native(raw & NATIVE_DROPS_MASK));
+    }
+
+    match (bytes::array(data, CURRENCY_OFFSET), bytes::array(data, ISSUER_OFFSET)) {
+        (Some(currency), Some(issuer)) if data.len() == ISSUED_LEN => Ok(Amount::Issued { value, currency, issuer }),
+        _ => Err(HookError::AmountParseFailed),
+    }
+}
+
+// Offset of the byte where `data` stops being a valid amount, None if it is
+// one. Only needed to explain why parse() failed.
+pub fn malformed_at(data: &[u8]) -> Option<usize> {
+    let len = match data.first() {
+        Some(byte) if byte & ISSUED_BIT == 0 => NATIVE_LEN,
+        Some(_) => ISSUED_LEN,
+        None => return Some(0),
+    };
+    if data.len() != len {
+        return Some(data.len().min(len));
+    }
+
+    // Well-sized amounts only fail on the sign of native values
+    parse(data).err().map(|_| 0)
+}
+
+// Serialize a positive native amount
+pub fn encode_native(drops: u64) -> [u8; NATIVE_LEN] {
+    ((drops & NATIVE_DROPS_MASK) | NATIVE_POSITIVE_BIT).to_be_bytes()
+}
+
+// Convert an issued value to a fixed-point integer with `decimals` decimal
+// places, truncating extra precision and saturating at u64::MAX
+// Negative and malformed values read as zero
+pub fn issued_value(value: &[u8; 8], decimals: u32) -> u64 {
+    checked_issued_value(value, decimals).unwrap_or(0)
+}
+
+// Like issued_value, but None for negative and malformed values
+pub fn checked_issued_value(value: &[u8; 8], decimals: u32) -> Option<u64> {
+    match Xfl::from_amount_value(value) {
+        Some(value) if !value.is_negative() => Some(value.to_int(decimals).unwrap_or(u64::MAX)),
+        _ => None,
+    }
+}
+
+// Standard currency codes are 12 zero bytes, three ASCII characters and
+// five zero bytes
+pub fn currency_code(code: &[u8; 3]) -> [u8; 20] {
+    let mut currency = [0u8; 20];
+    bytes::put(&mut currency, 12, code);
+    currency
+}
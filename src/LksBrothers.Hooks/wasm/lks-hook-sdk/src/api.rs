@@ -0,0 +1,259 @@
+// Hook API bindings
+// Functions the Hooks runtime provides to every hook. Hooks call them
+// through the helpers in this crate where one exists (fields, state, config,
+// log); the raw calls are public for everything else.
+//
+// These are the calls of the legacy hook ABI, which the simulated host
+// implements. Built for the ledger with the xahau feature, the same
+// functions are a shim over the Xahau Hooks API in xahau.rs instead, so hook
+// logic compiles unchanged against either runtime. Native builds always use
+// the simulated host, whatever the features.
+
+// Like the runtime's own calls, every function here needs its pointers and
+// lengths to describe valid hook memory, and takes the runtime's arguments
+#![allow(clippy::missing_safety_doc, clippy::too_many_arguments)]
+
+#[cfg(not(all(feature = "xahau", not(any(test, feature = "sim")))))]
+extern "C" {
+    pub fn otxn_type() -> i32;
+    pub fn otxn_slot(slot: i32, data: *mut u8, len: i32) -> i32;
+    pub fn meta_slot(slot: i32, data: *mut u8, len: i32) -> i32;
+    pub fn slot_set(slot: i32, data: *const u8, len: i32) -> i32;
+    pub fn accept(msg: *const u8, len: i32) -> i32;
+    pub fn reject(msg: *const u8, len: i32) -> i32;
+    pub fn trace(msg: *const u8, msg_len: i32, data: *const u8, data_len: i32, as_hex: i32) -> i32;
+    pub fn ledger_seq() -> u64;
+    pub fn otxn_id(data: *mut u8, len: i32, flags: u32) -> i32;
+    pub fn util_keylet(data: *mut u8, len: i32, keylet_type: u32,
+                       account: *const u8, account_len: i32, sequence: u32) -> i32;
+    pub fn util_keylet_line(data: *mut u8, len: i32, account: *const u8, issuer: *const u8,
+                            currency: *const u8) -> i32;
+    pub fn keylet_field(keylet: *const u8, keylet_len: i32, field: i32, data: *mut u8, len: i32) -> i32;
+    pub fn util_verify(data: *const u8, data_len: i32, signature: *const u8, signature_len: i32,
+                       key: *const u8, key_len: i32) -> i32;
+    pub fn util_sha512h(hash: *mut u8, hash_len: i32, data: *const u8, data_len: i32) -> i32;
+    pub fn etxn_reserve(count: u32) -> i32;
+    pub fn etxn_details(data: *mut u8, len: i32) -> i32;
+    pub fn etxn_fee_base(tx: *const u8, tx_len: i32) -> i64;
+    pub fn emit(hash: *mut u8, hash_len: i32, tx: *const u8, tx_len: i32) -> i32;
+    pub fn hook_account(account: *mut u8) -> i32;
+    pub fn hook_param(name: *const u8, name_len: i32, data: *mut u8, len: i32) -> i32;
+    pub fn otxn_param(name: *const u8, name_len: i32, data: *mut u8, len: i32) -> i32;
+    pub fn state(key: *const u8, key_len: i32, data: *mut u8, len: i32) -> i32;
+    pub fn state_set(key: *const u8, key_len: i32, data: *const u8, len: i32) -> i32;
+    pub fn state_foreign(data: *mut u8, len: i32, key: *const u8, key_len: i32,
+                         namespace: *const u8, namespace_len: i32,
+                         account: *const u8, account_len: i32) -> i32;
+    pub fn _g(id: u32, max_iterations: u32) -> i32;
+}
+
+// accept and reject passing the value the hook returns. The legacy runtime
+// takes it from the return value of hook(); Xahau records the code passed
+// here and ends the hook right away.
+#[cfg(not(all(feature = "xahau", not(any(test, feature = "sim")))))]
+pub unsafe fn accept_with(msg: *const u8, len: i32, _code: i64) -> i32 {
+    accept(msg, len)
+}
+
+#[cfg(not(all(feature = "xahau", not(any(test, feature = "sim")))))]
+pub unsafe fn reject_with(msg: *const u8, len: i32, _code: i64) -> i32 {
+    reject(msg, len)
+}
+
+#[cfg(all(feature = "xahau", not(any(test, feature = "sim"))))]
+pub use shim::*;
+
+// The legacy calls on top of Xahau. Lengths are never negative in hook
+// code, and results fit an i32 except where the legacy call returns wider.
+#[cfg(all(feature = "xahau", not(any(test, feature = "sim"))))]
+mod shim {
+    use crate::xahau;
+
+    #[inline(always)]
+    pub unsafe fn otxn_type() -> i32 {
+        xahau::otxn_type() as i32
+    }
+
+    // The legacy runtime reads fields of the originating transaction by
+    // field code; Xahau serializes the field the same way, without slots.
+    // Field 0, the whole transaction, is read from a slot.
+    #[inline(always)]
+    pub unsafe fn otxn_slot(field: i32, data: *mut u8, len: i32) -> i32 {
+        if field != 0 {
+            return xahau::otxn_field(data, len as u32, field as u32) as i32;
+        }
+
+        let tx = xahau::otxn_slot(0);
+        if tx < 0 {
+            return tx as i32;
+        }
+        let result = xahau::slot(data, len as u32, tx as u32);
+        xahau::slot_clear(tx as u32);
+        result as i32
+    }
+
+    // Metadata is only available in cbak: load it into a slot and read the
+    // field from a subslot
+    #[inline(always)]
+    pub unsafe fn meta_slot(field: i32, data: *mut u8, len: i32) -> i32 {
+        let meta = xahau::meta_slot(0);
+        if meta < 0 {
+            return meta as i32;
+        }
+        read_subfield(meta as u32, field, data, len)
+    }
+
+    // Xahau hooks can't rewrite fields of the originating transaction; hooks
+    // check fields::FEE_WRITABLE instead of finding out here
+    #[inline(always)]
+    pub unsafe fn slot_set(_field: i32, _data: *const u8, _len: i32) -> i32 {
+        xahau::NOT_IMPLEMENTED as i32
+    }
+
+    #[inline(always)]
+    pub unsafe fn accept(msg: *const u8, len: i32) -> i32 {
+        accept_with(msg, len, 0)
+    }
+
+    #[inline(always)]
+    pub unsafe fn reject(msg: *const u8, len: i32) -> i32 {
+        reject_with(msg, len, 0)
+    }
+
+    #[inline(always)]
+    pub unsafe fn accept_with(msg: *const u8, len: i32, code: i64) -> i32 {
+        xahau::accept(msg, len as u32, code) as i32
+    }
+
+    #[inline(always)]
+    pub unsafe fn reject_with(msg: *const u8, len: i32, code: i64) -> i32 {
+        xahau::rollback(msg, len as u32, code) as i32
+    }
+
+    #[inline(always)]
+    pub unsafe fn trace(msg: *const u8, msg_len: i32, data: *const u8, data_len: i32, as_hex: i32) -> i32 {
+        xahau::trace(msg, msg_len as u32, data, data_len as u32, as_hex as u32) as i32
+    }
+
+    #[inline(always)]
+    pub unsafe fn ledger_seq() -> u64 {
+        xahau::ledger_seq() as u64
+    }
+
+    #[inline(always)]
+    pub unsafe fn otxn_id(data: *mut u8, len: i32, flags: u32) -> i32 {
+        xahau::otxn_id(data, len as u32, flags) as i32
+    }
+
+    // The account keylets the hooks build (account root, NFT offer, check)
+    // take the account and a sequence as their first arguments
+    #[inline(always)]
+    pub unsafe fn util_keylet(data: *mut u8, len: i32, keylet_type: u32,
+                              account: *const u8, account_len: i32, sequence: u32) -> i32 {
+        xahau::util_keylet(data, len as u32, keylet_type, account as u32, account_len as u32, sequence,
+                           0, 0, 0) as i32
+    }
+
+    // Trust lines are keyed by both accounts and the currency, 20 bytes each
+    #[inline(always)]
+    pub unsafe fn util_keylet_line(data: *mut u8, len: i32, account: *const u8, issuer: *const u8,
+                                   currency: *const u8) -> i32 {
+        xahau::util_keylet(data, len as u32, xahau::KEYLET_LINE, account as u32, 20, issuer as u32, 20,
+                           currency as u32, 20) as i32
+    }
+
+    #[inline(always)]
+    pub unsafe fn keylet_field(keylet: *const u8, keylet_len: i32, field: i32, data: *mut u8, len: i32) -> i32 {
+        let object = xahau::slot_set(keylet, keylet_len as u32, 0);
+        if object < 0 {
+            return object as i32;
+        }
+        read_subfield(object as u32, field, data, len)
+    }
+
+    #[inline(always)]
+    pub unsafe fn util_verify(data: *const u8, data_len: i32, signature: *const u8, signature_len: i32,
+                              key: *const u8, key_len: i32) -> i32 {
+        xahau::util_verify(data, data_len as u32, signature, signature_len as u32, key, key_len as u32) as i32
+    }
+
+    #[inline(always)]
+    pub unsafe fn util_sha512h(hash: *mut u8, hash_len: i32, data: *const u8, data_len: i32) -> i32 {
+        xahau::util_sha512h(hash, hash_len as u32, data, data_len as u32) as i32
+    }
+
+    #[inline(always)]
+    pub unsafe fn etxn_reserve(count: u32) -> i32 {
+        xahau::etxn_reserve(count) as i32
+    }
+
+    #[inline(always)]
+    pub unsafe fn etxn_details(data: *mut u8, len: i32) -> i32 {
+        xahau::etxn_details(data, len as u32) as i32
+    }
+
+    #[inline(always)]
+    pub unsafe fn etxn_fee_base(tx: *const u8, tx_len: i32) -> i64 {
+        xahau::etxn_fee_base(tx, tx_len as u32)
+    }
+
+    #[inline(always)]
+    pub unsafe fn emit(hash: *mut u8, hash_len: i32, tx: *const u8, tx_len: i32) -> i32 {
+        xahau::emit(hash, hash_len as u32, tx, tx_len as u32) as i32
+    }
+
+    #[inline(always)]
+    pub unsafe fn hook_account(account: *mut u8) -> i32 {
+        xahau::hook_account(account, 20) as i32
+    }
+
+    #[inline(always)]
+    pub unsafe fn hook_param(name: *const u8, name_len: i32, data: *mut u8, len: i32) -> i32 {
+        xahau::hook_param(data, len as u32, name, name_len as u32) as i32
+    }
+
+    #[inline(always)]
+    pub unsafe fn otxn_param(name: *const u8, name_len: i32, data: *mut u8, len: i32) -> i32 {
+        xahau::otxn_param(data, len as u32, name, name_len as u32) as i32
+    }
+
+    #[inline(always)]
+    pub unsafe fn state(key: *const u8, key_len: i32, data: *mut u8, len: i32) -> i32 {
+        xahau::state(data, len as u32, key, key_len as u32) as i32
+    }
+
+    #[inline(always)]
+    pub unsafe fn state_set(key: *const u8, key_len: i32, data: *const u8, len: i32) -> i32 {
+        xahau::state_set(data, len as u32, key, key_len as u32) as i32
+    }
+
+    #[inline(always)]
+    pub unsafe fn state_foreign(data: *mut u8, len: i32, key: *const u8, key_len: i32,
+                                namespace: *const u8, namespace_len: i32,
+                                account: *const u8, account_len: i32) -> i32 {
+        xahau::state_foreign(data, len as u32, key, key_len as u32, namespace, namespace_len as u32,
+                             account, account_len as u32) as i32
+    }
+
+    // Inlined so the guard call stays in the loop it guards, where SetHook
+    // looks for it
+    #[inline(always)]
+    pub unsafe fn _g(id: u32, max_iterations: u32) -> i32 {
+        xahau::_g(id, max_iterations)
+    }
+
+    // Read `field` of the object in `slot` and free both slots again; a
+    // hook only has 255 of them
+    unsafe fn read_subfield(slot: u32, field: i32, data: *mut u8, len: i32) -> i32 {
+        let sub = xahau::slot_subfield(slot, field as u32, 0);
+        let result = if sub < 0 {
+            sub
+        } else {
+            let result = xahau::slot(data, len as u32, sub as u32);
+            xahau::slot_clear(sub as u32);
+            result
+        };
+        xahau::slot_clear(slot);
+        result as i32
+    }
+}
@@ -0,0 +1,113 @@
+// Panic-free byte handling for the LKS hooks
+// Hooks must not contain reachable panics (see the panic handler in lib.rs),
+// but range indexing and copy_from_slice check their bounds in out-of-line
+// functions that a size-optimized build doesn't inline, even where the
+// bounds are constant. Hook code slices, copies and decodes its buffers
+// through these helpers instead, which fall back to short reads and
+// truncated copies rather than panicking.
+//
+// Slice and array comparisons compile to a call to memcmp, whose loop has no
+// guard, so hook code compares bytes with eq() and cmp() instead.
+
+use core::cmp::Ordering;
+
+// Upper bound on bytes compared per hook execution. Memo types dominate: up
+// to 32 memos, each compared with the few directives as long as it.
+pub const MAX_COMPARED: u32 = 1024;
+
+// Copy as much of `src` as fits into the start of `dst`, returning the
+// number of bytes copied
+#[inline(always)]
+pub fn copy(dst: &mut [u8], src: &[u8]) -> usize {
+    let len = dst.len().min(src.len());
+    // Both regions hold at least `len` bytes, and `dst` is borrowed mutably
+    // so they can't overlap. Lowers to memory.copy with bulk-memory.
+    unsafe {
+        core::ptr::copy_nonoverlapping(src.as_ptr(), dst.as_mut_ptr(), len);
+    }
+    len
+}
+
+// Copy `src` into `dst` starting at `offset`, truncated at the end of `dst`
+#[inline(always)]
+pub fn put(dst: &mut [u8], offset: usize, src: &[u8]) -> usize {
+    match dst.get_mut(offset..) {
+        Some(rest) => copy(rest, src),
+        None => 0,
+    }
+}
+
+// The first `len` bytes of `data`, all of it when shorter
+#[inline(always)]
+pub fn head(data: &[u8], len: usize) -> &[u8] {
+    data.get(..len).unwrap_or(data)
+}
+
+// `data` from `offset` on, empty when `offset` is past the end
+#[inline(always)]
+pub fn tail(data: &[u8], offset: usize) -> &[u8] {
+    data.get(offset..).unwrap_or(&[])
+}
+
+// The `N` bytes of `data` at `offset`, None when `data` is too short
+#[inline(always)]
+pub fn array<const N: usize>(data: &[u8], offset: usize) -> Option<[u8; N]> {
+    data.get(offset..)?.get(..N)?.try_into().ok()
+}
+
+#[inline(always)]
+pub fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    array(data, offset).map(u32::from_be_bytes)
+}
+
+#[inline(always)]
+pub fn u64_at(data: &[u8], offset: usize) -> Option<u64> {
+    array(data, offset).map(u64::from_be_bytes)
+}
+
+// Lexicographic order of `a` and `b`, like slice cmp
+pub fn cmp(a: &[u8], b: &[u8]) -> Ordering {
+    let mut ordering = Ordering::Equal;
+    guarded_loop!(i in 0, a.len().min(b.len()); max MAX_COMPARED; {
+        ordering = ordering.then(a.get(i).cmp(&b.get(i)));
+    });
+    ordering.then(a.len().cmp(&b.len()))
+}
+
+#[inline(always)]
+pub fn eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && cmp(a, b).is_eq()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copies_and_reads_within_bounds() {
+        let mut buf = [0u8; 6];
+        assert_eq!(put(&mut buf, 4, b"abc"), 2);
+        assert_eq!(put(&mut buf, 7, b"abc"), 0);
+        assert_eq!(copy(&mut buf, b"xy"), 2);
+        assert_eq!(&buf, b"xy\0\0ab");
+
+        assert_eq!(u32_at(&buf, 2), Some(0x0000_6162));
+        assert_eq!(u32_at(&buf, 3), None);
+        assert_eq!(u64_at(&buf, 0), None);
+        assert_eq!(tail(&buf, 4), b"ab");
+        assert_eq!(tail(&buf, 9), b"");
+        assert_eq!(head(&buf, 2), b"xy");
+        assert_eq!(head(&buf, 9), &buf);
+    }
+
+    #[test]
+    fn compares_like_slices() {
+        let cases: [(&[u8], &[u8]); 6] =
+            [(b"", b""), (b"ab", b"ab"), (b"ab", b"ac"), (b"b", b"ab"), (b"ab", b"abc"), (b"abc", b"")];
+        for (a, b) in cases {
+            assert_eq!(cmp(a, b), a.cmp(b), "{a:?} {b:?}");
+            assert_eq!(cmp(b, a), b.cmp(a), "{b:?} {a:?}");
+            assert_eq!(eq(a, b), a == b, "{a:?} {b:?}");
+        }
+    }
+}
@@ -0,0 +1,297 @@
+// Text forms of account ids for the LKS hooks
+// Admin commands may name an account as its 20 raw bytes, as 40 hex digits
+// or as a classic r-address, and sim builds print accounts as r-addresses.
+// An r-address is the base58check encoding (in Ripple's alphabet) of the
+// account id behind type prefix 0: the prefixed id followed by the first 4
+// bytes of its double SHA-256, so a mistyped address fails its checksum
+// instead of naming some other account.
+//
+// Everything works in fixed buffers with guarded loops, so hooks decode
+// addresses themselves.
+
+use crate::account::ACCOUNT_ID_LEN;
+use crate::bytes;
+
+// r-addresses are 25 to 35 characters long
+pub const R_ADDRESS_MIN_LEN: usize = 25;
+pub const R_ADDRESS_MAX_LEN: usize = 35;
+pub const HEX_ACCOUNT_LEN: usize = 2 * ACCOUNT_ID_LEN;
+
+// Longest text form parse_account() takes
+pub const MAX_ACCOUNT_TEXT_LEN: usize = HEX_ACCOUNT_LEN;
+
+// Ripple's base58 alphabet
+const ALPHABET: &[u8; 58] = b"rpshnaf39wBUDNEGHJKLM4PQRST7VWXYZ2bcdeCg65jkm8oFqi1tuvAxyz";
+
+// Digit of each ASCII character, 0xFF for those outside the alphabet
+const DIGITS: [u8; 128] = {
+    let mut digits = [0xFF; 128];
+    let mut i = 0;
+    while i < ALPHABET.len() {
+        digits[ALPHABET[i] as usize] = i as u8;
+        i += 1;
+    }
+    digits
+};
+
+const ACCOUNT_PREFIX: u8 = 0;
+const CHECKSUM_LEN: usize = 4;
+
+// Prefix, account id and checksum
+const PAYLOAD_LEN: usize = 1 + ACCOUNT_ID_LEN + CHECKSUM_LEN;
+
+// Guard budgets per hook execution: accounts encoded or decoded, and the
+// SHA-256 digests their checksums take
+const MAX_ACCOUNTS: u32 = 4;
+const MAX_DIGESTS: u32 = 2 * MAX_ACCOUNTS;
+
+// An account named in an admin command: 20 raw bytes, 40 hex digits or an
+// r-address
+pub fn parse_account(text: &[u8]) -> Option<[u8; ACCOUNT_ID_LEN]> {
+    match text.len() {
+        ACCOUNT_ID_LEN => bytes::array(text, 0),
+        HEX_ACCOUNT_LEN => decode_hex_account(text),
+        _ => decode_r_address(text),
+    }
+}
+
+// Account id written as 40 hex digits, in either case
+pub fn decode_hex_account(text: &[u8]) -> Option<[u8; ACCOUNT_ID_LEN]> {
+    if text.len() != HEX_ACCOUNT_LEN {
+        return None;
+    }
+
+    let mut account = [0u8; ACCOUNT_ID_LEN];
+    guarded_loop!(i in 0, ACCOUNT_ID_LEN; max ACCOUNT_ID_LEN as u32 * MAX_ACCOUNTS; {
+        let high = hex_digit(*text.get(2 * i)?)?;
+        let low = hex_digit(*text.get(2 * i + 1)?)?;
+        *account.get_mut(i)? = (high << 4) | low;
+    });
+
+    Some(account)
+}
+
+fn hex_digit(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+// Write the r-address of `account` into `out`, returning its length
+pub fn encode_r_address(account: &[u8; ACCOUNT_ID_LEN], out: &mut [u8; R_ADDRESS_MAX_LEN]) -> usize {
+    let payload = prefixed(account);
+
+    // Base58 digits of the payload, least significant first, by repeated
+    // multiplication by 256. 25 bytes never take more than 35 digits.
+    let mut digits = [0u8; R_ADDRESS_MAX_LEN];
+    let mut len = 0;
+    guarded_loop!(i in 0, PAYLOAD_LEN; max PAYLOAD_LEN as u32 * MAX_ACCOUNTS; {
+        let mut carry = payload.get(i).copied().unwrap_or(0) as u32;
+        guarded_loop!(j in 0, len; max (PAYLOAD_LEN * R_ADDRESS_MAX_LEN) as u32 * MAX_ACCOUNTS; {
+            if let Some(digit) = digits.get_mut(j) {
+                carry += (*digit as u32) << 8;
+                *digit = (carry % 58) as u8;
+                carry /= 58;
+            }
+        });
+        guarded_while!(max R_ADDRESS_MAX_LEN as u32 * MAX_ACCOUNTS; carry > 0; {
+            if let Some(digit) = digits.get_mut(len) {
+                *digit = (carry % 58) as u8;
+            }
+            len += 1;
+            carry /= 58;
+        });
+    });
+
+    // Leading zero bytes are written as leading zero digits
+    let zeros = leading_zeros(&payload);
+    let mut written = 0;
+    guarded_loop!(i in 0, zeros + len; max R_ADDRESS_MAX_LEN as u32 * MAX_ACCOUNTS; {
+        let digit = if i < zeros { 0 } else { digits.get(zeros + len - 1 - i).copied().unwrap_or(0) };
+        if let Some(c) = out.get_mut(i) {
+            *c = ALPHABET[digit as usize % ALPHABET.len()];
+            written += 1;
+        }
+    });
+
+    written
+}
+
+// Account id of an r-address, None unless it is well-formed with a valid
+// checksum
+pub fn decode_r_address(address: &[u8]) -> Option<[u8; ACCOUNT_ID_LEN]> {
+    if address.len() < R_ADDRESS_MIN_LEN || address.len() > R_ADDRESS_MAX_LEN {
+        return None;
+    }
+
+    // The payload as a big-endian number, by repeated multiplication by 58
+    let mut payload = [0u8; PAYLOAD_LEN];
+    let mut zero_digits = 0;
+    guarded_loop!(i in 0, address.len(); max R_ADDRESS_MAX_LEN as u32 * MAX_ACCOUNTS; {
+        let digit = DIGITS.get(*address.get(i)? as usize).copied().filter(|&digit| digit < 58)?;
+        zero_digits += (digit == 0 && zero_digits == i) as usize;
+
+        let mut carry = digit as u32;
+        guarded_loop!(j in 0, PAYLOAD_LEN; max (R_ADDRESS_MAX_LEN * PAYLOAD_LEN) as u32 * MAX_ACCOUNTS; {
+            if let Some(byte) = payload.get_mut(PAYLOAD_LEN - 1 - j) {
+                carry += *byte as u32 * 58;
+                *byte = carry as u8;
+                carry >>= 8;
+            }
+        });
+        if carry != 0 {
+            return None;
+        }
+    });
+
+    // Each leading zero digit stands for one leading zero byte, so padded
+    // addresses don't decode
+    if zero_digits != leading_zeros(&payload) || payload[0] != ACCOUNT_PREFIX {
+        return None;
+    }
+
+    let account = bytes::array(&payload, 1)?;
+    if !bytes::eq(&prefixed(&account), &payload) {
+        return None;
+    }
+
+    Some(account)
+}
+
+// Prefixed account id followed by its checksum
+fn prefixed(account: &[u8; ACCOUNT_ID_LEN]) -> [u8; PAYLOAD_LEN] {
+    let mut payload = [0u8; PAYLOAD_LEN];
+    payload[0] = ACCOUNT_PREFIX;
+    bytes::put(&mut payload, 1, account);
+
+    let checksum = sha256(&sha256(bytes::head(&payload, 1 + ACCOUNT_ID_LEN)));
+    bytes::put(&mut payload, 1 + ACCOUNT_ID_LEN, bytes::head(&checksum, CHECKSUM_LEN));
+    payload
+}
+
+fn leading_zeros(payload: &[u8; PAYLOAD_LEN]) -> usize {
+    let mut zeros = 0;
+    guarded_while!(max PAYLOAD_LEN as u32 * MAX_ACCOUNTS; payload.get(zeros) == Some(&0); {
+        zeros += 1;
+    });
+    zeros
+}
+
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const H: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+// Longest message hashed: it and its padding fit one 64-byte block
+const MAX_HASHED_LEN: usize = 55;
+
+// SHA-256 of a message of at most MAX_HASHED_LEN bytes; checksums only hash
+// the 21-byte payload and a 32-byte digest
+fn sha256(data: &[u8]) -> [u8; 32] {
+    let len = data.len().min(MAX_HASHED_LEN);
+    let mut block = [0u8; 64];
+    bytes::put(&mut block, 0, bytes::head(data, len));
+    bytes::put(&mut block, len, &[0x80]);
+    bytes::put(&mut block, 56, &(len as u64 * 8).to_be_bytes());
+
+    let mut w = [0u32; 64];
+    guarded_loop!(i in 0, 64; max 64 * MAX_DIGESTS; {
+        w[i & 63] = if i < 16 {
+            bytes::u32_at(&block, 4 * i).unwrap_or(0)
+        } else {
+            let (w15, w2) = (w[(i - 15) & 63], w[(i - 2) & 63]);
+            let s0 = w15.rotate_right(7) ^ w15.rotate_right(18) ^ (w15 >> 3);
+            let s1 = w2.rotate_right(17) ^ w2.rotate_right(19) ^ (w2 >> 10);
+            w[(i - 16) & 63].wrapping_add(s0).wrapping_add(w[(i - 7) & 63]).wrapping_add(s1)
+        };
+    });
+
+    let mut v = H;
+    guarded_loop!(i in 0, 64; max 64 * MAX_DIGESTS; {
+        let s1 = v[4].rotate_right(6) ^ v[4].rotate_right(11) ^ v[4].rotate_right(25);
+        let ch = (v[4] & v[5]) ^ (!v[4] & v[6]);
+        let t1 = v[7].wrapping_add(s1).wrapping_add(ch).wrapping_add(K[i & 63]).wrapping_add(w[i & 63]);
+        let s0 = v[0].rotate_right(2) ^ v[0].rotate_right(13) ^ v[0].rotate_right(22);
+        let maj = (v[0] & v[1]) ^ (v[0] & v[2]) ^ (v[1] & v[2]);
+        let t2 = s0.wrapping_add(maj);
+
+        v = [t1.wrapping_add(t2), v[0], v[1], v[2], v[3].wrapping_add(t1), v[4], v[5], v[6]];
+    });
+
+    let mut digest = [0u8; 32];
+    guarded_loop!(i in 0, 8; max 8 * MAX_DIGESTS; {
+        bytes::put(&mut digest, 4 * i, &H[i & 7].wrapping_add(v[i & 7]).to_be_bytes());
+    });
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim;
+
+    // Genesis account of every XRPL-derived network
+    const GENESIS: &[u8] = b"rHb9CJAWyB4rj91VRWn96DkukG4bwdtyTh";
+    const GENESIS_HEX: &[u8] = b"B5F762798A53D543A014CAF8B297CFF8F2F937E8";
+
+    fn encode(account: &[u8; ACCOUNT_ID_LEN]) -> std::vec::Vec<u8> {
+        let mut address = [0u8; R_ADDRESS_MAX_LEN];
+        let len = encode_r_address(account, &mut address);
+        address[..len].to_vec()
+    }
+
+    #[test]
+    fn round_trips_known_addresses() {
+        sim::reset();
+        let genesis = decode_hex_account(GENESIS_HEX).unwrap();
+        assert_eq!(encode(&genesis), GENESIS);
+        assert_eq!(encode(&[0; ACCOUNT_ID_LEN]), b"rrrrrrrrrrrrrrrrrrrrrhoLvTp");
+
+        sim::begin();
+        assert_eq!(decode_r_address(GENESIS), Some(genesis));
+        assert_eq!(decode_r_address(b"rrrrrrrrrrrrrrrrrrrrBZbvji"), Some({
+            let mut one = [0; ACCOUNT_ID_LEN];
+            one[19] = 1;
+            one
+        }));
+        assert_eq!(sim::with(|host| host.guard_violation), None);
+    }
+
+    #[test]
+    fn refuses_malformed_addresses() {
+        sim::reset();
+        let mut typo = GENESIS.to_vec();
+        typo[5] = b'B';
+        let mut padded = std::vec![b'r'];
+        padded.extend_from_slice(b"rrrrrrrrrrrrrrrrrrrrBZbvji");
+
+        for address in [&typo[..], b"rHb9CJAWyB4rj91VRWn96DkukG4bwdtyT0", &padded, &GENESIS[..24], b"xHb9CJAWyB4rj91VRWn96DkukG4bwdtyTh"] {
+            sim::begin();
+            assert_eq!(decode_r_address(address), None);
+        }
+    }
+
+    #[test]
+    fn parses_every_account_form() {
+        sim::reset();
+        let genesis = decode_hex_account(GENESIS_HEX).unwrap();
+        assert_eq!(parse_account(&genesis), Some(genesis));
+        assert_eq!(parse_account(&GENESIS_HEX.to_ascii_lowercase()), Some(genesis));
+        assert_eq!(parse_account(GENESIS), Some(genesis));
+        assert_eq!(parse_account(&genesis[..19]), None);
+        assert_eq!(parse_account(b"B5F762798A53D543A014CAF8B297CFF8F2F937EG"), None);
+    }
+}
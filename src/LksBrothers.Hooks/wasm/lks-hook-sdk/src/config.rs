@@ -0,0 +1,214 @@
+// Hook parameters for the LKS hooks
+// Parameters are set on the hook at SetHook time, so operators can tune the
+// hook without redeploying the wasm. The foundation can also override single
+// parameters at runtime with an admin command; overrides live in hook state
+// and take precedence over the SetHook value. Overrides of wasms from before
+// state values were versioned are still read, until one replaces them.
+//
+// Every hook lists the SetHook parameters it reads with their encoding, so
+// deployment tooling (lks-hook-params) encodes them the way the readers
+// here take them.
+
+use crate::account::ACCOUNT_ID_LEN;
+use crate::api::hook_param;
+use crate::error::HookError;
+use crate::state::{self, Layout};
+use crate::{admin, bytes, denylist, kyc};
+
+// Admin commands: [name length u8][name][value]; an empty value removes the
+// override. Names must fit in a state key.
+pub const MAX_NAME_LEN: usize = state::KEY_LEN - 4;
+pub const MAX_COMMAND_LEN: usize = 256;
+
+// Largest SetHook parameter name and value, and parameters per hook
+pub const MAX_PARAM_NAME_LEN: usize = 32;
+pub const MAX_PARAM_VALUE_LEN: usize = 256;
+pub const MAX_PARAMS: usize = 16;
+
+// How the value of a parameter is encoded
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    // flag(): one byte, nonzero for on
+    Flag,
+    // u64_param(): eight bytes big-endian
+    U64,
+    // bytes_param() of an account id
+    Account,
+    // bytes_param() of this many bytes
+    Bytes(usize),
+    // records_param() of up to `max` records of `len` bytes
+    Records { len: usize, max: usize },
+}
+
+impl Encoding {
+    fn layout(self) -> Layout {
+        match self {
+            Encoding::Flag => Layout::Fixed(1),
+            Encoding::U64 => Layout::Fixed(8),
+            Encoding::Account => Layout::Fixed(ACCOUNT_ID_LEN),
+            Encoding::Bytes(len) => Layout::Fixed(len),
+            Encoding::Records { len, .. } => Layout::Records(len),
+        }
+    }
+
+    // Whether the hook reads `value` as set; it reads anything else as unset
+    pub fn fits(self, value: &[u8]) -> bool {
+        match self {
+            Encoding::Records { len, max } => self.layout().fits(value.len()) && value.len() <= len * max,
+            _ => self.layout().fits(value.len()),
+        }
+    }
+}
+
+// A SetHook parameter a hook reads
+#[derive(Clone, Copy, Debug)]
+pub struct Param {
+    pub name: &'static [u8],
+    pub encoding: Encoding,
+}
+
+// Parameters the SDK reads for every hook
+pub const PARAMS: &[Param] = &[
+    Param { name: admin::PARAM_FOUNDATION_GRACE, encoding: Encoding::U64 },
+    Param { name: kyc::PARAM_KYC_ACCOUNT, encoding: Encoding::Account },
+    Param { name: kyc::PARAM_KYC_NAMESPACE, encoding: Encoding::Bytes(state::NAMESPACE_LEN) },
+    Param { name: denylist::PARAM_DENY_ACCOUNT, encoding: Encoding::Account },
+    Param { name: denylist::PARAM_DENY_NAMESPACE, encoding: Encoding::Bytes(state::NAMESPACE_LEN) },
+    Param { name: denylist::PARAM_FAIL_CLOSED, encoding: Encoding::Flag },
+    Param { name: denylist::PARAM_SECURITY_ADMIN, encoding: Encoding::Account },
+];
+
+pub fn encode_flag(on: bool) -> [u8; 1] {
+    [on as u8]
+}
+
+pub fn encode_u64(value: u64) -> [u8; 8] {
+    value.to_be_bytes()
+}
+
+// Read a single-byte boolean parameter, falling back to the default when unset
+pub fn flag(name: &[u8], default: bool) -> bool {
+    let mut value = [0u8; 1];
+    if read(name, &mut value, Layout::Fixed(1)) != 1 {
+        return default;
+    }
+
+    value[0] != 0
+}
+
+// Read a big-endian u64 parameter, falling back to the default when unset
+pub fn u64_param(name: &[u8], default: u64) -> u64 {
+    let mut value = [0u8; 8];
+    if read(name, &mut value, Layout::Fixed(8)) != 8 {
+        return default;
+    }
+
+    u64::from_be_bytes(value)
+}
+
+// Read a parameter of `out.len()` bytes into `out`, returning its length
+// (zero when unset)
+pub fn bytes_param(name: &[u8], out: &mut [u8]) -> usize {
+    read(name, out, Layout::Fixed(out.len()))
+}
+
+// Read a parameter made of `record_len`-byte records into `out`, returning
+// its length (zero when unset)
+pub fn records_param(name: &[u8], record_len: usize, out: &mut [u8]) -> usize {
+    read(name, out, Layout::Records(record_len))
+}
+
+// Apply an encoded override command, returning the length of the new value
+pub fn apply_command(command: &[u8]) -> Result<usize, HookError> {
+    let name_len = command.first().map_or(0, |&len| len as usize);
+    let name = match command.get(1..1 + name_len) {
+        Some(name) if name_len > 0 && name_len <= MAX_NAME_LEN => name,
+        _ => return Err(HookError::AdminCommandInvalid),
+    };
+
+    let value = bytes::tail(command, 1 + name_len);
+    set_override(name, value)?;
+
+    Ok(value.len())
+}
+
+// Override a parameter; an empty value removes the override
+pub fn set_override(name: &[u8], value: &[u8]) -> Result<(), HookError> {
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err(HookError::AdminCommandInvalid);
+    }
+
+    let mut key = override_key(name);
+    if value.is_empty() {
+        state::erase(&key)?;
+    } else {
+        state::store(&key, value)?;
+    }
+
+    // The override of an older wasm must not resurface once this one is
+    // removed
+    key[3] = state::NS_CONFIG;
+    state::erase(&key)
+}
+
+fn override_key(name: &[u8]) -> [u8; state::KEY_LEN] {
+    state::key(state::NS_OVERRIDE, &[name])
+}
+
+// Runtime overrides win over the SetHook parameter. Overrides of older wasms
+// are only taken in the parameter's `layout`.
+fn read(name: &[u8], out: &mut [u8], layout: Layout) -> usize {
+    if name.len() <= MAX_NAME_LEN {
+        let mut key = override_key(name);
+        let len = state::load(&key, out);
+        if len > 0 {
+            return len;
+        }
+
+        key[3] = state::NS_CONFIG;
+        let len = state::load_v0(&key, out, layout);
+        if len > 0 {
+            return len;
+        }
+    }
+
+    let result = unsafe {
+        hook_param(name.as_ptr(), name.len() as i32, out.as_mut_ptr(), out.len() as i32)
+    };
+
+    if result <= 0 {
+        return 0;
+    }
+
+    (result as usize).min(out.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim;
+
+    #[test]
+    fn reads_overrides_in_their_own_layout_only() {
+        sim::reset();
+        let mut value = [0u8; 8];
+
+        // An override one byte short is as long, with its version byte, as
+        // the parameter's value, and still reads as malformed
+        set_override(b"MAXFEE", &[0; 7]).unwrap();
+        assert_eq!(read(b"MAXFEE", &mut value, Layout::Fixed(8)), 7);
+        assert_eq!(u64_param(b"MAXFEE", 12), 12);
+
+        // Overrides of older wasms are read until one replaces them
+        set_override(b"MAXFEE", &[]).unwrap();
+        let v0 = state::key(state::NS_CONFIG, &[b"MAXFEE"]);
+        sim::with(|host| host.state.insert(v0.to_vec(), 5u64.to_be_bytes().to_vec()));
+        assert_eq!(u64_param(b"MAXFEE", 12), 5);
+        set_override(b"MAXFEE", &7u64.to_be_bytes()).unwrap();
+        assert_eq!(u64_param(b"MAXFEE", 12), 7);
+
+        set_override(b"MAXFEE", &[]).unwrap();
+        assert_eq!(u64_param(b"MAXFEE", 12), 12);
+        assert!(sim::with(|host| host.state.is_empty()));
+    }
+}
@@ -0,0 +1,173 @@
+// Emergency denylist shared by the LKS hooks
+// Security bans a compromised account across every LKS hook within one
+// ledger: the ban is a single entry in the hook state of the security admin
+// account, written by the compliance hook installed there on an Invoke from
+// SECADMIN (the foundation while that isn't set), and every LKS hook reads
+// it from the next transaction on. The zero-fee hook declines to sponsor a
+// banned account and the compliance hook rejects its transactions. Hooks on
+// other accounts read the list from the hook set in DENYACCT and DENYNS;
+// hooks without them read their own state, which is where the list lives
+// on the security account.
+//
+// DENYFAIL decides what happens when the list can't be read: by default
+// the hooks fail open and treat nobody as banned, with it set they fail
+// closed and treat everyone as banned until it reads again. Either way the
+// failure is traced, as is every ban matched.
+//
+// Entry: [ledger the ban was placed in u32 big-endian]
+
+use crate::account::{AccountId, ACCOUNT_ID_LEN};
+use crate::api::ledger_seq;
+use crate::error::HookError;
+use crate::foreign::Sibling;
+use crate::text::{self, Text};
+use crate::{admin, bytes, config, log, state};
+
+// Invoke parameter carrying denylist commands
+pub const PARAM_COMMAND: &[u8] = b"LKSDENY";
+
+// Account allowed to change the list, on the hook keeping it
+pub const PARAM_SECURITY_ADMIN: &[u8] = b"SECADMIN";
+
+// Account and HookNamespace of the hook keeping the list
+pub const PARAM_DENY_ACCOUNT: &[u8] = b"DENYACCT";
+pub const PARAM_DENY_NAMESPACE: &[u8] = b"DENYNS";
+
+// Treat every account as banned while the list can't be read
+pub const PARAM_FAIL_CLOSED: &[u8] = b"DENYFAIL";
+
+// Command operations
+pub const OP_BAN: u8 = 1;
+pub const OP_LIFT: u8 = 2;
+
+// Length of an encoded command: op, account
+pub const COMMAND_LEN: usize = 1 + ACCOUNT_ID_LEN;
+
+// Whether `account` is banned, tracing the match
+pub fn is_banned(account: &[u8; ACCOUNT_ID_LEN]) -> bool {
+    let key = state::account_key(state::NS_DENYLIST, account);
+    let ban = match Sibling::configured(PARAM_DENY_ACCOUNT, PARAM_DENY_NAMESPACE) {
+        Some(list) => list.try_load_entry::<u32>(&key),
+        None => Some(state::load_entry::<u32>(&key)),
+    };
+
+    match ban {
+        Some(Some(ledger)) => {
+            if log::enabled(log::Level::Warn) {
+                let mut msg = Text::<{ text::MESSAGE_LEN }>::new();
+                msg.push(b"LKS account on the emergency denylist, account ").hex(bytes::head(account, 4));
+                log::warn(log::EV_DENYLISTED, msg.as_bytes(), ledger as u64, 1);
+            }
+            true
+        }
+        Some(None) => false,
+        None => {
+            let fail_closed = config::flag(PARAM_FAIL_CLOSED, false);
+            log::error(log::EV_DENYLISTED, b"LKS emergency denylist unreadable", 0, fail_closed as u64);
+            fail_closed
+        }
+    }
+}
+
+// Whether `account` may change the list kept by this hook
+pub fn is_security_admin(account: &[u8; ACCOUNT_ID_LEN]) -> bool {
+    let mut admin = [0u8; ACCOUNT_ID_LEN];
+    if config::bytes_param(PARAM_SECURITY_ADMIN, &mut admin) != ACCOUNT_ID_LEN {
+        return admin::is_foundation(account);
+    }
+
+    AccountId::new(admin).matches(account)
+}
+
+// Apply an encoded command: [op, account(20)]
+// Returns the operation applied
+pub fn apply_command(command: &[u8]) -> Result<u8, HookError> {
+    let (op, account) = match (command.first(), bytes::array(command, 1)) {
+        (Some(&op), Some(account)) if command.len() == COMMAND_LEN => (op, account),
+        _ => return Err(HookError::AdminCommandInvalid),
+    };
+    let key = state::account_key(state::NS_DENYLIST, &account);
+
+    match op {
+        OP_BAN => {
+            let ledger = unsafe { ledger_seq() } as u32;
+            state::store_entry(&key, &ledger)?
+        }
+        OP_LIFT => state::erase(&key)?,
+        _ => return Err(HookError::AdminCommandInvalid),
+    }
+
+    Ok(op)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim;
+
+    const USER: [u8; 20] = [0xAA; 20];
+    const SECURITY_HOOK: [u8; 20] = [0x5E; 20];
+    const NAMESPACE: [u8; 32] = [0x5F; 32];
+
+    fn command(op: u8) -> std::vec::Vec<u8> {
+        let mut command = std::vec![op];
+        command.extend_from_slice(&USER);
+        command
+    }
+
+    #[test]
+    fn bans_and_lifts_accounts_in_own_state() {
+        sim::reset();
+        sim::with(|host| host.ledger_seq = 1234);
+        assert!(!is_banned(&USER));
+
+        assert_eq!(apply_command(&command(OP_BAN)), Ok(OP_BAN));
+        assert!(is_banned(&USER));
+        let key = state::account_key(state::NS_DENYLIST, &USER);
+        assert_eq!(sim::with(|host| host.value(&key).map(<[u8]>::to_vec)), Some(1234u32.to_be_bytes().to_vec()));
+        let matched = log::encode(log::Level::Warn, log::EV_DENYLISTED, 1234, 1);
+        assert!(sim::with(|host| host.traces.iter().any(|(_, data)| data[..] == matched[..])));
+
+        assert_eq!(apply_command(&command(OP_LIFT)), Ok(OP_LIFT));
+        assert!(!is_banned(&USER));
+        assert_eq!(apply_command(&command(3)), Err(HookError::AdminCommandInvalid));
+        assert_eq!(apply_command(&USER), Err(HookError::AdminCommandInvalid));
+    }
+
+    #[test]
+    fn reads_the_security_hook_and_fails_as_configured() {
+        sim::reset();
+        sim::with(|host| {
+            host.hook_params.insert(PARAM_DENY_ACCOUNT.to_vec(), SECURITY_HOOK.to_vec());
+            host.hook_params.insert(PARAM_DENY_NAMESPACE.to_vec(), NAMESPACE.to_vec());
+        });
+        assert!(!is_banned(&USER));
+
+        let key = state::account_key(state::NS_DENYLIST, &USER);
+        let entry = (SECURITY_HOOK.to_vec(), NAMESPACE.to_vec(), key.to_vec());
+        sim::with(|host| host.foreign_state.insert(entry, std::vec![state::VERSION, 0, 0, 4, 0xD2]));
+        assert!(is_banned(&USER));
+
+        // An unreadable list fails open unless configured to fail closed
+        sim::with(|host| {
+            host.foreign_state.clear();
+            host.foreign_fails = true;
+        });
+        assert!(!is_banned(&USER));
+        sim::with(|host| host.hook_params.insert(PARAM_FAIL_CLOSED.to_vec(), std::vec![1]));
+        assert!(is_banned(&USER));
+        let unreadable = log::encode(log::Level::Error, log::EV_DENYLISTED, 0, 1);
+        assert!(sim::with(|host| host.traces.iter().any(|(_, data)| data[..] == unreadable[..])));
+    }
+
+    #[test]
+    fn security_admin_defaults_to_the_foundation() {
+        sim::reset();
+        assert!(is_security_admin(admin::FOUNDATION_ACCOUNT.as_bytes()));
+        assert!(!is_security_admin(&SECURITY_HOOK));
+
+        sim::with(|host| host.hook_params.insert(PARAM_SECURITY_ADMIN.to_vec(), SECURITY_HOOK.to_vec()));
+        assert!(is_security_admin(&SECURITY_HOOK));
+        assert!(!is_security_admin(admin::FOUNDATION_ACCOUNT.as_bytes()));
+    }
+}
@@ -0,0 +1,407 @@
+// Typed encoding of LKS state entries
+// Every value the hooks keep in state is a short run of fields, and every
+// entry is written and read back through a Writer and a Reader here instead
+// of offsets into a byte buffer, so the layout of an entry is the order its
+// fields are encoded in. Types implementing Entry are loaded and stored
+// whole with state::load_entry() and state::store_entry(); an entry whose
+// value doesn't decode to the end, like one of another layout, reads as
+// missing. The version byte in front of every value stays with state.rs.
+//
+// Encodings:
+//   integers   fixed width, big-endian
+//   accounts   the 20-byte account id
+//   flags      up to 8 booleans in one byte, the first in the lowest bit
+//   varints    LEB128: 7 bits per byte, least significant first, the high
+//              bit set on every byte but the last, at most 10 bytes
+// Varints suit entries whose counters stay small; schema.rs describes them
+// by their longest encoding.
+//
+// Reads and writes never panic: a Reader running out of data returns None
+// and a Writer running out of room fails on finish().
+
+use crate::account::ACCOUNT_ID_LEN;
+use crate::bytes;
+use crate::error::HookError;
+
+// Longest encoded entry; values kept in state may be longer, but not the
+// ones read and written as entries
+pub const MAX_ENTRY_LEN: usize = 64;
+
+// Longest varint, a u64 in 7-bit groups
+pub const MAX_VARINT_LEN: usize = 10;
+
+// Most flags packed into one byte
+pub const MAX_FLAGS: usize = 8;
+
+// Guard budget: varints encoded or decoded per hook execution
+const MAX_VARINTS: u32 = 16;
+
+// A value kept in hook state. Implementations are #[inline(always)], so
+// their fields fold into the fixed buffers of state.rs instead of being
+// bounds checked one by one.
+pub trait Entry: Sized {
+    fn encode(&self, w: &mut Writer);
+    fn decode(r: &mut Reader) -> Option<Self>;
+}
+
+// Encode `entry` into `buf`, returning the encoded bytes
+#[inline(always)]
+pub fn encode<'a, E: Entry>(entry: &E, buf: &'a mut [u8]) -> Result<&'a [u8], HookError> {
+    let mut w = Writer::new(buf);
+    entry.encode(&mut w);
+    w.finish()
+}
+
+// Decode an entry taking up all of `data`
+#[inline(always)]
+pub fn decode<E: Entry>(data: &[u8]) -> Option<E> {
+    let mut r = Reader::new(data);
+    let entry = E::decode(&mut r)?;
+    r.is_empty().then_some(entry)
+}
+
+pub struct Writer<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+    overflow: bool,
+}
+
+impl<'a> Writer<'a> {
+    #[inline(always)]
+    pub fn new(buf: &'a mut [u8]) -> Writer<'a> {
+        Writer { buf, len: 0, overflow: false }
+    }
+
+    #[inline(always)]
+    pub fn bytes(&mut self, data: &[u8]) -> &mut Self {
+        let written = bytes::put(self.buf, self.len, data);
+        self.overflow |= written < data.len();
+        self.len += written;
+        self
+    }
+
+    // bytes() for fields of a fixed width, which lower to plain stores
+    #[inline(always)]
+    pub fn array<const N: usize>(&mut self, value: &[u8; N]) -> &mut Self {
+        match self.buf.get_mut(self.len..).and_then(|rest| rest.get_mut(..N)) {
+            Some(field) => {
+                bytes::copy(field, value);
+                self.len += N;
+            }
+            None => self.overflow = true,
+        }
+        self
+    }
+
+    #[inline(always)]
+    pub fn u8(&mut self, value: u8) -> &mut Self {
+        self.array(&[value])
+    }
+
+    #[inline(always)]
+    pub fn u16(&mut self, value: u16) -> &mut Self {
+        self.array(&value.to_be_bytes())
+    }
+
+    #[inline(always)]
+    pub fn u32(&mut self, value: u32) -> &mut Self {
+        self.array(&value.to_be_bytes())
+    }
+
+    #[inline(always)]
+    pub fn u64(&mut self, value: u64) -> &mut Self {
+        self.array(&value.to_be_bytes())
+    }
+
+    #[inline(always)]
+    pub fn account(&mut self, account: &[u8; ACCOUNT_ID_LEN]) -> &mut Self {
+        self.array(account)
+    }
+
+    // Flags past MAX_FLAGS don't fit and fail the write
+    pub fn flags(&mut self, flags: &[bool]) -> &mut Self {
+        let mut byte = 0u8;
+        guarded_loop!(i in 0, flags.len().min(MAX_FLAGS); max MAX_FLAGS as u32 * MAX_VARINTS; {
+            byte |= (flags.get(i).copied().unwrap_or(false) as u8) << i;
+        });
+        self.overflow |= flags.len() > MAX_FLAGS;
+        self.u8(byte)
+    }
+
+    pub fn varint(&mut self, mut value: u64) -> &mut Self {
+        let mut encoded = [0u8; MAX_VARINT_LEN];
+        let mut len = 0;
+        guarded_loop!(i in 0, MAX_VARINT_LEN; max MAX_VARINT_LEN as u32 * MAX_VARINTS; {
+            let group = (value & 0x7F) as u8;
+            value >>= 7;
+            if let Some(byte) = encoded.get_mut(i) {
+                *byte = group | if value != 0 { 0x80 } else { 0 };
+            }
+            len = i + 1;
+            if value == 0 {
+                break;
+            }
+        });
+        self.bytes(bytes::head(&encoded, len))
+    }
+
+    // The bytes written, or StateWriteFailed when they didn't all fit
+    #[inline(always)]
+    pub fn finish(self) -> Result<&'a [u8], HookError> {
+        if self.overflow {
+            return Err(HookError::StateWriteFailed);
+        }
+
+        Ok(bytes::head(self.buf, self.len))
+    }
+}
+
+pub struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    #[inline(always)]
+    pub fn new(data: &'a [u8]) -> Reader<'a> {
+        Reader { data }
+    }
+
+    // Whether every byte has been read
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    // The bytes not read yet, consuming them
+    pub fn rest(&mut self) -> &'a [u8] {
+        core::mem::take(&mut self.data)
+    }
+
+    #[inline(always)]
+    pub fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (value, rest) = self.data.split_first_chunk()?;
+        self.data = rest;
+        Some(*value)
+    }
+
+    #[inline(always)]
+    pub fn u8(&mut self) -> Option<u8> {
+        self.array().map(u8::from_be_bytes)
+    }
+
+    #[inline(always)]
+    pub fn u16(&mut self) -> Option<u16> {
+        self.array().map(u16::from_be_bytes)
+    }
+
+    #[inline(always)]
+    pub fn u32(&mut self) -> Option<u32> {
+        self.array().map(u32::from_be_bytes)
+    }
+
+    #[inline(always)]
+    pub fn u64(&mut self) -> Option<u64> {
+        self.array().map(u64::from_be_bytes)
+    }
+
+    #[inline(always)]
+    pub fn account(&mut self) -> Option<[u8; ACCOUNT_ID_LEN]> {
+        self.array()
+    }
+
+    // `N` flags; a byte with flags set past them doesn't decode
+    pub fn flags<const N: usize>(&mut self) -> Option<[bool; N]> {
+        let byte = self.u8()? as u16;
+        if N > MAX_FLAGS || byte >> N != 0 {
+            return None;
+        }
+
+        let mut flags = [false; N];
+        guarded_loop!(i in 0, N; max MAX_FLAGS as u32 * MAX_VARINTS; {
+            if let Some(flag) = flags.get_mut(i) {
+                *flag = byte & (1 << i) != 0;
+            }
+        });
+        Some(flags)
+    }
+
+    // Varints running past 64 bits or past the data don't decode
+    pub fn varint(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+        guarded_loop!(i in 0, MAX_VARINT_LEN; max MAX_VARINT_LEN as u32 * MAX_VARINTS; {
+            let byte = self.u8()?;
+            let group = (byte & 0x7F) as u64;
+            if i == MAX_VARINT_LEN - 1 && group > 1 {
+                return None;
+            }
+            value |= group << (7 * i);
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        });
+
+        None
+    }
+}
+
+// Markers: present or not, with the one byte they hold nonzero
+impl Entry for bool {
+    #[inline(always)]
+    fn encode(&self, w: &mut Writer) {
+        w.u8(*self as u8);
+    }
+
+    #[inline(always)]
+    fn decode(r: &mut Reader) -> Option<bool> {
+        r.u8().map(|byte| byte != 0)
+    }
+}
+
+impl Entry for u8 {
+    #[inline(always)]
+    fn encode(&self, w: &mut Writer) {
+        w.u8(*self);
+    }
+
+    #[inline(always)]
+    fn decode(r: &mut Reader) -> Option<u8> {
+        r.u8()
+    }
+}
+
+impl Entry for u16 {
+    #[inline(always)]
+    fn encode(&self, w: &mut Writer) {
+        w.u16(*self);
+    }
+
+    #[inline(always)]
+    fn decode(r: &mut Reader) -> Option<u16> {
+        r.u16()
+    }
+}
+
+impl Entry for u32 {
+    #[inline(always)]
+    fn encode(&self, w: &mut Writer) {
+        w.u32(*self);
+    }
+
+    #[inline(always)]
+    fn decode(r: &mut Reader) -> Option<u32> {
+        r.u32()
+    }
+}
+
+impl Entry for u64 {
+    #[inline(always)]
+    fn encode(&self, w: &mut Writer) {
+        w.u64(*self);
+    }
+
+    #[inline(always)]
+    fn decode(r: &mut Reader) -> Option<u64> {
+        r.u64()
+    }
+}
+
+// Accounts, hashes and state keys
+impl<const N: usize> Entry for [u8; N] {
+    #[inline(always)]
+    fn encode(&self, w: &mut Writer) {
+        w.array(self);
+    }
+
+    fn decode(r: &mut Reader) -> Option<[u8; N]> {
+        r.array()
+    }
+}
+
+// Entries of a few fields, like epoch counters, without a type of their own
+macro_rules! tuple_entry {
+    ($($field:ident $i:tt),+) => {
+        impl<$($field: Entry),+> Entry for ($($field,)+) {
+            #[inline(always)]
+            fn encode(&self, w: &mut Writer) {
+                $(self.$i.encode(w);)+
+            }
+
+            #[inline(always)]
+            fn decode(r: &mut Reader) -> Option<Self> {
+                Some(($($field::decode(r)?,)+))
+            }
+        }
+    };
+}
+
+tuple_entry!(A 0, B 1);
+tuple_entry!(A 0, B 1, C 2);
+tuple_entry!(A 0, B 1, C 2, D 3);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim;
+
+    fn round_trip<E: Entry + PartialEq + core::fmt::Debug>(entry: E, encoded: &[u8]) {
+        let mut buf = [0u8; 64];
+        assert_eq!(encode(&entry, &mut buf), Ok(encoded));
+        assert_eq!(decode::<E>(encoded), Some(entry));
+    }
+
+    #[test]
+    fn round_trips_fixed_width_fields() {
+        sim::reset();
+        round_trip(true, &[1]);
+        round_trip(0xABu8, &[0xAB]);
+        round_trip(0x0102u16, &[1, 2]);
+        round_trip(0x0102_0304u32, &[1, 2, 3, 4]);
+        round_trip(u64::MAX, &[0xFF; 8]);
+        round_trip([0xAA; ACCOUNT_ID_LEN], &[0xAA; ACCOUNT_ID_LEN]);
+        round_trip((3u32, 42u64), &[0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 42]);
+        round_trip((false, 7u16, [9u8; 2], 1u8), &[0, 0, 7, 9, 9, 1]);
+
+        // Entries of another length don't decode
+        assert_eq!(decode::<(u32, u64)>(&[0; 11]), None);
+        assert_eq!(decode::<(u32, u64)>(&[0; 13]), None);
+        assert_eq!(decode::<bool>(&[]), None);
+    }
+
+    #[test]
+    fn round_trips_flags_and_varints() {
+        sim::reset();
+        let mut buf = [0u8; 32];
+        let mut w = Writer::new(&mut buf);
+        w.flags(&[true, false, true]).varint(0).varint(127).varint(128).varint(300).varint(u64::MAX);
+        let encoded = w.finish().unwrap();
+        assert_eq!(encoded[..8], [0b101, 0, 0x7F, 0x80, 0x01, 0xAC, 0x02, 0xFF]);
+        assert_eq!(encoded.len(), 1 + 1 + 1 + 2 + 2 + MAX_VARINT_LEN);
+
+        let mut r = Reader::new(encoded);
+        assert_eq!(r.flags::<3>(), Some([true, false, true]));
+        for value in [0, 127, 128, 300, u64::MAX] {
+            assert_eq!(r.varint(), Some(value));
+        }
+        assert!(r.is_empty());
+        assert_eq!(sim::with(|host| host.guard_violation), None);
+
+        // Unknown flags, varints past 64 bits and cut-off varints
+        assert_eq!(Reader::new(&[0b1001]).flags::<3>(), None);
+        let mut overlong = [0xFF; MAX_VARINT_LEN];
+        overlong[MAX_VARINT_LEN - 1] = 0x02;
+        assert_eq!(Reader::new(&overlong).varint(), None);
+        assert_eq!(Reader::new(&[0x80, 0x80]).varint(), None);
+    }
+
+    #[test]
+    fn fails_writes_that_overflow() {
+        sim::reset();
+        let mut buf = [0u8; 6];
+        assert_eq!(encode(&(1u32, 2u32), &mut buf), Err(HookError::StateWriteFailed));
+        assert_eq!(encode(&(1u32, 2u16), &mut buf), Ok(&[0, 0, 0, 1, 0, 2][..]));
+
+        let mut w = Writer::new(&mut buf);
+        w.flags(&[true; MAX_FLAGS + 1]);
+        assert_eq!(w.finish(), Err(HookError::StateWriteFailed));
+    }
+}
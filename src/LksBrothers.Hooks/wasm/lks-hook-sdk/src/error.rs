@@ -0,0 +1,144 @@
+// Structured error codes for the LKS hooks
+// Every rejection or declined sponsorship carries a stable numeric code and
+// message so node operators can grep hook traces and alert on them
+
+use crate::api::{accept_with, reject_with};
+use crate::log;
+
+// Codes are grouped by range:
+//   1xx - the transaction could not be read or parsed (transaction rejected)
+//   2xx - sponsorship declined by policy (transaction accepted with normal fee)
+//   3xx - the transaction is refused by a gating hook (transaction rejected)
+// Codes are part of the operator-facing interface and must never be renumbered
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(i64)]
+pub enum HookError {
+    FeeReadFailed = 101,
+    FeeWriteFailed = 102,
+    AmountReadFailed = 103,
+    AmountParseFailed = 104,
+    AccountReadFailed = 105,
+    AdminCommandInvalid = 106,
+    Unauthorized = 107,
+    StateWriteFailed = 108,
+    FieldReadFailed = 109,
+    MemoParseFailed = 110,
+    TxIdReadFailed = 111,
+    EmitFailed = 112,
+    IssuerMismatch = 113,
+    AmountValueInvalid = 114,
+
+    BudgetExceeded = 201,
+    RateLimited = 202,
+    WrongIssuer = 203,
+    WrongCurrency = 204,
+    AccountBlocked = 205,
+    FeeCapExceeded = 206,
+    TierNotSponsored = 207,
+    DustAmount = 208,
+    PairLimited = 209,
+    OptedOut = 210,
+    BreakerOpen = 211,
+    NotVerified = 212,
+    SponsorshipPaused = 213,
+    AbuseSuspected = 214,
+    DestinationTagMissing = 215,
+    BurstLimited = 216,
+    TakerOrder = 217,
+    MakerQuotaExceeded = 218,
+    Denylisted = 219,
+    CoPayCoversFee = 220,
+    TxTypeNotSponsored = 221,
+    FeeNotWritable = 222,
+
+    KycRequired = 301,
+    DestinationTagRequired = 302,
+    AccountBanned = 303,
+}
+
+impl HookError {
+    pub fn code(self) -> i64 {
+        self as i64
+    }
+
+    // Rejections fail the transaction, declines only withhold sponsorship
+    pub fn is_rejection(self) -> bool {
+        !(200..300).contains(&self.code())
+    }
+
+    // Value returned from hook(): negative for rejections, positive for declines
+    pub fn return_value(self) -> i64 {
+        if self.is_rejection() {
+            -self.code()
+        } else {
+            self.code()
+        }
+    }
+
+    // Stable message passed to accept/reject, prefixed with the code for grepping
+    pub fn message(self) -> &'static [u8] {
+        match self {
+            HookError::FeeReadFailed => b"LKS-E101 fee read failed",
+            HookError::FeeWriteFailed => b"LKS-E102 fee write failed",
+            HookError::AmountReadFailed => b"LKS-E103 amount read failed",
+            HookError::AmountParseFailed => b"LKS-E104 amount parse failed",
+            HookError::AccountReadFailed => b"LKS-E105 account read failed",
+            HookError::AdminCommandInvalid => b"LKS-E106 invalid admin command",
+            HookError::Unauthorized => b"LKS-E107 admin command not signed by foundation",
+            HookError::StateWriteFailed => b"LKS-E108 hook state write failed",
+            HookError::FieldReadFailed => b"LKS-E109 transaction field read failed",
+            HookError::MemoParseFailed => b"LKS-E110 memo parse failed",
+            HookError::TxIdReadFailed => b"LKS-E111 transaction hash read failed",
+            HookError::EmitFailed => b"LKS-E112 settlement emit failed",
+            HookError::IssuerMismatch => b"LKS-E113 LKS currency code from another issuer",
+            HookError::AmountValueInvalid => b"LKS-E114 LKS amount negative or malformed",
+            HookError::BudgetExceeded => b"LKS-E201 sponsorship budget exceeded",
+            HookError::RateLimited => b"LKS-E202 account rate limited",
+            HookError::WrongIssuer => b"LKS-E203 wrong LKS issuer",
+            HookError::WrongCurrency => b"LKS-E204 not an LKS currency",
+            HookError::AccountBlocked => b"LKS-E205 account blocked by registry",
+            HookError::FeeCapExceeded => b"LKS-E206 fee above sponsorship cap",
+            HookError::TierNotSponsored => b"LKS-E207 amount above sponsored tiers",
+            HookError::DustAmount => b"LKS-E208 amount below dust threshold",
+            HookError::PairLimited => b"LKS-E209 account pair rate limited",
+            HookError::OptedOut => b"LKS-E210 sponsorship declined by memo directive",
+            HookError::BreakerOpen => b"LKS-E211 sponsorship paused, foundation reserve low",
+            HookError::NotVerified => b"LKS-E212 sponsorship limited to KYC-verified accounts",
+            HookError::SponsorshipPaused => b"LKS-E213 sponsorship paused by foundation",
+            HookError::AbuseSuspected => b"LKS-E214 sponsorship withheld, activity flagged for review",
+            HookError::DestinationTagMissing => b"LKS-E215 destination requires a tag or invoice id for sponsorship",
+            HookError::BurstLimited => b"LKS-E216 account held back after a burst of sponsored transactions",
+            HookError::TakerOrder => b"LKS-E217 offer takes liquidity, only resting offers are sponsored",
+            HookError::MakerQuotaExceeded => b"LKS-E218 maker offer quota used up for this epoch",
+            HookError::Denylisted => b"LKS-E219 sponsorship withheld, account on the emergency denylist",
+            HookError::CoPayCoversFee => b"LKS-E220 fee within the user co-pay, nothing left to sponsor",
+            HookError::TxTypeNotSponsored => b"LKS-E221 transaction type not sponsored by this hook",
+            HookError::FeeNotWritable => b"LKS-E222 fee can't be lowered on this runtime",
+            HookError::KycRequired => b"LKS-E301 account not KYC verified",
+            HookError::DestinationTagRequired => b"LKS-E302 destination requires a tag or invoice id",
+            HookError::AccountBanned => b"LKS-E303 account on the emergency denylist",
+        }
+    }
+}
+
+// Trace the error code and reject the transaction, or accept it with its
+// normal fee when only sponsorship was declined. Returns the value for the
+// hook to return.
+pub fn finish_with_error(err: HookError) -> i64 {
+    let msg = err.message();
+    if err.is_rejection() {
+        log::error(log::EV_FAILED, msg, err.code() as u64, 0);
+    } else {
+        log::info(log::EV_FAILED, msg, err.code() as u64, 0);
+    }
+
+    unsafe {
+        if err.is_rejection() {
+            reject_with(msg.as_ptr(), msg.len() as i32, err.return_value());
+        } else {
+            accept_with(msg.as_ptr(), msg.len() as i32, err.return_value());
+        }
+    }
+
+    err.return_value()
+}
@@ -0,0 +1,303 @@
+// Canonical transaction field accessors for the LKS hooks
+// Fields are addressed by their XRPL field code (type code << 16 | field code)
+// and decoded from their serialized big-endian form. Optional fields that are
+// absent read as None; fields that are present but malformed are errors.
+
+use crate::amount::{self, Amount};
+use crate::bytes;
+use crate::api::{meta_slot, otxn_slot, slot_set};
+use crate::error::HookError;
+
+pub type FieldId = i32;
+
+const fn field(type_code: i32, field_code: i32) -> FieldId {
+    (type_code << 16) | field_code
+}
+
+// Serialized type codes
+pub const ST_UINT16: i32 = 1;
+pub const ST_UINT32: i32 = 2;
+pub const ST_UINT64: i32 = 3;
+pub const ST_HASH128: i32 = 4;
+pub const ST_HASH256: i32 = 5;
+pub const ST_AMOUNT: i32 = 6;
+pub const ST_BLOB: i32 = 7;
+pub const ST_ACCOUNT: i32 = 8;
+pub const ST_OBJECT: i32 = 14;
+pub const ST_ARRAY: i32 = 15;
+pub const ST_UINT8: i32 = 16;
+pub const ST_HASH160: i32 = 17;
+pub const ST_PATHSET: i32 = 18;
+pub const ST_VECTOR256: i32 = 19;
+pub const ST_UINT192: i32 = 21;
+pub const ST_UINT384: i32 = 22;
+pub const ST_UINT512: i32 = 23;
+
+// The whole serialized originating transaction rather than one of its fields
+pub const SF_TRANSACTION: FieldId = 0;
+
+pub const SF_TRANSACTION_RESULT: FieldId = field(ST_UINT8, 3);
+pub const SF_FLAGS: FieldId = field(ST_UINT32, 2);
+pub const SF_SEQUENCE: FieldId = field(ST_UINT32, 4);
+pub const SF_DESTINATION_TAG: FieldId = field(ST_UINT32, 14);
+pub const SF_OFFER_SEQUENCE: FieldId = field(ST_UINT32, 25);
+pub const SF_TICKET_SEQUENCE: FieldId = field(ST_UINT32, 41);
+pub const SF_INVOICE_ID: FieldId = field(ST_HASH256, 17);
+pub const SF_CHANNEL: FieldId = field(ST_HASH256, 22);
+pub const SF_CHECK_ID: FieldId = field(ST_HASH256, 24);
+pub const SF_NFTOKEN_BUY_OFFER: FieldId = field(ST_HASH256, 28);
+pub const SF_NFTOKEN_SELL_OFFER: FieldId = field(ST_HASH256, 29);
+pub const SF_AMOUNT: FieldId = field(ST_AMOUNT, 1);
+pub const SF_BALANCE: FieldId = field(ST_AMOUNT, 2);
+pub const SF_LIMIT_AMOUNT: FieldId = field(ST_AMOUNT, 3);
+pub const SF_TAKER_PAYS: FieldId = field(ST_AMOUNT, 4);
+pub const SF_TAKER_GETS: FieldId = field(ST_AMOUNT, 5);
+pub const SF_FEE: FieldId = field(ST_AMOUNT, 8);
+pub const SF_SEND_MAX: FieldId = field(ST_AMOUNT, 9);
+pub const SF_DELIVER_MIN: FieldId = field(ST_AMOUNT, 10);
+pub const SF_NFTOKEN_BROKER_FEE: FieldId = field(ST_AMOUNT, 19);
+pub const SF_ACCOUNT: FieldId = field(ST_ACCOUNT, 1);
+pub const SF_OWNER: FieldId = field(ST_ACCOUNT, 2);
+pub const SF_DESTINATION: FieldId = field(ST_ACCOUNT, 3);
+pub const SF_SIGNERS: FieldId = field(ST_ARRAY, 3);
+pub const SF_MEMOS: FieldId = field(ST_ARRAY, 9);
+pub const SF_PATHS: FieldId = field(ST_PATHSET, 1);
+pub const SF_NFTOKEN_OFFERS: FieldId = field(ST_VECTOR256, 4);
+
+// Field codes inside objects and arrays
+pub const OBJECT_END: (i32, i32) = (ST_OBJECT, 1);
+pub const ARRAY_END: (i32, i32) = (ST_ARRAY, 1);
+
+// Host return code for fields absent from the transaction
+const DOESNT_EXIST: i32 = -5;
+
+// Largest serialized account field: one length byte and the 20-byte AccountID
+const ACCOUNT_FIELD_LEN: usize = 21;
+
+// Multi-signed transactions carry at most 32 Signer objects of 3 fields each
+pub const MAX_SIGNERS: u32 = 32;
+const MAX_SIGNERS_LEN: usize = 4352;
+const SIGNER_OBJECT: i32 = 16;
+
+// Read the serialized bytes of a field of the originating transaction
+pub fn read_raw(field: FieldId, out: &mut [u8]) -> Result<Option<&[u8]>, HookError> {
+    let result = unsafe {
+        otxn_slot(field, out.as_mut_ptr(), out.len() as i32)
+    };
+
+    if result == 0 || result == DOESNT_EXIST {
+        return Ok(None);
+    }
+
+    if result < 0 || result as usize > out.len() {
+        return Err(HookError::FieldReadFailed);
+    }
+
+    out.get(..result as usize).map(Some).ok_or(HookError::FieldReadFailed)
+}
+
+// The transaction fee in drops; every transaction has one
+pub fn read_fee() -> Result<u64, HookError> {
+    let mut buffer = [0u8; amount::NATIVE_LEN];
+    let data = match read_raw(SF_FEE, &mut buffer) {
+        Ok(Some(data)) => data,
+        _ => return Err(HookError::FeeReadFailed),
+    };
+
+    match amount::parse(data) {
+        Ok(Amount::Native(drops)) => Ok(drops),
+        _ => Err(HookError::FeeReadFailed),
+    }
+}
+
+pub const TES_SUCCESS: u8 = 0;
+
+// Result code of the originating transaction from its metadata, available
+// in cbak once an emitted transaction was applied
+pub fn read_transaction_result() -> Result<Option<u8>, HookError> {
+    let mut result = [0u8; 1];
+    match unsafe { meta_slot(SF_TRANSACTION_RESULT, result.as_mut_ptr(), result.len() as i32) } {
+        1 => Ok(Some(result[0])),
+        0 | DOESNT_EXIST => Ok(None),
+        _ => Err(HookError::FieldReadFailed),
+    }
+}
+
+// Whether hooks can rewrite the fee the user pays. Xahau hooks can't modify
+// the originating transaction at all, so there write_fee() always fails.
+pub const FEE_WRITABLE: bool = !cfg!(feature = "xahau");
+
+// Replace the fee the user pays, in drops
+pub fn write_fee(drops: u64) -> Result<(), HookError> {
+    let encoded = amount::encode_native(drops);
+    let written = unsafe {
+        slot_set(SF_FEE, encoded.as_ptr(), encoded.len() as i32)
+    };
+
+    if written != encoded.len() as i32 {
+        return Err(HookError::FeeWriteFailed);
+    }
+
+    Ok(())
+}
+
+// The source account; every transaction has one
+pub fn read_account() -> Result<[u8; 20], HookError> {
+    match read_account_field(SF_ACCOUNT) {
+        Ok(Some(account)) => Ok(account),
+        _ => Err(HookError::AccountReadFailed),
+    }
+}
+
+// The sequence that identifies objects the transaction creates (escrows,
+// offers). Ticketed transactions have a zero Sequence and use their
+// TicketSequence instead.
+pub fn read_sequence() -> Result<Option<u32>, HookError> {
+    match read_u32(SF_SEQUENCE)? {
+        Some(0) | None => read_u32(SF_TICKET_SEQUENCE),
+        sequence => Ok(sequence),
+    }
+}
+
+// Number of signers of a multi-signed transaction, zero when single-signed
+pub fn signer_count() -> Result<u32, HookError> {
+    let mut buffer = [0u8; MAX_SIGNERS_LEN];
+    match read_raw(SF_SIGNERS, &mut buffer)? {
+        Some(signers) => count_signers(signers),
+        None => Ok(0),
+    }
+}
+
+// Walk the serialized contents of a Signers array, counting Signer objects.
+// Signer fields (Account, SigningPubKey, TxnSignature) are all
+// variable-length encoded.
+pub fn count_signers(signers: &[u8]) -> Result<u32, HookError> {
+    let mut count = 0;
+    let mut in_signer = false;
+    let mut pos = 0;
+
+    guarded_while!(max MAX_SIGNERS * 5; pos < signers.len(); {
+        let (type_code, field_code, header_len) =
+            decode_field_header(bytes::tail(signers, pos)).ok_or(HookError::FieldReadFailed)?;
+        pos += header_len;
+
+        match ((type_code, field_code), in_signer) {
+            (ARRAY_END, false) => break,
+            (OBJECT_END, true) => in_signer = false,
+            ((ST_OBJECT, SIGNER_OBJECT), false) => {
+                in_signer = true;
+                count += 1;
+            }
+            ((ST_BLOB, _), true) | ((ST_ACCOUNT, _), true) => {
+                let (length, vl_len) =
+                    decode_vl_length(bytes::tail(signers, pos)).ok_or(HookError::FieldReadFailed)?;
+                pos += vl_len + length;
+            }
+            _ => return Err(HookError::FieldReadFailed),
+        }
+    });
+
+    if in_signer || pos > signers.len() {
+        return Err(HookError::FieldReadFailed);
+    }
+
+    Ok(count)
+}
+
+pub fn read_destination() -> Result<Option<[u8; 20]>, HookError> {
+    read_account_field(SF_DESTINATION)
+}
+
+// Account fields are variable-length encoded; hosts may hand them over with
+// or without the length prefix
+pub fn read_account_field(field: FieldId) -> Result<Option<[u8; 20]>, HookError> {
+    let mut buffer = [0u8; ACCOUNT_FIELD_LEN];
+    let data = match read_raw(field, &mut buffer)? {
+        Some(data) => data,
+        None => return Ok(None),
+    };
+
+    let account_id = if data.len() == 20 { data } else { strip_vl(data)? };
+    match bytes::array(account_id, 0) {
+        Some(account) if account_id.len() == 20 => Ok(Some(account)),
+        _ => Err(HookError::AccountReadFailed),
+    }
+}
+
+pub fn read_amount(field: FieldId) -> Result<Option<Amount>, HookError> {
+    let mut buffer = [0u8; amount::ISSUED_LEN];
+    let data = match read_raw(field, &mut buffer) {
+        Ok(Some(data)) => data,
+        Ok(None) => return Ok(None),
+        Err(_) => return Err(HookError::AmountReadFailed),
+    };
+
+    amount::parse(data).map(Some)
+}
+
+pub fn read_u32(field: FieldId) -> Result<Option<u32>, HookError> {
+    let mut buffer = [0u8; 4];
+    match read_raw(field, &mut buffer)? {
+        Some(data) if data.len() == 4 => Ok(Some(u32::from_be_bytes(buffer))),
+        Some(_) => Err(HookError::FieldReadFailed),
+        None => Ok(None),
+    }
+}
+
+pub fn read_hash256(field: FieldId) -> Result<Option<[u8; 32]>, HookError> {
+    let mut buffer = [0u8; 32];
+    match read_raw(field, &mut buffer)? {
+        Some(data) if data.len() == 32 => Ok(Some(buffer)),
+        Some(_) => Err(HookError::FieldReadFailed),
+        None => Ok(None),
+    }
+}
+
+// Strip the variable-length prefix from a serialized field and check that
+// the encoded length matches the data that follows
+pub fn strip_vl(data: &[u8]) -> Result<&[u8], HookError> {
+    let (length, header_len) = decode_vl_length(data).ok_or(HookError::FieldReadFailed)?;
+
+    if data.len() != header_len + length {
+        return Err(HookError::FieldReadFailed);
+    }
+
+    Ok(bytes::tail(data, header_len))
+}
+
+// Decode a variable-length prefix, returning the length and the prefix size
+pub fn decode_vl_length(data: &[u8]) -> Option<(usize, usize)> {
+    let b1 = *data.first()? as usize;
+
+    if b1 <= 192 {
+        return Some((b1, 1));
+    }
+
+    if b1 <= 240 {
+        let b2 = *data.get(1)? as usize;
+        return Some((193 + (b1 - 193) * 256 + b2, 2));
+    }
+
+    if b1 <= 254 {
+        let b2 = *data.get(1)? as usize;
+        let b3 = *data.get(2)? as usize;
+        return Some((12481 + (b1 - 241) * 65536 + b2 * 256 + b3, 3));
+    }
+
+    None
+}
+
+// Decode a serialized field header, returning (type code, field code,
+// header length). Codes of 16 and above spill into extra header bytes.
+pub fn decode_field_header(data: &[u8]) -> Option<(i32, i32, usize)> {
+    let b1 = *data.first()? as i32;
+    let type_code = b1 >> 4;
+    let field_code = b1 & 0x0F;
+
+    match (type_code, field_code) {
+        (0, 0) => Some((*data.get(1)? as i32, *data.get(2)? as i32, 3)),
+        (0, field_code) => Some((*data.get(1)? as i32, field_code, 2)),
+        (type_code, 0) => Some((type_code, *data.get(1)? as i32, 2)),
+        (type_code, field_code) => Some((type_code, field_code, 1)),
+    }
+}
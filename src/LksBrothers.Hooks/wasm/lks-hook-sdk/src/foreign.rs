@@ -0,0 +1,127 @@
+// Cross-hook state for the LKS hooks
+// The LKS hooks run on different accounts (zero-fee, staking, compliance)
+// and share facts through each other's state: a hook reads the entries of a
+// sibling with state_foreign, given the account the sibling is installed on
+// and the HookNamespace it keeps its state under. Each sibling is configured
+// with a pair of hook parameters naming the two; a sibling missing either is
+// not consulted.
+//
+// Siblings key their entries with state::key like every LKS hook, so their
+// facts about an account sit under state::account_key of the namespace
+// byte that holds them:
+//   KYC marker       NS_KYC     [expires ledger u32]   compliance hook
+//   staked balance   NS_STAKE   [micro-LKS u64]        staking hook
+//   denylist ban     NS_DENYLIST [ban ledger u32]      compliance hook on the security account
+// The staking hook isn't built on this SDK and writes its balances without
+// a version byte, so they are read raw.
+
+use crate::account::ACCOUNT_ID_LEN;
+use crate::entry::{self, Entry};
+use crate::{bytes, config, state};
+
+pub struct Sibling {
+    account: [u8; ACCOUNT_ID_LEN],
+    namespace: [u8; state::NAMESPACE_LEN],
+}
+
+impl Sibling {
+    // The sibling whose account and namespace are set in the hook
+    // parameters `account_param` and `namespace_param`
+    pub fn configured(account_param: &[u8], namespace_param: &[u8]) -> Option<Sibling> {
+        let mut account = [0u8; ACCOUNT_ID_LEN];
+        if config::bytes_param(account_param, &mut account) != ACCOUNT_ID_LEN {
+            return None;
+        }
+
+        let mut namespace = [0u8; state::NAMESPACE_LEN];
+        if config::bytes_param(namespace_param, &mut namespace) != state::NAMESPACE_LEN {
+            return None;
+        }
+
+        Some(Sibling { account, namespace })
+    }
+
+    // Read an entry the sibling wrote with the SDK, without its version
+    // byte, returning its length (zero when missing)
+    pub fn load(&self, key: &[u8; state::KEY_LEN], out: &mut [u8]) -> usize {
+        state::load_sibling(key, &self.namespace, &self.account, out)
+    }
+
+    // load() telling a failed read from a missing entry: None when the
+    // sibling's state couldn't be read
+    pub fn try_load(&self, key: &[u8; state::KEY_LEN], out: &mut [u8]) -> Option<usize> {
+        state::try_load_sibling(key, &self.namespace, &self.account, out)
+    }
+
+    // Read an entry the sibling wrote with the SDK as `E`: Some(None) when
+    // it is missing or doesn't decode, None when the sibling's state
+    // couldn't be read
+    pub fn try_load_entry<E: Entry>(&self, key: &[u8; state::KEY_LEN]) -> Option<Option<E>> {
+        let mut value = [0u8; state::MAX_VALUE_LEN];
+        let len = self.try_load(key, &mut value)?;
+        Some(entry::decode(bytes::head(&value, len)))
+    }
+
+    pub fn load_entry<E: Entry>(&self, key: &[u8; state::KEY_LEN]) -> Option<E> {
+        self.try_load_entry(key).flatten()
+    }
+
+    // Read an entry as it is stored, for siblings outside the SDK
+    pub fn load_raw(&self, key: &[u8; state::KEY_LEN], out: &mut [u8]) -> usize {
+        state::load_foreign(key, &self.namespace, &self.account, out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim;
+
+    const COMPLIANCE_HOOK: [u8; 20] = [0xC0; 20];
+    const NAMESPACE: [u8; 32] = [0xC1; 32];
+
+    fn sibling() -> Sibling {
+        sim::reset();
+        sim::with(|host| {
+            host.hook_params.insert(b"SIBACCT".to_vec(), COMPLIANCE_HOOK.to_vec());
+            host.hook_params.insert(b"SIBNS".to_vec(), NAMESPACE.to_vec());
+        });
+        Sibling::configured(b"SIBACCT", b"SIBNS").unwrap()
+    }
+
+    fn set_entry(key: &[u8; state::KEY_LEN], entry: &[u8]) {
+        let entry_key = (COMPLIANCE_HOOK.to_vec(), NAMESPACE.to_vec(), key.to_vec());
+        sim::with(|host| host.foreign_state.insert(entry_key, entry.to_vec()));
+    }
+
+    #[test]
+    fn reads_sibling_entries_by_version() {
+        let sibling = sibling();
+        let key = state::account_key(state::NS_KYC, &[0xAA; 20]);
+        let mut marker = [0u8; 4];
+        assert_eq!(sibling.load(&key, &mut marker), 0);
+
+        // Unversioned and current entries read the same, raw reads keep the
+        // version byte
+        set_entry(&key, &[0, 0, 3, 0xE9]);
+        assert_eq!(sibling.load(&key, &mut marker), 4);
+        assert_eq!(marker, [0, 0, 3, 0xE9]);
+        set_entry(&key, &[state::VERSION, 0, 0, 3, 0xE8]);
+        assert_eq!(sibling.load(&key, &mut marker), 4);
+        assert_eq!(marker, [0, 0, 3, 0xE8]);
+        let mut raw = [0u8; 5];
+        assert_eq!(sibling.load_raw(&key, &mut raw), 5);
+
+        // Entries of a newer layout read as missing
+        set_entry(&key, &[state::VERSION + 1, 0, 0, 3, 0xE8]);
+        assert_eq!(sibling.load(&key, &mut marker), 0);
+        assert!(sim::with(|host| host.state.is_empty()));
+    }
+
+    #[test]
+    fn siblings_need_account_and_namespace() {
+        sim::reset();
+        sim::with(|host| host.hook_params.insert(b"SIBACCT".to_vec(), COMPLIANCE_HOOK.to_vec()));
+        assert!(Sibling::configured(b"SIBACCT", b"SIBNS").is_none());
+    }
+}
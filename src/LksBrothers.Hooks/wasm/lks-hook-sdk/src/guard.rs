@@ -0,0 +1,72 @@
+// Guard calls for the Hooks execution model
+// The Hooks runtime only accepts wasm whose loops are provably bounded: every
+// loop must call _g(id, maxiter) on each iteration, where id is a constant
+// unique to the loop and maxiter the most times the guard may be hit during
+// the whole hook execution. Loops in helpers called several times per
+// execution budget for every call.
+// Every loop in the hook goes through guarded_loop! so the wasm passes
+// hook-cleaner validation at SetHook time.
+//
+// Loops hidden in core (iterators, slice comparison, memcpy, u128 division)
+// carry no guard, so hook code uses explicit guarded index loops, compares
+// bytes with bytes::eq and bytes::cmp, and must be built with the
+// bulk-memory target feature so copies lower to memory.copy. lks-hook-bench
+// fails on any loop left without a guard in the built wasm.
+
+use crate::api::_g;
+
+// Guard id for a loop, derived from its source location so ids stay unique
+// across modules
+pub const fn guard_id(file: &str, line: u32) -> u32 {
+    // FNV-1a over the file name
+    let bytes = file.as_bytes();
+    let mut hash: u32 = 0x811C_9DC5;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+        i += 1;
+    }
+
+    hash ^ line.wrapping_mul(0x9E37_79B1)
+}
+
+#[inline(always)]
+pub fn guard(id: u32, max_iterations: u32) {
+    unsafe {
+        _g(id, max_iterations);
+    }
+}
+
+// Run `$body` for `$i` in `$start..$end`, allowing at most `$max` iterations
+// per hook execution.
+// The guard is hit once more than the loop runs, when the condition fails.
+// The body must not `continue`, as that would skip the increment.
+#[macro_export]
+macro_rules! guarded_loop {
+    ($i:ident in $start:expr, $end:expr; max $max:expr; $body:block) => {{
+        const GUARD_ID: u32 = $crate::guard::guard_id(file!(), line!());
+        let end = $end;
+        let mut $i = $start;
+        while {
+            $crate::guard::guard(GUARD_ID, ($max) + 1);
+            $i < end
+        } {
+            $body
+            $i += 1;
+        }
+    }};
+}
+
+// Run `$body` while `$cond` holds, allowing at most `$max` iterations per
+// hook execution
+#[macro_export]
+macro_rules! guarded_while {
+    (max $max:expr; $cond:expr; $body:block) => {{
+        const GUARD_ID: u32 = $crate::guard::guard_id(file!(), line!());
+        while {
+            $crate::guard::guard(GUARD_ID, ($max) + 1);
+            $cond
+        } $body
+    }};
+}
@@ -0,0 +1,60 @@
+// KYC markers shared by the LKS hooks
+// The foundation records accounts that passed KYC in hook state, optionally
+// until a ledger after which they have to be verified again. The zero-fee
+// hook can restrict sponsorship to verified accounts, and the compliance
+// hook rejects transactions from everyone else. Hooks installed under the
+// same HookNamespace share the markers; a hook on another account reads
+// them from the compliance hook set in KYCACCT and KYCNS as well as its own.
+//
+// Marker: [expires ledger u32 big-endian], 0 when it never expires
+
+use crate::api::ledger_seq;
+use crate::bytes;
+use crate::error::HookError;
+use crate::foreign::Sibling;
+use crate::state;
+
+// Invoke parameter carrying KYC admin commands
+pub const PARAM_COMMAND: &[u8] = b"LKSKYC";
+
+// Account and HookNamespace of the compliance hook keeping the markers
+pub const PARAM_KYC_ACCOUNT: &[u8] = b"KYCACCT";
+pub const PARAM_KYC_NAMESPACE: &[u8] = b"KYCNS";
+
+// Admin command operations
+pub const OP_VERIFY: u8 = 1;
+pub const OP_REVOKE: u8 = 2;
+
+// Length of an encoded KYC command: op, account, expiry ledger
+pub const COMMAND_LEN: usize = 25;
+
+pub fn is_verified(account: &[u8; 20]) -> bool {
+    let key = state::account_key(state::NS_KYC, account);
+    let marker = state::load_entry::<u32>(&key).or_else(|| {
+        Sibling::configured(PARAM_KYC_ACCOUNT, PARAM_KYC_NAMESPACE)?.load_entry(&key)
+    });
+    let expires = match marker {
+        Some(expires) => expires,
+        None => return false,
+    };
+
+    expires == 0 || (unsafe { ledger_seq() } as u32) < expires
+}
+
+// Apply an encoded KYC command: [op, account(20), expires ledger u32]
+// Returns the operation applied
+pub fn apply_command(command: &[u8]) -> Result<u8, HookError> {
+    let (op, account) = match (command.first(), bytes::array(command, 1)) {
+        (Some(&op), Some(account)) if command.len() == COMMAND_LEN => (op, account),
+        _ => return Err(HookError::AdminCommandInvalid),
+    };
+    let key = state::account_key(state::NS_KYC, &account);
+
+    match op {
+        OP_VERIFY => state::store_entry(&key, &bytes::u32_at(command, 21).unwrap_or(0))?,
+        OP_REVOKE => state::erase(&key)?,
+        _ => return Err(HookError::AdminCommandInvalid),
+    }
+
+    Ok(op)
+}
@@ -0,0 +1,54 @@
+// SDK shared by the LKS hooks
+// Hook API bindings, ledger field parsing, amounts and XFL math, hook state
+// and parameter helpers, tracing, and the simulated host the hooks are
+// tested against. Every hook crate in the workspace builds on it, so a fix
+// here reaches all of them.
+
+// Natively built with the sim feature (and for tests) the Hook API is
+// provided by the simulated host in sim.rs instead of the Hooks runtime
+#![cfg_attr(not(any(test, feature = "sim")), no_std)]
+
+#[macro_use]
+pub mod guard;
+
+pub mod account;
+pub mod admin;
+pub mod amount;
+pub mod api;
+pub mod bytes;
+pub mod codec;
+pub mod config;
+pub mod denylist;
+pub mod entry;
+pub mod error;
+pub mod fields;
+pub mod foreign;
+pub mod kyc;
+pub mod log;
+pub mod memo;
+pub mod schema;
+pub mod state;
+pub mod text;
+pub mod txn;
+pub mod xfl;
+
+// The Xahau runtime's own API, which api.rs shims the legacy calls onto
+#[cfg(all(feature = "xahau", not(any(test, feature = "sim"))))]
+pub mod xahau;
+
+#[cfg(any(test, feature = "sim"))]
+pub mod sim;
+
+// Panic handler required for no_std
+// Hooks must not contain reachable panics. The handler calls a function the
+// Hooks runtime doesn't provide, so a release build with a reachable panic
+// imports it and SetHook refuses the wasm; lks-hook-bench checks the imports
+// of every hook before that.
+#[cfg(not(any(test, feature = "sim")))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    extern "C" {
+        fn panic_is_reachable() -> !;
+    }
+    unsafe { panic_is_reachable() }
+}
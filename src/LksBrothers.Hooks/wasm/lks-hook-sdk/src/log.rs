@@ -0,0 +1,121 @@
+// Structured, level-based tracing for the LKS hooks
+// Every trace is one event: a static message plus a fixed 19-byte payload,
+// traced as hex so node logs can be parsed without knowing the message text:
+//   [level u8][event code u16][a u64][b u64]   (big-endian)
+// Event codes are part of the operator-facing interface, like error codes,
+// and must never be renumbered.
+//
+// The most verbose level compiled in is chosen with the log-off, log-error,
+// log-warn, log-info and log-debug features (the most verbose enabled one
+// wins, Info without any). Events above it compile to nothing, so their
+// messages don't end up in the wasm.
+
+use crate::api::trace;
+use crate::bytes;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[repr(u8)]
+pub enum Level {
+    Error = 1,
+    Warn = 2,
+    Info = 3,
+    Debug = 4,
+}
+
+pub const MAX_LEVEL: u8 = if cfg!(feature = "log-debug") {
+    Level::Debug as u8
+} else if cfg!(feature = "log-info") {
+    Level::Info as u8
+} else if cfg!(feature = "log-warn") {
+    Level::Warn as u8
+} else if cfg!(feature = "log-error") {
+    Level::Error as u8
+} else if cfg!(feature = "log-off") {
+    0
+} else {
+    Level::Info as u8
+};
+
+pub const PAYLOAD_LEN: usize = 19;
+
+// Event codes
+pub const EV_TX_TYPE: u16 = 1; // a: transaction type
+pub const EV_FAILED: u16 = 2; // a: error code
+pub const EV_SPONSORED: u16 = 3; // a: sponsored drops, b: transaction type
+pub const EV_DUST: u16 = 4; // a: amount in the token's smallest unit
+pub const EV_FEE_CAP: u16 = 5; // a: original fee, b: cap
+pub const EV_DUPLICATE: u16 = 6; // a: sponsored drops
+pub const EV_REGISTRY: u16 = 7; // a: resulting account flags
+pub const EV_CONFIG: u16 = 8; // a: override length
+pub const EV_CURRENCY: u16 = 9; // a: command op
+pub const EV_BREAKER_OPEN: u16 = 10; // a: reserve balance
+pub const EV_BREAKER_CLOSE: u16 = 11; // a: reserve balance
+pub const EV_SETTLED: u16 = 12; // a: settled drops
+pub const EV_SETTLE_FAILED: u16 = 13; // a: pending drops, b: error code
+pub const EV_KYC: u16 = 14; // a: command op
+pub const EV_SETTLE_CONFIRMED: u16 = 15; // a: settled drops
+pub const EV_SETTLE_RETRY: u16 = 16; // a: failed drops, b: failures in a row
+pub const EV_SETTLE_FLAGGED: u16 = 17; // a: pending drops, b: failures in a row
+pub const EV_ADMIN: u16 = 18; // a: command op, b: first 8 bytes of the signer
+pub const EV_PAUSED: u16 = 19; // a: transaction type
+pub const EV_PAUSE_SET: u16 = 20; // a: 1 when paused, 0 when resumed
+pub const EV_VOUCHER: u16 = 21; // a: voucher nonce, b: expiry ledger
+pub const EV_VOUCHER_INVALID: u16 = 22; // a: voucher nonce, b: reason
+pub const EV_REFERRAL: u16 = 23; // a: referral code, b: accounts referred
+pub const EV_ABUSE: u16 = 24; // a: abuse score, b: threshold
+pub const EV_EXCHANGE: u16 = 25; // a: policy flags
+pub const EV_FOUNDATION: u16 = 26; // a: rotation phase, b: retire ledger, or the ledger it retired
+pub const EV_AUDIT: u16 = 27; // a: original fee, b: first 8 bytes of the account
+pub const EV_MALFORMED: u16 = 28; // a: field code, b: byte offset in the field
+pub const EV_BURST: u16 = 29; // a: strikes, b: ledger the hold ends at
+pub const EV_MAKER: u16 = 30; // a: offer flags, b: 1 when priced through the reference
+pub const EV_DENYLISTED: u16 = 31; // a: ban ledger, 0 when the list is unreadable, b: 1 when treated as banned
+pub const EV_DENYLIST_SET: u16 = 32; // a: command op, b: first 8 bytes of the account
+pub const EV_TX_TYPE_OFF: u16 = 33; // a: transaction type
+
+// Whether events of `level` are compiled in, so callers can skip building
+// messages nobody will see
+pub const fn enabled(level: Level) -> bool {
+    level as u8 <= MAX_LEVEL
+}
+
+#[inline(always)]
+pub fn error(event: u16, msg: &[u8], a: u64, b: u64) {
+    log(Level::Error, event, msg, a, b);
+}
+
+#[inline(always)]
+pub fn warn(event: u16, msg: &[u8], a: u64, b: u64) {
+    log(Level::Warn, event, msg, a, b);
+}
+
+#[inline(always)]
+pub fn info(event: u16, msg: &[u8], a: u64, b: u64) {
+    log(Level::Info, event, msg, a, b);
+}
+
+#[inline(always)]
+pub fn debug(event: u16, msg: &[u8], a: u64, b: u64) {
+    log(Level::Debug, event, msg, a, b);
+}
+
+#[inline(always)]
+pub fn log(level: Level, event: u16, msg: &[u8], a: u64, b: u64) {
+    if !enabled(level) {
+        return;
+    }
+
+    let payload = encode(level, event, a, b);
+    unsafe {
+        trace(msg.as_ptr(), msg.len() as i32, payload.as_ptr(), payload.len() as i32, 1);
+    }
+}
+
+pub fn encode(level: Level, event: u16, a: u64, b: u64) -> [u8; PAYLOAD_LEN] {
+    let mut payload = [0u8; PAYLOAD_LEN];
+    payload[0] = level as u8;
+    bytes::put(&mut payload, 1, &event.to_be_bytes());
+    bytes::put(&mut payload, 3, &a.to_be_bytes());
+    bytes::put(&mut payload, 11, &b.to_be_bytes());
+    payload
+}
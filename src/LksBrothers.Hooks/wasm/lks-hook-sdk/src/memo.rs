@@ -0,0 +1,226 @@
+// Memo directives for the LKS hooks
+// Integrators steer sponsorship by attaching memos whose MemoType names an
+// LKS directive, e.g. exchanges paying their own fees for accounting reasons
+// attach "lks/no-sponsor", and partners onboarding users have them attach
+// their referral code. Memos are decoded once per transaction into a
+// Directives value so features never decode memos themselves.
+
+use crate::bytes;
+use crate::error::HookError;
+use crate::fields::{self, ST_BLOB, ST_OBJECT, SF_MEMOS};
+
+// The transaction pays its own fee
+pub const DIRECTIVE_NO_SPONSOR: &[u8] = b"lks/no-sponsor";
+
+// The sender was referred by the partner whose code is the MemoData: 1 to 8
+// printable ASCII characters, zero-padded to REFERRAL_CODE_LEN
+pub const DIRECTIVE_REFERRAL: &[u8] = b"lks/referral";
+pub const REFERRAL_CODE_LEN: usize = 8;
+
+// Transactions carry at most this many serialized memo bytes (1 KB limit)
+pub const MAX_MEMOS_LEN: usize = 1024;
+
+// Upper bound on memos and memo fields decoded per hook execution
+// Transactions with more memos than this are rejected rather than letting
+// the guards abort the hook
+const MAX_MEMOS: u32 = 32;
+const MAX_MEMO_FIELDS: u32 = 3 * MAX_MEMOS;
+
+// Field codes of the Memo object and its blobs
+const MEMO_OBJECT: i32 = 10;
+const MEMO_TYPE: i32 = 12;
+const MEMO_DATA: i32 = 13;
+const MEMO_FORMAT: i32 = 14;
+
+#[derive(Clone, Copy, Default)]
+pub struct Memo<'a> {
+    pub memo_type: &'a [u8],
+    pub data: &'a [u8],
+    pub format: &'a [u8],
+}
+
+// Directives found in the memos of a transaction
+#[derive(Clone, Copy, Default)]
+pub struct Directives {
+    pub no_sponsor: bool,
+    // The first valid referral code
+    pub referral: Option<[u8; REFERRAL_CODE_LEN]>,
+}
+
+impl Directives {
+    fn apply(&mut self, memo: &Memo) {
+        if bytes::eq(memo.memo_type, DIRECTIVE_NO_SPONSOR) {
+            self.no_sponsor = true;
+        }
+        if bytes::eq(memo.memo_type, DIRECTIVE_REFERRAL) && self.referral.is_none() {
+            self.referral = referral_code(memo.data);
+        }
+    }
+}
+
+// Invalid codes are ignored like unknown directives
+fn referral_code(data: &[u8]) -> Option<[u8; REFERRAL_CODE_LEN]> {
+    if data.is_empty() || data.len() > REFERRAL_CODE_LEN {
+        return None;
+    }
+
+    let mut code = [0u8; REFERRAL_CODE_LEN];
+    let mut printable = true;
+    guarded_loop!(i in 0, data.len(); max REFERRAL_CODE_LEN as u32 * MAX_MEMOS; {
+        let byte = data.get(i).copied().unwrap_or(0);
+        printable &= (0x21..=0x7E).contains(&byte);
+        if let Some(slot) = code.get_mut(i) {
+            *slot = byte;
+        }
+    });
+
+    printable.then_some(code)
+}
+
+// Read and decode the directives of the originating transaction
+pub fn read_directives() -> Result<Directives, HookError> {
+    let mut buffer = [0u8; MAX_MEMOS_LEN];
+    let memos = match fields::read_raw(SF_MEMOS, &mut buffer)? {
+        Some(memos) => memos,
+        None => return Ok(Directives::default()),
+    };
+
+    parse_directives(memos)
+}
+
+pub fn parse_directives(memos: &[u8]) -> Result<Directives, HookError> {
+    let mut directives = Directives::default();
+    for_each_memo(memos, |memo| directives.apply(memo))?;
+    Ok(directives)
+}
+
+// Walk the serialized contents of a Memos array, calling `f` for each memo
+pub fn for_each_memo<'a>(memos: &'a [u8], mut f: impl FnMut(&Memo<'a>)) -> Result<(), HookError> {
+    let mut pos = 0;
+    let mut count = 0;
+
+    guarded_while!(max MAX_MEMOS; pos < memos.len(); {
+        let (type_code, field_code, header_len) =
+            fields::decode_field_header(bytes::tail(memos, pos)).ok_or(HookError::MemoParseFailed)?;
+
+        if (type_code, field_code) == fields::ARRAY_END {
+            break;
+        }
+        if (type_code, field_code) != (ST_OBJECT, MEMO_OBJECT) || count == MAX_MEMOS {
+            return Err(HookError::MemoParseFailed);
+        }
+        count += 1;
+
+        let (memo, memo_len) = parse_memo(bytes::tail(memos, pos + header_len))?;
+        f(&memo);
+        pos += header_len + memo_len;
+    });
+
+    Ok(())
+}
+
+// Parse the fields of one Memo object up to its end marker, returning the
+// memo and the bytes consumed
+// Fields must appear once each in canonical order, so a memo takes at most
+// four iterations plus the final condition check
+fn parse_memo(data: &[u8]) -> Result<(Memo<'_>, usize), HookError> {
+    let mut memo = Memo::default();
+    let mut pos = 0;
+    let mut last_field = 0;
+    let mut ended = false;
+
+    guarded_while!(max MAX_MEMO_FIELDS + 2 * MAX_MEMOS; !ended; {
+        let (type_code, field_code, header_len) =
+            fields::decode_field_header(bytes::tail(data, pos)).ok_or(HookError::MemoParseFailed)?;
+        pos += header_len;
+
+        if (type_code, field_code) == fields::OBJECT_END {
+            ended = true;
+        } else {
+            if type_code != ST_BLOB || field_code <= last_field {
+                return Err(HookError::MemoParseFailed);
+            }
+            last_field = field_code;
+
+            let (length, vl_len) =
+                fields::decode_vl_length(bytes::tail(data, pos)).ok_or(HookError::MemoParseFailed)?;
+            let value = data.get(pos + vl_len..pos + vl_len + length).ok_or(HookError::MemoParseFailed)?;
+            pos += vl_len + length;
+
+            match field_code {
+                MEMO_TYPE => memo.memo_type = value,
+                MEMO_DATA => memo.data = value,
+                MEMO_FORMAT => memo.format = value,
+                _ => return Err(HookError::MemoParseFailed),
+            }
+        }
+    });
+
+    Ok((memo, pos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Serialize one Memo object with the given MemoType and MemoData
+    fn memo(memo_type: &[u8], data: &[u8]) -> std::vec::Vec<u8> {
+        let mut out = std::vec![0xEA, 0x7C, memo_type.len() as u8];
+        out.extend_from_slice(memo_type);
+        out.extend_from_slice(&[0x7D, data.len() as u8]);
+        out.extend_from_slice(data);
+        out.push(0xE1);
+        out
+    }
+
+    #[test]
+    fn finds_no_sponsor_directive_among_memos() {
+        let mut memos = memo(b"text/plain", b"invoice 42");
+        memos.extend(memo(DIRECTIVE_NO_SPONSOR, b""));
+        memos.push(0xF1);
+
+        assert!(parse_directives(&memos).unwrap().no_sponsor);
+        assert!(!parse_directives(&memo(b"text/plain", b"hi")).unwrap().no_sponsor);
+    }
+
+    #[test]
+    fn reads_first_valid_referral_code() {
+        let mut memos = memo(DIRECTIVE_REFERRAL, b"bad code");
+        memos.extend(memo(DIRECTIVE_REFERRAL, b"PARTNER1"));
+        memos.extend(memo(DIRECTIVE_REFERRAL, b"OTHER"));
+        memos.push(0xF1);
+        assert_eq!(parse_directives(&memos).unwrap().referral, Some(*b"PARTNER1"));
+
+        assert_eq!(parse_directives(&memo(DIRECTIVE_REFERRAL, b"ACME")).unwrap().referral, Some(*b"ACME\0\0\0\0"));
+        assert_eq!(parse_directives(&memo(DIRECTIVE_REFERRAL, b"TOOLONGCODE")).unwrap().referral, None);
+    }
+
+    #[test]
+    fn rejects_truncated_memos() {
+        let memos = memo(DIRECTIVE_NO_SPONSOR, b"");
+        assert!(parse_directives(&memos[..memos.len() - 4]).is_err());
+        assert!(parse_directives(&[0xEA, 0x7C, 0x20, b'l']).is_err());
+    }
+
+    #[test]
+    fn rejects_memos_beyond_guard_budget() {
+        crate::sim::reset();
+
+        let mut memos = std::vec::Vec::new();
+        for _ in 0..=MAX_MEMOS {
+            memos.extend(memo(b"text/plain", b"x"));
+        }
+        assert!(parse_directives(&memos).is_err());
+        assert_eq!(crate::sim::with(|host| host.guard_violation), None);
+
+        // Repeated fields would otherwise let one memo spin the field loop
+        crate::sim::reset();
+        let mut repeated = std::vec![0xEA];
+        for _ in 0..200 {
+            repeated.extend_from_slice(&[0x7C, 0x00]);
+        }
+        assert!(parse_directives(&repeated).is_err());
+
+        assert_eq!(crate::sim::with(|host| host.guard_violation), None);
+    }
+}
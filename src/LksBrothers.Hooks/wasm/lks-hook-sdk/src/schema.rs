@@ -0,0 +1,334 @@
+// State schema of the LKS hooks
+// The key and value layout of the entries in every LKS namespace, written
+// down once for the hooks and for the off-chain tooling reading their state
+// (lks-state-export). A hook changing what it stores under a namespace
+// changes its schema here in the same commit; the tests check the schema
+// against the lengths state.rs tells v0 entries apart by.
+//
+// Keys are described after the "LKS" marker and namespace byte, values
+// after their version byte. Integers are big-endian, varints as entry.rs
+// encodes them. Ids longer than a key
+// holds are truncated like state::key() truncates them, so those fields
+// only carry the start of the id.
+//
+// Hooks don't read the schema, they use the namespace constants of
+// state.rs, so none of it ends up in a ledger build.
+
+use crate::account::ACCOUNT_ID_LEN;
+use crate::entry::{self, Reader};
+use crate::state::{self, KEY_LEN};
+
+// Key bytes after the marker and namespace byte
+pub const KEY_ID_LEN: usize = KEY_LEN - 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    // 20-byte account id
+    Account,
+    // Big-endian unsigned integer of up to 8 bytes
+    Uint,
+    // Unsigned integer as a varint; its len is the longest encoding
+    Varint,
+    // Opaque bytes: hashes, codes, ids
+    Bytes,
+    // ASCII text, like parameter names; trailing zero bytes are padding
+    Text,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Field {
+    pub name: &'static str,
+    pub kind: Kind,
+    // Zero for a last field taking up the rest of the key or value
+    pub len: usize,
+}
+
+#[derive(Debug)]
+pub struct Schema {
+    pub namespace: u8,
+    pub name: &'static str,
+    pub key: &'static [Field],
+    pub value: &'static [Field],
+    // Value fields every entry has; the ones after them are present
+    // together or not at all
+    pub required: usize,
+    // Written without a version byte, by a hook not built on this SDK or by
+    // a wasm from before values were versioned
+    pub raw: bool,
+}
+
+const fn field(name: &'static str, kind: Kind, len: usize) -> Field {
+    Field { name, kind, len }
+}
+
+const fn account(name: &'static str) -> Field {
+    field(name, Kind::Account, ACCOUNT_ID_LEN)
+}
+
+const fn u8_(name: &'static str) -> Field {
+    field(name, Kind::Uint, 1)
+}
+
+const fn u16_(name: &'static str) -> Field {
+    field(name, Kind::Uint, 2)
+}
+
+const fn u32_(name: &'static str) -> Field {
+    field(name, Kind::Uint, 4)
+}
+
+const fn u64_(name: &'static str) -> Field {
+    field(name, Kind::Uint, 8)
+}
+
+const fn varint(name: &'static str) -> Field {
+    field(name, Kind::Varint, entry::MAX_VARINT_LEN)
+}
+
+const fn bytes(name: &'static str, len: usize) -> Field {
+    field(name, Kind::Bytes, len)
+}
+
+const fn schema(namespace: u8, name: &'static str, key: &'static [Field], value: &'static [Field]) -> Schema {
+    Schema { namespace, name, key, value, required: value.len(), raw: false }
+}
+
+const ACCOUNT_KEY: &[Field] = &[account("account")];
+const MARKER: &[Field] = &[u8_("marker")];
+// Limit counters: [epoch u32][value u64]
+const COUNTER: &[Field] = &[u32_("epoch"), u64_("value")];
+const CONFIG_KEY: &[Field] = &[field("parameter", Kind::Text, 0)];
+
+// Every LKS namespace, in namespace order
+pub const SCHEMAS: &[Schema] = &[
+    schema(state::NS_REGISTRY, "registry", ACCOUNT_KEY, &[u8_("flags")]),
+    schema(state::NS_ESCROW, "escrow", &[account("owner"), u32_("sequence")], MARKER),
+    schema(state::NS_CHANNEL, "channel", &[bytes("channel", KEY_ID_LEN)], MARKER),
+    schema(state::NS_TRUSTLINE, "trustline", ACCOUNT_KEY, MARKER),
+    schema(state::NS_ACCOUNT_LIMIT, "account_limit", ACCOUNT_KEY, COUNTER),
+    schema(state::NS_PAIR_LIMIT, "pair_limit",
+           &[account("low"), bytes("high", KEY_ID_LEN - ACCOUNT_ID_LEN)], COUNTER),
+    schema(state::NS_BUDGET, "budget", &[], COUNTER),
+    schema(state::NS_RECEIPT, "receipt", &[u32_("slot")],
+           &[u32_("number"), account("account"), u32_("ledger"), u64_("fee"), u8_("category")]),
+    schema(state::NS_RECEIPT_HEAD, "receipt_head", &[], &[u32_("next_number")]),
+    schema(state::NS_PRUNE_INDEX, "prune_index", &[u32_("epoch"), u32_("item")], &[bytes("target", KEY_LEN)]),
+    schema(state::NS_PRUNE_COUNT, "prune_count", &[u32_("epoch")], &[u32_("items"), u32_("next_epoch")]),
+    schema(state::NS_PRUNE_CURSOR, "prune_cursor", &[],
+           &[u32_("head_epoch"), u32_("next_item"), u32_("tail_epoch")]),
+    schema(state::NS_SEEN, "seen", &[bytes("transaction", KEY_ID_LEN)], &[u32_("epoch")]),
+    Schema { raw: true, ..schema(state::NS_CONFIG, "config_v0", CONFIG_KEY, &[bytes("value", 0)]) },
+    schema(state::NS_CURRENCY, "currency", &[bytes("currency", 20)], &[account("issuer"), u8_("decimals")]),
+    schema(state::NS_NFT_OFFER, "nft_offer", &[bytes("offer", KEY_ID_LEN)], MARKER),
+    schema(state::NS_BREAKER, "breaker", &[], &[u8_("open"), u64_("balance"), u32_("ledger")]),
+    schema(state::NS_SETTLEMENT, "settlement", &[],
+           &[u64_("pending"), u32_("last_ledger"), u64_("in_flight"), u8_("failures")]),
+    schema(state::NS_KYC, "kyc", ACCOUNT_KEY, &[u32_("expires")]),
+    Schema { raw: true, ..schema(state::NS_STAKE, "stake", ACCOUNT_KEY, &[u64_("staked")]) },
+    schema(state::NS_PAUSE, "pause", &[], MARKER),
+    Schema {
+        required: 1,
+        ..schema(state::NS_FOUNDATION, "foundation", &[],
+                 &[account("account"), account("pending"), u32_("retire_ledger")])
+    },
+    schema(state::NS_VOUCHER, "voucher", ACCOUNT_KEY, &[u64_("nonce")]),
+    schema(state::NS_REFERRAL, "referral", ACCOUNT_KEY, &[bytes("code", 8), u32_("ledger")]),
+    schema(state::NS_REFERRER, "referrer", &[bytes("code", 8)], &[u64_("referred")]),
+    schema(state::NS_STATS, "stats", &[u32_("slot"), u8_("kind"), u16_("id")],
+           &[u32_("epoch"), u64_("transactions"), u64_("drops")]),
+    schema(state::NS_ONBOARDING, "onboarding", ACCOUNT_KEY, &[u64_("sponsored")]),
+    schema(state::NS_ACTIVITY, "activity", ACCOUNT_KEY,
+           &[u32_("last_ledger"), varint("burst"), u64_("last_amount"), varint("repeats")]),
+    schema(state::NS_EXCHANGE, "exchange", ACCOUNT_KEY, &[u8_("flags")]),
+    schema(state::NS_CHECK, "check", &[bytes("check", KEY_ID_LEN)], MARKER),
+    schema(state::NS_BURST, "burst", ACCOUNT_KEY,
+           &[u32_("last_refill"), varint("level"), varint("strikes"), u32_("held_until")]),
+    schema(state::NS_MAKER, "maker", ACCOUNT_KEY, COUNTER),
+    schema(state::NS_DENYLIST, "denylist", ACCOUNT_KEY, &[u32_("banned_at")]),
+    schema(state::NS_OVERRIDE, "config", CONFIG_KEY, &[bytes("value", 0)]),
+];
+
+// Schema of the entries in `namespace`. Namespaces are numbered from one
+// without gaps, so the lookup is an index.
+pub fn lookup(namespace: u8) -> Option<&'static Schema> {
+    SCHEMAS.get((namespace as usize).wrapping_sub(1)).filter(|schema| schema.namespace == namespace)
+}
+
+impl Schema {
+    // Split the id bytes of `key` into its key fields, None when they don't
+    // fit them or the padding after them isn't zero
+    pub fn split_key<'a>(&self, key: &'a [u8; KEY_LEN], out: &mut Fields<'a>) -> Option<usize> {
+        let id = key.get(KEY_LEN - KEY_ID_LEN..)?;
+        let len = match self.key.last() {
+            Some(field) if field.len == 0 => KEY_ID_LEN,
+            _ => self.key.iter().map(|field| field.len).sum(),
+        };
+        let (id, padding) = id.split_at(len.min(KEY_ID_LEN));
+        if padding.iter().any(|&byte| byte != 0) {
+            return None;
+        }
+        split(self.key, self.key.len(), id, out)
+    }
+
+    // Split a stored entry into its layout version (None for raw entries)
+    // and its value fields, None when it doesn't fit them
+    pub fn split_entry<'a>(&self, entry: &'a [u8], out: &mut Fields<'a>) -> Option<(Option<u8>, usize)> {
+        if self.raw {
+            return split(self.value, self.required, entry, out).map(|count| (None, count));
+        }
+        let (version, value) = state::unpack_entry(self.namespace, entry)?;
+        split(self.value, self.required, value, out).map(|count| (Some(version), count))
+    }
+
+    // Length of a value holding `fields` of its fields, None when one of them
+    // takes up the rest or is a varint
+    pub fn value_len(&self, fields: usize) -> Option<usize> {
+        let fields = self.value.get(..fields)?;
+        if fields.iter().any(|field| field.len == 0 || field.kind == Kind::Varint) {
+            return None;
+        }
+        Some(fields.iter().map(|field| field.len).sum())
+    }
+}
+
+// Most fields of a key or value, and the fields split out of one
+pub const MAX_FIELDS: usize = 5;
+pub type Fields<'a> = [(&'static Field, &'a [u8]); MAX_FIELDS];
+
+const NO_FIELD: Field = field("", Kind::Bytes, 0);
+
+// Fields to split into
+pub fn fields<'a>() -> Fields<'a> {
+    [(&NO_FIELD, &[]); MAX_FIELDS]
+}
+
+// Split `data` into the values of `fields`, None when it doesn't fit them.
+// Fields from `required` on may be missing, all of them together.
+fn split<'a>(fields: &'static [Field], required: usize, data: &'a [u8], out: &mut Fields<'a>) -> Option<usize> {
+    let mut rest = data;
+    let mut count = 0;
+    for field in fields {
+        if rest.is_empty() && count >= required {
+            break;
+        }
+        let len = match field.kind {
+            Kind::Varint => varint_len(rest)?,
+            _ if field.len == 0 => rest.len(),
+            _ => field.len,
+        };
+        if rest.len() < len {
+            return None;
+        }
+        let (value, tail) = rest.split_at(len);
+        *out.get_mut(count)? = (field, value);
+        rest = tail;
+        count += 1;
+    }
+
+    if !rest.is_empty() || (count != required && count != fields.len()) {
+        return None;
+    }
+    Some(count)
+}
+
+// Length of the varint `data` starts with, None when it doesn't decode
+fn varint_len(data: &[u8]) -> Option<usize> {
+    let mut r = Reader::new(data);
+    r.varint()?;
+    Some(data.len() - r.rest().len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn covers_every_namespace_in_order() {
+        for (i, schema) in SCHEMAS.iter().enumerate() {
+            assert_eq!(schema.namespace as usize, i + 1, "{}", schema.name);
+            assert_eq!(lookup(schema.namespace).map(|found| found.name), Some(schema.name));
+        }
+        assert_eq!(SCHEMAS.len(), state::NS_OVERRIDE as usize);
+        assert!(lookup(0).is_none());
+        assert!(lookup(state::NS_OVERRIDE + 1).is_none());
+    }
+
+    #[test]
+    fn keys_fit_and_names_are_unique() {
+        for schema in SCHEMAS {
+            let key_len: usize = schema.key.iter().map(|field| field.len).sum();
+            assert!(key_len <= KEY_ID_LEN, "{}", schema.name);
+            assert!(schema.required <= schema.value.len() && schema.required > 0, "{}", schema.name);
+
+            let names: std::vec::Vec<&str> = schema.key.iter().chain(schema.value).map(|field| field.name).collect();
+            for (i, name) in names.iter().enumerate() {
+                assert!(!names[i + 1..].contains(name), "{}.{name}", schema.name);
+            }
+        }
+    }
+
+    #[test]
+    fn matches_the_v0_layouts() {
+        for schema in SCHEMAS {
+            match state::v0_layout(schema.namespace) {
+                Some(state::Layout::Fixed(len)) => assert_eq!(schema.value_len(schema.required), Some(len), "{}", schema.name),
+                Some(state::Layout::Records(_)) => panic!("{} has a v0 record layout", schema.name),
+                None => {}
+            }
+        }
+    }
+
+    #[test]
+    fn splits_entries_into_fields() {
+        let foundation = lookup(state::NS_FOUNDATION).unwrap();
+        let mut out = fields();
+
+        // v0 and current entries, with and without a pending rotation
+        assert_eq!(foundation.split_entry(&[7; 20], &mut out), Some((Some(0), 1)));
+        assert_eq!(out[0].1, &[7; 20]);
+        assert_eq!(foundation.split_entry(&[state::VERSION; 45], &mut out), Some((Some(state::VERSION), 3)));
+        assert_eq!((out[2].0.name, out[2].1), ("retire_ledger", &[state::VERSION; 4][..]));
+
+        // Partly present optional fields and trailing bytes don't fit
+        assert_eq!(foundation.split_entry(&[state::VERSION; 41], &mut out), None);
+        assert_eq!(foundation.split_entry(&[state::VERSION; 46], &mut out), None);
+
+        let stake = lookup(state::NS_STAKE).unwrap();
+        assert_eq!(stake.split_entry(&[1; 8], &mut out), Some((None, 1)));
+    }
+
+    #[test]
+    fn splits_varint_fields_by_their_encoding() {
+        let burst = lookup(state::NS_BURST).unwrap();
+        let mut out = fields();
+        assert_eq!(burst.value_len(burst.required), None);
+
+        // A level of 300 takes two bytes, no strikes one
+        let entry = [state::VERSION, 0, 0, 4, 0, 0xAC, 0x02, 0x00, 0, 0, 4, 8];
+        assert_eq!(burst.split_entry(&entry, &mut out), Some((Some(state::VERSION), 4)));
+        assert_eq!((out[1].0.name, out[1].1), ("level", &[0xAC, 0x02][..]));
+        assert_eq!((out[2].0.name, out[2].1), ("strikes", &[0x00][..]));
+
+        // Varints cut off by the end of the entry don't fit
+        assert_eq!(burst.split_entry(&entry[..6], &mut out), None);
+    }
+
+    #[test]
+    fn splits_keys_into_fields() {
+        let mut out = fields();
+
+        let config = lookup(state::NS_OVERRIDE).unwrap();
+        let key = state::key(state::NS_OVERRIDE, &[b"TIERS"]);
+        assert_eq!(config.split_key(&key, &mut out), Some(1));
+        assert_eq!(&out[0].1[..6], b"TIERS\0");
+
+        let escrow = lookup(state::NS_ESCROW).unwrap();
+        let mut key = state::key(state::NS_ESCROW, &[&[0xAA; 20], &7u32.to_be_bytes()]);
+        assert_eq!(escrow.split_key(&key, &mut out), Some(2));
+        assert_eq!((out[1].0.name, out[1].1), ("sequence", &[0u8, 0, 0, 7][..]));
+
+        // Bytes past the fields must be padding
+        key[KEY_LEN - 1] = 1;
+        assert_eq!(escrow.split_key(&key, &mut fields()), None);
+    }
+}
@@ -0,0 +1,411 @@
+// Simulated hook host for unit tests and off-chain tooling
+// Built with the sim feature (and for tests): implements the Hook API on top
+// of an in-memory ledger model so the hook logic runs natively, one simulated
+// host per thread
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::slice;
+use std::vec::Vec;
+
+use crate::fields;
+
+// Hook API return codes
+pub const INTERNAL_ERROR: i32 = -2;
+pub const TOO_SMALL: i32 = -4;
+pub const DOESNT_EXIST: i32 = -5;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Accepted(Vec<u8>),
+    Rejected(Vec<u8>),
+}
+
+// Hook state entries by key
+pub type State = BTreeMap<Vec<u8>, Vec<u8>>;
+
+// Foreign state entries are addressed by (account, namespace, key)
+pub type ForeignKey = (Vec<u8>, Vec<u8>, Vec<u8>);
+
+#[derive(Default)]
+pub struct Host {
+    pub tx_type: i32,
+    // Originating transaction fields by field code, serialized
+    pub fields: BTreeMap<i32, Vec<u8>>,
+    // Metadata fields of the originating transaction, for callbacks
+    pub meta: BTreeMap<i32, Vec<u8>>,
+    pub otxn_params: BTreeMap<Vec<u8>, Vec<u8>>,
+    pub hook_params: BTreeMap<Vec<u8>, Vec<u8>>,
+    pub state: State,
+    // State of other hooks
+    pub foreign_state: BTreeMap<ForeignKey, Vec<u8>>,
+    pub ledger_seq: u64,
+    pub otxn_id: [u8; 32],
+    pub hook_account: [u8; 20],
+    // Ledger objects by keylet, each with its serialized fields
+    pub ledger: BTreeMap<Vec<u8>, BTreeMap<i32, Vec<u8>>>,
+    // Transactions emitted by the hook; emit fails while `emit_fails` is set
+    pub emitted: Vec<Vec<u8>>,
+    pub emit_fails: bool,
+    // Reads of other hooks' state fail while `foreign_fails` is set
+    pub foreign_fails: bool,
+    // Per-run bookkeeping
+    pub guards: BTreeMap<u32, u32>,
+    // Traced (message, data) pairs
+    pub traces: Vec<(Vec<u8>, Vec<u8>)>,
+    pub outcome: Option<Outcome>,
+    // First guard whose budget was exceeded, as (id, max_iterations)
+    pub guard_violation: Option<(u32, u32)>,
+}
+
+impl Host {
+    // Value of a hook state entry without its version byte, as the hook
+    // reads it
+    pub fn value(&self, key: &[u8]) -> Option<&[u8]> {
+        self.state.get(key).and_then(|entry| entry.get(1..))
+    }
+}
+
+std::thread_local! {
+    static HOST: RefCell<Host> = RefCell::new(Host::default());
+}
+
+pub fn reset() {
+    with(|host| *host = Host::default());
+}
+
+// Host functions borrow the host too, so `f` must not call into hook code
+pub fn with<R>(f: impl FnOnce(&mut Host) -> R) -> R {
+    HOST.with(|host| f(&mut host.borrow_mut()))
+}
+
+// Run a hook entry point once against the current transaction and ledger
+// state
+pub fn run(hook: extern "C" fn() -> i64) -> i64 {
+    begin();
+    finish(hook())
+}
+
+// Run a callback entry point once, with the emitted transaction as the
+// originating transaction; `what` is 0 when it was applied, 1 when it
+// expired without being applied
+pub fn run_cbak(cbak: extern "C" fn(u32) -> i64, what: u32) -> i64 {
+    begin();
+    finish(cbak(what))
+}
+
+// Clear the per-run bookkeeping; run() and run_cbak() do this themselves,
+// runners executing the compiled wasm call it before each run
+pub fn begin() {
+    with(|host| {
+        host.guards.clear();
+        host.traces.clear();
+        host.outcome = None;
+        host.guard_violation = None;
+    });
+}
+
+fn finish(result: i64) -> i64 {
+    if let Some((id, max_iterations)) = with(|host| host.guard_violation) {
+        panic!("guard {id:#x} exceeded {max_iterations} iterations");
+    }
+
+    result
+}
+
+unsafe fn bytes<'a>(ptr: *const u8, len: i32) -> &'a [u8] {
+    if len <= 0 {
+        return &[];
+    }
+    slice::from_raw_parts(ptr, len as usize)
+}
+
+// Copy `value` into a hook-provided buffer using Hook API conventions
+unsafe fn write_out(value: Option<&Vec<u8>>, ptr: *mut u8, len: i32) -> i32 {
+    let value = match value {
+        Some(value) => value,
+        None => return DOESNT_EXIST,
+    };
+
+    if value.len() > len.max(0) as usize {
+        return TOO_SMALL;
+    }
+
+    std::ptr::copy_nonoverlapping(value.as_ptr(), ptr, value.len());
+    value.len() as i32
+}
+
+#[no_mangle]
+unsafe extern "C" fn otxn_type() -> i32 {
+    with(|host| host.tx_type)
+}
+
+// Field 0 reads the whole transaction
+#[no_mangle]
+unsafe extern "C" fn otxn_slot(slot: i32, data: *mut u8, len: i32) -> i32 {
+    if slot == fields::SF_TRANSACTION {
+        return with(|host| write_out(Some(&serialize(&host.fields)), data, len));
+    }
+    with(|host| write_out(host.fields.get(&slot), data, len))
+}
+
+// Serialize transaction fields in canonical order. Fields are kept the way
+// hooks read them, so variable-length values get their length prefix back.
+fn serialize(tx: &BTreeMap<i32, Vec<u8>>) -> Vec<u8> {
+    let mut out = Vec::new();
+    for (&field, value) in tx {
+        let (type_code, field_code) = ((field >> 16) as u8, field as u8);
+        match (type_code < 16, field_code < 16) {
+            (true, true) => out.push(type_code << 4 | field_code),
+            (true, false) => out.extend_from_slice(&[type_code << 4, field_code]),
+            (false, true) => out.extend_from_slice(&[field_code, type_code]),
+            (false, false) => out.extend_from_slice(&[0, type_code, field_code]),
+        }
+
+        if matches!(field >> 16, fields::ST_BLOB | fields::ST_ACCOUNT | fields::ST_VECTOR256) {
+            match value.len() {
+                len @ 0..=192 => out.push(len as u8),
+                len => {
+                    let len = len - 193;
+                    out.extend_from_slice(&[193 + (len >> 8) as u8, len as u8]);
+                }
+            }
+        }
+        out.extend_from_slice(value);
+    }
+    out
+}
+
+#[no_mangle]
+unsafe extern "C" fn meta_slot(slot: i32, data: *mut u8, len: i32) -> i32 {
+    with(|host| write_out(host.meta.get(&slot), data, len))
+}
+
+#[no_mangle]
+unsafe extern "C" fn slot_set(slot: i32, data: *const u8, len: i32) -> i32 {
+    let value = bytes(data, len).to_vec();
+    with(|host| host.fields.insert(slot, value));
+    len
+}
+
+#[no_mangle]
+unsafe extern "C" fn accept(msg: *const u8, len: i32) -> i32 {
+    let msg = bytes(msg, len).to_vec();
+    with(|host| host.outcome = Some(Outcome::Accepted(msg)));
+    0
+}
+
+#[no_mangle]
+unsafe extern "C" fn reject(msg: *const u8, len: i32) -> i32 {
+    let msg = bytes(msg, len).to_vec();
+    with(|host| host.outcome = Some(Outcome::Rejected(msg)));
+    0
+}
+
+#[no_mangle]
+unsafe extern "C" fn trace(msg: *const u8, msg_len: i32, data: *const u8, data_len: i32,
+                               _as_hex: i32) -> i32 {
+    let msg = bytes(msg, msg_len).to_vec();
+    let data = bytes(data, data_len).to_vec();
+    with(|host| host.traces.push((msg, data)));
+    0
+}
+
+#[no_mangle]
+unsafe extern "C" fn ledger_seq() -> u64 {
+    with(|host| host.ledger_seq)
+}
+
+#[no_mangle]
+unsafe extern "C" fn otxn_id(data: *mut u8, len: i32, _flags: u32) -> i32 {
+    with(|host| write_out(Some(&host.otxn_id.to_vec()), data, len))
+}
+
+// Keylets are [type u16][32-byte id]. The simulator derives the id from the
+// account and sequence directly instead of hashing them.
+#[no_mangle]
+unsafe extern "C" fn util_keylet(data: *mut u8, len: i32, keylet_type: u32,
+                                     account: *const u8, account_len: i32, sequence: u32) -> i32 {
+    let mut keylet = (keylet_type as u16).to_be_bytes().to_vec();
+    keylet.extend_from_slice(bytes(account, account_len));
+    keylet.extend_from_slice(&sequence.to_be_bytes());
+    keylet.resize(34, 0);
+    write_out(Some(&keylet), data, len)
+}
+
+// Trust line keylets don't depend on which of the two accounts is named
+// first, like on ledger
+pub fn line_keylet(a: &[u8; 20], b: &[u8; 20], currency: &[u8; 20]) -> Vec<u8> {
+    let (low, high) = if a <= b { (a, b) } else { (b, a) };
+    let mut keylet = std::vec![0, 9];
+    keylet.extend_from_slice(&low[..10]);
+    keylet.extend_from_slice(&high[..10]);
+    keylet.extend_from_slice(&currency[8..]);
+    keylet
+}
+
+#[no_mangle]
+unsafe extern "C" fn util_keylet_line(data: *mut u8, len: i32, account: *const u8, issuer: *const u8,
+                                      currency: *const u8) -> i32 {
+    let [account, issuer, currency] = [account, issuer, currency].map(|ptr| {
+        <[u8; 20]>::try_from(bytes(ptr, 20)).unwrap()
+    });
+    write_out(Some(&line_keylet(&account, &issuer, &currency)), data, len)
+}
+
+#[no_mangle]
+unsafe extern "C" fn keylet_field(keylet: *const u8, keylet_len: i32, field: i32,
+                                      data: *mut u8, len: i32) -> i32 {
+    let keylet = bytes(keylet, keylet_len);
+    with(|host| match host.ledger.get(keylet) {
+        Some(object) => write_out(object.get(&field), data, len),
+        None => DOESNT_EXIST,
+    })
+}
+
+// Signatures
+// The simulator stands in for real signatures with a keyed checksum: sign()
+// produces the only signature util_verify accepts for a key and message
+
+pub const SIGNATURE_LEN: usize = 64;
+
+pub fn sign(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut signature = std::vec![0u8; SIGNATURE_LEN];
+    for (i, byte) in key.iter().chain(data).enumerate() {
+        let at = i % SIGNATURE_LEN;
+        signature[at] = signature[at].rotate_left(3) ^ byte ^ i as u8;
+    }
+    signature
+}
+
+#[no_mangle]
+unsafe extern "C" fn util_verify(data: *const u8, data_len: i32, signature: *const u8, signature_len: i32,
+                                 key: *const u8, key_len: i32) -> i32 {
+    let key = bytes(key, key_len);
+    (!key.is_empty() && bytes(signature, signature_len) == sign(key, bytes(data, data_len))) as i32
+}
+
+// Hashing
+// The simulator stands in for SHA-512Half with four FNV-1a lanes, seeded
+// apart, which is as collision-free as the tests need
+
+pub const HASH_LEN: usize = 32;
+
+pub fn sha512h(data: &[u8]) -> [u8; HASH_LEN] {
+    let mut hash = [0u8; HASH_LEN];
+    for (lane, out) in hash.chunks_mut(8).enumerate() {
+        let mut state = 0xCBF2_9CE4_8422_2325u64 ^ lane as u64;
+        for byte in data {
+            state = (state ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01B3);
+        }
+        out.copy_from_slice(&state.to_be_bytes());
+    }
+    hash
+}
+
+#[no_mangle]
+unsafe extern "C" fn util_sha512h(hash: *mut u8, hash_len: i32, data: *const u8, data_len: i32) -> i32 {
+    write_out(Some(&sha512h(bytes(data, data_len)).to_vec()), hash, hash_len)
+}
+
+// Emission
+// EmitDetails is a placeholder object and every emitted transaction costs a
+// flat EMIT_FEE drops
+
+pub const EMIT_FEE: i64 = 10;
+const EMIT_DETAILS: [u8; 3] = [0xED, 0x00, 0xE1];
+
+#[no_mangle]
+unsafe extern "C" fn etxn_reserve(count: u32) -> i32 {
+    count as i32
+}
+
+#[no_mangle]
+unsafe extern "C" fn etxn_details(data: *mut u8, len: i32) -> i32 {
+    write_out(Some(&EMIT_DETAILS.to_vec()), data, len)
+}
+
+#[no_mangle]
+unsafe extern "C" fn etxn_fee_base(_tx: *const u8, _tx_len: i32) -> i64 {
+    EMIT_FEE
+}
+
+#[no_mangle]
+unsafe extern "C" fn emit(hash: *mut u8, hash_len: i32, tx: *const u8, tx_len: i32) -> i32 {
+    let tx = bytes(tx, tx_len).to_vec();
+    with(|host| {
+        if host.emit_fails {
+            return -1;
+        }
+        host.emitted.push(tx);
+        write_out(Some(&std::vec![0xE0; 32]), hash, hash_len)
+    })
+}
+
+#[no_mangle]
+unsafe extern "C" fn hook_account(account: *mut u8) -> i32 {
+    with(|host| std::ptr::copy_nonoverlapping(host.hook_account.as_ptr(), account, 20));
+    20
+}
+
+#[no_mangle]
+unsafe extern "C" fn hook_param(name: *const u8, name_len: i32, data: *mut u8, len: i32) -> i32 {
+    let name = bytes(name, name_len);
+    with(|host| write_out(host.hook_params.get(name), data, len))
+}
+
+#[no_mangle]
+unsafe extern "C" fn otxn_param(name: *const u8, name_len: i32, data: *mut u8, len: i32) -> i32 {
+    let name = bytes(name, name_len);
+    with(|host| write_out(host.otxn_params.get(name), data, len))
+}
+
+#[no_mangle]
+unsafe extern "C" fn state(key: *const u8, key_len: i32, data: *mut u8, len: i32) -> i32 {
+    let key = bytes(key, key_len);
+    with(|host| write_out(host.state.get(key), data, len))
+}
+
+#[no_mangle]
+unsafe extern "C" fn state_set(key: *const u8, key_len: i32, data: *const u8, len: i32) -> i32 {
+    let key = bytes(key, key_len).to_vec();
+    let value = bytes(data, len).to_vec();
+
+    // Empty values delete the entry
+    with(|host| {
+        if value.is_empty() {
+            host.state.remove(&key);
+        } else {
+            host.state.insert(key, value);
+        }
+    });
+    len.max(0)
+}
+
+#[no_mangle]
+unsafe extern "C" fn state_foreign(data: *mut u8, len: i32, key: *const u8, key_len: i32,
+                                   namespace: *const u8, namespace_len: i32,
+                                   account: *const u8, account_len: i32) -> i32 {
+    let entry = (bytes(account, account_len).to_vec(), bytes(namespace, namespace_len).to_vec(),
+                 bytes(key, key_len).to_vec());
+    with(|host| {
+        if host.foreign_fails {
+            return INTERNAL_ERROR;
+        }
+        write_out(host.foreign_state.get(&entry), data, len)
+    })
+}
+
+// Guard violations abort the hook on ledger. Panicking can't unwind out of
+// a host function, so the simulator records the violation and run() reports it
+#[no_mangle]
+unsafe extern "C" fn _g(id: u32, max_iterations: u32) -> i32 {
+    with(|host| {
+        let count = host.guards.entry(id).or_insert(0);
+        *count += 1;
+        if *count > max_iterations && host.guard_violation.is_none() {
+            host.guard_violation = Some((id, max_iterations));
+        }
+    });
+    1
+}
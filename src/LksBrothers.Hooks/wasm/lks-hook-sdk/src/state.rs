@@ -0,0 +1,458 @@
+// Hook state helpers
+// Hook state is a key/value store owned by the hook account. Keys are 32 bytes
+// and every LKS entry starts with the "LKS" marker followed by a namespace byte
+//
+// Values start with the version of the layout they were written in, so a
+// redeployed wasm can still read what an older one left behind: load() hands
+// out the value without its version byte and upgrades older entries to the
+// current layout, writing them back as it goes. Entries from before values
+// were versioned (v0) carry no version byte and are told apart by length,
+// which every namespace declares in v0_layout(). Config overrides, whose
+// length varies with the parameter, can't be told apart that way: they moved
+// to NS_OVERRIDE, and the v0 ones left in NS_CONFIG are read with load_v0().
+//
+// Values are typed entries (entry.rs), loaded and stored whole with
+// load_entry() and store_entry(); load() and store() move the raw bytes.
+
+use core::mem::MaybeUninit;
+use core::slice;
+
+use crate::api::{state, state_foreign, state_set, util_sha512h};
+use crate::bytes;
+use crate::entry::{self, Entry};
+use crate::error::HookError;
+
+pub const KEY_LEN: usize = 32;
+
+// Largest value a state entry can hold, including its version byte
+pub const MAX_VALUE_LEN: usize = 256;
+
+// Version of the layouts this wasm writes. A wasm that changes the layout of
+// an entry bumps it and converts older values in upgrade().
+pub const VERSION: u8 = 1;
+
+// Length of the values of a namespace, without the version byte
+#[derive(Clone, Copy)]
+pub enum Layout {
+    Fixed(usize),
+    // One or more records of this length, like tier tables
+    Records(usize),
+}
+
+impl Layout {
+    pub(crate) fn fits(self, len: usize) -> bool {
+        match self {
+            Layout::Fixed(value_len) => len == value_len,
+            Layout::Records(record_len) => record_len > 0 && len > 0 && len.is_multiple_of(record_len),
+        }
+    }
+}
+
+const KEY_MARKER: &[u8] = b"LKS";
+
+// Hook API return code for entries that don't exist
+const DOESNT_EXIST: i32 = -5;
+
+// Guard budget for key derivation: parts per key and keys derived per
+// hook execution (pruning derives a few per step and every parameter read
+// one more)
+const MAX_KEY_PARTS: u32 = 4;
+const MAX_KEYS_PER_CALL: u32 = 256;
+
+// Longest id hashed_key() takes, and the length of the hash standing in for
+// it
+const MAX_HASHED_ID: usize = 64;
+const HASH_LEN: usize = 32;
+
+// Hooks keep their state under a 32-byte HookNamespace
+pub const NAMESPACE_LEN: usize = 32;
+
+// Namespaces for LKS state entries
+pub const NS_REGISTRY: u8 = 0x01;
+pub const NS_ESCROW: u8 = 0x02;
+pub const NS_CHANNEL: u8 = 0x03;
+// No longer written: TrustSet markers of older wasms
+pub const NS_TRUSTLINE: u8 = 0x04;
+pub const NS_ACCOUNT_LIMIT: u8 = 0x05;
+pub const NS_PAIR_LIMIT: u8 = 0x06;
+pub const NS_BUDGET: u8 = 0x07;
+pub const NS_RECEIPT: u8 = 0x08;
+pub const NS_RECEIPT_HEAD: u8 = 0x09;
+pub const NS_PRUNE_INDEX: u8 = 0x0A;
+pub const NS_PRUNE_COUNT: u8 = 0x0B;
+pub const NS_PRUNE_CURSOR: u8 = 0x0C;
+pub const NS_SEEN: u8 = 0x0D;
+// Only read: config overrides of older wasms, in v0 layout
+pub const NS_CONFIG: u8 = 0x0E;
+pub const NS_CURRENCY: u8 = 0x0F;
+pub const NS_NFT_OFFER: u8 = 0x10;
+pub const NS_BREAKER: u8 = 0x11;
+pub const NS_SETTLEMENT: u8 = 0x12;
+pub const NS_KYC: u8 = 0x13;
+// Written by the staking hook: [staked micro-LKS u64 big-endian]
+pub const NS_STAKE: u8 = 0x14;
+pub const NS_PAUSE: u8 = 0x15;
+pub const NS_FOUNDATION: u8 = 0x16;
+pub const NS_VOUCHER: u8 = 0x17;
+pub const NS_REFERRAL: u8 = 0x18;
+pub const NS_REFERRER: u8 = 0x19;
+pub const NS_STATS: u8 = 0x1A;
+pub const NS_ONBOARDING: u8 = 0x1B;
+pub const NS_ACTIVITY: u8 = 0x1C;
+pub const NS_EXCHANGE: u8 = 0x1D;
+pub const NS_CHECK: u8 = 0x1E;
+pub const NS_BURST: u8 = 0x1F;
+pub const NS_MAKER: u8 = 0x20;
+pub const NS_DENYLIST: u8 = 0x21;
+pub const NS_OVERRIDE: u8 = 0x22;
+
+// Derive the state key of an LKS entry: the "LKS" marker, the namespace byte
+// and the concatenated id parts. Ids longer than the 28 bytes available are
+// truncated, so callers put their most distinguishing part first.
+// Every LKS state key is built here so the layout stays consistent.
+pub fn key(namespace: u8, parts: &[&[u8]]) -> [u8; KEY_LEN] {
+    let mut key = [0u8; KEY_LEN];
+    bytes::copy(&mut key, KEY_MARKER);
+    key[3] = namespace;
+
+    let mut pos = 4;
+    guarded_loop!(i in 0, parts.len(); max (MAX_KEY_PARTS + 1) * MAX_KEYS_PER_CALL; {
+        if let Some(part) = parts.get(i) {
+            pos += bytes::put(&mut key, pos, part);
+        }
+    });
+
+    key
+}
+
+// Derive the state key of an entry whose id doesn't fit a key, like a pair of
+// accounts: the concatenated id parts are hashed (SHA-512Half) and the hash
+// stands in for them. None if the host fails to hash.
+pub fn hashed_key(namespace: u8, parts: &[&[u8]]) -> Option<[u8; KEY_LEN]> {
+    let mut id = [0u8; MAX_HASHED_ID];
+    let mut len = 0;
+    guarded_loop!(i in 0, parts.len(); max MAX_KEY_PARTS; {
+        if let Some(part) = parts.get(i) {
+            len += bytes::put(&mut id, len, part);
+        }
+    });
+
+    let mut hash = [0u8; HASH_LEN];
+    let hashed = unsafe { util_sha512h(hash.as_mut_ptr(), HASH_LEN as i32, id.as_ptr(), len as i32) };
+    if hashed != HASH_LEN as i32 {
+        return None;
+    }
+
+    Some(key(namespace, &[&hash]))
+}
+
+// Convenience for the common per-account entry
+pub fn account_key(namespace: u8, account: &[u8; 20]) -> [u8; KEY_LEN] {
+    key(namespace, &[account])
+}
+
+// Read the value of an entry into `out`, returning the number of bytes read
+// Missing entries (and read failures) read as empty
+pub fn load(key: &[u8; KEY_LEN], out: &mut [u8]) -> usize {
+    let mut len = 0;
+    read(key, v0_layout(key[3]), &mut |value| len = bytes::copy(out, value));
+    len
+}
+
+// Read an entry written before values were versioned into `out`, returning
+// its length. Entries that don't fit `layout` read as empty.
+pub fn load_v0(key: &[u8; KEY_LEN], out: &mut [u8], layout: Layout) -> usize {
+    let result = unsafe { state(key.as_ptr(), KEY_LEN as i32, out.as_mut_ptr(), out.len() as i32) };
+    if result <= 0 || !layout.fits(result as usize) {
+        return 0;
+    }
+
+    result as usize
+}
+
+// Read an entry as `E`. Missing entries and values that don't decode as one
+// read as None.
+pub fn load_entry<E: Entry>(key: &[u8; KEY_LEN]) -> Option<E> {
+    // Decoded where the host wrote it, without copying it out first
+    let mut entry = None;
+    read_value(key, &mut |value| entry = entry::decode(value));
+    entry
+}
+
+// read() of an entry in its namespace's v0 layout, kept out of line so the
+// layout lookup isn't repeated in every load_entry()
+#[inline(never)]
+fn read_value(key: &[u8; KEY_LEN], f: &mut dyn FnMut(&[u8])) {
+    read(key, v0_layout(key[3]), f)
+}
+
+// Hand the value of an entry, in the current layout, to `f`. Missing and
+// unreadable entries never reach it.
+fn read(key: &[u8; KEY_LEN], v0: Option<Layout>, f: &mut dyn FnMut(&[u8])) {
+    // Only the bytes the host writes are read, so the buffer isn't cleared
+    // first; loads are frequent enough for that to show in the budget
+    let mut buffer = MaybeUninit::<[u8; MAX_VALUE_LEN]>::uninit();
+    let result = unsafe {
+        state(key.as_ptr(), KEY_LEN as i32, buffer.as_mut_ptr().cast(), MAX_VALUE_LEN as i32)
+    };
+    if result <= 0 {
+        return;
+    }
+    let entry = unsafe {
+        slice::from_raw_parts(buffer.as_ptr().cast::<u8>(), (result as usize).min(MAX_VALUE_LEN))
+    };
+
+    let (version, value) = match unpack(entry, v0) {
+        Some(unpacked) => unpacked,
+        None => return,
+    };
+
+    if version == VERSION {
+        return f(value);
+    }
+
+    // Values written by a newer wasm can't be read
+    if version > VERSION {
+        return;
+    }
+
+    let mut upgraded = [0u8; MAX_VALUE_LEN];
+    let len = bytes::copy(&mut upgraded, value);
+    let len = match upgrade(key[3], version, &mut upgraded, len) {
+        Some(len) => len,
+        None => return,
+    };
+    let upgraded = bytes::head(&upgraded, len);
+
+    // The read stands even if the upgraded entry can't be written back
+    let _ = store(key, upgraded);
+    f(upgraded)
+}
+
+// Read an entry of another hook's state, installed on `account` under
+// `namespace`. Its layout is the other hook's, so it is read as is.
+pub fn load_foreign(key: &[u8; KEY_LEN], namespace: &[u8; NAMESPACE_LEN], account: &[u8; 20],
+                    out: &mut [u8]) -> usize {
+    try_load_foreign(key, namespace, account, out).unwrap_or(0)
+}
+
+// load_foreign() telling a failed read from a missing entry: None when the
+// host couldn't read the other hook's state, Some(0) when the entry is
+// missing
+pub fn try_load_foreign(key: &[u8; KEY_LEN], namespace: &[u8; NAMESPACE_LEN], account: &[u8; 20],
+                        out: &mut [u8]) -> Option<usize> {
+    let result = unsafe {
+        state_foreign(out.as_mut_ptr(), out.len() as i32, key.as_ptr(), KEY_LEN as i32,
+                      namespace.as_ptr(), NAMESPACE_LEN as i32, account.as_ptr(), 20)
+    };
+
+    match result {
+        0 | DOESNT_EXIST => Some(0),
+        result if result < 0 => None,
+        result => Some((result as usize).min(out.len())),
+    }
+}
+
+// Read an entry of a sibling LKS hook, installed on `account` under
+// `namespace`. Siblings write their entries with this module, so the entry
+// is read like load() reads our own, except that older entries can't be
+// written back.
+pub fn load_sibling(key: &[u8; KEY_LEN], namespace: &[u8; NAMESPACE_LEN], account: &[u8; 20],
+                    out: &mut [u8]) -> usize {
+    try_load_sibling(key, namespace, account, out).unwrap_or(0)
+}
+
+// load_sibling() telling a failed read from a missing entry, as
+// try_load_foreign() does
+pub fn try_load_sibling(key: &[u8; KEY_LEN], namespace: &[u8; NAMESPACE_LEN], account: &[u8; 20],
+                        out: &mut [u8]) -> Option<usize> {
+    let mut entry = [0u8; MAX_VALUE_LEN];
+    let len = try_load_foreign(key, namespace, account, &mut entry)?;
+    let (version, value) = match unpack(bytes::head(&entry, len), v0_layout(key[3])) {
+        Some(unpacked) => unpacked,
+        None => return Some(0),
+    };
+
+    if version == VERSION {
+        return Some(bytes::copy(out, value));
+    }
+    if version > VERSION {
+        return Some(0);
+    }
+
+    let mut upgraded = [0u8; MAX_VALUE_LEN];
+    let len = bytes::copy(&mut upgraded, value);
+    match upgrade(key[3], version, &mut upgraded, len) {
+        Some(len) => Some(bytes::copy(out, bytes::head(&upgraded, len))),
+        None => Some(0),
+    }
+}
+
+// Split a stored entry of `namespace` into its layout version and value, as
+// load() reads it
+pub fn unpack_entry(namespace: u8, entry: &[u8]) -> Option<(u8, &[u8])> {
+    unpack(entry, v0_layout(namespace))
+}
+
+// Split a stored entry into its layout version and value. Entries matching
+// the v0 layout of their namespace have no version byte.
+fn unpack(entry: &[u8], v0: Option<Layout>) -> Option<(u8, &[u8])> {
+    match (v0, entry.first()) {
+        (Some(layout), _) if layout.fits(entry.len()) => Some((0, entry)),
+        (_, Some(&version)) => Some((version, bytes::tail(entry, 1))),
+        _ => None,
+    }
+}
+
+// Write `data` as the value of an entry, in the current layout version
+pub fn store(key: &[u8; KEY_LEN], data: &[u8]) -> Result<(), HookError> {
+    if data.is_empty() {
+        return erase(key);
+    }
+    if data.len() >= MAX_VALUE_LEN {
+        return Err(HookError::StateWriteFailed);
+    }
+
+    let mut entry = [0u8; MAX_VALUE_LEN];
+    entry[0] = VERSION;
+    let len = 1 + bytes::put(&mut entry, 1, data);
+    set(key, bytes::head(&entry, len))
+}
+
+// Write `entry` as the value of an entry
+// Encoded straight after the version byte, without going through store()
+pub fn store_entry<E: Entry>(key: &[u8; KEY_LEN], entry: &E) -> Result<(), HookError> {
+    let mut versioned = [0u8; 1 + entry::MAX_ENTRY_LEN];
+    versioned[0] = VERSION;
+    let (_, value) = versioned.split_at_mut(1);
+    let len = entry::encode(entry, value)?.len();
+    if len == 0 {
+        return erase(key);
+    }
+    set(key, bytes::head(&versioned, 1 + len))
+}
+
+// Writing an empty value deletes the entry and releases its reserve
+pub fn erase(key: &[u8; KEY_LEN]) -> Result<(), HookError> {
+    set(key, &[])
+}
+
+fn set(key: &[u8; KEY_LEN], entry: &[u8]) -> Result<(), HookError> {
+    let result = unsafe {
+        state_set(key.as_ptr(), KEY_LEN as i32, entry.as_ptr(), entry.len() as i32)
+    };
+
+    if result < 0 {
+        return Err(HookError::StateWriteFailed);
+    }
+
+    Ok(())
+}
+
+// Value lengths of each namespace's entries before values were versioned
+// NS_CONFIG has no versioned entries to tell apart and is read with
+// load_v0(); NS_OVERRIDE has no v0 entries.
+// Inlined so loads of a known namespace resolve it at compile time.
+#[inline(always)]
+pub(crate) fn v0_layout(namespace: u8) -> Option<Layout> {
+    let len = match namespace {
+        NS_REGISTRY | NS_ESCROW | NS_CHANNEL | NS_TRUSTLINE | NS_NFT_OFFER | NS_PAUSE => 1,
+        NS_RECEIPT_HEAD | NS_SEEN | NS_KYC => 4,
+        NS_PRUNE_COUNT => 8,
+        NS_ACCOUNT_LIMIT | NS_PAIR_LIMIT | NS_BUDGET | NS_PRUNE_CURSOR => 12,
+        NS_BREAKER => 13,
+        NS_FOUNDATION => 20,
+        NS_CURRENCY | NS_SETTLEMENT => 21,
+        NS_PRUNE_INDEX => KEY_LEN,
+        NS_RECEIPT => 37,
+        _ => return None,
+    };
+
+    Some(Layout::Fixed(len))
+}
+
+// Convert a value written in an older `version` to the current layout in
+// place, returning its new length. No layout has changed since values were
+// versioned, so v0 values only gain their version byte.
+fn upgrade(_namespace: u8, version: u8, _value: &mut [u8], len: usize) -> Option<usize> {
+    match version {
+        0 => Some(len),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim;
+
+    #[test]
+    fn hashes_ids_longer_than_a_key() {
+        sim::reset();
+        let low = [0x11u8; 20];
+        let mut high = [0x22u8; 20];
+        let pair = hashed_key(NS_PAIR_LIMIT, &[&low, &high]).unwrap();
+        assert_eq!(&pair[..4], &key(NS_PAIR_LIMIT, &[])[..4]);
+        assert_eq!(hashed_key(NS_PAIR_LIMIT, &[&low, &high]), Some(pair));
+
+        // Plain keys drop what doesn't fit; hashed ones tell it apart
+        high[19] = 0x23;
+        assert_eq!(key(NS_PAIR_LIMIT, &[&low, &[0x22; 20]]), key(NS_PAIR_LIMIT, &[&low, &high]));
+        assert_ne!(hashed_key(NS_PAIR_LIMIT, &[&low, &high]), Some(pair));
+    }
+
+    #[test]
+    fn upgrades_unversioned_entries_on_read() {
+        sim::reset();
+        let counter = key(NS_BUDGET, &[]);
+        let v0 = [0u8, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 42];
+        sim::with(|host| host.state.insert(counter.to_vec(), v0.to_vec()));
+
+        let mut out = [0u8; 12];
+        assert_eq!(load(&counter, &mut out), 12);
+        assert_eq!(out, v0);
+
+        // Written back in the current layout, which reads the same
+        let mut entry = std::vec![VERSION];
+        entry.extend_from_slice(&v0);
+        assert_eq!(sim::with(|host| host.state[counter.as_slice()].clone()), entry);
+        assert_eq!(load(&counter, &mut out), 12);
+        assert_eq!(out, v0);
+    }
+
+    #[test]
+    fn reads_v0_entries_only_in_their_layout() {
+        sim::reset();
+        let tiers = key(NS_CONFIG, &[b"TIERS"]);
+        let records = Layout::Records(9);
+        let mut out = [0u8; 72];
+
+        // A v0 table of two tiers
+        sim::with(|host| host.state.insert(tiers.to_vec(), std::vec![7; 18]));
+        assert_eq!(load_v0(&tiers, &mut out, records), 18);
+
+        // Entries of any other length read as missing, however they start
+        sim::with(|host| host.state.insert(tiers.to_vec(), std::vec![VERSION, 7, 7, 7, 7, 7, 7, 7, 7, 7]));
+        assert_eq!(load_v0(&tiers, &mut out, records), 0);
+        sim::with(|host| host.state.insert(tiers.to_vec(), std::vec![7; 73]));
+        assert_eq!(load_v0(&tiers, &mut out, records), 0);
+    }
+
+    #[test]
+    fn leaves_values_of_newer_wasms_untouched() {
+        sim::reset();
+        let tiers = key(NS_OVERRIDE, &[b"TIERS"]);
+        let mut out = [0u8; 72];
+
+        sim::with(|host| host.state.insert(tiers.to_vec(), std::vec![VERSION, 7, 7, 7, 7, 7, 7, 7, 7, 7]));
+        assert_eq!(load(&tiers, &mut out), 9);
+
+        // Values written by a newer wasm read as missing and stay untouched
+        sim::with(|host| host.state.insert(tiers.to_vec(), std::vec![VERSION + 1, 7, 7, 7, 7, 7, 7, 7, 7, 7]));
+        assert_eq!(load(&tiers, &mut out), 0);
+        assert_eq!(sim::with(|host| host.state[tiers.as_slice()][0]), VERSION + 1);
+
+        store(&tiers, &[]).unwrap();
+        assert!(sim::with(|host| host.state.is_empty()));
+    }
+}
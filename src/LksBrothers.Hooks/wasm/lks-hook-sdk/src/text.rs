@@ -0,0 +1,91 @@
+// Allocation-free text building for trace messages
+// The hook has no allocator and core::fmt would bloat the wasm, so messages
+// that carry accounts or numbers are assembled in a fixed buffer. Input that
+// doesn't fit is dropped, so a message is truncated rather than failing.
+
+// Guard budgets per hook execution: hex-encoded bytes in total, and calls
+// to each of hex() and decimal()
+const MAX_HEX_BYTES: u32 = 64;
+const MAX_CALLS: u32 = 8;
+
+// Trace messages longer than this are cut off by the host anyway
+pub const MESSAGE_LEN: usize = 128;
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+pub struct Text<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> Text<N> {
+    pub fn new() -> Self {
+        Text { buf: [0u8; N], len: 0 }
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) -> &mut Self {
+        self.len += crate::bytes::put(&mut self.buf, self.len, bytes);
+        self
+    }
+
+    // Upper-case hex, two digits per byte
+    pub fn hex(&mut self, bytes: &[u8]) -> &mut Self {
+        guarded_loop!(i in 0, bytes.len(); max MAX_HEX_BYTES + MAX_CALLS; {
+            if let Some(&byte) = bytes.get(i) {
+                let byte = byte as usize;
+                self.push(&[HEX_DIGITS[byte >> 4], HEX_DIGITS[byte & 0x0F]]);
+            }
+        });
+        self
+    }
+
+    pub fn decimal(&mut self, value: u64) -> &mut Self {
+        // u64::MAX has 20 digits
+        let mut digits = [0u8; 20];
+        let mut start = digits.len();
+        let mut rest = value;
+
+        guarded_while!(max 21 * MAX_CALLS; start == digits.len() || rest > 0; {
+            start -= 1;
+            if let Some(digit) = digits.get_mut(start) {
+                *digit = b'0' + (rest % 10) as u8;
+            }
+            rest /= 10;
+        });
+
+        self.push(crate::bytes::tail(&digits, start))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.buf.get(..self.len).unwrap_or(&[])
+    }
+}
+
+impl<const N: usize> Default for Text<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_hex_and_decimals() {
+        let mut text = Text::<32>::new();
+        text.push(b"acct ").hex(&[0x0A, 0xBC]).push(b" fee ").decimal(0);
+        assert_eq!(text.as_bytes(), b"acct 0ABC fee 0");
+
+        let mut text = Text::<32>::new();
+        text.decimal(u64::MAX);
+        assert_eq!(text.as_bytes(), b"18446744073709551615");
+    }
+
+    #[test]
+    fn truncates_at_capacity() {
+        let mut text = Text::<6>::new();
+        text.push(b"fee ").decimal(1234);
+        assert_eq!(text.as_bytes(), b"fee 12");
+    }
+}
@@ -0,0 +1,267 @@
+// Generic walk over the fields of the originating transaction
+// Policies that look at arbitrary fields (is there a SendMax, does the
+// transaction carry EmitDetails) read the whole serialized transaction once
+// and walk its top-level fields, instead of reading each field by code.
+//
+// Fields are yielded as (field code, value). Variable-length values come
+// without their length prefix, like read_raw returns them; objects, arrays
+// and path sets come with their contents up to and including their end
+// marker. Nested fields are skipped over, not yielded.
+
+use crate::amount;
+use crate::bytes;
+use crate::error::HookError;
+use crate::fields::{self, FieldId, ST_ACCOUNT, ST_AMOUNT, ST_ARRAY, ST_BLOB, ST_HASH128, ST_HASH160,
+                    ST_HASH256, ST_OBJECT, ST_PATHSET, ST_UINT16, ST_UINT192, ST_UINT32, ST_UINT384,
+                    ST_UINT512, ST_UINT64, ST_UINT8, ST_VECTOR256};
+
+// Largest serialized transaction read: a full Signers array, 1 KB of memos
+// and a full PathSet, plus the fixed fields
+pub const MAX_TRANSACTION_LEN: usize = 10240;
+
+// Upper bounds per walk. A transaction has a few dozen top-level fields; the
+// nested fields of a full Signers array, memos and paths stay well below
+// MAX_STEPS. Larger transactions fail to parse rather than letting the
+// guards abort the hook. The guards budget for one walk per hook execution.
+const MAX_FIELDS: u32 = 64;
+const MAX_STEPS: u32 = 768;
+
+// Read the serialized originating transaction into `out`
+pub fn read_transaction(out: &mut [u8]) -> Result<&[u8], HookError> {
+    fields::read_raw(fields::SF_TRANSACTION, out)?.ok_or(HookError::FieldReadFailed)
+}
+
+// Cursor over the top-level fields of a serialized transaction. Loops
+// calling next_field must be guarded; for_each_field does that.
+pub struct FieldIter<'a> {
+    data: &'a [u8],
+    pos: usize,
+    // Fields and nested items walked so far
+    fields: u32,
+    steps: u32,
+}
+
+impl<'a> FieldIter<'a> {
+    pub fn new(data: &'a [u8]) -> FieldIter<'a> {
+        FieldIter { data, pos: 0, fields: 0, steps: 0 }
+    }
+
+    // The next field, None at the end of the transaction
+    pub fn next_field(&mut self) -> Result<Option<(FieldId, &'a [u8])>, HookError> {
+        if self.pos >= self.data.len() {
+            return Ok(None);
+        }
+        if self.fields == MAX_FIELDS {
+            return Err(HookError::FieldReadFailed);
+        }
+        self.fields += 1;
+
+        let (type_code, field_code, header_len) =
+            fields::decode_field_header(bytes::tail(self.data, self.pos)).ok_or(HookError::FieldReadFailed)?;
+        self.pos += header_len;
+        let rest = bytes::tail(self.data, self.pos);
+
+        let (start, len) = match type_code {
+            ST_OBJECT | ST_ARRAY => (0, self.container_len(rest)?),
+            ST_PATHSET => (0, self.path_set_len(rest)?),
+            _ => value_extent(type_code, rest)?,
+        };
+
+        let value = bytes::tail(rest, start).get(..len).ok_or(HookError::FieldReadFailed)?;
+        self.pos += start + len;
+        Ok(Some(((type_code << 16) | field_code, value)))
+    }
+
+    fn step(&mut self) -> Result<(), HookError> {
+        if self.steps == MAX_STEPS {
+            return Err(HookError::FieldReadFailed);
+        }
+        self.steps += 1;
+        Ok(())
+    }
+
+    // Length of the contents of an object or array up to and including the
+    // end marker closing it
+    fn container_len(&mut self, data: &[u8]) -> Result<usize, HookError> {
+        let mut pos = 0;
+        let mut depth = 1u32;
+
+        guarded_while!(max MAX_STEPS; depth > 0; {
+            self.step()?;
+            let (type_code, field_code, header_len) =
+                fields::decode_field_header(bytes::tail(data, pos)).ok_or(HookError::FieldReadFailed)?;
+            pos += header_len;
+
+            if (type_code, field_code) == fields::OBJECT_END || (type_code, field_code) == fields::ARRAY_END {
+                depth -= 1;
+            } else if type_code == ST_OBJECT || type_code == ST_ARRAY {
+                depth += 1;
+            } else if type_code == ST_PATHSET {
+                pos += self.path_set_len(bytes::tail(data, pos))?;
+            } else {
+                let (start, len) = value_extent(type_code, bytes::tail(data, pos))?;
+                pos += start + len;
+            }
+        });
+
+        if pos > data.len() {
+            return Err(HookError::FieldReadFailed);
+        }
+        Ok(pos)
+    }
+
+    // Length of a PathSet up to and including its 0x00 terminator. Each step
+    // is a type byte followed by 20 bytes per bit set of account, currency
+    // and issuer; paths are separated by 0xFF.
+    fn path_set_len(&mut self, data: &[u8]) -> Result<usize, HookError> {
+        let mut pos = 0;
+        let mut ended = false;
+
+        guarded_while!(max MAX_STEPS; !ended; {
+            self.step()?;
+            let step_type = *data.get(pos).ok_or(HookError::FieldReadFailed)?;
+            pos += 1;
+
+            match step_type {
+                0x00 => ended = true,
+                0xFF => {}
+                _ if step_type & !0x31 == 0 => pos += 20 * step_type.count_ones() as usize,
+                _ => return Err(HookError::FieldReadFailed),
+            }
+        });
+
+        if pos > data.len() {
+            return Err(HookError::FieldReadFailed);
+        }
+        Ok(pos)
+    }
+}
+
+// Offset and length of a fixed-size or variable-length value at the start
+// of `data`
+fn value_extent(type_code: i32, data: &[u8]) -> Result<(usize, usize), HookError> {
+    let len = match type_code {
+        ST_UINT8 => 1,
+        ST_UINT16 => 2,
+        ST_UINT32 => 4,
+        ST_UINT64 => 8,
+        ST_HASH128 => 16,
+        ST_HASH160 => 20,
+        ST_UINT192 => 24,
+        ST_HASH256 => 32,
+        ST_UINT384 => 48,
+        ST_UINT512 => 64,
+        ST_AMOUNT => match data.first() {
+            Some(byte) if byte & 0x80 != 0 => amount::ISSUED_LEN,
+            _ => amount::NATIVE_LEN,
+        },
+        ST_BLOB | ST_ACCOUNT | ST_VECTOR256 => {
+            let (length, vl_len) = fields::decode_vl_length(data).ok_or(HookError::FieldReadFailed)?;
+            return Ok((vl_len, length));
+        }
+        _ => return Err(HookError::FieldReadFailed),
+    };
+
+    Ok((0, len))
+}
+
+// Walk the top-level fields of a serialized transaction, calling `f` with
+// each field code and value
+pub fn for_each_field(tx: &[u8], mut f: impl FnMut(FieldId, &[u8])) -> Result<(), HookError> {
+    let mut fields = FieldIter::new(tx);
+    let mut ended = false;
+
+    guarded_while!(max MAX_FIELDS; !ended; {
+        match fields.next_field()? {
+            Some((field, value)) => f(field, value),
+            None => ended = true,
+        }
+    });
+
+    Ok(())
+}
+
+// Whether the transaction carries `field` at the top level
+pub fn has_field(tx: &[u8], field: FieldId) -> Result<bool, HookError> {
+    let mut found = false;
+    for_each_field(tx, |code, _| found |= code == field)?;
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim;
+    use std::vec::Vec;
+
+    fn transaction() {
+        sim::reset();
+        let mut signers = std::vec![0xE0, 0x10, 0x81, 0x14];
+        signers.extend_from_slice(&[0x11; 20]);
+        signers.extend_from_slice(&[0x73, 0x02, 0xAB, 0xCD, 0xE1, 0xF1]);
+        let mut paths = std::vec![0x30];
+        paths.extend_from_slice(&[0x22; 40]);
+        paths.extend_from_slice(&[0xFF, 0x01]);
+        paths.extend_from_slice(&[0x33; 20]);
+        paths.push(0x00);
+
+        sim::with(|host| {
+            host.fields.insert(fields::SF_FEE, amount::encode_native(12).to_vec());
+            host.fields.insert(fields::SF_SEQUENCE, 7u32.to_be_bytes().to_vec());
+            host.fields.insert(fields::SF_ACCOUNT, std::vec![0xAA; 20]);
+            host.fields.insert(fields::SF_SEND_MAX, std::vec![0xD5; amount::ISSUED_LEN]);
+            host.fields.insert(fields::SF_SIGNERS, signers);
+            host.fields.insert(fields::SF_PATHS, paths);
+        });
+    }
+
+    #[test]
+    fn walks_top_level_fields_in_canonical_order() {
+        transaction();
+        let mut buffer = [0u8; MAX_TRANSACTION_LEN];
+        let tx = read_transaction(&mut buffer).unwrap();
+
+        let mut walked = Vec::new();
+        for_each_field(tx, |field, value| walked.push((field, value.to_vec()))).unwrap();
+
+        let expected: Vec<_> = sim::with(|host| host.fields.clone().into_iter().collect());
+        assert_eq!(walked, expected);
+        assert!(has_field(tx, fields::SF_SEND_MAX).unwrap());
+        assert!(!has_field(tx, fields::SF_DESTINATION).unwrap());
+    }
+
+    #[test]
+    fn rejects_truncated_transactions() {
+        transaction();
+        let mut buffer = [0u8; MAX_TRANSACTION_LEN];
+        let tx = read_transaction(&mut buffer).unwrap();
+
+        // Every cut either ends at a field boundary or fails to parse, and
+        // never trips a guard
+        for len in 1..tx.len() {
+            sim::begin();
+            let _ = for_each_field(&tx[..len], |_, _| {});
+            assert_eq!(sim::with(|host| host.guard_violation), None);
+        }
+        assert!(for_each_field(&tx[..tx.len() - 1], |_, _| {}).is_err());
+    }
+
+    #[test]
+    fn rejects_transactions_beyond_guard_budget() {
+        sim::reset();
+        let mut tx = Vec::new();
+        for _ in 0..=MAX_FIELDS {
+            tx.extend_from_slice(&[0x24, 0, 0, 0, 7]);
+        }
+        assert!(for_each_field(&tx, |_, _| {}).is_err());
+        assert_eq!(sim::with(|host| host.guard_violation), None);
+
+        // Deeply nested empty objects count against the steps
+        sim::begin();
+        let mut nested = std::vec![0xEA; MAX_STEPS as usize + 1];
+        nested.push(0xE1);
+        assert!(for_each_field(&nested, |_, _| {}).is_err());
+
+        assert_eq!(sim::with(|host| host.guard_violation), None);
+    }
+}
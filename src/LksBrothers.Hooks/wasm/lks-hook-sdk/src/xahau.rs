@@ -0,0 +1,60 @@
+// Xahau Hooks API v1 bindings
+// The functions as the Xahau runtime exports them to hooks. Hook code doesn't
+// call these directly: with the xahau feature the functions in api.rs are
+// implemented on top of them, so the same hook logic builds for both ABIs.
+// Every pointer is a 32-bit linear memory address and every call returns
+// a count of bytes or a negative error code.
+
+// Error code for calls a hook can't make
+pub const NOT_IMPLEMENTED: i64 = -14;
+
+// util_keylet type of trust lines
+pub const KEYLET_LINE: u32 = 9;
+
+extern "C" {
+    pub fn accept(read_ptr: *const u8, read_len: u32, error_code: i64) -> i64;
+    pub fn rollback(read_ptr: *const u8, read_len: u32, error_code: i64) -> i64;
+    pub fn trace(mread_ptr: *const u8, mread_len: u32, dread_ptr: *const u8, dread_len: u32,
+                 as_hex: u32) -> i64;
+    pub fn ledger_seq() -> i64;
+
+    pub fn otxn_type() -> i64;
+    pub fn otxn_field(write_ptr: *mut u8, write_len: u32, field_id: u32) -> i64;
+    pub fn otxn_id(write_ptr: *mut u8, write_len: u32, flags: u32) -> i64;
+    pub fn otxn_param(write_ptr: *mut u8, write_len: u32, read_ptr: *const u8, read_len: u32) -> i64;
+    pub fn hook_param(write_ptr: *mut u8, write_len: u32, read_ptr: *const u8, read_len: u32) -> i64;
+    pub fn hook_account(write_ptr: *mut u8, write_len: u32) -> i64;
+
+    // Slots hold ledger objects and transaction metadata; slot number 0
+    // asks for the next free slot
+    pub fn otxn_slot(slot_no: u32) -> i64;
+    pub fn meta_slot(slot_no: u32) -> i64;
+    pub fn slot(write_ptr: *mut u8, write_len: u32, slot_no: u32) -> i64;
+    pub fn slot_set(read_ptr: *const u8, read_len: u32, slot_no: u32) -> i64;
+    pub fn slot_subfield(parent_slot: u32, field_id: u32, new_slot: u32) -> i64;
+    pub fn slot_clear(slot_no: u32) -> i64;
+
+    // The meaning of a to f depends on the keylet type; pointers are passed
+    // as addresses
+    pub fn util_keylet(write_ptr: *mut u8, write_len: u32, keylet_type: u32,
+                       a: u32, b: u32, c: u32, d: u32, e: u32, f: u32) -> i64;
+
+    // 1 when `sread` is a valid signature of `dread` by the public key
+    // `kread` (ed25519 keys carry the 0xED prefix), 0 otherwise
+    pub fn util_verify(dread_ptr: *const u8, dread_len: u32, sread_ptr: *const u8, sread_len: u32,
+                       kread_ptr: *const u8, kread_len: u32) -> i64;
+    // The first half of the SHA-512 of `read`, 32 bytes
+    pub fn util_sha512h(write_ptr: *mut u8, write_len: u32, read_ptr: *const u8, read_len: u32) -> i64;
+
+    pub fn etxn_reserve(count: u32) -> i64;
+    pub fn etxn_details(write_ptr: *mut u8, write_len: u32) -> i64;
+    pub fn etxn_fee_base(read_ptr: *const u8, read_len: u32) -> i64;
+    pub fn emit(write_ptr: *mut u8, write_len: u32, read_ptr: *const u8, read_len: u32) -> i64;
+
+    pub fn state(write_ptr: *mut u8, write_len: u32, kread_ptr: *const u8, kread_len: u32) -> i64;
+    pub fn state_set(read_ptr: *const u8, read_len: u32, kread_ptr: *const u8, kread_len: u32) -> i64;
+    pub fn state_foreign(write_ptr: *mut u8, write_len: u32, kread_ptr: *const u8, kread_len: u32,
+                         nread_ptr: *const u8, nread_len: u32, aread_ptr: *const u8, aread_len: u32) -> i64;
+
+    pub fn _g(id: u32, maxiter: u32) -> i32;
+}
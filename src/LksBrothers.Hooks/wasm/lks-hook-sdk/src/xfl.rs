@@ -0,0 +1,333 @@
+// XFL (XRPL floating point) arithmetic for the LKS zero-fee hook
+// Issued-currency values are stored on ledger as XFL numbers:
+//   bit 63     zero (set in serialized STAmounts to mark an issued amount)
+//   bit 62     sign, set for positive numbers
+//   bits 61-54 exponent, biased by 97
+//   bits 53-0  mantissa, normalized to [10^15, 10^16)
+// Canonical zero is all bits clear.
+
+use core::cmp::Ordering;
+
+const ISSUED_BIT: u64 = 1 << 63;
+const SIGN_BIT: u64 = 1 << 62;
+const EXPONENT_BIAS: i32 = 97;
+const MANTISSA_MASK: u64 = (1 << 54) - 1;
+
+pub const MIN_MANTISSA: u64 = 1_000_000_000_000_000;
+pub const MAX_MANTISSA: u64 = 9_999_999_999_999_999;
+pub const MIN_EXPONENT: i32 = -96;
+pub const MAX_EXPONENT: i32 = 80;
+
+// XFL operations a single hook execution may perform; bounds the guard
+// budget of the normalization loops
+const MAX_OPERATIONS: u32 = 32;
+
+// Every power of ten representable in a u64
+const POWERS_OF_TEN: [u64; 20] = [
+    1,
+    10,
+    100,
+    1_000,
+    10_000,
+    100_000,
+    1_000_000,
+    10_000_000,
+    100_000_000,
+    1_000_000_000,
+    10_000_000_000,
+    100_000_000_000,
+    1_000_000_000_000,
+    10_000_000_000_000,
+    100_000_000_000_000,
+    1_000_000_000_000_000,
+    10_000_000_000_000_000,
+    100_000_000_000_000_000,
+    1_000_000_000_000_000_000,
+    10_000_000_000_000_000_000,
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Xfl(u64);
+
+impl Xfl {
+    pub const ZERO: Xfl = Xfl(0);
+    pub const ONE: Xfl = Xfl(6089866696204910592);
+
+    // Accept only canonical encodings: zero or a normalized mantissa
+    pub fn from_raw(raw: u64) -> Option<Xfl> {
+        if raw == 0 {
+            return Some(Xfl::ZERO);
+        }
+
+        let mantissa = raw & MANTISSA_MASK;
+        if raw & ISSUED_BIT != 0 || !(MIN_MANTISSA..=MAX_MANTISSA).contains(&mantissa) {
+            return None;
+        }
+
+        Some(Xfl(raw))
+    }
+
+    pub fn to_raw(self) -> u64 {
+        self.0
+    }
+
+    // Build a number from an unnormalized mantissa and exponent. Values too
+    // small to represent round to zero; values too large are None.
+    pub fn new(negative: bool, mantissa: u64, exponent: i32) -> Option<Xfl> {
+        normalize(negative, mantissa as u128, exponent)
+    }
+
+    pub fn from_int(value: u64) -> Xfl {
+        // A u64 is always within range
+        normalize(false, value as u128, 0).unwrap_or(Xfl::ZERO)
+    }
+
+    // Decode the 8-byte value of a serialized issued STAmount
+    pub fn from_amount_value(value: &[u8; 8]) -> Option<Xfl> {
+        let raw = u64::from_be_bytes(*value);
+        if raw & ISSUED_BIT == 0 {
+            return None;
+        }
+
+        let raw = raw & !ISSUED_BIT;
+        if raw & MANTISSA_MASK == 0 {
+            return Some(Xfl::ZERO);
+        }
+
+        Xfl::from_raw(raw)
+    }
+
+    // Encode as the 8-byte value of a serialized issued STAmount
+    pub fn to_amount_value(self) -> [u8; 8] {
+        (self.0 | ISSUED_BIT).to_be_bytes()
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn is_negative(self) -> bool {
+        !self.is_zero() && self.0 & SIGN_BIT == 0
+    }
+
+    pub fn mantissa(self) -> u64 {
+        self.0 & MANTISSA_MASK
+    }
+
+    pub fn exponent(self) -> i32 {
+        if self.is_zero() {
+            return 0;
+        }
+        ((self.0 >> 54) & 0xFF) as i32 - EXPONENT_BIAS
+    }
+
+    pub fn negate(self) -> Xfl {
+        if self.is_zero() {
+            return self;
+        }
+        Xfl(self.0 ^ SIGN_BIT)
+    }
+
+    pub fn checked_add(self, other: Xfl) -> Option<Xfl> {
+        if self.is_zero() {
+            return Some(other);
+        }
+        if other.is_zero() {
+            return Some(self);
+        }
+
+        // Align on the smaller exponent by scaling up the larger one; beyond
+        // 19 digits apart the smaller operand is below the precision limit
+        let (high, low) = if self.exponent() >= other.exponent() { (self, other) } else { (other, self) };
+        let shift = (high.exponent() - low.exponent()) as usize;
+        let scale = match POWERS_OF_TEN.get(shift) {
+            Some(scale) => *scale as i128,
+            None => return Some(high),
+        };
+
+        let high_value = signed(high) * scale;
+        let sum = high_value + signed(low);
+
+        normalize(sum < 0, sum.unsigned_abs(), low.exponent())
+    }
+
+    pub fn checked_sub(self, other: Xfl) -> Option<Xfl> {
+        self.checked_add(other.negate())
+    }
+
+    pub fn checked_mul(self, other: Xfl) -> Option<Xfl> {
+        if self.is_zero() || other.is_zero() {
+            return Some(Xfl::ZERO);
+        }
+
+        let product = self.mantissa() as u128 * other.mantissa() as u128;
+        normalize(self.is_negative() != other.is_negative(), product,
+                  self.exponent() + other.exponent())
+    }
+
+    // Convert to an integer with `decimals` decimal places, truncating extra
+    // precision; e.g. drops are to_int(6) of an XRP value. Negative values
+    // and values above u64::MAX are None.
+    pub fn to_int(self, decimals: u32) -> Option<u64> {
+        if self.is_zero() {
+            return Some(0);
+        }
+        if self.is_negative() {
+            return None;
+        }
+
+        let exponent = self.exponent() + decimals as i32;
+        if exponent >= 0 {
+            let scale = POWERS_OF_TEN.get(exponent as usize)?;
+            self.mantissa().checked_mul(*scale)
+        } else {
+            match POWERS_OF_TEN.get((-exponent) as usize) {
+                Some(scale) => Some(self.mantissa() / scale),
+                None => Some(0),
+            }
+        }
+    }
+}
+
+impl Ord for Xfl {
+    fn cmp(&self, other: &Xfl) -> Ordering {
+        let sign = |x: &Xfl| if x.is_zero() { 0 } else if x.is_negative() { -1 } else { 1 };
+
+        match sign(self).cmp(&sign(other)) {
+            Ordering::Equal => {}
+            ordering => return ordering,
+        }
+
+        let magnitude = (self.exponent(), self.mantissa()).cmp(&(other.exponent(), other.mantissa()));
+        if self.is_negative() {
+            magnitude.reverse()
+        } else {
+            magnitude
+        }
+    }
+}
+
+impl PartialOrd for Xfl {
+    fn partial_cmp(&self, other: &Xfl) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn signed(value: Xfl) -> i128 {
+    let mantissa = value.mantissa() as i128;
+    if value.is_negative() { -mantissa } else { mantissa }
+}
+
+// Bring a mantissa into [10^15, 10^16), truncating digits shifted out
+fn normalize(negative: bool, mantissa: u128, exponent: i32) -> Option<Xfl> {
+    if mantissa == 0 {
+        return Some(Xfl::ZERO);
+    }
+
+    let mut mantissa = mantissa;
+    let mut exponent = exponent;
+
+    // u128 mantissas hold at most 39 digits
+    guarded_while!(max 39 * MAX_OPERATIONS; mantissa > MAX_MANTISSA as u128; {
+        mantissa /= 10;
+        exponent += 1;
+    });
+    guarded_while!(max 16 * MAX_OPERATIONS; mantissa < MIN_MANTISSA as u128; {
+        mantissa *= 10;
+        exponent -= 1;
+    });
+
+    if exponent < MIN_EXPONENT {
+        return Some(Xfl::ZERO);
+    }
+    if exponent > MAX_EXPONENT {
+        return None;
+    }
+
+    let mut raw = mantissa as u64 | (((exponent + EXPONENT_BIAS) as u64) << 54);
+    if !negative {
+        raw |= SIGN_BIT;
+    }
+
+    Some(Xfl(raw))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xfl(mantissa: u64, exponent: i32) -> Xfl {
+        Xfl::new(false, mantissa, exponent).unwrap()
+    }
+
+    #[test]
+    fn decodes_known_vectors() {
+        // float_one() and its negation as returned by the Hooks API
+        assert_eq!(Xfl::from_int(1).to_raw(), 6089866696204910592);
+        assert_eq!(Xfl::ONE.negate().to_raw(), 1478180677777522688);
+
+        let one = Xfl::from_raw(6089866696204910592).unwrap();
+        assert_eq!(one.mantissa(), MIN_MANTISSA);
+        assert_eq!(one.exponent(), -15);
+        assert!(!one.is_negative());
+        assert!(Xfl::from_raw(1478180677777522688).unwrap().is_negative());
+    }
+
+    #[test]
+    fn rejects_non_canonical_encodings() {
+        // Unnormalized mantissa
+        assert_eq!(Xfl::from_raw(SIGN_BIT | (97u64 << 54) | 5), None);
+        // Issued-amount marker bit
+        assert_eq!(Xfl::from_raw(Xfl::ONE.to_raw() | ISSUED_BIT), None);
+    }
+
+    #[test]
+    fn round_trips_amount_values() {
+        let value = xfl(12345, -2);
+        assert_eq!(Xfl::from_amount_value(&value.to_amount_value()), Some(value));
+
+        // Canonical STAmount zero and native amounts
+        assert_eq!(Xfl::from_amount_value(&ISSUED_BIT.to_be_bytes()), Some(Xfl::ZERO));
+        assert_eq!(Xfl::from_amount_value(&(SIGN_BIT | 10).to_be_bytes()), None);
+    }
+
+    #[test]
+    fn compares_across_signs_and_exponents() {
+        let small = xfl(5, -1);
+        let large = xfl(5, 3);
+
+        assert!(small < large);
+        assert!(large.negate() < small.negate());
+        assert!(small.negate() < Xfl::ZERO);
+        assert!(Xfl::ZERO < small);
+        assert_eq!(xfl(10, 0).cmp(&xfl(1, 1)), Ordering::Equal);
+    }
+
+    #[test]
+    fn adds_and_subtracts() {
+        assert_eq!(Xfl::ONE.checked_add(Xfl::ONE), Some(Xfl::from_int(2)));
+        assert_eq!(xfl(15, -1).checked_sub(xfl(5, -1)), Some(Xfl::ONE));
+        assert_eq!(Xfl::ONE.checked_sub(Xfl::ONE), Some(Xfl::ZERO));
+        assert_eq!(Xfl::ONE.checked_sub(Xfl::from_int(3)), Some(Xfl::from_int(2).negate()));
+
+        // Operands far below the precision of the other are absorbed
+        assert_eq!(xfl(1, 40).checked_add(Xfl::ONE), Some(xfl(1, 40)));
+    }
+
+    #[test]
+    fn multiplies() {
+        assert_eq!(xfl(5, -1).checked_mul(Xfl::from_int(2)), Some(Xfl::ONE));
+        assert_eq!(Xfl::ONE.negate().checked_mul(Xfl::ONE.negate()), Some(Xfl::ONE));
+        assert_eq!(xfl(1, 60).checked_mul(xfl(1, 60)), None);
+        assert_eq!(xfl(1, -60).checked_mul(xfl(1, -60)), Some(Xfl::ZERO));
+    }
+
+    #[test]
+    fn converts_to_integers() {
+        assert_eq!(xfl(1234567, -6).to_int(6), Some(1234567));
+        assert_eq!(xfl(1234567, -6).to_int(0), Some(1));
+        assert_eq!(xfl(1, -20).to_int(6), Some(0));
+        assert_eq!(xfl(1, 30).to_int(0), None);
+        assert_eq!(Xfl::ONE.negate().to_int(0), None);
+    }
+}
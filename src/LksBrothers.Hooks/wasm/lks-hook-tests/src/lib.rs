@@ -0,0 +1,223 @@
+// End-to-end tests of the compiled LKS hooks
+// Runs the release wasm of a hook in wasmi, with the Hook API backed by the
+// SDK's simulated host acting as a scripted ledger: transactions are
+// submitted one after another in advancing ledgers, and hook state, SetHook
+// parameters and ledger objects carry over between them as they do on
+// ledger. The scenarios under tests/ drive whole flows through the hook as
+// deployed, where the hook crates' unit tests run its logic natively.
+//
+// Run them with
+//   cargo test -p lks-hook-tests
+
+use std::collections::BTreeMap;
+
+use lks_hook_bench::{build_hook, Entry, Runner};
+use lks_hook_sdk::amount::{self, Amount};
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::fields::{self, FieldId};
+use lks_hook_sdk::sim::{self, Outcome};
+use lks_hook_sdk::xfl::Xfl;
+
+// Transaction types
+pub const PAYMENT: i32 = 0;
+pub const OFFER_CREATE: i32 = 7;
+pub const INVOKE: i32 = 99;
+
+// Issuer compiled into the zero-fee hook
+pub const LKS_ISSUER: [u8; 20] = [
+    0x4C, 0x4B, 0x53, 0x00, 0x9A, 0xBC, 0xDE, 0xF0, 0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0, 0x12, 0x34, 0x56,
+    0x78,
+];
+
+// Ledger the scripted ledger opens at
+const FIRST_LEDGER: u64 = 1000;
+
+// Fee every scripted transaction offers, in drops
+pub const FEE: u64 = 12;
+
+// An issued amount of `units` of `code` from `issuer`
+pub fn issued(units: u64, code: &[u8; 3], issuer: &[u8; 20]) -> Vec<u8> {
+    let mut amount = Xfl::from_int(units).to_amount_value().to_vec();
+    amount.extend_from_slice(&amount::currency_code(code));
+    amount.extend_from_slice(issuer);
+    amount
+}
+
+pub fn lks(units: u64) -> Vec<u8> {
+    issued(units, b"LKS", &LKS_ISSUER)
+}
+
+pub fn drops(drops: u64) -> Vec<u8> {
+    amount::encode_native(drops).to_vec()
+}
+
+// A transaction as submitted, before any hook ran on it
+#[derive(Clone)]
+pub struct Tx {
+    tx_type: i32,
+    fields: BTreeMap<FieldId, Vec<u8>>,
+    params: BTreeMap<Vec<u8>, Vec<u8>>,
+    memos: Vec<u8>,
+}
+
+impl Tx {
+    pub fn new(tx_type: i32, account: &[u8; 20]) -> Tx {
+        let mut fields = BTreeMap::new();
+        fields.insert(fields::SF_ACCOUNT, account.to_vec());
+        fields.insert(fields::SF_FEE, drops(FEE));
+        Tx { tx_type, fields, params: BTreeMap::new(), memos: Vec::new() }
+    }
+
+    pub fn payment(account: &[u8; 20], destination: &[u8; 20], amount: Vec<u8>) -> Tx {
+        Tx::new(PAYMENT, account).field(fields::SF_DESTINATION, destination.to_vec()).field(fields::SF_AMOUNT, amount)
+    }
+
+    pub fn offer(account: &[u8; 20], taker_gets: Vec<u8>, taker_pays: Vec<u8>) -> Tx {
+        Tx::new(OFFER_CREATE, account).field(fields::SF_TAKER_GETS, taker_gets).field(fields::SF_TAKER_PAYS, taker_pays)
+    }
+
+    pub fn invoke(account: &[u8; 20]) -> Tx {
+        Tx::new(INVOKE, account)
+    }
+
+    pub fn field(mut self, field: FieldId, value: Vec<u8>) -> Tx {
+        self.fields.insert(field, value);
+        self
+    }
+
+    pub fn param(mut self, name: &[u8], value: Vec<u8>) -> Tx {
+        self.params.insert(name.to_vec(), value);
+        self
+    }
+
+    // Append a memo; memo type and data must stay under 128 bytes each
+    pub fn memo(mut self, memo_type: &[u8], data: &[u8]) -> Tx {
+        self.memos.extend_from_slice(&[0xEA, 0x7C, memo_type.len() as u8]);
+        self.memos.extend_from_slice(memo_type);
+        self.memos.extend_from_slice(&[0x7D, data.len() as u8]);
+        self.memos.extend_from_slice(data);
+        self.memos.push(0xE1);
+        self
+    }
+}
+
+// What the hook made of a submitted transaction
+#[derive(Clone, Debug)]
+pub struct Applied {
+    // Value the hook returned
+    pub result: i64,
+    pub outcome: Option<Outcome>,
+    // Fee the transaction carries after the hook, in drops
+    pub fee: u64,
+}
+
+impl Applied {
+    // Accepted with its fee sponsored
+    pub fn sponsored(&self) -> bool {
+        self.accepted() && self.result == 0 && self.fee == 0
+    }
+
+    // Accepted untouched, the account paying its own fee, after the hook
+    // declined it with `error`
+    pub fn declined(&self, error: HookError) -> bool {
+        self.accepted() && self.result == error.return_value() && self.fee == FEE
+    }
+
+    pub fn accepted(&self) -> bool {
+        matches!(self.outcome, Some(Outcome::Accepted(_)))
+    }
+
+    pub fn rejected(&self) -> bool {
+        matches!(self.outcome, Some(Outcome::Rejected(_)))
+    }
+}
+
+// The ledger a compiled hook is installed on
+pub struct Ledger {
+    runner: Runner,
+    ledger_seq: u64,
+    // Transactions submitted so far, giving each its own id
+    submitted: u32,
+}
+
+impl Ledger {
+    // A fresh ledger running the release wasm of `package`; the simulated
+    // host is per thread, so every test gets its own ledger
+    pub fn new(package: &str, artifact: &str) -> Ledger {
+        let path = build_hook(package, artifact, &[]).unwrap_or_else(|err| panic!("{package}: {err}"));
+        let runner = Runner::load(&path).unwrap_or_else(|err| panic!("{package}: {err}"));
+        sim::reset();
+        Ledger { runner, ledger_seq: FIRST_LEDGER, submitted: 0 }
+    }
+
+    pub fn zero_fee() -> Ledger {
+        Ledger::new("lks-zero-fee-hook", "zero_fee_hook")
+    }
+
+    pub fn compliance() -> Ledger {
+        Ledger::new("lks-compliance-hook", "compliance_hook")
+    }
+
+    // Set a SetHook parameter of the installed hook
+    pub fn set_param(&mut self, name: &[u8], value: Vec<u8>) {
+        sim::with(|host| host.hook_params.insert(name.to_vec(), value));
+    }
+
+    // Close `ledgers` ledgers; later transactions go into the next open one
+    pub fn advance(&mut self, ledgers: u64) {
+        self.ledger_seq += ledgers;
+    }
+
+    pub fn ledger_seq(&self) -> u64 {
+        self.ledger_seq
+    }
+
+    // Run the hook on `tx` in the open ledger. A trap or an exceeded loop
+    // guard fails the test, as either would fail the transaction on ledger.
+    pub fn submit(&mut self, tx: &Tx) -> Applied {
+        self.submitted += 1;
+        let mut otxn_id = [0u8; 32];
+        otxn_id[..4].copy_from_slice(&self.submitted.to_be_bytes());
+
+        sim::with(|host| {
+            host.tx_type = tx.tx_type;
+            host.fields = tx.fields.clone();
+            if !tx.memos.is_empty() {
+                let mut memos = tx.memos.clone();
+                memos.push(0xF1);
+                host.fields.insert(fields::SF_MEMOS, memos);
+            }
+            host.otxn_params = tx.params.clone();
+            host.otxn_id = otxn_id;
+            host.ledger_seq = self.ledger_seq;
+            host.emitted.clear();
+        });
+
+        let measurement = self.runner.run(Entry::Hook).unwrap_or_else(|err| panic!("hook trapped: {err}"));
+        let (outcome, guard_violation, fee) =
+            sim::with(|host| (host.outcome.clone(), host.guard_violation, host.fields.get(&fields::SF_FEE).cloned()));
+        if let Some((id, max_iterations)) = guard_violation {
+            panic!("guard {id:#x} exceeded {max_iterations} iterations");
+        }
+        let fee = match fee.as_deref().map(amount::parse) {
+            Some(Ok(Amount::Native(drops))) => drops,
+            _ => panic!("fee is not a native amount"),
+        };
+
+        Applied { result: measurement.result, outcome, fee }
+    }
+
+    // Hook state value under `key`, without its version byte
+    pub fn value(&self, key: &[u8]) -> Option<Vec<u8>> {
+        sim::with(|host| host.value(key).map(<[u8]>::to_vec))
+    }
+
+    pub fn state(&self) -> sim::State {
+        sim::with(|host| host.state.clone())
+    }
+
+    // Transactions the hook emitted on the last submitted one
+    pub fn emitted(&self) -> Vec<Vec<u8>> {
+        sim::with(|host| host.emitted.clone())
+    }
+}
@@ -0,0 +1,45 @@
+// End-to-end scenarios for the compiled compliance hook
+
+use lks_hook_sdk::admin::FOUNDATION_ACCOUNT;
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::fields;
+use lks_hook_sdk::kyc;
+use lks_hook_tests::{lks, Ledger, Tx};
+
+const USER: [u8; 20] = [0xAA; 20];
+const MERCHANT: [u8; 20] = [0xBB; 20];
+
+fn kyc_command(op: u8, account: &[u8; 20], expires: u32) -> Tx {
+    let mut command = vec![op];
+    command.extend_from_slice(account);
+    command.extend_from_slice(&expires.to_be_bytes());
+    Tx::invoke(FOUNDATION_ACCOUNT.as_bytes()).param(kyc::PARAM_COMMAND, command)
+}
+
+#[test]
+fn transacting_follows_the_foundation_kyc_registry() {
+    let mut ledger = Ledger::compliance();
+
+    let unverified = ledger.submit(&Tx::payment(&USER, &MERCHANT, lks(25)));
+    assert!(unverified.rejected());
+    assert_eq!(unverified.result, HookError::KycRequired.return_value());
+
+    // Only the foundation verifies accounts
+    let forged = kyc_command(kyc::OP_VERIFY, &USER, 0).field(fields::SF_ACCOUNT, USER.to_vec());
+    assert!(ledger.submit(&forged).rejected());
+    assert!(ledger.submit(&Tx::payment(&USER, &MERCHANT, lks(25))).rejected());
+
+    assert!(ledger.submit(&kyc_command(kyc::OP_VERIFY, &USER, 0)).accepted());
+    let verified = ledger.submit(&Tx::payment(&USER, &MERCHANT, lks(25)));
+    assert!(verified.accepted());
+    assert_eq!(verified.result, 0);
+
+    // Verification expiring with a ledger lapses on its own
+    assert!(ledger.submit(&kyc_command(kyc::OP_VERIFY, &MERCHANT, ledger.ledger_seq() as u32 + 10)).accepted());
+    assert!(ledger.submit(&Tx::payment(&MERCHANT, &USER, lks(1))).accepted());
+    ledger.advance(10);
+    assert!(ledger.submit(&Tx::payment(&MERCHANT, &USER, lks(1))).rejected());
+
+    assert!(ledger.submit(&kyc_command(kyc::OP_REVOKE, &USER, 0)).accepted());
+    assert!(ledger.submit(&Tx::payment(&USER, &MERCHANT, lks(25))).rejected());
+}
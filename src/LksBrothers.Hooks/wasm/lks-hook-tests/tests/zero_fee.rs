@@ -0,0 +1,143 @@
+// End-to-end scenarios for the compiled zero-fee hook
+
+use lks_hook_sdk::admin::FOUNDATION_ACCOUNT;
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::state;
+use lks_hook_tests::{drops, issued, lks, Ledger, Tx, FEE};
+
+const USER: [u8; 20] = [0xAA; 20];
+const MERCHANT: [u8; 20] = [0xBB; 20];
+const SPAMMER: [u8; 20] = [0xCC; 20];
+
+const EPOCH_LEDGERS: u64 = 100;
+
+// Epoch and value of a limit counter
+fn counter(ledger: &Ledger, key: &[u8]) -> Option<(u32, u64)> {
+    let value = ledger.value(key)?;
+    Some((u32::from_be_bytes(value[..4].try_into().unwrap()), u64::from_be_bytes(value[4..12].try_into().unwrap())))
+}
+
+fn epoch(ledger: &Ledger) -> u32 {
+    (ledger.ledger_seq() / EPOCH_LEDGERS) as u32
+}
+
+fn command(op: &[u8]) -> Tx {
+    Tx::invoke(FOUNDATION_ACCOUNT.as_bytes()).param(b"LKSCMD", op.to_vec())
+}
+
+#[test]
+fn spam_is_capped_per_pair_and_account_until_the_next_epoch() {
+    let mut ledger = Ledger::zero_fee();
+    ledger.set_param(b"EPOCHLEN", EPOCH_LEDGERS.to_be_bytes().to_vec());
+    ledger.set_param(b"ACCTCAP", 5u64.to_be_bytes().to_vec());
+    ledger.set_param(b"PAIRCAP", 3u64.to_be_bytes().to_vec());
+
+    // Ping-ponging with one merchant runs into the pair cap first
+    for _ in 0..3 {
+        assert!(ledger.submit(&Tx::payment(&SPAMMER, &MERCHANT, lks(1))).sponsored());
+    }
+    assert!(ledger.submit(&Tx::payment(&SPAMMER, &MERCHANT, lks(1))).declined(HookError::PairLimited));
+    assert!(ledger.submit(&Tx::payment(&MERCHANT, &SPAMMER, lks(1))).declined(HookError::PairLimited));
+
+    // Spreading the spam over fresh destinations runs into the account cap
+    for destination in 1..=2u8 {
+        assert!(ledger.submit(&Tx::payment(&SPAMMER, &[destination; 20], lks(1))).sponsored());
+    }
+    for destination in 3..=10u8 {
+        let applied = ledger.submit(&Tx::payment(&SPAMMER, &[destination; 20], lks(1)));
+        assert!(applied.declined(HookError::RateLimited));
+    }
+    let spammer_key = state::account_key(state::NS_ACCOUNT_LIMIT, &SPAMMER);
+    assert_eq!(counter(&ledger, &spammer_key), Some((epoch(&ledger), 5)));
+
+    // Declined spam doesn't cost anyone else their sponsorship
+    assert!(ledger.submit(&Tx::payment(&USER, &MERCHANT, lks(1))).sponsored());
+
+    ledger.advance(EPOCH_LEDGERS);
+    assert!(ledger.submit(&Tx::payment(&SPAMMER, &MERCHANT, lks(1))).sponsored());
+    assert_eq!(counter(&ledger, &spammer_key), Some((epoch(&ledger), 1)));
+}
+
+#[test]
+fn exhausted_budget_passes_fees_on_until_the_next_epoch() {
+    let mut ledger = Ledger::zero_fee();
+    ledger.set_param(b"EPOCHLEN", EPOCH_LEDGERS.to_be_bytes().to_vec());
+    ledger.set_param(b"BUDGET", 36u64.to_be_bytes().to_vec());
+
+    let budget_key = state::key(state::NS_BUDGET, &[]);
+    for sender in 1..=3u8 {
+        assert!(ledger.submit(&Tx::payment(&[sender; 20], &MERCHANT, lks(25))).sponsored());
+        ledger.advance(1);
+    }
+    assert_eq!(counter(&ledger, &budget_key), Some((epoch(&ledger), 36)));
+
+    // Every account pays its own fee once the budget is spent, and the
+    // declined transactions leave it as it was
+    for sender in 4..=6u8 {
+        assert!(ledger.submit(&Tx::payment(&[sender; 20], &MERCHANT, lks(25))).declined(HookError::BudgetExceeded));
+    }
+    assert_eq!(counter(&ledger, &budget_key), Some((epoch(&ledger), 36)));
+
+    ledger.advance(EPOCH_LEDGERS);
+    assert!(ledger.submit(&Tx::payment(&USER, &MERCHANT, lks(25))).sponsored());
+    assert_eq!(counter(&ledger, &budget_key), Some((epoch(&ledger), 12)));
+}
+
+#[test]
+fn foundation_pauses_and_resumes_sponsorship() {
+    let mut ledger = Ledger::zero_fee();
+    assert!(ledger.submit(&Tx::payment(&USER, &MERCHANT, lks(25))).sponsored());
+
+    // Only the foundation can pull the switch
+    let before = ledger.state();
+    let forged = ledger.submit(&Tx::invoke(&USER).param(b"LKSCMD", vec![0x06, 1]));
+    assert_eq!(forged.result, HookError::Unauthorized.return_value());
+    assert_eq!(ledger.state(), before);
+
+    assert_eq!(ledger.submit(&command(&[0x06, 1])).result, 0);
+    let pause_key = state::key(state::NS_PAUSE, &[]);
+    assert_eq!(ledger.value(&pause_key), Some(vec![1]));
+
+    // Paused, every transaction passes through untouched and leaves the
+    // state alone
+    let paused = ledger.state();
+    for tx in [
+        Tx::payment(&USER, &MERCHANT, lks(25)),
+        Tx::offer(&USER, lks(25), drops(5_000)),
+        Tx::payment(&MERCHANT, &USER, lks(1)),
+    ] {
+        assert!(ledger.submit(&tx).declined(HookError::SponsorshipPaused));
+    }
+    assert_eq!(ledger.state(), paused);
+
+    // Commands also arrive as memos
+    ledger.advance(1);
+    let resume = Tx::invoke(FOUNDATION_ACCOUNT.as_bytes()).memo(b"lks/admin", &[0x06, 0]);
+    assert_eq!(ledger.submit(&resume).result, 0);
+    assert!(ledger.submit(&Tx::payment(&USER, &MERCHANT, lks(25))).sponsored());
+}
+
+#[test]
+fn sponsors_offers_trading_lks_only() {
+    let mut ledger = Ledger::zero_fee();
+    let usd = |units| issued(units, b"USD", &[0x99; 20]);
+
+    // Either side of the book
+    assert!(ledger.submit(&Tx::offer(&USER, lks(25), drops(5_000))).sponsored());
+    assert!(ledger.submit(&Tx::offer(&MERCHANT, drops(5_000), lks(25))).sponsored());
+    assert!(ledger.submit(&Tx::offer(&USER, lks(25), usd(10))).sponsored());
+
+    // Offers without LKS pay their own fee and leave no trace in state
+    let before = ledger.state();
+    let applied = ledger.submit(&Tx::offer(&USER, usd(10), drops(5_000)));
+    assert!(applied.accepted() && applied.fee == FEE);
+    assert_eq!(ledger.state(), before);
+
+    // So do offers in a token posing as LKS
+    let fake = ledger.submit(&Tx::offer(&USER, issued(25, b"LKS", &[0x99; 20]), drops(5_000)));
+    assert!(fake.accepted() && fake.fee == FEE);
+
+    // Offers count against the same limits as payments
+    let account_key = state::account_key(state::NS_ACCOUNT_LIMIT, &USER);
+    assert_eq!(ledger.value(&account_key).map(|value| value[11]), Some(2));
+}
@@ -0,0 +1,140 @@
+// Decoding and writing out LKS hook state entries
+// Every LKS entry becomes a row naming its namespace, with its raw key and
+// value and the fields the schema splits them into. Accounts are written as
+// r-addresses, integers as numbers, ids and codes as hex. Entries whose key
+// or value doesn't fit their schema are kept with their raw value only, so
+// nothing on ledger goes missing from an export.
+//
+// JSON is an array of rows with their fields by name. CSV has a line per
+// field (namespace, key, version, field, value), since every namespace has
+// other fields; rows without fields get a single "raw" line.
+
+use lks_hook_sdk::bytes;
+use lks_hook_sdk::codec;
+use lks_hook_sdk::entry::Reader;
+use lks_hook_sdk::schema::{self, Field, Kind};
+use lks_hook_sdk::state;
+use serde_json::{Map, Value};
+
+use crate::{to_hex, Entry};
+
+pub struct Row {
+    // Schema name, or the namespace byte in hex for namespaces it lacks
+    pub namespace: String,
+    pub key: String,
+    // Layout version of the value; None for raw entries and entries that
+    // don't fit their schema
+    pub version: Option<u8>,
+    // The entry as stored, version byte included
+    pub value: String,
+    // Key fields followed by value fields
+    pub fields: Option<Vec<(&'static str, Value)>>,
+}
+
+// The row of an LKS entry, None for entries of other hooks
+pub fn decode(entry: &Entry) -> Option<Row> {
+    if !entry.key.starts_with(b"LKS") {
+        return None;
+    }
+
+    let namespace = entry.key[3];
+    let mut row = Row {
+        namespace: format!("{namespace:02X}"),
+        key: to_hex(&entry.key),
+        version: None,
+        value: to_hex(&entry.data),
+        fields: None,
+    };
+    let Some(schema) = schema::lookup(namespace) else {
+        return Some(row);
+    };
+    row.namespace = schema.name.to_string();
+
+    let mut key_fields = schema::fields();
+    let mut value_fields = schema::fields();
+    let Some(key_count) = schema.split_key(&entry.key, &mut key_fields) else {
+        return Some(row);
+    };
+    let Some((version, value_count)) = schema.split_entry(&entry.data, &mut value_fields) else {
+        return Some(row);
+    };
+    row.version = version;
+
+    // Values written by a newer wasm can't be read
+    if version.is_some_and(|version| version > state::VERSION) {
+        return Some(row);
+    }
+
+    let fields = key_fields[..key_count].iter().chain(&value_fields[..value_count]);
+    row.fields = Some(fields.map(|(field, data)| (field.name, render(field, data))).collect());
+    Some(row)
+}
+
+fn render(field: &Field, data: &[u8]) -> Value {
+    match field.kind {
+        Kind::Account => match bytes::array(data, 0) {
+            Some(account) => {
+                let mut address = [0u8; codec::R_ADDRESS_MAX_LEN];
+                let len = codec::encode_r_address(&account, &mut address);
+                Value::String(String::from_utf8_lossy(bytes::head(&address, len)).into_owned())
+            }
+            None => Value::String(to_hex(data)),
+        },
+        Kind::Uint => Value::from(data.iter().fold(0u64, |value, &byte| value << 8 | u64::from(byte))),
+        Kind::Varint => Reader::new(data).varint().map_or_else(|| Value::String(to_hex(data)), Value::from),
+        Kind::Bytes => Value::String(to_hex(data)),
+        Kind::Text => {
+            let text = data.iter().rposition(|&byte| byte != 0).map_or(&data[..0], |last| &data[..=last]);
+            Value::String(String::from_utf8_lossy(text).into_owned())
+        }
+    }
+}
+
+pub fn to_json(rows: &[Row]) -> String {
+    let rows: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            let mut object = Map::new();
+            object.insert("namespace".into(), Value::from(row.namespace.as_str()));
+            object.insert("key".into(), Value::from(row.key.as_str()));
+            object.insert("version".into(), row.version.map_or(Value::Null, Value::from));
+            object.insert("value".into(), Value::from(row.value.as_str()));
+            object.insert(
+                "fields".into(),
+                row.fields.as_ref().map_or(Value::Null, |fields| {
+                    Value::Object(fields.iter().map(|(name, value)| (name.to_string(), value.clone())).collect())
+                }),
+            );
+            Value::Object(object)
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&rows).unwrap_or_default()
+}
+
+pub fn to_csv(rows: &[Row]) -> String {
+    let mut csv = String::from("namespace,key,version,field,value\n");
+    for row in rows {
+        let version = row.version.map(|version| version.to_string()).unwrap_or_default();
+        let mut line = |field: &str, value: &str| {
+            csv.push_str(&format!("{},{},{version},{field},{}\n", quoted(&row.namespace), row.key, quoted(value)));
+        };
+        match &row.fields {
+            Some(fields) => fields.iter().for_each(|(name, value)| match value {
+                Value::String(text) => line(name, text),
+                other => line(name, &other.to_string()),
+            }),
+            None => line("raw", &row.value),
+        }
+    }
+    csv
+}
+
+// Quote CSV values holding separators, quotes or line breaks
+fn quoted(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,116 @@
+// Hook state export for the LKS hooks
+// Reads the hook state entries of an LKS hook, from a node or from a ledger
+// dump, decodes the LKS ones with the SDK's state schema and writes them out
+// as JSON or CSV for reconciliation and dashboards. The schema is the one
+// the hooks are built against, so the export never drifts from the layout
+// on ledger.
+//
+// Export the state a hook keeps under a namespace from a node's JSON-RPC port
+//   cargo run -p lks-state-export -- --node 127.0.0.1:5005 --account r... --namespace <64 hex digits>
+// or every LKS entry of a dump
+//   cargo run -p lks-state-export -- --dump ledger.json --csv
+// A dump is any JSON holding HookState objects, like the result of
+// ledger_data or account_namespace.
+
+pub mod export;
+pub mod source;
+
+use lks_hook_sdk::state::KEY_LEN;
+
+// A hook state entry as stored on ledger
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub key: [u8; KEY_LEN],
+    pub data: Vec<u8>,
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02X}")).collect()
+}
+
+pub fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| text.get(i..i + 2).and_then(|digits| u8::from_str_radix(digits, 16).ok()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::account::AccountId;
+    use lks_hook_sdk::state;
+
+    const USER: [u8; 20] = [0xAA; 20];
+
+    fn hook_state(key: &[u8], data: &[u8]) -> serde_json::Value {
+        serde_json::json!({
+            "LedgerEntryType": "HookState",
+            "HookStateKey": to_hex(key),
+            "HookStateData": to_hex(data),
+        })
+    }
+
+    #[test]
+    fn reads_hook_state_anywhere_in_a_dump() {
+        let registry = state::account_key(state::NS_REGISTRY, &USER);
+        let dump = serde_json::json!({
+            "result": {
+                "state": [
+                    { "LedgerEntryType": "AccountRoot", "Balance": "1000" },
+                    hook_state(&registry, &[state::VERSION, 1]),
+                ],
+                "namespace_entries": [hook_state(&[0x11; KEY_LEN], &[2])],
+            }
+        });
+
+        let entries = source::entries(&dump).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0], Entry { key: registry, data: vec![state::VERSION, 1] });
+
+        let broken = serde_json::json!([hook_state(&[0x11; 31], &[2])]);
+        assert!(source::entries(&broken).is_err());
+    }
+
+    #[test]
+    fn decodes_entries_with_the_schema() {
+        let receipt = state::key(state::NS_RECEIPT, &[&3u32.to_be_bytes()]);
+        let mut data = vec![state::VERSION];
+        data.extend_from_slice(&7u32.to_be_bytes());
+        data.extend_from_slice(&USER);
+        data.extend_from_slice(&1200u32.to_be_bytes());
+        data.extend_from_slice(&12u64.to_be_bytes());
+        data.push(2);
+
+        let row = export::decode(&Entry { key: receipt, data }).unwrap();
+        let json = export::to_json(std::slice::from_ref(&row));
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value[0]["namespace"], "receipt");
+        assert_eq!(value[0]["version"], state::VERSION);
+        assert_eq!(value[0]["fields"]["slot"], 3);
+        assert_eq!(value[0]["fields"]["account"], AccountId::new(USER).to_r_address());
+        assert_eq!(value[0]["fields"]["fee"], 12);
+
+        // Entries of other hooks are left out; LKS entries that don't fit
+        // their schema keep their raw value
+        assert!(export::decode(&Entry { key: [0x11; KEY_LEN], data: vec![1] }).is_none());
+        let odd = export::decode(&Entry { key: receipt, data: vec![state::VERSION, 1, 2] }).unwrap();
+        assert!(odd.fields.is_none());
+        assert_eq!(odd.value, "010102");
+    }
+
+    #[test]
+    fn writes_a_csv_row_per_field() {
+        let config = state::key(state::NS_OVERRIDE, &[b"MAXFEE"]);
+        let rows = [export::decode(&Entry { key: config, data: vec![state::VERSION, 0, 0x10] }).unwrap()];
+
+        let key = to_hex(&config);
+        assert_eq!(
+            export::to_csv(&rows),
+            format!("namespace,key,version,field,value\nconfig,{key},1,parameter,MAXFEE\nconfig,{key},1,value,0010\n")
+        );
+    }
+}
@@ -0,0 +1,38 @@
+// Export the LKS hook state of a node or a ledger dump to stdout
+
+use std::process::ExitCode;
+
+use lks_state_export::{export, source};
+
+const USAGE: &str = "usage: lks-state-export (--dump <file> | --node <host:port> --account <account> \
+                     --namespace <hex>) [--csv]";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let option = |name: &str| args.iter().position(|arg| arg == name).and_then(|i| args.get(i + 1)).cloned();
+    let csv = args.iter().any(|arg| arg == "--csv");
+
+    let entries = match (option("--dump"), option("--node"), option("--account"), option("--namespace")) {
+        (Some(path), None, None, None) => source::read_dump(&path),
+        (None, Some(node), Some(account), Some(namespace)) => source::fetch(&node, &account, &namespace),
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let entries = match entries {
+        Ok(entries) => entries,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let rows: Vec<export::Row> = entries.iter().filter_map(export::decode).collect();
+    if csv {
+        print!("{}", export::to_csv(&rows));
+    } else {
+        println!("{}", export::to_json(&rows));
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,111 @@
+// Where hook state entries come from
+// A node is asked with account_namespace for the entries a hook keeps under
+// one HookNamespace, page by page. A dump is read whole; HookState objects
+// are picked out wherever they sit in it, so the results of ledger_data,
+// account_namespace and ledger_entry all work as dumps.
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+
+use serde_json::{json, Value};
+
+use crate::{from_hex, Entry};
+
+// Pages fetched before giving up on a node that keeps returning markers
+const MAX_PAGES: usize = 10_000;
+
+pub fn read_dump(path: &str) -> Result<Vec<Entry>, String> {
+    let text = std::fs::read_to_string(path).map_err(|err| format!("reading {path}: {err}"))?;
+    let dump: Value = serde_json::from_str(&text).map_err(|err| format!("parsing {path}: {err}"))?;
+    entries(&dump)
+}
+
+// Every HookState object in `value`
+pub fn entries(value: &Value) -> Result<Vec<Entry>, String> {
+    let mut entries = Vec::new();
+    collect(value, &mut entries)?;
+    Ok(entries)
+}
+
+fn collect(value: &Value, entries: &mut Vec<Entry>) -> Result<(), String> {
+    match value {
+        Value::Object(object) => {
+            if let (Some(key), Some(data)) = (object.get("HookStateKey"), object.get("HookStateData")) {
+                entries.push(entry(key, data)?);
+                return Ok(());
+            }
+            object.values().try_for_each(|value| collect(value, entries))
+        }
+        Value::Array(values) => values.iter().try_for_each(|value| collect(value, entries)),
+        _ => Ok(()),
+    }
+}
+
+fn entry(key: &Value, data: &Value) -> Result<Entry, String> {
+    let key_hex = key.as_str().unwrap_or_default();
+    let key = from_hex(key_hex)
+        .and_then(|key| key.try_into().ok())
+        .ok_or_else(|| format!("HookStateKey {key_hex} isn't 32 hex-encoded bytes"))?;
+    let data_hex = data.as_str().unwrap_or_default();
+    let data = from_hex(data_hex).ok_or_else(|| format!("HookStateData {data_hex} isn't hex"))?;
+
+    Ok(Entry { key, data })
+}
+
+// The entries `account` keeps under `namespace` (64 hex digits), read from
+// the JSON-RPC port of the node at `node` (host:port) in the last validated
+// ledger
+pub fn fetch(node: &str, account: &str, namespace: &str) -> Result<Vec<Entry>, String> {
+    let mut entries = Vec::new();
+    let mut marker = Value::Null;
+
+    for _ in 0..MAX_PAGES {
+        let mut params = json!({
+            "account": account,
+            "namespace_id": namespace,
+            "ledger_index": "validated",
+        });
+        if !marker.is_null() {
+            params["marker"] = marker;
+        }
+
+        let response = call(node, &json!({ "method": "account_namespace", "params": [params] }))?;
+        let result = &response["result"];
+        if result["status"] != "success" {
+            let error = result["error_message"].as_str().or(result["error"].as_str()).unwrap_or("unknown error");
+            return Err(format!("account_namespace failed: {error}"));
+        }
+
+        collect(&result["namespace_entries"], &mut entries)?;
+        marker = result["marker"].clone();
+        if marker.is_null() {
+            return Ok(entries);
+        }
+    }
+
+    Err(format!("account_namespace still paging after {MAX_PAGES} pages"))
+}
+
+// One JSON-RPC request over plain HTTP; the connection is closed after the
+// response, which is read whole
+fn call(node: &str, request: &Value) -> Result<Value, String> {
+    let body = request.to_string();
+    let mut stream = TcpStream::connect(node).map_err(|err| format!("connecting to {node}: {err}"))?;
+    write!(
+        stream,
+        "POST / HTTP/1.0\r\nHost: {node}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+    )
+    .map_err(|err| format!("sending to {node}: {err}"))?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).map_err(|err| format!("reading from {node}: {err}"))?;
+    let response = String::from_utf8_lossy(&response);
+    let (head, body) = response.split_once("\r\n\r\n").ok_or_else(|| format!("{node} sent no HTTP response"))?;
+
+    let status = head.lines().next().unwrap_or_default();
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(format!("{node} answered {status}"));
+    }
+    serde_json::from_str(body).map_err(|err| format!("parsing the response of {node}: {err}"))
+}
@@ -0,0 +1,180 @@
+// Abuse scoring for the LKS zero-fee hook
+// Farming sponsorship tends to look the same: freshly created accounts
+// sending bursts of payments of one amount. Every transaction up for
+// sponsorship is scored on those signs, and at ABUSEMAX or above it pays
+// its own fee and is traced for review. Registry partners and transactions
+// carrying a partner voucher are vetted already and aren't scored.
+//
+// Score:
+//   account younger than ABUSEAGE ledgers   AGE_POINTS
+//   per transaction in the current burst    BURST_POINTS
+//   per repeat of the same amount           REPEAT_POINTS
+// Account age is the distance to the AccountRoot's Sequence, which starts
+// at the ledger the account was created in and only grows, so accounts
+// never look older than they are. Accounts whose AccountRoot can't be read
+// aren't scored on age.
+//
+// State layout:
+//   activity per account   [last ledger u32][burst u32][last amount u64][repeats u32]
+
+use lks_hook_sdk::api::{keylet_field, ledger_seq, util_keylet};
+use lks_hook_sdk::bytes;
+use lks_hook_sdk::entry::{Entry, Reader, Writer};
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::fields::SF_SEQUENCE;
+use lks_hook_sdk::text::{self, Text};
+use lks_hook_sdk::{account, log, state};
+use crate::config;
+
+// Score at which sponsorship is withheld, zero to disable scoring
+pub const PARAM_ABUSE_MAX: &[u8] = b"ABUSEMAX";
+pub const DEFAULT_ABUSE_MAX: u64 = 0;
+
+// Accounts younger than this many ledgers score AGE_POINTS
+pub const PARAM_ABUSE_AGE: &[u8] = b"ABUSEAGE";
+pub const DEFAULT_ABUSE_AGE: u64 = 1_000;
+
+pub const AGE_POINTS: u64 = 40;
+pub const BURST_POINTS: u64 = 10;
+pub const REPEAT_POINTS: u64 = 15;
+
+// Transactions at most this many ledgers apart belong to one burst
+pub const BURST_LEDGERS: u32 = 4;
+
+const KEYLET_ACCOUNT: u32 = 3;
+const KEYLET_LEN: usize = 34;
+
+// Burst and repeat counts stay small, so they are kept as varints
+struct Activity {
+    last_ledger: u32,
+    burst: u32,
+    last_amount: u64,
+    repeats: u32,
+}
+
+impl Entry for Activity {
+    #[inline(always)]
+    fn encode(&self, w: &mut Writer) {
+        w.u32(self.last_ledger).varint(self.burst as u64).u64(self.last_amount).varint(self.repeats as u64);
+    }
+
+    #[inline(always)]
+    fn decode(r: &mut Reader) -> Option<Activity> {
+        Some(Activity {
+            last_ledger: r.u32()?,
+            burst: u32::try_from(r.varint()?).ok()?,
+            last_amount: r.u64()?,
+            repeats: u32::try_from(r.varint()?).ok()?,
+        })
+    }
+}
+
+// Score a transaction of `source` moving `amount` and record it in the
+// account's activity. Declines with AbuseSuspected at ABUSEMAX or above.
+pub fn check(source: &[u8; 20], amount: Option<u64>) -> Result<(), HookError> {
+    let max = config::u64_param(PARAM_ABUSE_MAX, DEFAULT_ABUSE_MAX);
+    if max == 0 {
+        return Ok(());
+    }
+
+    let ledger = unsafe { ledger_seq() } as u32;
+    let key = state::account_key(state::NS_ACTIVITY, source);
+    let last = state::load_entry::<Activity>(&key);
+    let burst = match &last {
+        Some(last) if ledger.saturating_sub(last.last_ledger) <= BURST_LEDGERS => last.burst.saturating_add(1),
+        _ => 0,
+    };
+    let repeats = match (amount, &last) {
+        (Some(value), Some(last)) if value == last.last_amount => last.repeats.saturating_add(1),
+        _ => 0,
+    };
+
+    let activity = Activity { last_ledger: ledger, burst, last_amount: amount.unwrap_or(0), repeats };
+    state::store_entry(&key, &activity)?;
+
+    let young = match account_sequence(source) {
+        Some(sequence) => (ledger.saturating_sub(sequence) as u64) < config::u64_param(PARAM_ABUSE_AGE, DEFAULT_ABUSE_AGE),
+        None => false,
+    };
+    let score = (young as u64 * AGE_POINTS)
+        .saturating_add(BURST_POINTS.saturating_mul(burst as u64))
+        .saturating_add(REPEAT_POINTS.saturating_mul(repeats as u64));
+    if score < max {
+        return Ok(());
+    }
+
+    if log::enabled(log::Level::Warn) {
+        let mut msg = Text::<{ text::MESSAGE_LEN }>::new();
+        msg.push(b"LKS activity flagged for review, account ").hex(bytes::head(source, 4));
+        log::warn(log::EV_ABUSE, msg.as_bytes(), score, max);
+    }
+    Err(HookError::AbuseSuspected)
+}
+
+// Sequence of the account's AccountRoot
+fn account_sequence(source: &[u8; 20]) -> Option<u32> {
+    let mut keylet = [0u8; KEYLET_LEN];
+    let result = unsafe {
+        util_keylet(keylet.as_mut_ptr(), keylet.len() as i32, KEYLET_ACCOUNT,
+                    source.as_ptr(), account::ACCOUNT_ID_LEN as i32, 0)
+    };
+    if result != KEYLET_LEN as i32 {
+        return None;
+    }
+
+    let mut sequence = [0u8; 4];
+    let result = unsafe {
+        keylet_field(keylet.as_ptr(), keylet.len() as i32, SF_SEQUENCE,
+                     sequence.as_mut_ptr(), sequence.len() as i32)
+    };
+    if result != sequence.len() as i32 {
+        return None;
+    }
+
+    Some(u32::from_be_bytes(sequence))
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::amount;
+    use lks_hook_sdk::{fields, sim};
+    use crate::fixtures::{lks_amount_bytes, lks_payment, written_fee, USER};
+    use crate::hook;
+
+    #[test]
+    fn withholds_sponsorship_from_abusive_activity() {
+        lks_payment(25, 12);
+        let mut keylet = std::vec![0, 3];
+        keylet.extend_from_slice(&USER);
+        keylet.resize(34, 0);
+        sim::with(|host| {
+            host.hook_params.insert(PARAM_ABUSE_MAX.to_vec(), 50u64.to_be_bytes().to_vec());
+            host.ledger.entry(keylet).or_default().insert(fields::SF_SEQUENCE, 990u32.to_be_bytes().to_vec());
+        });
+        let run = |ledger: u64, units: u64, otxn: u8| {
+            let amount = lks_amount_bytes(units);
+            sim::with(|host| {
+                host.ledger_seq = ledger;
+                host.otxn_id = [otxn; 32];
+                host.fields.insert(fields::SF_FEE, amount::encode_native(12).to_vec());
+                host.fields.insert(fields::SF_AMOUNT, amount);
+            });
+            sim::run(hook)
+        };
+
+        // A young account alone stays below the threshold
+        assert_eq!(run(1000, 25, 1), 0);
+
+        // The same amount again within the burst window doesn't
+        assert_eq!(run(1002, 25, 2), HookError::AbuseSuspected.return_value());
+        assert_eq!(written_fee(), 12);
+        let score = AGE_POINTS + BURST_POINTS + REPEAT_POINTS;
+        let flagged = log::encode(log::Level::Warn, log::EV_ABUSE, score, 50);
+        assert!(sim::with(|host| host.traces.iter().any(|(_, data)| data[..] == flagged[..])));
+
+        // Activity that calmed down is sponsored again
+        assert_eq!(run(1100, 30, 3), 0);
+        assert_eq!(written_fee(), 0);
+    }
+}
@@ -0,0 +1,123 @@
+// Rolling analytics counters for the LKS zero-fee hook
+// With ANALYTICS set, the hook keeps counters per limit epoch in its own
+// state so dashboards can be built from ledger state alone: sponsored
+// transactions and drops in total and per transaction type, and declined
+// sponsorships per reason code. Buckets live in a ring of STATSKEEP slots,
+// one per epoch: the first write of an epoch takes over the slot of the
+// epoch STATSKEEP before it, so retention is bounded without pruning.
+//
+// Counter entry: [epoch u32][transactions u64][drops u64]
+// Readers skip entries whose epoch is older than the window. Transactions
+// passed through while sponsorship is paused aren't counted.
+
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::state;
+use crate::{config, limits};
+
+// Keep analytics counters
+pub const PARAM_ANALYTICS: &[u8] = b"ANALYTICS";
+
+// Epochs kept before a bucket is reused
+pub const PARAM_STATS_EPOCHS: &[u8] = b"STATSKEEP";
+pub const DEFAULT_STATS_EPOCHS: u64 = 16;
+const MAX_STATS_EPOCHS: u64 = 1024;
+
+// Counters per bucket, identified by kind and id
+pub const KIND_SPONSORED: u8 = 0; // id 0
+pub const KIND_TX_TYPE: u8 = 1; // id: transaction type
+pub const KIND_DECLINED: u8 = 2; // id: error code; drops stay zero
+
+type Counter = (u32, u64, u64);
+
+// Count a sponsorship of `fee` drops for a transaction of `tx_type`
+pub fn record_sponsored(tx_type: i32, fee: u64) -> Result<(), HookError> {
+    if !config::flag(PARAM_ANALYTICS, false) {
+        return Ok(());
+    }
+
+    let epoch = limits::current_epoch();
+    add(epoch, KIND_SPONSORED, 0, fee)?;
+    add(epoch, KIND_TX_TYPE, tx_type as u16, fee)
+}
+
+// Count a declined sponsorship. Rejections aren't declines and are left out.
+pub fn record_declined(err: HookError) -> Result<(), HookError> {
+    if err.is_rejection() || !config::flag(PARAM_ANALYTICS, false) {
+        return Ok(());
+    }
+
+    add(limits::current_epoch(), KIND_DECLINED, err.code() as u16, 0)
+}
+
+// State key of a counter in the bucket of `epoch`
+pub fn counter_key(epoch: u32, kind: u8, id: u16) -> [u8; state::KEY_LEN] {
+    let epochs = config::u64_param(PARAM_STATS_EPOCHS, DEFAULT_STATS_EPOCHS).clamp(1, MAX_STATS_EPOCHS);
+    let slot = (epoch as u64 % epochs) as u32;
+
+    state::key(state::NS_STATS, &[&slot.to_be_bytes(), &[kind], &id.to_be_bytes()])
+}
+
+fn add(epoch: u32, kind: u8, id: u16, drops: u64) -> Result<(), HookError> {
+    let key = counter_key(epoch, kind, id);
+
+    // Counters left by an epoch that dropped out of the window start over
+    let (count, total) = match state::load_entry::<Counter>(&key) {
+        Some((counted, count, total)) if counted == epoch => (count, total),
+        _ => (0, 0),
+    };
+
+    state::store_entry::<Counter>(&key, &(epoch, count.saturating_add(1), total.saturating_add(drops)))
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::sim;
+    use crate::fixtures::lks_payment;
+    use crate::{hook, TX_TYPE_PAYMENT};
+
+    // Run an LKS payment by USER paying `fee` at `ledger` with analytics on,
+    // keeping two epochs
+    fn counted_payment(fee: u64, ledger: u64, state: &mut sim::State) -> i64 {
+        lks_payment(25, fee);
+        sim::with(|host| {
+            host.state = std::mem::take(state);
+            host.ledger_seq = ledger;
+            host.otxn_id = [ledger as u8; 32];
+            host.hook_params.insert(PARAM_ANALYTICS.to_vec(), std::vec![1]);
+            host.hook_params.insert(PARAM_STATS_EPOCHS.to_vec(), 2u64.to_be_bytes().to_vec());
+        });
+        let result = sim::run(hook);
+        *state = sim::with(|host| host.state.clone());
+        result
+    }
+
+    // (epoch, transactions, drops) of an analytics counter
+    fn stats(epoch: u32, kind: u8, id: u16) -> Option<(u32, u64, u64)> {
+        let key = counter_key(epoch, kind, id);
+        sim::with(|host| host.value(&key).map(<[u8]>::to_vec)).map(|entry| {
+            (u32::from_be_bytes(entry[..4].try_into().unwrap()),
+             u64::from_be_bytes(entry[4..12].try_into().unwrap()),
+             u64::from_be_bytes(entry[12..].try_into().unwrap()))
+        })
+    }
+
+    #[test]
+    fn keeps_rolling_analytics_counters_per_epoch() {
+        let mut state = sim::State::new();
+        assert_eq!(counted_payment(12, 1000, &mut state), 0);
+        assert_eq!(counted_payment(10, 1001, &mut state), 0);
+        let declined = HookError::FeeCapExceeded;
+        assert_eq!(counted_payment(5_000, 1002, &mut state), declined.return_value());
+
+        // Ledger 1000 is in epoch 3 of 256 ledgers
+        assert_eq!(stats(3, KIND_SPONSORED, 0), Some((3, 2, 22)));
+        assert_eq!(stats(3, KIND_TX_TYPE, TX_TYPE_PAYMENT as u16), Some((3, 2, 22)));
+        assert_eq!(stats(3, KIND_DECLINED, declined.code() as u16), Some((3, 1, 0)));
+
+        // Two epochs later the bucket is taken over
+        assert_eq!(counted_payment(12, 1000 + 2 * 256, &mut state), 0);
+        assert_eq!(stats(5, KIND_SPONSORED, 0), Some((5, 1, 12)));
+        assert_eq!(stats(5, KIND_DECLINED, declined.code() as u16), Some((3, 1, 0)));
+    }
+}
@@ -0,0 +1,163 @@
+// Circuit breaker on the foundation reserve for the LKS zero-fee hook
+// Sponsorship stops when the reserve paying fee reimbursements runs low, so
+// they never fail for lack of funds, and resumes once it has been topped up.
+// The breaker opens below one threshold and only closes above a higher one,
+// so a balance hovering around a single limit doesn't flap.
+//
+// The reserve is the hook account, which emits the settlement payments; the
+// foundation role can be rotated to another account without moving it. The
+// balance is read from the hook AccountRoot, and the last balance seen is
+// mirrored in hook state and used when the ledger object can't be read.
+
+use lks_hook_sdk::account;
+use lks_hook_sdk::amount::{self, Amount};
+use lks_hook_sdk::api::{hook_account, keylet_field, ledger_seq, util_keylet};
+use lks_hook_sdk::entry::{Entry, Reader, Writer};
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::fields::SF_BALANCE;
+use lks_hook_sdk::{log, state};
+use crate::config;
+
+// Open the breaker when the reserve balance (in drops) drops below this
+pub const PARAM_OPEN_BELOW: &[u8] = b"BRKOPEN";
+pub const DEFAULT_OPEN_BELOW: u64 = 50_000_000;
+
+// Close it again once the balance is back above this
+pub const PARAM_CLOSE_ABOVE: &[u8] = b"BRKCLOSE";
+pub const DEFAULT_CLOSE_ABOVE: u64 = 100_000_000;
+
+const KEYLET_ACCOUNT: u32 = 3;
+const KEYLET_LEN: usize = 34;
+
+// Breaker entry: [open u8][last balance u64 big-endian][ledger u32 big-endian]
+struct Breaker {
+    open: bool,
+    balance: u64,
+    ledger: u32,
+}
+
+impl Entry for Breaker {
+    #[inline(always)]
+    fn encode(&self, w: &mut Writer) {
+        w.u8(self.open as u8).u64(self.balance).u32(self.ledger);
+    }
+
+    #[inline(always)]
+    fn decode(r: &mut Reader) -> Option<Breaker> {
+        Some(Breaker { open: r.u8()? != 0, balance: r.u64()?, ledger: r.u32()? })
+    }
+}
+
+// Decline sponsorship while the breaker is open
+pub fn check() -> Result<(), HookError> {
+    let key = breaker_key();
+    let stored = state::load_entry::<Breaker>(&key);
+    let was_open = matches!(stored, Some(Breaker { open: true, .. }));
+
+    let balance = match (reserve_balance(), &stored) {
+        (Some(balance), _) => balance,
+        // Without a balance the breaker keeps its last position
+        (None, Some(last)) => last.balance,
+        (None, None) => return Ok(()),
+    };
+
+    let open_below = config::u64_param(PARAM_OPEN_BELOW, DEFAULT_OPEN_BELOW);
+    let close_above = config::u64_param(PARAM_CLOSE_ABOVE, DEFAULT_CLOSE_ABOVE);
+    let open = if was_open { balance < close_above } else { balance < open_below };
+
+    if open && !was_open {
+        log::warn(log::EV_BREAKER_OPEN, b"LKS circuit breaker opened", balance, open_below);
+    } else if was_open && !open {
+        log::info(log::EV_BREAKER_CLOSE, b"LKS circuit breaker closed", balance, close_above);
+    }
+
+    if !matches!(stored, Some(last) if last.open == open && last.balance == balance) {
+        let ledger = unsafe { ledger_seq() } as u32;
+        state::store_entry(&key, &Breaker { open, balance, ledger })?;
+    }
+
+    if open {
+        return Err(HookError::BreakerOpen);
+    }
+
+    Ok(())
+}
+
+// Balance of the hook AccountRoot in drops
+fn reserve_balance() -> Option<u64> {
+    let mut reserve = [0u8; account::ACCOUNT_ID_LEN];
+    if unsafe { hook_account(reserve.as_mut_ptr()) } != account::ACCOUNT_ID_LEN as i32 {
+        return None;
+    }
+
+    let mut keylet = [0u8; KEYLET_LEN];
+    let result = unsafe {
+        util_keylet(keylet.as_mut_ptr(), keylet.len() as i32, KEYLET_ACCOUNT,
+                    reserve.as_ptr(), account::ACCOUNT_ID_LEN as i32, 0)
+    };
+    if result != KEYLET_LEN as i32 {
+        return None;
+    }
+
+    let mut balance = [0u8; amount::NATIVE_LEN];
+    let result = unsafe {
+        keylet_field(keylet.as_ptr(), keylet.len() as i32, SF_BALANCE,
+                     balance.as_mut_ptr(), balance.len() as i32)
+    };
+    if result != amount::NATIVE_LEN as i32 {
+        return None;
+    }
+
+    match amount::parse(&balance) {
+        Ok(Amount::Native(drops)) => Some(drops),
+        _ => None,
+    }
+}
+
+fn breaker_key() -> [u8; state::KEY_LEN] {
+    state::key(state::NS_BREAKER, &[])
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::admin::FOUNDATION_ACCOUNT;
+    use lks_hook_sdk::{fields, sim};
+    use crate::fixtures::{lks_payment, written_fee};
+    use crate::hook;
+
+    #[test]
+    fn breaker_pauses_sponsorship_until_reserve_recovers() {
+        const HOOK_ACCOUNT: [u8; 20] = [0x40; 20];
+        let account_keylet = |account: &[u8]| {
+            let mut keylet = std::vec![0, 3];
+            keylet.extend_from_slice(account);
+            keylet.resize(34, 0);
+            keylet
+        };
+        let set_balance = |account: &[u8], drops: u64| {
+            let balance = amount::encode_native(drops).to_vec();
+            sim::with(|host| {
+                host.ledger.entry(account_keylet(account)).or_default().insert(fields::SF_BALANCE, balance);
+            });
+        };
+
+        // The settlements are paid by the hook account, so the foundation
+        // account's own balance doesn't matter
+        lks_payment(25, 12);
+        sim::with(|host| host.hook_account = HOOK_ACCOUNT);
+        set_balance(FOUNDATION_ACCOUNT.as_bytes(), 0);
+        let set_balance = |drops: u64| set_balance(&HOOK_ACCOUNT, drops);
+        set_balance(DEFAULT_OPEN_BELOW - 1);
+        assert_eq!(sim::run(hook), HookError::BreakerOpen.return_value());
+        assert_eq!(written_fee(), 12);
+
+        // Between the thresholds the breaker stays open
+        set_balance(DEFAULT_CLOSE_ABOVE - 1);
+        assert_eq!(sim::run(hook), HookError::BreakerOpen.return_value());
+
+        set_balance(DEFAULT_CLOSE_ABOVE);
+        assert_eq!(sim::run(hook), 0);
+        assert_eq!(written_fee(), 0);
+    }
+}
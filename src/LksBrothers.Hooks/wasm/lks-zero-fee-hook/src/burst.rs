@@ -0,0 +1,181 @@
+// Burst limiting for the LKS zero-fee hook
+// The per-epoch caps still let an account spend its whole allowance in a few
+// ledgers. A token bucket per account smooths that out: the bucket holds up
+// to BURSTCAP sponsorships and refills one every BURSTRATE ledgers. An
+// account finding it empty is held back for BURSTHOLD ledgers, twice as
+// long every time it runs dry again, and pays its own fees while held. The
+// bucket only starts refilling once the hold is over, so an account coming
+// straight back is held again, for longer. Strikes are forgiven once the
+// bucket has refilled completely.
+// Registry partners, vouchered transactions and accounts still onboarding
+// aren't limited, as with the caps.
+//
+// State layout:
+//   bucket per account   [last refill ledger u32][level u32][strikes u32][held until ledger u32]
+
+use lks_hook_sdk::api::ledger_seq;
+use lks_hook_sdk::bytes;
+use lks_hook_sdk::entry::{Entry, Reader, Writer};
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::text::{self, Text};
+use lks_hook_sdk::{log, state};
+use crate::config;
+
+// Sponsorships an account can have back to back, zero to disable
+pub const PARAM_BURST_CAP: &[u8] = b"BURSTCAP";
+pub const DEFAULT_BURST_CAP: u64 = 0;
+
+// Ledgers it takes to refill one sponsorship
+pub const PARAM_BURST_RATE: &[u8] = b"BURSTRATE";
+pub const DEFAULT_BURST_RATE: u64 = 4;
+
+// Ledgers an account is held back after first running dry
+pub const PARAM_BURST_HOLD: &[u8] = b"BURSTHOLD";
+pub const DEFAULT_BURST_HOLD: u64 = 8;
+
+// Holds stop doubling after this many strikes
+pub const MAX_BACKOFF_SHIFT: u32 = 6;
+
+// The level and strikes stay small, so they are kept as varints
+struct Bucket {
+    last: u32,
+    level: u32,
+    strikes: u32,
+    held_until: u32,
+}
+
+impl Entry for Bucket {
+    #[inline(always)]
+    fn encode(&self, w: &mut Writer) {
+        w.u32(self.last).varint(self.level as u64).varint(self.strikes as u64).u32(self.held_until);
+    }
+
+    #[inline(always)]
+    fn decode(r: &mut Reader) -> Option<Bucket> {
+        Some(Bucket {
+            last: r.u32()?,
+            level: u32::try_from(r.varint()?).ok()?,
+            strikes: u32::try_from(r.varint()?).ok()?,
+            held_until: r.u32()?,
+        })
+    }
+}
+
+// Take a sponsorship from the bucket of `source`, or hold the account back
+// when it is empty. Declines with BurstLimited while the account is held.
+pub fn check(source: &[u8; 20]) -> Result<(), HookError> {
+    let cap = config::u64_param(PARAM_BURST_CAP, DEFAULT_BURST_CAP).min(u32::MAX as u64) as u32;
+    if cap == 0 {
+        return Ok(());
+    }
+    let rate = config::u64_param(PARAM_BURST_RATE, DEFAULT_BURST_RATE).clamp(1, u32::MAX as u64) as u32;
+
+    let ledger = unsafe { ledger_seq() } as u32;
+    let key = state::account_key(state::NS_BURST, source);
+    let Bucket { mut last, level, mut strikes, mut held_until } =
+        state::load_entry(&key).unwrap_or(Bucket { last: ledger, level: cap, strikes: 0, held_until: 0 });
+    let mut level = level.min(cap);
+
+    if ledger < held_until {
+        log::debug(log::EV_BURST, b"LKS account held back after a burst", strikes as u64, held_until as u64);
+        return Err(HookError::BurstLimited);
+    }
+
+    // Refill whole sponsorships, keeping the ledgers towards the next one
+    let refills = ledger.saturating_sub(last) / rate;
+    level = level.saturating_add(refills).min(cap);
+    last = if level == cap { ledger } else { last + refills * rate };
+    if level == cap {
+        strikes = 0;
+    }
+
+    let result = if level > 0 {
+        level -= 1;
+        Ok(())
+    } else {
+        strikes = strikes.saturating_add(1);
+        let hold = config::u64_param(PARAM_BURST_HOLD, DEFAULT_BURST_HOLD)
+            .saturating_mul(1u64 << (strikes - 1).min(MAX_BACKOFF_SHIFT))
+            .min(u32::MAX as u64) as u32;
+        last = ledger.saturating_add(hold);
+        held_until = last;
+
+        if log::enabled(log::Level::Warn) {
+            let mut msg = Text::<{ text::MESSAGE_LEN }>::new();
+            msg.push(b"LKS burst limit reached, account ").hex(bytes::head(source, 4));
+            log::warn(log::EV_BURST, msg.as_bytes(), strikes as u64, last as u64);
+        }
+        Err(HookError::BurstLimited)
+    };
+
+    state::store_entry(&key, &Bucket { last, level, strikes, held_until })?;
+    result
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::amount;
+    use lks_hook_sdk::{fields, sim};
+    use crate::fixtures::{lks_payment, written_fee, USER};
+    use crate::{hook, registry};
+
+    #[test]
+    fn holds_back_bursts_for_growing_periods() {
+        lks_payment(25, 12);
+        sim::with(|host| {
+            host.hook_params.insert(PARAM_BURST_CAP.to_vec(), 2u64.to_be_bytes().to_vec());
+            host.hook_params.insert(PARAM_BURST_RATE.to_vec(), 4u64.to_be_bytes().to_vec());
+            host.hook_params.insert(PARAM_BURST_HOLD.to_vec(), 8u64.to_be_bytes().to_vec());
+        });
+        let mut otxn = 0u8;
+        let mut run = |ledger: u64| {
+            otxn += 1;
+            sim::with(|host| {
+                host.ledger_seq = ledger;
+                host.otxn_id = [otxn; 32];
+                host.fields.insert(fields::SF_FEE, amount::encode_native(12).to_vec());
+            });
+            sim::run(hook)
+        };
+        let held = |strikes: u64, until: u64| {
+            let event = log::encode(log::Level::Warn, log::EV_BURST, strikes, until);
+            sim::with(|host| host.traces.iter().any(|(_, data)| data[..] == event[..]))
+        };
+
+        // A full bucket covers a burst of BURSTCAP
+        assert_eq!(run(1000), 0);
+        assert_eq!(run(1000), 0);
+        assert_eq!(run(1001), HookError::BurstLimited.return_value());
+        assert_eq!(written_fee(), 12);
+        assert!(held(1, 1009));
+        assert_eq!(run(1008), HookError::BurstLimited.return_value());
+
+        // Coming straight back after the hold doubles it
+        assert_eq!(run(1009), HookError::BurstLimited.return_value());
+        assert!(held(2, 1025));
+
+        // Waiting a refill after the hold earns one sponsorship
+        assert_eq!(run(1029), 0);
+        assert_eq!(written_fee(), 0);
+        assert_eq!(run(1029), HookError::BurstLimited.return_value());
+        assert!(held(3, 1061));
+        let key = state::account_key(state::NS_BURST, &USER);
+        // Level and strikes are varints of a byte each
+        let mut bucket = 1061u32.to_be_bytes().to_vec();
+        bucket.extend_from_slice(&[0, 3]);
+        bucket.extend_from_slice(&1061u32.to_be_bytes());
+        assert_eq!(sim::with(|host| host.value(&key).map(<[u8]>::to_vec)), Some(bucket));
+
+        // A full refill forgives the strikes
+        assert_eq!(run(1069), 0);
+        assert_eq!(run(1069), 0);
+        assert_eq!(run(1069), HookError::BurstLimited.return_value());
+        assert!(held(1, 1077));
+
+        // Registry partners aren't limited
+        let partner = state::account_key(state::NS_REGISTRY, &USER).to_vec();
+        sim::with(|host| host.state.insert(partner, std::vec![state::VERSION, registry::FLAG_ALLOWED]));
+        assert_eq!(run(1070), 0);
+    }
+}
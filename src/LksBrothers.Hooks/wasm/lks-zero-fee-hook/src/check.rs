@@ -0,0 +1,142 @@
+// Check handling for the LKS zero-fee hook
+// CheckCreate names the most a check can pay in SendMax, CheckCash the
+// amount taken (Amount) or the least accepted (DeliverMin), and CheckCancel
+// only the check. Checks created for LKS are remembered in hook state by
+// their id, so cancelling them is sponsored too; cashing or cancelling
+// consumes the check and its marker.
+
+use lks_hook_sdk::account;
+use lks_hook_sdk::api::util_keylet;
+use lks_hook_sdk::bytes;
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::fields::{self, SF_AMOUNT, SF_CHECK_ID, SF_DELIVER_MIN, SF_SEND_MAX};
+use lks_hook_sdk::state;
+use crate::{lks_amount, pass_through, rules, sponsor_transfer, sponsor_transfer_using, sponsor_using, Uses};
+use crate::{TX_TYPE_CHECK_CASH, TX_TYPE_CHECK_CREATE};
+
+// Marker value stored for checks paying LKS
+const MARKER: bool = true;
+
+const KEYLET_CHECK: u32 = 15;
+const KEYLET_LEN: usize = 34;
+
+pub fn handle_check(tx_type: i32) -> Result<(), HookError> {
+    if tx_type == TX_TYPE_CHECK_CREATE {
+        // Checks paying other currencies pay their own fee
+        let value = rules::asset(SF_SEND_MAX)?;
+
+        // The check's id is the key of its keylet, derived from the creator
+        // and the creating sequence (or ticket)
+        let owner = fields::read_account()?;
+        if let Some(check) = fields::read_sequence()?.and_then(|sequence| check_id(&owner, sequence)) {
+            state::store_entry(&check_key(&check), &MARKER)?;
+        }
+
+        return sponsor_transfer(value, b"LKS COIN check creation fee sponsored",
+                                       b"Zero-fee LKS COIN check accepted");
+    }
+
+    // CheckCash and CheckCancel reference the check by CheckID
+    let key = match fields::read_hash256(SF_CHECK_ID)? {
+        Some(check) => check_key(&check),
+        None => return pass_through(b"Non-LKS check processed normally"),
+    };
+    let known = state::load_entry::<bool>(&key).is_some();
+
+    let value = if tx_type == TX_TYPE_CHECK_CASH {
+        match lks_amount(SF_AMOUNT)? {
+            Some(value) => Some(value),
+            None => lks_amount(SF_DELIVER_MIN)?,
+        }
+    } else {
+        None
+    };
+    if !known && value.is_none() {
+        return pass_through(b"Non-LKS check processed normally");
+    }
+
+    // The marker goes with the check once settling it is sponsored
+    let uses = if known { Uses::Marker(&key) } else { Uses::Nothing };
+    match value {
+        Some(value) => sponsor_transfer_using(value, uses, b"LKS COIN check cash fee sponsored",
+                                                           b"Zero-fee LKS COIN check cash accepted"),
+        None => sponsor_using(None, uses, b"LKS COIN check settlement fee sponsored",
+                                          b"Zero-fee LKS COIN check settlement accepted"),
+    }
+}
+
+fn check_id(owner: &[u8; 20], sequence: u32) -> Option<[u8; 32]> {
+    let mut keylet = [0u8; KEYLET_LEN];
+    let result = unsafe {
+        util_keylet(keylet.as_mut_ptr(), keylet.len() as i32, KEYLET_CHECK,
+                    owner.as_ptr(), account::ACCOUNT_ID_LEN as i32, sequence)
+    };
+    if result != KEYLET_LEN as i32 {
+        return None;
+    }
+
+    bytes::array(&keylet, 2)
+}
+
+fn check_key(check: &[u8; 32]) -> [u8; state::KEY_LEN] {
+    state::key(state::NS_CHECK, &[check])
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use lks_hook_sdk::amount;
+    use lks_hook_sdk::error::HookError;
+    use lks_hook_sdk::{fields, sim};
+    use crate::fixtures::{lks_amount_bytes, lks_payment, written_fee, MERCHANT, USER};
+    use crate::{config, hook, TX_TYPE_CHECK_CANCEL, TX_TYPE_CHECK_CASH, TX_TYPE_CHECK_CREATE};
+
+    #[test]
+    fn sponsors_lks_checks_from_creation_to_settlement() {
+        lks_payment(25, 12);
+        let send_max = lks_amount_bytes(25);
+        sim::with(|host| {
+            host.tx_type = TX_TYPE_CHECK_CREATE;
+            host.fields.remove(&fields::SF_AMOUNT);
+            host.fields.insert(fields::SF_SEND_MAX, send_max);
+            host.fields.insert(fields::SF_SEQUENCE, 7u32.to_be_bytes().to_vec());
+        });
+        assert_eq!(sim::run(hook), 0);
+        assert_eq!(written_fee(), 0);
+
+        // The simulator's check ids are the creator and sequence
+        let mut check = USER.to_vec();
+        check.extend_from_slice(&7u32.to_be_bytes());
+        check.resize(32, 0);
+        let settle = |tx_type: i32, otxn: u8, amount: Option<(fields::FieldId, std::vec::Vec<u8>)>, result: i64| {
+            let fee = amount::encode_native(12).to_vec();
+            let check = check.clone();
+            sim::with(|host| {
+                host.tx_type = tx_type;
+                host.otxn_id = [otxn; 32];
+                host.fields.clear();
+                host.fields.insert(fields::SF_FEE, fee);
+                host.fields.insert(fields::SF_ACCOUNT, MERCHANT.to_vec());
+                host.fields.insert(fields::SF_CHECK_ID, check);
+                if let Some((field, value)) = amount {
+                    host.fields.insert(field, value);
+                }
+            });
+            assert_eq!(sim::run(hook), result);
+            written_fee()
+        };
+
+        // Cancelling relies on the marker, which goes with the check. A
+        // cancel declined by the fee cap keeps it for the retry.
+        let fee_cap = 11u64.to_be_bytes().to_vec();
+        sim::with(|host| host.hook_params.insert(config::PARAM_MAX_FEE.to_vec(), fee_cap));
+        assert_eq!(settle(TX_TYPE_CHECK_CANCEL, 1, None, HookError::FeeCapExceeded.return_value()), 12);
+        sim::with(|host| host.hook_params.remove(config::PARAM_MAX_FEE));
+        assert_eq!(settle(TX_TYPE_CHECK_CANCEL, 1, None, 0), 0);
+        assert_eq!(settle(TX_TYPE_CHECK_CANCEL, 2, None, 0), 12);
+
+        // Cashing names its amount
+        assert_eq!(settle(TX_TYPE_CHECK_CASH, 3, Some((fields::SF_DELIVER_MIN, lks_amount_bytes(20))), 0), 0);
+        let native = amount::encode_native(5_000).to_vec();
+        assert_eq!(settle(TX_TYPE_CHECK_CASH, 4, Some((fields::SF_AMOUNT, native)), 0), 12);
+    }
+}
@@ -0,0 +1,22 @@
+// Hook parameters of the LKS zero-fee hook
+// Parameters are read through the SDK's config module, so SetHook values and
+// foundation overrides work the same way for every LKS hook.
+
+pub use lks_hook_sdk::config::*;
+
+// Sponsor only the first LKS TrustSet of each account
+pub const PARAM_TRUSTSET_FIRST_ONLY: &[u8] = b"TSFIRST";
+
+// Sponsor only accounts with a KYC marker
+pub const PARAM_KYC_ONLY: &[u8] = b"KYCONLY";
+
+// Sponsor cross-currency payments funded by or bridged through LKS
+pub const PARAM_CROSS_CURRENCY: &[u8] = b"XCUR";
+
+// Largest original fee (in drops) the foundation will sponsor
+pub const PARAM_MAX_FEE: &[u8] = b"MAXFEE";
+pub const DEFAULT_MAX_FEE: u64 = 1_000;
+
+// Smallest LKS payment (in micro-LKS) the foundation will sponsor
+pub const PARAM_MIN_AMOUNT: &[u8] = b"MINAMT";
+pub const DEFAULT_MIN_AMOUNT: u64 = 1_000;
@@ -0,0 +1,110 @@
+// Sponsored currencies for the LKS zero-fee hook
+// LKS COIN is always sponsored. Further LKS-family tokens (LKSGOLD, ...) are
+// added and removed by the foundation with an admin command and kept in hook
+// state, one entry per currency code. Each currency code has a single
+// issuer; the same code from any other issuer is not an LKS token.
+
+use lks_hook_sdk::account::AccountId;
+use lks_hook_sdk::amount;
+use lks_hook_sdk::bytes;
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::state;
+use crate::{LKS_CURRENCY_CODE, LKS_ISSUER};
+
+// Admin command operations
+const OP_ADD: u8 = 1;
+const OP_REMOVE: u8 = 2;
+
+// Length of an encoded currency command: op, decimals, currency, issuer
+pub const COMMAND_LEN: usize = 42;
+
+// Currency entries: [issuer(20), decimals u8]
+type Listing = ([u8; 20], u8);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Token {
+    pub issuer: AccountId,
+    // Decimal places of the token's smallest unit, which amounts are
+    // converted to before tier and dust checks
+    pub decimals: u32,
+}
+
+// Look up a sponsored currency by its 20-byte currency code
+pub fn lookup(currency: &[u8; 20]) -> Option<Token> {
+    if bytes::eq(currency, &amount::currency_code(&LKS_CURRENCY_CODE)) {
+        return Some(Token { issuer: LKS_ISSUER, decimals: amount::LKS_DECIMALS });
+    }
+
+    let (issuer, decimals) = state::load_entry::<Listing>(&currency_key(currency))?;
+    Some(Token { issuer: AccountId::new(issuer), decimals: decimals as u32 })
+}
+
+// Apply an encoded currency command: [op, decimals, currency(20), issuer(20)]
+// The issuer is ignored when removing. LKS COIN itself can't be changed.
+pub fn apply_command(command: &[u8]) -> Result<u8, HookError> {
+    let (op, decimals, currency) = match (command.first(), command.get(1), bytes::array::<20>(command, 2)) {
+        (Some(&op), Some(&decimals), Some(currency)) if command.len() == COMMAND_LEN => (op, decimals, currency),
+        _ => return Err(HookError::AdminCommandInvalid),
+    };
+
+    if bytes::eq(&currency, &amount::currency_code(&LKS_CURRENCY_CODE)) {
+        return Err(HookError::AdminCommandInvalid);
+    }
+
+    let key = currency_key(&currency);
+    match op {
+        OP_ADD => {
+            let issuer = bytes::array(command, 22).unwrap_or_default();
+            state::store_entry::<Listing>(&key, &(issuer, decimals))?;
+        }
+        OP_REMOVE => state::erase(&key)?,
+        _ => return Err(HookError::AdminCommandInvalid),
+    }
+
+    Ok(op)
+}
+
+fn currency_key(currency: &[u8; 20]) -> [u8; state::KEY_LEN] {
+    state::key(state::NS_CURRENCY, &[currency])
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use lks_hook_sdk::admin::FOUNDATION_ACCOUNT;
+    use lks_hook_sdk::xfl::Xfl;
+    use lks_hook_sdk::{fields, sim};
+    use crate::fixtures::{lks_payment, written_fee};
+    use crate::{dispatch, hook, TX_TYPE_INVOKE};
+
+    #[test]
+    fn sponsors_currency_added_by_foundation() {
+        let mut gold = [0u8; 20];
+        gold[..7].copy_from_slice(b"LKSGOLD");
+        let issuer = [0xCC; 20];
+
+        let mut amount = Xfl::from_int(25).to_amount_value().to_vec();
+        amount.extend_from_slice(&gold);
+        amount.extend_from_slice(&issuer);
+
+        let mut command = std::vec![1, 6];
+        command.extend_from_slice(&gold);
+        command.extend_from_slice(&issuer);
+
+        sim::reset();
+        sim::with(|host| {
+            host.tx_type = TX_TYPE_INVOKE;
+            host.fields.insert(fields::SF_ACCOUNT, FOUNDATION_ACCOUNT.as_bytes().to_vec());
+            host.otxn_params.insert(dispatch::PARAM_CURRENCY.to_vec(), command);
+        });
+        assert_eq!(sim::run(hook), 0);
+
+        let currencies = sim::with(|host| std::mem::take(&mut host.state));
+        lks_payment(25, 12);
+        sim::with(|host| {
+            host.state = currencies;
+            host.fields.insert(fields::SF_AMOUNT, amount);
+        });
+        assert_eq!(sim::run(hook), 0);
+        assert_eq!(written_fee(), 0);
+    }
+}
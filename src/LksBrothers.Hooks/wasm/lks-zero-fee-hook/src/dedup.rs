@@ -0,0 +1,71 @@
+// Duplicate detection for sponsored transactions
+// Nodes may run the hook more than once for the same transaction (retries,
+// re-application after a ledger close). The hash of every sponsored
+// transaction is recorded so a repeat run doesn't count against the limits
+// or write a second receipt.
+//
+// The window is the limit epoch: marks start with their epoch and are
+// registered for pruning, so they expire together with the counters they
+// protect.
+
+use lks_hook_sdk::api::otxn_id;
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::state;
+use crate::prune;
+
+pub const TX_ID_LEN: usize = 32;
+
+// Mark entries: [epoch u32]
+pub struct TxMark {
+    key: [u8; state::KEY_LEN],
+    epoch: u32,
+}
+
+// Look up the originating transaction for `epoch`
+pub fn current(epoch: u32) -> Result<TxMark, HookError> {
+    let mut id = [0u8; TX_ID_LEN];
+    let result = unsafe { otxn_id(id.as_mut_ptr(), id.len() as i32, 0) };
+    if result != TX_ID_LEN as i32 {
+        return Err(HookError::TxIdReadFailed);
+    }
+
+    Ok(TxMark { key: state::key(state::NS_SEEN, &[&id]), epoch })
+}
+
+// Whether the transaction was already sponsored in this epoch
+pub fn is_duplicate(mark: &TxMark) -> bool {
+    state::load_entry::<u32>(&mark.key) == Some(mark.epoch)
+}
+
+pub fn record(mark: &TxMark) -> Result<(), HookError> {
+    state::store_entry(&mark.key, &mark.epoch)?;
+    prune::register(&mark.key, mark.epoch)
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use lks_hook_sdk::amount;
+    use lks_hook_sdk::{fields, sim, state};
+    use crate::fixtures::{lks_payment, written_fee, USER};
+    use crate::{hook, receipt};
+
+    #[test]
+    fn repeated_transaction_is_counted_once() {
+        lks_payment(25, 12);
+        assert_eq!(sim::run(hook), 0);
+
+        // The node runs the same transaction again with its original fee
+        let fee = amount::encode_native(12).to_vec();
+        sim::with(|host| host.fields.insert(fields::SF_FEE, fee));
+        assert_eq!(sim::run(hook), 0);
+        assert_eq!(written_fee(), 0);
+
+        let head_key = receipt::head_key();
+        let account_key = state::account_key(state::NS_ACCOUNT_LIMIT, &USER);
+        let (head, counter) = sim::with(|host| {
+            (host.value(&head_key).unwrap().to_vec(), host.value(&account_key).unwrap().to_vec())
+        });
+        assert_eq!(u32::from_be_bytes(head[..4].try_into().unwrap()), 1);
+        assert_eq!(u64::from_be_bytes(counter[4..].try_into().unwrap()), 1);
+    }
+}
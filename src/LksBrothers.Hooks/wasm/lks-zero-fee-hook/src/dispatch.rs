@@ -0,0 +1,345 @@
+// Admin command dispatcher for the LKS zero-fee hook
+// The foundation configures the hook at runtime with Invoke transactions.
+// Typed commands [op u8][payload] arrive in the LKSCMD parameter or as the
+// MemoData of memos typed "lks/admin", several per transaction if needed;
+// the per-area parameters (LKSREG, LKSCFG, LKSCUR and the KYC command) carry
+// the payload of one command each. Commands are only applied once the signer
+// is known to be the foundation, and each applied command leaves an audit
+// trace of its op and signer. Commands naming an account last (registry,
+// foundation rotation, exchange policy) take it as 20 bytes, 40 hex digits
+// or an r-address.
+
+use lks_hook_sdk::admin::{is_foundation, read_otxn_param, rotate_foundation};
+use lks_hook_sdk::api::accept;
+use lks_hook_sdk::{bytes, codec};
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::fields::{self, SF_MEMOS};
+use lks_hook_sdk::text::{self, Text};
+use lks_hook_sdk::{kyc, log, memo};
+use crate::{config, currency, exchange, limits, pause, pass_through, registry};
+
+// Transaction parameters carrying admin commands
+pub const PARAM_COMMAND: &[u8] = b"LKSCMD";
+pub const PARAM_REGISTRY: &[u8] = b"LKSREG";
+pub const PARAM_CONFIG: &[u8] = b"LKSCFG";
+pub const PARAM_CURRENCY: &[u8] = b"LKSCUR";
+
+pub const MEMO_TYPE_COMMAND: &[u8] = b"lks/admin";
+
+// Command ops and their payloads
+pub const OP_REGISTRY: u8 = 0x01; // registry command
+pub const OP_CONFIG: u8 = 0x02; // config override command
+pub const OP_CURRENCY: u8 = 0x03; // currency command
+pub const OP_KYC: u8 = 0x04; // KYC command
+pub const OP_SET_BUDGET: u8 = 0x05; // [drops per epoch u64]
+pub const OP_PAUSE: u8 = 0x06; // [paused u8]
+pub const OP_ROTATE_FOUNDATION: u8 = 0x07; // [new foundation account]
+pub const OP_EXCHANGE: u8 = 0x08; // exchange policy command
+
+const MAX_COMMAND_LEN: usize = 1 + config::MAX_COMMAND_LEN;
+
+// Commands per transaction, so their traces stay within the text guard budget
+const MAX_COMMANDS: u32 = 3;
+
+pub fn handle_invoke() -> Result<(), HookError> {
+    let mut registry_command = [0u8; registry::MAX_COMMAND_LEN];
+    let registry_len = read_otxn_param(PARAM_REGISTRY, &mut registry_command);
+    let mut config_command = [0u8; config::MAX_COMMAND_LEN];
+    let config_len = read_otxn_param(PARAM_CONFIG, &mut config_command);
+    let mut currency_command = [0u8; currency::COMMAND_LEN];
+    let currency_len = read_otxn_param(PARAM_CURRENCY, &mut currency_command);
+    let mut kyc_command = [0u8; kyc::COMMAND_LEN];
+    let kyc_len = read_otxn_param(kyc::PARAM_COMMAND, &mut kyc_command);
+    let mut command = [0u8; MAX_COMMAND_LEN];
+    let command_len = read_otxn_param(PARAM_COMMAND, &mut command);
+
+    // Memos that don't decode carry no commands
+    let mut memo_buffer = [0u8; memo::MAX_MEMOS_LEN];
+    let memos = match fields::read_raw(SF_MEMOS, &mut memo_buffer) {
+        Ok(Some(memos)) => memos,
+        _ => &[],
+    };
+    let mut memo_commands = 0;
+    if memo::for_each_memo(memos, |memo| memo_commands += bytes::eq(memo.memo_type, MEMO_TYPE_COMMAND) as u32).is_err() {
+        memo_commands = 0;
+    }
+
+    let commands = (registry_len > 0) as u32 + (config_len > 0) as u32 + (currency_len > 0) as u32
+        + (kyc_len > 0) as u32 + (command_len > 0) as u32 + memo_commands;
+
+    // Invoke transactions without an admin command are not ours to handle
+    if commands == 0 {
+        return pass_through(b"Invoke without LKS admin command processed normally");
+    }
+
+    // Only the foundation may run admin commands
+    let source = fields::read_account()?;
+    if !is_foundation(&source) {
+        return Err(HookError::Unauthorized);
+    }
+    if commands > MAX_COMMANDS {
+        return Err(HookError::AdminCommandInvalid);
+    }
+
+    if registry_len > 0 {
+        apply(&source, OP_REGISTRY, bytes::head(&registry_command, registry_len))?;
+    }
+    if config_len > 0 {
+        apply(&source, OP_CONFIG, bytes::head(&config_command, config_len))?;
+    }
+    if currency_len > 0 {
+        apply(&source, OP_CURRENCY, bytes::head(&currency_command, currency_len))?;
+    }
+    if kyc_len > 0 {
+        apply(&source, OP_KYC, bytes::head(&kyc_command, kyc_len))?;
+    }
+    if command_len > 0 {
+        apply_typed(&source, bytes::head(&command, command_len))?;
+    }
+    if memo_commands > 0 {
+        let mut result = Ok(());
+        memo::for_each_memo(memos, |memo| {
+            if bytes::eq(memo.memo_type, MEMO_TYPE_COMMAND) && result.is_ok() {
+                result = apply_typed(&source, memo.data);
+            }
+        })?;
+        result?;
+    }
+
+    let success_msg = b"LKS admin command applied";
+    unsafe {
+        accept(success_msg.as_ptr(), success_msg.len() as i32);
+    }
+
+    Ok(())
+}
+
+fn apply_typed(source: &[u8; 20], command: &[u8]) -> Result<(), HookError> {
+    match command.first() {
+        Some(&op) => apply(source, op, bytes::tail(command, 1)),
+        None => Err(HookError::AdminCommandInvalid),
+    }
+}
+
+// Apply one command signed by `source` and trace its audit record
+fn apply(source: &[u8; 20], op: u8, payload: &[u8]) -> Result<(), HookError> {
+    match op {
+        OP_REGISTRY => {
+            let (flags, account) = registry::apply_command(payload)?;
+
+            if log::enabled(log::Level::Info) {
+                let mut msg = Text::<{ text::MESSAGE_LEN }>::new();
+                msg.push(b"LKS registry updated, account ").hex(&account);
+                log::info(log::EV_REGISTRY, msg.as_bytes(), flags as u64, 0);
+            }
+        }
+        OP_CONFIG => {
+            let value_len = config::apply_command(payload)?;
+            log::info(log::EV_CONFIG, b"LKS config override updated", value_len as u64, 0);
+        }
+        OP_CURRENCY => {
+            let currency_op = currency::apply_command(payload)?;
+            log::info(log::EV_CURRENCY, b"LKS sponsored currencies updated", currency_op as u64, 0);
+        }
+        OP_KYC => {
+            let kyc_op = kyc::apply_command(payload)?;
+
+            if log::enabled(log::Level::Info) {
+                let mut msg = Text::<{ text::MESSAGE_LEN }>::new();
+                msg.push(b"LKS KYC marker updated, account ").hex(bytes::head(bytes::tail(payload, 1), 20));
+                log::info(log::EV_KYC, msg.as_bytes(), kyc_op as u64, 0);
+            }
+        }
+        OP_SET_BUDGET => {
+            let budget = bytes::u64_at(payload, 0).filter(|_| payload.len() == 8)
+                .ok_or(HookError::AdminCommandInvalid)?;
+            config::set_override(limits::PARAM_BUDGET, &budget.to_be_bytes())?;
+        }
+        OP_PAUSE => match payload {
+            [paused] => pause::set_paused(*paused != 0)?,
+            _ => return Err(HookError::AdminCommandInvalid),
+        },
+        OP_ROTATE_FOUNDATION => {
+            let account = codec::parse_account(payload).ok_or(HookError::AdminCommandInvalid)?;
+            rotate_foundation(&account)?;
+        }
+        OP_EXCHANGE => {
+            let (flags, destination) = exchange::apply_command(payload)?;
+
+            if log::enabled(log::Level::Info) {
+                let mut msg = Text::<{ text::MESSAGE_LEN }>::new();
+                msg.push(b"LKS exchange policy updated, destination ").hex(&destination);
+                log::info(log::EV_EXCHANGE, msg.as_bytes(), flags as u64, 0);
+            }
+        }
+        _ => return Err(HookError::AdminCommandInvalid),
+    }
+
+    let signer = bytes::u64_at(source, 0).unwrap_or(0);
+    log::info(log::EV_ADMIN, b"LKS admin command applied", op as u64, signer);
+
+    Ok(())
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::admin::{self, FOUNDATION_ACCOUNT};
+    use lks_hook_sdk::{sim, state};
+    use crate::fixtures::{foundation_invoke, lks_payment, written_fee, USER};
+    use crate::{hook, TX_TYPE_INVOKE};
+
+    // Serialized Memos array holding one admin command memo
+    fn command_memo(command: &[u8]) -> std::vec::Vec<u8> {
+        let mut memos = std::vec![0xEA, 0x7C, MEMO_TYPE_COMMAND.len() as u8];
+        memos.extend_from_slice(MEMO_TYPE_COMMAND);
+        memos.extend_from_slice(&[0x7D, command.len() as u8]);
+        memos.extend_from_slice(command);
+        memos.extend_from_slice(&[0xE1, 0xF1]);
+        memos
+    }
+
+    #[test]
+    fn foundation_invoke_overrides_config() {
+        let mut command = std::vec![config::PARAM_MAX_FEE.len() as u8];
+        command.extend_from_slice(config::PARAM_MAX_FEE);
+        command.extend_from_slice(&5u64.to_be_bytes());
+
+        sim::reset();
+        sim::with(|host| {
+            host.tx_type = TX_TYPE_INVOKE;
+            host.fields.insert(fields::SF_ACCOUNT, USER.to_vec());
+            host.otxn_params.insert(PARAM_CONFIG.to_vec(), command.clone());
+        });
+        assert_eq!(sim::run(hook), HookError::Unauthorized.return_value());
+
+        sim::with(|host| host.fields.insert(fields::SF_ACCOUNT, FOUNDATION_ACCOUNT.as_bytes().to_vec()));
+        assert_eq!(sim::run(hook), 0);
+
+        // The override lowers the fee cap below the payment's fee
+        let overrides = sim::with(|host| std::mem::take(&mut host.state));
+        lks_payment(25, 12);
+        sim::with(|host| host.state = overrides);
+        assert_eq!(sim::run(hook), HookError::FeeCapExceeded.return_value());
+        assert_eq!(written_fee(), 12);
+    }
+
+    #[test]
+    fn typed_commands_pause_sponsorship_and_set_budget() {
+        let mut budget = std::vec![OP_SET_BUDGET];
+        budget.extend_from_slice(&5u64.to_be_bytes());
+
+        foundation_invoke(FOUNDATION_ACCOUNT.as_bytes());
+        sim::with(|host| {
+            host.otxn_params.insert(PARAM_COMMAND.to_vec(), std::vec![OP_PAUSE, 1]);
+            host.fields.insert(fields::SF_MEMOS, command_memo(&budget));
+        });
+        assert_eq!(sim::run(hook), 0);
+
+        // Every applied command leaves an audit record naming the signer
+        let signer = u64::from_be_bytes(FOUNDATION_ACCOUNT.as_bytes()[..8].try_into().unwrap());
+        let payloads: std::vec::Vec<std::vec::Vec<u8>> =
+            sim::with(|host| host.traces.iter().map(|(_, data)| data.clone()).collect());
+        for op in [OP_PAUSE, OP_SET_BUDGET] {
+            let audit = log::encode(log::Level::Info, log::EV_ADMIN, op as u64, signer);
+            assert!(payloads.contains(&audit.to_vec()));
+        }
+
+        let admin_state = sim::with(|host| std::mem::take(&mut host.state));
+        lks_payment(25, 12);
+        sim::with(|host| host.state = admin_state.clone());
+        assert_eq!(sim::run(hook), HookError::SponsorshipPaused.return_value());
+        assert_eq!(written_fee(), 12);
+
+        // Resumed, the payment runs into the lowered budget
+        foundation_invoke(FOUNDATION_ACCOUNT.as_bytes());
+        sim::with(|host| {
+            host.state = admin_state;
+            host.otxn_params.insert(PARAM_COMMAND.to_vec(), std::vec![OP_PAUSE, 0]);
+        });
+        assert_eq!(sim::run(hook), 0);
+
+        let admin_state = sim::with(|host| std::mem::take(&mut host.state));
+        lks_payment(25, 12);
+        sim::with(|host| host.state = admin_state);
+        assert_eq!(sim::run(hook), HookError::BudgetExceeded.return_value());
+    }
+
+    #[test]
+    fn rotated_foundation_replaces_compiled_account() {
+        let successor = [0xF0; 20];
+        let mut rotate = std::vec![OP_ROTATE_FOUNDATION];
+        rotate.extend_from_slice(&successor);
+
+        foundation_invoke(&USER);
+        sim::with(|host| host.otxn_params.insert(PARAM_COMMAND.to_vec(), rotate.clone()));
+        assert_eq!(sim::run(hook), HookError::Unauthorized.return_value());
+
+        sim::with(|host| host.fields.insert(fields::SF_ACCOUNT, FOUNDATION_ACCOUNT.as_bytes().to_vec()));
+        assert_eq!(sim::run(hook), 0);
+
+        // The compiled account can no longer administer the hook
+        let rotated = sim::with(|host| std::mem::take(&mut host.state));
+        foundation_invoke(FOUNDATION_ACCOUNT.as_bytes());
+        sim::with(|host| {
+            host.state = rotated.clone();
+            host.otxn_params.insert(PARAM_COMMAND.to_vec(), std::vec![OP_PAUSE, 1]);
+        });
+        assert_eq!(sim::run(hook), HookError::Unauthorized.return_value());
+
+        sim::with(|host| host.fields.insert(fields::SF_ACCOUNT, successor.to_vec()));
+        assert_eq!(sim::run(hook), 0);
+
+        // Unknown ops and malformed payloads are refused
+        for command in [std::vec![0xFF], std::vec![OP_PAUSE], std::vec![OP_ROTATE_FOUNDATION, 0]] {
+            foundation_invoke(&successor);
+            sim::with(|host| {
+                host.state = rotated.clone();
+                host.otxn_params.insert(PARAM_COMMAND.to_vec(), command);
+            });
+            assert_eq!(sim::run(hook), HookError::AdminCommandInvalid.return_value());
+        }
+    }
+
+    #[test]
+    fn rotated_foundation_shares_the_role_during_grace_period() {
+        let successor = [0xF0; 20];
+        let mut rotate = std::vec![OP_ROTATE_FOUNDATION];
+        rotate.extend_from_slice(&successor);
+
+        foundation_invoke(FOUNDATION_ACCOUNT.as_bytes());
+        sim::with(|host| {
+            host.ledger_seq = 1000;
+            host.hook_params.insert(admin::PARAM_FOUNDATION_GRACE.to_vec(), 10u64.to_be_bytes().to_vec());
+            host.otxn_params.insert(PARAM_COMMAND.to_vec(), rotate);
+        });
+        assert_eq!(sim::run(hook), 0);
+        let pending = log::encode(log::Level::Info, log::EV_FOUNDATION, admin::PHASE_PENDING, 1010);
+        assert!(sim::with(|host| host.traces.iter().any(|(_, data)| *data == pending)));
+        let rotated = sim::with(|host| host.state.clone());
+
+        // Until ledger 1010 both accounts administer the hook
+        let pause = |signer: &[u8; 20], ledger: u64| {
+            foundation_invoke(signer);
+            sim::with(|host| {
+                host.state = rotated.clone();
+                host.ledger_seq = ledger;
+                host.otxn_params.insert(PARAM_COMMAND.to_vec(), std::vec![OP_PAUSE, 1]);
+            });
+            sim::run(hook)
+        };
+        assert_eq!(pause(FOUNDATION_ACCOUNT.as_bytes(), 1009), 0);
+        let outgoing = log::encode(log::Level::Info, log::EV_FOUNDATION, admin::PHASE_OUTGOING, 1010);
+        assert!(sim::with(|host| host.traces.iter().any(|(_, data)| *data == outgoing)));
+        assert_eq!(pause(&successor, 1009), 0);
+        assert_eq!(pause(&USER, 1009), HookError::Unauthorized.return_value());
+
+        // Then the outgoing account retires
+        assert_eq!(pause(FOUNDATION_ACCOUNT.as_bytes(), 1010), HookError::Unauthorized.return_value());
+        assert_eq!(pause(&successor, 1010), 0);
+        let retired = log::encode(log::Level::Info, log::EV_FOUNDATION, admin::PHASE_RETIRED, 1010);
+        assert!(sim::with(|host| host.traces.iter().any(|(_, data)| *data == retired)));
+        let key = state::key(state::NS_FOUNDATION, &[]);
+        assert_eq!(sim::with(|host| host.value(&key).map(<[u8]>::to_vec)), Some(successor.to_vec()));
+    }
+}
@@ -0,0 +1,142 @@
+// Escrow and payment channel handling for the LKS zero-fee hook
+// EscrowFinish/EscrowCancel and close-only channel claims carry no Amount, so
+// LKS involvement is remembered in hook state when the escrow or channel is
+// first seen with an LKS amount
+
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::fields::{self, SF_AMOUNT, SF_BALANCE, SF_CHANNEL, SF_OFFER_SEQUENCE, SF_OWNER};
+use lks_hook_sdk::state;
+use crate::{field_has_lks, pass_through, rules, sponsor, sponsor_using, Uses};
+use crate::{TX_TYPE_ESCROW_CREATE, TX_TYPE_PAYCHAN_CLAIM, TX_TYPE_PAYCHAN_CREATE};
+
+// Marker value stored for escrows and channels holding LKS
+const MARKER: bool = true;
+
+pub fn handle_escrow(tx_type: i32) -> Result<(), HookError> {
+    if tx_type == TX_TYPE_ESCROW_CREATE {
+        // Escrows of other currencies pay their own fee
+        rules::asset(SF_AMOUNT)?;
+
+        // Escrows are identified by their owner and the creating sequence (or
+        // ticket)
+        let owner = fields::read_account()?;
+        if let Some(sequence) = fields::read_sequence()? {
+            state::store_entry(&escrow_key(&owner, sequence), &MARKER)?;
+        }
+
+        return sponsor(None, b"LKS COIN escrow creation fee sponsored",
+                             b"Zero-fee LKS COIN escrow accepted");
+    }
+
+    // EscrowFinish and EscrowCancel reference the escrow by Owner and OfferSequence
+    let key = match (fields::read_account_field(SF_OWNER)?, fields::read_u32(SF_OFFER_SEQUENCE)?) {
+        (Some(owner), Some(sequence)) => escrow_key(&owner, sequence),
+        _ => return pass_through(b"Non-LKS escrow processed normally"),
+    };
+
+    if state::load_entry::<bool>(&key).is_none() {
+        return pass_through(b"Non-LKS escrow processed normally");
+    }
+
+    // Finishing or cancelling consumes the escrow, so its marker goes too
+    sponsor_using(None, Uses::Marker(&key), b"LKS COIN escrow settlement fee sponsored",
+                                            b"Zero-fee LKS COIN escrow settlement accepted")
+}
+
+pub fn handle_payment_channel(tx_type: i32) -> Result<(), HookError> {
+    // The channel id is only known once the channel exists, so creation is
+    // sponsored on its Amount alone
+    if tx_type == TX_TYPE_PAYCHAN_CREATE {
+        rules::asset(SF_AMOUNT)?;
+        return sponsor(None, b"LKS COIN payment channel creation fee sponsored",
+                             b"Zero-fee LKS COIN payment channel accepted");
+    }
+
+    let has_lks_amount = field_has_lks(SF_AMOUNT)?
+        || (tx_type == TX_TYPE_PAYCHAN_CLAIM && field_has_lks(SF_BALANCE)?);
+    let channel = match fields::read_hash256(SF_CHANNEL)? {
+        Some(channel) => channel,
+        None => return pass_through(b"Non-LKS payment channel processed normally"),
+    };
+
+    let key = state::key(state::NS_CHANNEL, &[&channel]);
+    if has_lks_amount {
+        state::store_entry(&key, &MARKER)?;
+    } else {
+        // Claims without amounts (e.g. closing a channel) rely on the marker
+        if state::load_entry::<bool>(&key).is_none() {
+            return pass_through(b"Non-LKS payment channel processed normally");
+        }
+    }
+
+    sponsor(None, b"LKS COIN payment channel fee sponsored",
+                  b"Zero-fee LKS COIN payment channel operation accepted")
+}
+
+// State key for an escrow: owner account followed by the escrow sequence
+fn escrow_key(owner: &[u8; 20], sequence: u32) -> [u8; state::KEY_LEN] {
+    state::key(state::NS_ESCROW, &[owner, &sequence.to_be_bytes()])
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use lks_hook_sdk::amount;
+    use lks_hook_sdk::error::HookError;
+    use lks_hook_sdk::{fields, sim};
+    use crate::fixtures::{lks_payment, written_fee, MERCHANT, USER};
+    use crate::{hook, TX_TYPE_ESCROW_CREATE, TX_TYPE_ESCROW_FINISH};
+
+    #[test]
+    fn ticketed_escrow_is_tracked_by_its_ticket() {
+        lks_payment(25, 12);
+        sim::with(|host| {
+            host.tx_type = TX_TYPE_ESCROW_CREATE;
+            host.fields.insert(fields::SF_SEQUENCE, 0u32.to_be_bytes().to_vec());
+            host.fields.insert(fields::SF_TICKET_SEQUENCE, 55u32.to_be_bytes().to_vec());
+        });
+        assert_eq!(sim::run(hook), 0);
+
+        let fee = amount::encode_native(12).to_vec();
+        sim::with(|host| {
+            host.tx_type = TX_TYPE_ESCROW_FINISH;
+            host.otxn_id = [3; 32];
+            host.fields.clear();
+            host.fields.insert(fields::SF_FEE, fee);
+            host.fields.insert(fields::SF_ACCOUNT, MERCHANT.to_vec());
+            host.fields.insert(fields::SF_OWNER, USER.to_vec());
+            host.fields.insert(fields::SF_OFFER_SEQUENCE, 55u32.to_be_bytes().to_vec());
+        });
+        assert_eq!(sim::run(hook), 0);
+        assert_eq!(written_fee(), 0);
+    }
+
+    #[test]
+    fn declined_escrow_settlement_keeps_its_marker() {
+        lks_payment(25, 12);
+        sim::with(|host| {
+            host.tx_type = TX_TYPE_ESCROW_CREATE;
+            host.fields.insert(fields::SF_SEQUENCE, 9u32.to_be_bytes().to_vec());
+        });
+        assert_eq!(sim::run(hook), 0);
+
+        let finish = |otxn: u8, fee: u64| {
+            let fee = amount::encode_native(fee).to_vec();
+            sim::with(|host| {
+                host.tx_type = TX_TYPE_ESCROW_FINISH;
+                host.otxn_id = [otxn; 32];
+                host.fields.clear();
+                host.fields.insert(fields::SF_FEE, fee);
+                host.fields.insert(fields::SF_ACCOUNT, MERCHANT.to_vec());
+                host.fields.insert(fields::SF_OWNER, USER.to_vec());
+                host.fields.insert(fields::SF_OFFER_SEQUENCE, 9u32.to_be_bytes().to_vec());
+            });
+            (sim::run(hook), written_fee())
+        };
+
+        // A finish declined by the fee cap is sponsored when it comes again,
+        // and the escrow's marker only goes with the sponsored one
+        assert_eq!(finish(1, 2_000), (HookError::FeeCapExceeded.return_value(), 2_000));
+        assert_eq!(finish(1, 12), (0, 0));
+        assert_eq!(finish(2, 12), (0, 12));
+    }
+}
@@ -0,0 +1,121 @@
+// Exchange deposit policies for the LKS zero-fee hook
+// Exchanges credit deposits by DestinationTag or InvoiceID, and untagged
+// deposits end up as support tickets. The foundation can set a policy per
+// destination listing the identifiers it accepts; sponsored transactions
+// to that destination must then carry at least one of them. Without one the
+// transaction pays its own fee, or is rejected outright when the policy
+// says so.
+//
+// State layout:
+//   policy per destination   [flags u8]
+
+use lks_hook_sdk::codec;
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::fields::{self, SF_DESTINATION_TAG, SF_INVOICE_ID};
+use lks_hook_sdk::state;
+
+// Policy flags
+pub const FLAG_TAG: u8 = 0x01;
+pub const FLAG_INVOICE: u8 = 0x02;
+pub const FLAG_REJECT: u8 = 0x04;
+
+// Check the originating transaction against the policy of `destination`
+pub fn check(destination: &[u8; 20]) -> Result<(), HookError> {
+    let flags = policy(destination);
+    if flags & (FLAG_TAG | FLAG_INVOICE) == 0 {
+        return Ok(());
+    }
+
+    let mut buffer = [0u8; 32];
+    let tagged = flags & FLAG_TAG != 0 && matches!(fields::read_raw(SF_DESTINATION_TAG, &mut buffer), Ok(Some(_)));
+    let invoiced = flags & FLAG_INVOICE != 0 && matches!(fields::read_raw(SF_INVOICE_ID, &mut buffer), Ok(Some(_)));
+    if tagged || invoiced {
+        return Ok(());
+    }
+
+    if flags & FLAG_REJECT != 0 {
+        return Err(HookError::DestinationTagRequired);
+    }
+    Err(HookError::DestinationTagMissing)
+}
+
+pub fn policy(destination: &[u8; 20]) -> u8 {
+    state::load_entry::<u8>(&policy_key(destination)).unwrap_or(0)
+}
+
+// Apply an encoded policy command: [flags, destination], the destination as
+// 20 bytes, 40 hex digits or an r-address. Zero flags remove the policy.
+// Returns the flags set and the destination.
+pub fn apply_command(command: &[u8]) -> Result<(u8, [u8; 20]), HookError> {
+    let destination = command.get(1..).and_then(codec::parse_account);
+    let (flags, destination) = match (command.first(), destination) {
+        (Some(&flags), Some(destination)) => (flags & (FLAG_TAG | FLAG_INVOICE | FLAG_REJECT), destination),
+        _ => return Err(HookError::AdminCommandInvalid),
+    };
+
+    // Removing the policy releases its state reserve
+    let key = policy_key(&destination);
+    if flags == 0 {
+        state::erase(&key)?;
+    } else {
+        state::store_entry(&key, &flags)?;
+    }
+
+    Ok((flags, destination))
+}
+
+fn policy_key(destination: &[u8; 20]) -> [u8; state::KEY_LEN] {
+    state::account_key(state::NS_EXCHANGE, destination)
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::admin::FOUNDATION_ACCOUNT;
+    use lks_hook_sdk::sim;
+    use crate::fixtures::{foundation_invoke, lks_payment, written_fee, MERCHANT};
+    use crate::{dispatch, hook};
+
+    // Hook state after the foundation set the exchange policy `flags` on
+    // MERCHANT
+    fn exchange_policy(flags: u8) -> sim::State {
+        let mut command = std::vec![dispatch::OP_EXCHANGE, flags];
+        command.extend_from_slice(&MERCHANT);
+        foundation_invoke(FOUNDATION_ACCOUNT.as_bytes());
+        sim::with(|host| host.otxn_params.insert(dispatch::PARAM_COMMAND.to_vec(), command));
+        assert_eq!(sim::run(hook), 0);
+        sim::with(|host| host.state.clone())
+    }
+
+    #[test]
+    fn exchange_policies_require_a_tag_or_invoice() {
+        let tag = (fields::SF_DESTINATION_TAG, 7u32.to_be_bytes().to_vec());
+        let invoice = (fields::SF_INVOICE_ID, std::vec![0x11; 32]);
+        let run = |state: &sim::State, field: Option<&(fields::FieldId, std::vec::Vec<u8>)>| {
+            lks_payment(25, 12);
+            sim::with(|host| {
+                host.state = state.clone();
+                if let Some((field, value)) = field {
+                    host.fields.insert(*field, value.clone());
+                }
+            });
+            sim::run(hook)
+        };
+
+        // Untagged deposits pay their own fee
+        let declining = exchange_policy(FLAG_TAG | FLAG_INVOICE);
+        assert_eq!(run(&declining, None), HookError::DestinationTagMissing.return_value());
+        assert_eq!(written_fee(), 12);
+        assert_eq!(run(&declining, Some(&tag)), 0);
+        assert_eq!(run(&declining, Some(&invoice)), 0);
+        assert_eq!(written_fee(), 0);
+
+        // Or are refused, and only the listed identifiers count
+        let rejecting = exchange_policy(FLAG_TAG | FLAG_REJECT);
+        assert_eq!(run(&rejecting, None), HookError::DestinationTagRequired.return_value());
+        assert_eq!(run(&rejecting, Some(&invoice)), HookError::DestinationTagRequired.return_value());
+        assert_eq!(run(&rejecting, Some(&tag)), 0);
+
+        assert_eq!(run(&exchange_policy(0), None), 0);
+    }
+}
@@ -0,0 +1,56 @@
+// Test fixtures of the LKS zero-fee hook
+// The tests next to each module run transactions through the hook against
+// the SDK's simulated host. They start from the LKS payment built here and
+// read back what the hook wrote with these helpers.
+
+use lks_hook_sdk::amount::{self, Amount};
+use lks_hook_sdk::fields;
+use lks_hook_sdk::sim;
+use lks_hook_sdk::xfl::Xfl;
+use crate::{LKS_CURRENCY_CODE, LKS_ISSUER, TX_TYPE_INVOKE, TX_TYPE_PAYMENT};
+
+pub const USER: [u8; 20] = [0xAA; 20];
+pub const MERCHANT: [u8; 20] = [0xBB; 20];
+
+pub fn lks_amount_bytes(units: u64) -> std::vec::Vec<u8> {
+    let mut amount = Xfl::from_int(units).to_amount_value().to_vec();
+    amount.extend_from_slice(&amount::currency_code(&LKS_CURRENCY_CODE));
+    amount.extend_from_slice(LKS_ISSUER.as_bytes());
+    amount
+}
+
+// USER paying MERCHANT `units` LKS for `fee` drops, ready for sim::run(hook)
+pub fn lks_payment(units: u64, fee: u64) {
+    sim::reset();
+    let amount = lks_amount_bytes(units);
+    sim::with(|host| {
+        host.tx_type = TX_TYPE_PAYMENT;
+        host.ledger_seq = 1000;
+        host.fields.insert(fields::SF_FEE, amount::encode_native(fee).to_vec());
+        host.fields.insert(fields::SF_ACCOUNT, USER.to_vec());
+        host.fields.insert(fields::SF_DESTINATION, MERCHANT.to_vec());
+        host.fields.insert(fields::SF_AMOUNT, amount);
+    });
+}
+
+// Fee left in the transaction once the hook ran
+pub fn written_fee() -> u64 {
+    let fee = sim::with(|host| host.fields[&fields::SF_FEE].clone());
+    match amount::parse(&fee) {
+        Ok(Amount::Native(drops)) => drops,
+        _ => panic!("fee is not a native amount"),
+    }
+}
+
+pub fn traced(payload: &[u8]) -> bool {
+    sim::with(|host| host.traces.iter().any(|(_, data)| data == payload))
+}
+
+// Empty Invoke signed by `signer`, for admin commands
+pub fn foundation_invoke(signer: &[u8; 20]) {
+    sim::reset();
+    sim::with(|host| {
+        host.tx_type = TX_TYPE_INVOKE;
+        host.fields.insert(fields::SF_ACCOUNT, signer.to_vec());
+    });
+}
@@ -0,0 +1,494 @@
+// Zero Fee Hook for LKS COIN
+// This WebAssembly module implements the zero transaction fee model
+// by automatically paying network fees on behalf of users
+
+// Natively built with the sim feature (and for tests) the hook runs against
+// the SDK's simulated host instead of the Hooks runtime
+#![cfg_attr(not(any(test, feature = "sim")), no_std)]
+#![cfg_attr(not(any(test, feature = "sim")), no_main)]
+
+#[macro_use]
+extern crate lks_hook_sdk;
+
+mod abuse;
+mod analytics;
+mod breaker;
+mod burst;
+mod check;
+mod config;
+mod currency;
+mod dedup;
+mod dispatch;
+mod escrow;
+mod exchange;
+// The xahau build's tests only start from the payment
+#[cfg(test)]
+#[cfg_attr(feature = "xahau", allow(dead_code))]
+mod fixtures;
+mod limits;
+mod maintenance;
+mod maker;
+mod nft;
+mod onboarding;
+pub mod params;
+mod paths;
+mod pause;
+mod policy;
+mod prune;
+#[cfg(all(test, not(feature = "xahau")))]
+mod props;
+mod receipt;
+mod referral;
+mod registry;
+mod rules;
+mod sampling;
+mod settlement;
+mod staking;
+mod strict;
+mod trustset;
+mod txtypes;
+mod voucher;
+
+use lks_hook_sdk::account::AccountId;
+use lks_hook_sdk::admin::is_foundation_transaction;
+use lks_hook_sdk::api::{accept, otxn_type};
+use lks_hook_sdk::error::{finish_with_error, HookError};
+use lks_hook_sdk::text::{self, Text};
+use lks_hook_sdk::{bytes, fields, log, state};
+use rules::Sponsorship;
+
+// Transaction types
+const TX_TYPE_PAYMENT: i32 = 0;
+const TX_TYPE_ESCROW_CREATE: i32 = 1;
+const TX_TYPE_ESCROW_FINISH: i32 = 2;
+const TX_TYPE_ACCOUNT_SET: i32 = 3;
+const TX_TYPE_ESCROW_CANCEL: i32 = 4;
+const TX_TYPE_SET_REGULAR_KEY: i32 = 5;
+const TX_TYPE_OFFER_CREATE: i32 = 7;
+const TX_TYPE_OFFER_CANCEL: i32 = 8;
+const TX_TYPE_PAYCHAN_CREATE: i32 = 13;
+const TX_TYPE_PAYCHAN_FUND: i32 = 14;
+const TX_TYPE_PAYCHAN_CLAIM: i32 = 15;
+const TX_TYPE_CHECK_CREATE: i32 = 16;
+const TX_TYPE_CHECK_CASH: i32 = 17;
+const TX_TYPE_CHECK_CANCEL: i32 = 18;
+const TX_TYPE_TRUST_SET: i32 = 20;
+const TX_TYPE_NFTOKEN_MINT: i32 = 25;
+const TX_TYPE_NFTOKEN_CREATE_OFFER: i32 = 27;
+const TX_TYPE_NFTOKEN_CANCEL_OFFER: i32 = 28;
+const TX_TYPE_NFTOKEN_ACCEPT_OFFER: i32 = 29;
+const TX_TYPE_INVOKE: i32 = 99;
+const LKS_TRANSFER_TYPE: i32 = 1234;
+
+// LKS COIN issuer account and currency code (these would be configured)
+const LKS_ISSUER: AccountId = AccountId::new([
+    0x4C, 0x4B, 0x53, 0x00, 0x9A, 0xBC, 0xDE, 0xF0,
+    0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0,
+    0x12, 0x34, 0x56, 0x78
+]);
+const LKS_CURRENCY_CODE: [u8; 3] = *b"LKS";
+
+#[cfg_attr(not(feature = "xahau"), no_mangle)]
+pub extern "C" fn hook() -> i64 {
+    // Get the transaction type that triggered this hook
+    let tx_type = unsafe { otxn_type() };
+    
+    // Log the transaction type for debugging
+    log::debug(log::EV_TX_TYPE, b"Processing transaction type", tx_type as u64, 0);
+
+    // While the foundation has paused sponsorship everything but its admin
+    // commands goes through untouched
+    if tx_type != TX_TYPE_INVOKE && pause::is_paused() {
+        log::warn(log::EV_PAUSED, b"LKS sponsorship paused, transaction passed through", tx_type as u64, 0);
+        return finish_with_error(HookError::SponsorshipPaused);
+    }
+
+    // Types left out of the operator's allowlist go through untouched too
+    if tx_type != TX_TYPE_INVOKE && !txtypes::is_sponsored(tx_type) {
+        return finish_with_error(HookError::TxTypeNotSponsored);
+    }
+
+    // Delete a few expired state entries on every invocation; a failed
+    // cleanup must never affect the transaction
+    let _ = prune::run();
+
+    let result = if tx_type == TX_TYPE_INVOKE {
+        // Foundation-signed Invoke transactions carry admin commands
+        dispatch::handle_invoke()
+    } else if is_foundation_transaction() {
+        // Foundation housekeeping pays its own fees
+        pass_through(b"Foundation transaction bypasses LKS sponsorship")
+    } else if tx_type == LKS_TRANSFER_TYPE || tx_type == TX_TYPE_PAYMENT {
+        // Handle LKS COIN transfers with zero fees
+        handle_lks_transfer()
+    } else if tx_type == TX_TYPE_OFFER_CREATE || tx_type == TX_TYPE_OFFER_CANCEL {
+        // Handle DEX operations (OfferCreate/OfferCancel)
+        handle_dex_operation(tx_type)
+    } else if tx_type == TX_TYPE_ESCROW_CREATE
+        || tx_type == TX_TYPE_ESCROW_FINISH
+        || tx_type == TX_TYPE_ESCROW_CANCEL
+    {
+        // Handle LKS escrows
+        escrow::handle_escrow(tx_type)
+    } else if tx_type == TX_TYPE_PAYCHAN_CREATE
+        || tx_type == TX_TYPE_PAYCHAN_FUND
+        || tx_type == TX_TYPE_PAYCHAN_CLAIM
+    {
+        // Handle LKS payment channels
+        escrow::handle_payment_channel(tx_type)
+    } else if tx_type == TX_TYPE_CHECK_CREATE
+        || tx_type == TX_TYPE_CHECK_CASH
+        || tx_type == TX_TYPE_CHECK_CANCEL
+    {
+        // Handle LKS checks
+        check::handle_check(tx_type)
+    } else if tx_type == TX_TYPE_TRUST_SET {
+        // Handle trust lines to the LKS issuer
+        trustset::handle_trustset()
+    } else if tx_type == TX_TYPE_NFTOKEN_MINT
+        || tx_type == TX_TYPE_NFTOKEN_CREATE_OFFER
+        || tx_type == TX_TYPE_NFTOKEN_CANCEL_OFFER
+        || tx_type == TX_TYPE_NFTOKEN_ACCEPT_OFFER
+    {
+        // Handle LKS-priced NFT mints and offers
+        nft::handle_nft(tx_type)
+    } else if tx_type == TX_TYPE_ACCOUNT_SET || tx_type == TX_TYPE_SET_REGULAR_KEY {
+        // Handle account maintenance by LKS holders
+        maintenance::handle_maintenance()
+    } else {
+        // For any other transaction type, let it pass through normally
+        let msg = b"Transaction type not handled by LKS zero-fee hook";
+        unsafe {
+            accept(msg.as_ptr(), msg.len() as i32);
+        }
+        Ok(())
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(err) => {
+            // Analytics never affect the transaction
+            let _ = analytics::record_declined(err);
+            finish_with_error(err)
+        }
+    }
+}
+
+fn handle_lks_transfer() -> Result<(), HookError> {
+    // Check if this is an LKS COIN transaction
+    let declined = match rules::asset(fields::SF_AMOUNT) {
+        // The foundation will pay the network fee separately
+        // This would be handled by the node software
+        Ok(value) => return sponsor_transfer(value, b"LKS COIN transaction fee sponsored by foundation",
+                                                    b"Zero-fee LKS COIN transaction accepted"),
+        Err(err) => declined(err)?,
+    };
+
+    // Path payments delivering another currency can be funded with LKS
+    // (SendMax) or bridged through LKS order books (Paths)
+    if config::flag(config::PARAM_CROSS_CURRENCY, false) {
+        if let Some(value) = lks_amount(fields::SF_SEND_MAX)? {
+            return sponsor_transfer(value, b"LKS-funded cross-currency payment fee sponsored",
+                                           b"Zero-fee LKS-funded payment accepted");
+        }
+
+        // Undecodable paths are treated as not going through LKS
+        if paths::routes_through_lks().unwrap_or(false) {
+            return sponsor(None, b"LKS-bridged cross-currency payment fee sponsored",
+                                 b"Zero-fee LKS-bridged payment accepted");
+        }
+    }
+
+    // If not an LKS COIN transaction, it pays its own fee
+    Err(declined)
+}
+
+// Sponsor a payment moving `value` LKS, unless it is dust
+fn sponsor_transfer(value: u64, trace_msg: &[u8], success_msg: &[u8]) -> Result<(), HookError> {
+    sponsor_transfer_using(value, Uses::Nothing, trace_msg, success_msg)
+}
+
+// sponsor_transfer() also using up `uses`
+fn sponsor_transfer_using(value: u64, uses: Uses, trace_msg: &[u8], success_msg: &[u8]) -> Result<(), HookError> {
+    // Dust payments would let attackers burn foundation funds on fees
+    let min_amount = config::u64_param(config::PARAM_MIN_AMOUNT, config::DEFAULT_MIN_AMOUNT);
+    if value < min_amount {
+        log::debug(log::EV_DUST, b"LKS payment below dust threshold", value, 0);
+        return Err(HookError::DustAmount);
+    }
+
+    sponsor_using(Some(value), uses, trace_msg, success_msg)
+}
+
+fn handle_dex_operation(tx_type: i32) -> Result<(), HookError> {
+    // For DEX operations involving LKS COIN, also apply zero fees; the others
+    // pay their own
+    lks_coin_dex_operation()?;
+
+    // In maker mode only offers adding liquidity are sponsored
+    if tx_type == TX_TYPE_OFFER_CREATE && config::flag(maker::PARAM_MAKER_ONLY, false) {
+        return sponsor_maker_offer();
+    }
+    sponsor(None, b"LKS COIN DEX operation fee sponsored",
+                  b"Zero-fee LKS COIN DEX operation accepted")
+}
+
+#[inline(never)]
+fn sponsor_maker_offer() -> Result<(), HookError> {
+    let quota = maker::check(&fields::read_account()?, limits::current_epoch())?;
+    sponsor_using(None, Uses::MakerQuota(&quota), b"LKS COIN maker offer fee sponsored",
+                                                  b"Zero-fee LKS COIN maker offer accepted")
+}
+
+// Sponsor the fee of the originating transaction: run the sponsorship rules,
+// reduce the user fee by the sponsored share, write a receipt and trace the
+// fee the foundation is covering. `amount` is the LKS value moved, if any,
+// and selects the sponsorship tier
+#[inline(always)]
+fn sponsor(amount: Option<u64>, trace_msg: &[u8], success_msg: &[u8]) -> Result<(), HookError> {
+    sponsor_using(amount, Uses::Nothing, trace_msg, success_msg)
+}
+
+// What a sponsored transaction uses up besides the limits. It is only used up
+// once the fee is sponsored, so a transaction declined by a rule is sponsored
+// the same way when it is tried again.
+#[derive(Clone, Copy)]
+enum Uses<'a> {
+    Nothing,
+    // A maker offer counts against its quota
+    MakerQuota(&'a maker::Quota),
+    // Finishing an escrow or cashing a check consumes its marker
+    Marker(&'a [u8; state::KEY_LEN]),
+}
+
+// Sponsor as sponsor() does, also using up `uses`
+fn sponsor_using(amount: Option<u64>, uses: Uses, trace_msg: &[u8], success_msg: &[u8]) -> Result<(), HookError> {
+    // Declined before any rule records the transaction where the fee can't
+    // be lowered
+    if !fields::FEE_WRITABLE {
+        return Err(HookError::FeeNotWritable);
+    }
+
+    let epoch = limits::current_epoch();
+    let mut tx = Sponsorship::read(amount, epoch)?;
+    rules::evaluate(&rules::FEE_RULES, &mut tx)?;
+    let source = tx.source;
+    let original_fee = tx.original_fee;
+    let sponsored_fee = tx.fee;
+
+    // A transaction run again (node retries) gets the same fee but doesn't
+    // count against the limits or leave a second receipt
+    let mark = dedup::current(epoch)?;
+    if dedup::is_duplicate(&mark) {
+        fields::write_fee(original_fee - sponsored_fee)?;
+
+        if log::enabled(log::Level::Warn) {
+            let mut msg = Text::<{ text::MESSAGE_LEN }>::new();
+            msg.push(b"LKS duplicate transaction, side effects skipped, account ").hex(bytes::head(&source, 4));
+            log::warn(log::EV_DUPLICATE, msg.as_bytes(), sponsored_fee, 0);
+        }
+        unsafe {
+            accept(success_msg.as_ptr(), success_msg.len() as i32);
+        }
+        return Ok(());
+    }
+
+    // Transactions with a Destination are also limited per account pair, so
+    // two wallets can't ping-pong payments to farm sponsorship
+    tx.destination = fields::read_destination()?;
+    tx.voucher = voucher::read(&source);
+    rules::evaluate(&rules::LIMIT_RULES, &mut tx)?;
+
+    // Reduce the user fee by the sponsored share
+    fields::write_fee(original_fee - sponsored_fee)?;
+    limits::record(&tx.usage, sponsored_fee)?;
+    match uses {
+        Uses::Nothing => {}
+        Uses::MakerQuota(quota) => maker::record(quota)?,
+        Uses::Marker(key) => state::erase(key)?,
+    }
+    dedup::record(&mark)?;
+    if let Some(voucher) = &tx.voucher {
+        voucher::consume(&source, voucher)?;
+    }
+    if let Some(used) = tx.onboarding {
+        onboarding::record(&source, used)?;
+    }
+    referral::attribute(&source, tx.directives.referral)?;
+
+    // Leave a receipt for off-chain reconciliation
+    let tx_type = unsafe { otxn_type() };
+    receipt::write(&source, sponsored_fee, receipt::category(tx_type))?;
+    analytics::record_sponsored(tx_type, sponsored_fee)?;
+
+    // The foundation is reimbursed in periodic batches
+    settlement::accumulate(sponsored_fee)?;
+    
+    // Log that we're sponsoring this transaction, in full detail for the
+    // audit sample
+    if log::enabled(log::Level::Info) && sampling::sampled() {
+        let mut msg = Text::<{ text::MESSAGE_LEN }>::new();
+        msg.push(trace_msg).push(b", account ").hex(bytes::head(&source, 4)).push(b", drops ").decimal(sponsored_fee);
+        log::info(log::EV_SPONSORED, msg.as_bytes(), sponsored_fee, tx_type as u64);
+        let account = bytes::u64_at(&source, 0).unwrap_or(0);
+        log::info(log::EV_AUDIT, b"LKS sponsorship audited", original_fee, account);
+    } else {
+        log::info(log::EV_SPONSORED, trace_msg, sponsored_fee, tx_type as u64);
+    }
+    unsafe {
+        accept(success_msg.as_ptr(), success_msg.len() as i32);
+    }
+    
+    Ok(())
+}
+
+// Accept the transaction with its normal fee
+fn pass_through(msg: &[u8]) -> Result<(), HookError> {
+    unsafe {
+        accept(msg.as_ptr(), msg.len() as i32);
+    }
+
+    Ok(())
+}
+
+// Check whether an amount field of the originating transaction holds LKS COIN
+// or another sponsored LKS-family token
+fn field_has_lks(field: fields::FieldId) -> Result<bool, HookError> {
+    Ok(lks_amount(field)?.is_some())
+}
+
+// The value moved in an amount field of the originating transaction, if the
+// asset rules sponsor it. Their rejections (strict mode) are passed on.
+fn lks_amount(field: fields::FieldId) -> Result<Option<u64>, HookError> {
+    match rules::asset(field) {
+        Ok(units) => Ok(Some(units)),
+        Err(err) if err.is_rejection() => Err(err),
+        Err(_) => Ok(None),
+    }
+}
+
+// The asset rules' decline of an amount; their rejections are passed on
+fn declined(err: HookError) -> Result<HookError, HookError> {
+    if err.is_rejection() {
+        return Err(err);
+    }
+    Ok(err)
+}
+
+fn lks_coin_dex_operation() -> Result<(), HookError> {
+    // Offers involve LKS COIN on either side of the book; OfferCancel has
+    // neither field and falls back to the Amount check, whose decline stands
+    // for the operation's
+    if field_has_lks(fields::SF_TAKER_GETS)? || field_has_lks(fields::SF_TAKER_PAYS)? {
+        return Ok(());
+    }
+    rules::asset(fields::SF_AMOUNT).map(|_| ())
+}
+
+// Called when a transaction emitted by the hook was applied (what = 0) or
+// expired without being applied (what = 1). The hook only emits settlement
+// payments, so every callback settles its accounting.
+#[no_mangle]
+pub extern "C" fn cbak(what: u32) -> i64 {
+    // Applied transactions can still have failed with a tec code
+    let failed = what != 0 || !matches!(fields::read_transaction_result(), Ok(None | Some(fields::TES_SUCCESS)));
+
+    let result = settlement::on_callback(failed).and_then(|()| {
+        pass_through(b"LKS settlement callback processed")
+    });
+
+    match result {
+        Ok(()) => 0,
+        Err(err) => finish_with_error(err),
+    }
+}
+
+// Xahau calls hook() with an argument reserved for future use
+#[cfg(feature = "xahau")]
+#[export_name = "hook"]
+pub extern "C" fn xahau_hook(_reserved: u32) -> i64 {
+    hook()
+}
+
+// Required for no_main
+#[cfg(not(any(test, feature = "sim")))]
+#[no_mangle]
+pub extern "C" fn _start() {
+    // This function is required but not used in hooks
+}
+
+// Tests of the legacy build; the xahau build sponsors nothing
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::admin::FOUNDATION_ACCOUNT;
+    use lks_hook_sdk::amount;
+    use lks_hook_sdk::sim;
+    use crate::fixtures::{lks_payment, written_fee, USER};
+
+    #[test]
+    fn sponsors_lks_payment_and_writes_receipt() {
+        lks_payment(25, 12);
+
+        assert_eq!(sim::run(hook), 0);
+        assert_eq!(written_fee(), 0);
+
+        let key = receipt::slot_key(0);
+        let receipt = sim::with(|host| host.value(&key).unwrap().to_vec());
+        assert_eq!(&receipt[4..24], &USER);
+        assert_eq!(u64::from_be_bytes(receipt[28..36].try_into().unwrap()), 12);
+        assert_eq!(receipt[36], receipt::CATEGORY_PAYMENT);
+    }
+
+    #[test]
+    fn traces_structured_events_up_to_the_compiled_level() {
+        lks_payment(25, 12);
+        assert_eq!(sim::run(hook), 0);
+
+        let payloads: std::vec::Vec<std::vec::Vec<u8>> =
+            sim::with(|host| host.traces.iter().map(|(_, data)| data.clone()).collect());
+        let sponsored = log::encode(log::Level::Info, log::EV_SPONSORED, 12, TX_TYPE_PAYMENT as u64);
+        assert!(payloads.contains(&sponsored.to_vec()));
+
+        // Debug events are compiled out at the default level
+        assert!(payloads.iter().all(|payload| payload[0] <= log::MAX_LEVEL));
+    }
+
+    #[test]
+    fn declines_native_payment() {
+        lks_payment(25, 12);
+        sim::with(|host| host.fields.insert(fields::SF_AMOUNT, amount::encode_native(5_000).to_vec()));
+
+        assert_eq!(sim::run(hook), HookError::WrongCurrency.return_value());
+        assert_eq!(written_fee(), 12);
+        assert!(sim::with(|host| host.state.is_empty()));
+    }
+
+    #[test]
+    fn foundation_payment_bypasses_sponsorship() {
+        lks_payment(25, 12);
+        sim::with(|host| host.fields.insert(fields::SF_ACCOUNT, FOUNDATION_ACCOUNT.as_bytes().to_vec()));
+
+        assert_eq!(sim::run(hook), 0);
+        assert_eq!(written_fee(), 12);
+        assert!(sim::with(|host| host.state.is_empty()));
+    }
+}
+
+// The xahau build, run in the simulator with cargo test --features xahau
+#[cfg(all(test, feature = "xahau"))]
+mod xahau_tests {
+    use super::*;
+    use lks_hook_sdk::sim::{self, Outcome};
+    use crate::fixtures::{lks_payment, written_fee};
+
+    #[test]
+    fn declines_sponsorship_without_recording_it() {
+        lks_payment(25, 12);
+
+        assert_eq!(sim::run(hook), HookError::FeeNotWritable.return_value());
+        assert!(matches!(sim::with(|host| host.outcome.clone()), Some(Outcome::Accepted(_))));
+        assert_eq!(written_fee(), 12);
+        assert!(sim::with(|host| host.state.is_empty()));
+    }
+}
@@ -0,0 +1,145 @@
+// Sponsorship limits for the LKS zero-fee hook
+// Counters in hook state cap how much sponsorship can be farmed per ledger
+// epoch: per source account, per (source, destination) pair and in total.
+// Each counter stores the epoch it belongs to and resets when a new one starts.
+
+use lks_hook_sdk::api::ledger_seq;
+use lks_hook_sdk::bytes;
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::state;
+use crate::{config, prune};
+
+// Number of ledgers in a limit epoch
+pub const PARAM_EPOCH_LEDGERS: &[u8] = b"EPOCHLEN";
+pub const DEFAULT_EPOCH_LEDGERS: u64 = 256;
+
+// Sponsored transactions per source account per epoch
+pub const PARAM_ACCOUNT_CAP: &[u8] = b"ACCTCAP";
+pub const DEFAULT_ACCOUNT_CAP: u64 = 50;
+
+// Sponsored transactions between the same two accounts per epoch
+pub const PARAM_PAIR_CAP: &[u8] = b"PAIRCAP";
+pub const DEFAULT_PAIR_CAP: u64 = 10;
+
+// Total drops the foundation sponsors per epoch
+pub const PARAM_BUDGET: &[u8] = b"BUDGET";
+pub const DEFAULT_BUDGET: u64 = 10_000_000;
+
+// Counter entries: (epoch u32, value u64)
+type Counter = (u32, u64);
+
+// Counters read for one sponsorship decision, written back once it succeeds
+pub struct Usage {
+    epoch: u32,
+    account: Option<([u8; state::KEY_LEN], u64)>,
+    pair: Option<([u8; state::KEY_LEN], u64)>,
+    // Drops spent so far, None while the budget counter has no value for
+    // the epoch
+    budget_spent: Option<u64>,
+}
+
+impl Usage {
+    // Nothing checked yet in `epoch`
+    pub fn new(epoch: u32) -> Usage {
+        Usage { epoch, account: None, pair: None, budget_spent: None }
+    }
+}
+
+pub fn current_epoch() -> u32 {
+    let epoch_ledgers = config::u64_param(PARAM_EPOCH_LEDGERS, DEFAULT_EPOCH_LEDGERS).max(1);
+    let ledger = unsafe { ledger_seq() };
+    (ledger / epoch_ledgers) as u32
+}
+
+// Check the budget has room for a sponsorship of `fee` more drops
+pub fn check_budget(usage: &mut Usage, fee: u64) -> Result<(), HookError> {
+    let budget = config::u64_param(PARAM_BUDGET, DEFAULT_BUDGET);
+    let budget_spent = load_counted(&budget_key(), usage.epoch);
+    if budget_spent.unwrap_or(0).saturating_add(fee) > budget {
+        return Err(HookError::BudgetExceeded);
+    }
+
+    usage.budget_spent = budget_spent;
+    Ok(())
+}
+
+// Check the per-account cap and, for transactions with a destination, the
+// per-pair cap. Accounts exempt from them (registry partners) skip this
+// check but still count against the budget.
+pub fn check_caps(usage: &mut Usage, source: &[u8; 20], destination: Option<&[u8; 20]>) -> Result<(), HookError> {
+    let account_key = state::account_key(state::NS_ACCOUNT_LIMIT, source);
+    let account_count = load_counter(&account_key, usage.epoch);
+    if account_count >= config::u64_param(PARAM_ACCOUNT_CAP, DEFAULT_ACCOUNT_CAP) {
+        return Err(HookError::RateLimited);
+    }
+    usage.account = Some((account_key, account_count));
+
+    if let Some(destination) = destination {
+        // A pair that can't be counted isn't sponsored
+        let pair_key = pair_key(source, destination).ok_or(HookError::PairLimited)?;
+        let pair_count = load_counter(&pair_key, usage.epoch);
+        if pair_count >= config::u64_param(PARAM_PAIR_CAP, DEFAULT_PAIR_CAP) {
+            return Err(HookError::PairLimited);
+        }
+        usage.pair = Some((pair_key, pair_count));
+    }
+
+    Ok(())
+}
+
+// Record a sponsorship of `fee` drops against the checked counters.
+// Counters written for the first time in an epoch are registered for pruning;
+// sponsorships of no drops leave the budget at zero, so that is told by the
+// epoch the counter was read in rather than by its value.
+pub fn record(usage: &Usage, fee: u64) -> Result<(), HookError> {
+    let budget_key = budget_key();
+    store_counter(&budget_key, usage.epoch, usage.budget_spent.unwrap_or(0).saturating_add(fee))?;
+    if usage.budget_spent.is_none() {
+        prune::register(&budget_key, usage.epoch)?;
+    }
+
+    if let Some((key, count)) = usage.account {
+        store_counter(&key, usage.epoch, count + 1)?;
+        if count == 0 {
+            prune::register(&key, usage.epoch)?;
+        }
+    }
+
+    if let Some((key, count)) = usage.pair {
+        store_counter(&key, usage.epoch, count + 1)?;
+        if count == 0 {
+            prune::register(&key, usage.epoch)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn budget_key() -> [u8; state::KEY_LEN] {
+    state::key(state::NS_BUDGET, &[])
+}
+
+// Pairs are unordered so ping-pong payments between two wallets share one
+// counter. Both accounts don't fit a key, so it holds their hash.
+fn pair_key(a: &[u8; 20], b: &[u8; 20]) -> Option<[u8; state::KEY_LEN]> {
+    let (low, high) = if bytes::cmp(a, b).is_le() { (a, b) } else { (b, a) };
+
+    state::hashed_key(state::NS_PAIR_LIMIT, &[low, high])
+}
+
+// Counters from an earlier epoch read as zero
+pub fn load_counter(key: &[u8; state::KEY_LEN], epoch: u32) -> u64 {
+    load_counted(key, epoch).unwrap_or(0)
+}
+
+// Value of a counter in `epoch`, None when it wasn't written in that epoch
+fn load_counted(key: &[u8; state::KEY_LEN], epoch: u32) -> Option<u64> {
+    match state::load_entry::<Counter>(key) {
+        Some((counted, value)) if counted == epoch => Some(value),
+        _ => None,
+    }
+}
+
+pub fn store_counter(key: &[u8; state::KEY_LEN], epoch: u32, value: u64) -> Result<(), HookError> {
+    state::store_entry::<Counter>(key, &(epoch, value))
+}
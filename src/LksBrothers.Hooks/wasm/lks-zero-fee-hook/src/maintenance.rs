@@ -0,0 +1,141 @@
+// Account maintenance sponsorship for the LKS zero-fee hook
+// Setting up a wallet takes AccountSet transactions (default ripple, domain)
+// and rotating keys a SetRegularKey, which users still paid for. With MAINT
+// set the foundation sponsors them for LKS holders: accounts whose LKS trust
+// line holds at least MAINTMIN. The balance is read from the trust line
+// itself, so it can't be claimed by a transaction field.
+
+use lks_hook_sdk::account::ACCOUNT_ID_LEN;
+use lks_hook_sdk::amount::{self, Amount, LKS_DECIMALS};
+use lks_hook_sdk::api::{keylet_field, util_keylet_line};
+use lks_hook_sdk::bytes;
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::fields::{self, SF_BALANCE};
+use lks_hook_sdk::xfl::Xfl;
+use crate::{config, pass_through, sponsor, LKS_CURRENCY_CODE, LKS_ISSUER};
+
+// Sponsor AccountSet and SetRegularKey for LKS holders
+pub const PARAM_MAINTENANCE: &[u8] = b"MAINT";
+
+// Smallest LKS balance (in micro-LKS) of an LKS holder
+pub const PARAM_MAINTENANCE_MIN: &[u8] = b"MAINTMIN";
+pub const DEFAULT_MAINTENANCE_MIN: u64 = 1_000_000;
+
+const KEYLET_LEN: usize = 34;
+
+pub fn handle_maintenance() -> Result<(), HookError> {
+    if !config::flag(PARAM_MAINTENANCE, false) {
+        return pass_through(b"Account maintenance processed normally");
+    }
+
+    let source = fields::read_account()?;
+    if lks_balance(&source) < config::u64_param(PARAM_MAINTENANCE_MIN, DEFAULT_MAINTENANCE_MIN) {
+        return pass_through(b"Account maintenance by non-LKS holder processed normally");
+    }
+
+    sponsor(None, b"LKS holder account maintenance fee sponsored",
+                  b"Zero-fee LKS holder account maintenance accepted")
+}
+
+// LKS held by `holder` in micro-LKS, zero without a trust line
+fn lks_balance(holder: &[u8; 20]) -> u64 {
+    let currency = amount::currency_code(&LKS_CURRENCY_CODE);
+    let balance = match line_balance(holder, LKS_ISSUER.as_bytes(), &currency) {
+        Some(balance) => balance,
+        None => return 0,
+    };
+
+    // Trust line balances are kept from the low account's side: positive
+    // when the low account holds the token
+    let value = match amount::parse(&balance) {
+        Ok(Amount::Issued { value, .. }) => value,
+        _ => return 0,
+    };
+    let held = match Xfl::from_amount_value(&value) {
+        Some(value) if is_low(holder, LKS_ISSUER.as_bytes()) => value,
+        Some(value) => value.negate(),
+        None => return 0,
+    };
+
+    amount::issued_value(&held.to_amount_value(), LKS_DECIMALS)
+}
+
+// Balance field of the trust line between `holder` and `issuer` in
+// `currency`, None when there is no such line
+pub fn line_balance(holder: &[u8; ACCOUNT_ID_LEN], issuer: &[u8; ACCOUNT_ID_LEN],
+                    currency: &[u8; 20]) -> Option<[u8; amount::ISSUED_LEN]> {
+    let mut keylet = [0u8; KEYLET_LEN];
+    let result = unsafe {
+        util_keylet_line(keylet.as_mut_ptr(), keylet.len() as i32, holder.as_ptr(), issuer.as_ptr(),
+                         currency.as_ptr())
+    };
+    if result != KEYLET_LEN as i32 {
+        return None;
+    }
+
+    let mut balance = [0u8; amount::ISSUED_LEN];
+    let result = unsafe {
+        keylet_field(keylet.as_ptr(), keylet.len() as i32, SF_BALANCE,
+                     balance.as_mut_ptr(), balance.len() as i32)
+    };
+    (result == amount::ISSUED_LEN as i32).then_some(balance)
+}
+
+// Whether `a` sorts before `b`, compared as big-endian words so no loop
+// (and no guard) is involved
+fn is_low(a: &[u8; ACCOUNT_ID_LEN], b: &[u8; ACCOUNT_ID_LEN]) -> bool {
+    let words = |account: &[u8; ACCOUNT_ID_LEN]| {
+        (bytes::u64_at(account, 0).unwrap_or(0), bytes::u64_at(account, 8).unwrap_or(0),
+         bytes::u32_at(account, 16).unwrap_or(0))
+    };
+    words(a) < words(b)
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::sim;
+    use crate::fixtures::{lks_payment, written_fee, USER};
+    use crate::{hook, receipt, TX_TYPE_ACCOUNT_SET, TX_TYPE_SET_REGULAR_KEY};
+
+    #[test]
+    fn sponsors_account_maintenance_for_lks_holders() {
+        // USER sorts above the issuer, so its holdings show as a negative
+        // balance on the trust line
+        let set_holding = |units: u64| {
+            let mut balance = Xfl::from_int(units).negate().to_amount_value().to_vec();
+            balance.extend_from_slice(&amount::currency_code(&LKS_CURRENCY_CODE));
+            balance.extend_from_slice(&[0; 20]);
+            let keylet = sim::line_keylet(&USER, LKS_ISSUER.as_bytes(), &amount::currency_code(&LKS_CURRENCY_CODE));
+            sim::with(|host| host.ledger.entry(keylet).or_default().insert(fields::SF_BALANCE, balance));
+        };
+        lks_payment(25, 12);
+        sim::with(|host| {
+            host.tx_type = TX_TYPE_SET_REGULAR_KEY;
+            host.fields.remove(&fields::SF_AMOUNT);
+            host.fields.remove(&fields::SF_DESTINATION);
+        });
+
+        // Off by default
+        set_holding(5);
+        assert_eq!(sim::run(hook), 0);
+        assert_eq!(written_fee(), 12);
+
+        sim::with(|host| host.hook_params.insert(PARAM_MAINTENANCE.to_vec(), std::vec![1]));
+        assert_eq!(sim::run(hook), 0);
+        assert_eq!(written_fee(), 0);
+        let key = receipt::slot_key(0);
+        let receipt = sim::with(|host| host.value(&key).unwrap().to_vec());
+        assert_eq!(receipt[36], receipt::CATEGORY_ACCOUNT);
+
+        // Holding less than one LKS isn't enough
+        set_holding(0);
+        sim::with(|host| {
+            host.tx_type = TX_TYPE_ACCOUNT_SET;
+            host.otxn_id = [0x01; 32];
+            host.fields.insert(fields::SF_FEE, amount::encode_native(12).to_vec());
+        });
+        assert_eq!(sim::run(hook), 0);
+        assert_eq!(written_fee(), 12);
+    }
+}
@@ -0,0 +1,209 @@
+// DEX maker incentives for the LKS zero-fee hook
+// To bootstrap liquidity, maker mode (MAKERONLY) sponsors only the LKS
+// offers that add liquidity: offers resting on the book rather than
+// crossing it. Offers placed with tfImmediateOrCancel or tfFillOrKill never
+// rest and pay their own fee. Hooks can't see the book, so the others are
+// judged by their quality against the reference price MAKERREF, in drops
+// per million of the token's smallest unit (one LKS COIN): an offer selling
+// LKS below it or buying LKS above it would cross the book. tfPassive
+// offers don't take offers at their own quality, so they may sit at the
+// reference price itself. Offers against other tokens, and every offer
+// while no reference is set, are judged on their flags alone.
+// Each account has MAKERCAP sponsored offers per limit epoch. Cancelling
+// offers is sponsored as before.
+//
+// State layout:
+//   quota per account   [epoch u32][offers sponsored u64]
+
+use core::cmp::Ordering;
+
+use lks_hook_sdk::amount::Amount;
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::fields::{self, SF_FLAGS, SF_TAKER_GETS, SF_TAKER_PAYS};
+use lks_hook_sdk::{log, state};
+use crate::{config, limits, lks_amount, prune};
+
+// Sponsor resting offers only
+pub const PARAM_MAKER_ONLY: &[u8] = b"MAKERONLY";
+
+// Reference price in drops per million units, zero to judge on flags alone
+pub const PARAM_MAKER_REF: &[u8] = b"MAKERREF";
+pub const DEFAULT_MAKER_REF: u64 = 0;
+
+// Sponsored maker offers per account per epoch
+pub const PARAM_MAKER_CAP: &[u8] = b"MAKERCAP";
+pub const DEFAULT_MAKER_CAP: u64 = 20;
+
+// OfferCreate flags
+pub const TF_PASSIVE: u32 = 0x0001_0000;
+pub const TF_IMMEDIATE_OR_CANCEL: u32 = 0x0002_0000;
+pub const TF_FILL_OR_KILL: u32 = 0x0004_0000;
+
+const UNITS_PER_PRICE: u64 = 1_000_000;
+
+// Maker offers an account had sponsored in the epoch, checked against its
+// quota and written back once the offer is sponsored
+pub struct Quota {
+    key: [u8; state::KEY_LEN],
+    epoch: u32,
+    used: u64,
+}
+
+// One side of an offer
+enum Leg {
+    Lks(u64),
+    Native(u64),
+    Other,
+}
+
+// Check the originating OfferCreate of `source` rests on the book and fits
+// the account's quota in limit `epoch`. Declines taker-style offers with
+// TakerOrder and offers over the quota with MakerQuotaExceeded.
+pub fn check(source: &[u8; 20], epoch: u32) -> Result<Quota, HookError> {
+    let flags = fields::read_u32(SF_FLAGS)?.unwrap_or(0);
+    if flags & (TF_IMMEDIATE_OR_CANCEL | TF_FILL_OR_KILL) != 0 {
+        log::debug(log::EV_MAKER, b"LKS offer can't rest on the book", flags as u64, 0);
+        return Err(HookError::TakerOrder);
+    }
+    if crosses_reference(flags & TF_PASSIVE != 0)? {
+        log::debug(log::EV_MAKER, b"LKS offer priced through the reference", flags as u64, 1);
+        return Err(HookError::TakerOrder);
+    }
+
+    let key = state::account_key(state::NS_MAKER, source);
+    let used = limits::load_counter(&key, epoch);
+    if used >= config::u64_param(PARAM_MAKER_CAP, DEFAULT_MAKER_CAP) {
+        return Err(HookError::MakerQuotaExceeded);
+    }
+
+    Ok(Quota { key, epoch, used })
+}
+
+// Count a sponsored maker offer. Quotas written for the first time in an
+// epoch are registered for pruning.
+pub fn record(quota: &Quota) -> Result<(), HookError> {
+    limits::store_counter(&quota.key, quota.epoch, quota.used + 1)?;
+    if quota.used == 0 {
+        prune::register(&quota.key, quota.epoch)?;
+    }
+    Ok(())
+}
+
+fn crosses_reference(passive: bool) -> Result<bool, HookError> {
+    let reference = config::u64_param(PARAM_MAKER_REF, DEFAULT_MAKER_REF);
+    if reference == 0 {
+        return Ok(false);
+    }
+
+    // Compare the drops per million units the offer asks or bids with the
+    // reference price
+    let (ordering, selling) = match (leg(SF_TAKER_GETS)?, leg(SF_TAKER_PAYS)?) {
+        (Leg::Lks(units), Leg::Native(drops)) => (price_ordering(drops, units, reference), true),
+        (Leg::Native(drops), Leg::Lks(units)) => (price_ordering(drops, units, reference), false),
+        _ => return Ok(false),
+    };
+
+    Ok(match ordering {
+        Ordering::Less => selling,
+        Ordering::Greater => !selling,
+        Ordering::Equal => !passive,
+    })
+}
+
+pub fn price_ordering(drops: u64, units: u64, reference: u64) -> Ordering {
+    wide_mul(drops, UNITS_PER_PRICE).cmp(&wide_mul(reference, units))
+}
+
+// a * b as its (high, low) words, multiplied by 32-bit halves so it never
+// reaches the u128 builtins
+fn wide_mul(a: u64, b: u64) -> (u64, u64) {
+    const LOW: u64 = 0xFFFF_FFFF;
+    let (a_high, a_low) = (a >> 32, a & LOW);
+    let (b_high, b_low) = (b >> 32, b & LOW);
+
+    let low = a_low * b_low;
+    let cross = a_high * b_low;
+    let other_cross = a_low * b_high;
+    let middle = (low >> 32) + (cross & LOW) + (other_cross & LOW);
+
+    (a_high * b_high + (cross >> 32) + (other_cross >> 32) + (middle >> 32), (middle << 32) | (low & LOW))
+}
+
+fn leg(field: fields::FieldId) -> Result<Leg, HookError> {
+    if let Some(units) = lks_amount(field)? {
+        return Ok(Leg::Lks(units));
+    }
+
+    Ok(match fields::read_amount(field) {
+        Ok(Some(Amount::Native(drops))) => Leg::Native(drops),
+        _ => Leg::Other,
+    })
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::amount;
+    use lks_hook_sdk::sim;
+    use crate::fixtures::{lks_amount_bytes, written_fee, USER};
+    use crate::{hook, TX_TYPE_OFFER_CREATE};
+
+    #[test]
+    fn maker_mode_sponsors_resting_offers_only() {
+        sim::reset();
+        sim::with(|host| {
+            host.ledger_seq = 1000;
+            host.hook_params.insert(PARAM_MAKER_ONLY.to_vec(), std::vec![1]);
+            host.hook_params.insert(PARAM_MAKER_REF.to_vec(), 100u64.to_be_bytes().to_vec());
+            host.hook_params.insert(PARAM_MAKER_CAP.to_vec(), 2u64.to_be_bytes().to_vec());
+        });
+        let mut otxn = 0u8;
+        let mut offer = |gets: std::vec::Vec<u8>, pays: std::vec::Vec<u8>, flags: u32| {
+            otxn += 1;
+            sim::with(|host| {
+                host.tx_type = TX_TYPE_OFFER_CREATE;
+                host.otxn_id = [otxn; 32];
+                host.fields.clear();
+                host.fields.insert(fields::SF_FEE, amount::encode_native(12).to_vec());
+                host.fields.insert(fields::SF_ACCOUNT, USER.to_vec());
+                host.fields.insert(fields::SF_TAKER_GETS, gets);
+                host.fields.insert(fields::SF_TAKER_PAYS, pays);
+                host.fields.insert(fields::SF_FLAGS, flags.to_be_bytes().to_vec());
+            });
+            sim::run(hook)
+        };
+        let native = |drops: u64| amount::encode_native(drops).to_vec();
+        let taker = HookError::TakerOrder.return_value();
+
+        // Orders that can't rest on the book take liquidity
+        for flags in [TF_IMMEDIATE_OR_CANCEL, TF_FILL_OR_KILL | TF_PASSIVE] {
+            assert_eq!(offer(lks_amount_bytes(25), native(5_000), flags), taker);
+            assert_eq!(written_fee(), 12);
+        }
+
+        // Asks below the reference price would fill against bids, and only
+        // passive offers rest at the reference itself
+        assert_eq!(offer(lks_amount_bytes(25), native(2_000), 0), taker);
+        let priced_through = log::encode(log::Level::Debug, log::EV_MAKER, 0, 1);
+        let traced = sim::with(|host| host.traces.iter().any(|(_, data)| data[..] == priced_through[..]));
+        assert_eq!(traced, log::enabled(log::Level::Debug));
+        assert_eq!(offer(lks_amount_bytes(25), native(2_500), 0), taker);
+        assert_eq!(offer(lks_amount_bytes(25), native(2_500), TF_PASSIVE), 0);
+        assert_eq!(written_fee(), 0);
+
+        // Bids above it would fill against asks
+        assert_eq!(offer(native(3_000), lks_amount_bytes(25), 0), taker);
+        assert_eq!(offer(native(2_000), lks_amount_bytes(25), 0), 0);
+
+        // Two maker offers per epoch
+        assert_eq!(offer(lks_amount_bytes(25), native(5_000), 0), HookError::MakerQuotaExceeded.return_value());
+        let mut quota = 3u32.to_be_bytes().to_vec();
+        quota.extend_from_slice(&2u64.to_be_bytes());
+        let key = state::account_key(state::NS_MAKER, &USER);
+        assert_eq!(sim::with(|host| host.value(&key).map(<[u8]>::to_vec)), Some(quota));
+
+        // Outside maker mode every LKS offer is sponsored
+        sim::with(|host| host.hook_params.remove(PARAM_MAKER_ONLY));
+        assert_eq!(offer(lks_amount_bytes(25), native(2_000), TF_IMMEDIATE_OR_CANCEL), 0);
+    }
+}
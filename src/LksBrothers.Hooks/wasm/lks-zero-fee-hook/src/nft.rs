@@ -0,0 +1,166 @@
+// NFT (XLS-20) sponsorship for the LKS zero-fee hook
+// Mints and offers priced in LKS are sponsored on their Amount like payments.
+// Accepting and cancelling only reference offers by id, so LKS offers are
+// remembered in hook state when they are created, keyed by the offer's
+// ledger id (its NFT offer keylet).
+
+use lks_hook_sdk::api::util_keylet;
+use lks_hook_sdk::bytes;
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::fields::{self, SF_AMOUNT, SF_NFTOKEN_BROKER_FEE, SF_NFTOKEN_BUY_OFFER,
+                           SF_NFTOKEN_OFFERS, SF_NFTOKEN_SELL_OFFER};
+use lks_hook_sdk::state;
+use crate::{field_has_lks, pass_through, rules, sponsor};
+use crate::{TX_TYPE_NFTOKEN_ACCEPT_OFFER, TX_TYPE_NFTOKEN_CANCEL_OFFER, TX_TYPE_NFTOKEN_CREATE_OFFER};
+
+// Marker value stored for offers priced in LKS
+const MARKER: bool = true;
+
+// Keylet type of an NFT offer and the length of a serialized keylet
+const KEYLET_NFT_OFFER: u32 = 23;
+const KEYLET_LEN: usize = 34;
+
+// Offers looked at in one NFTokenCancelOffer; longer cancel lists are not
+// sponsored
+const MAX_CANCEL_OFFERS: usize = 32;
+const OFFER_ID_LEN: usize = 32;
+
+pub fn handle_nft(tx_type: i32) -> Result<(), HookError> {
+    if tx_type == TX_TYPE_NFTOKEN_ACCEPT_OFFER {
+        return handle_accept_offer();
+    }
+
+    if tx_type == TX_TYPE_NFTOKEN_CANCEL_OFFER {
+        return handle_cancel_offer();
+    }
+
+    // NFTokenMint and NFTokenCreateOffer carry the price in Amount; a mint
+    // without one creates no offer and, like those priced in other
+    // currencies, pays its own fee
+    let value = rules::asset(SF_AMOUNT)?;
+
+    if tx_type == TX_TYPE_NFTOKEN_CREATE_OFFER {
+        let owner = fields::read_account()?;
+        if let Some(sequence) = fields::read_sequence()? {
+            state::store_entry(&offer_key(&offer_id(&owner, sequence)?), &MARKER)?;
+        }
+    }
+
+    sponsor(Some(value), b"LKS COIN NFT transaction fee sponsored",
+                         b"Zero-fee LKS COIN NFT transaction accepted")
+}
+
+// Brokered sales are sponsored on an LKS broker fee; otherwise one of the
+// accepted offers must be an LKS offer
+fn handle_accept_offer() -> Result<(), HookError> {
+    let mut lks_offer = field_has_lks(SF_NFTOKEN_BROKER_FEE)?;
+
+    let offers = [fields::read_hash256(SF_NFTOKEN_SELL_OFFER)?,
+                  fields::read_hash256(SF_NFTOKEN_BUY_OFFER)?];
+    guarded_loop!(i in 0, offers.len(); max 2; {
+        if let Some(id) = offers[i] {
+            lks_offer |= consume_marker(&id)?;
+        }
+    });
+
+    if !lks_offer {
+        return pass_through(b"Non-LKS NFT offer accepted normally");
+    }
+
+    sponsor(None, b"LKS COIN NFT offer acceptance fee sponsored",
+                  b"Zero-fee LKS COIN NFT offer acceptance accepted")
+}
+
+fn handle_cancel_offer() -> Result<(), HookError> {
+    let mut buffer = [0u8; 2 + MAX_CANCEL_OFFERS * OFFER_ID_LEN];
+    let offers = match fields::read_raw(SF_NFTOKEN_OFFERS, &mut buffer) {
+        Ok(Some(data)) => fields::strip_vl(data)?,
+        _ => return pass_through(b"Non-LKS NFT offer cancelled normally"),
+    };
+
+    if offers.len() % OFFER_ID_LEN != 0 {
+        return Err(HookError::FieldReadFailed);
+    }
+
+    let mut lks_offer = false;
+    guarded_loop!(i in 0, offers.len() / OFFER_ID_LEN; max MAX_CANCEL_OFFERS as u32; {
+        if let Some(id) = bytes::array(offers, i * OFFER_ID_LEN) {
+            lks_offer |= consume_marker(&id)?;
+        }
+    });
+
+    if !lks_offer {
+        return pass_through(b"Non-LKS NFT offer cancelled normally");
+    }
+
+    sponsor(None, b"LKS COIN NFT offer cancellation fee sponsored",
+                  b"Zero-fee LKS COIN NFT offer cancellation accepted")
+}
+
+// Accepting or cancelling removes the offer from the ledger, so its marker
+// goes too. Returns whether the offer was an LKS offer.
+fn consume_marker(id: &[u8; OFFER_ID_LEN]) -> Result<bool, HookError> {
+    let key = offer_key(id);
+    if state::load_entry::<bool>(&key).is_none() {
+        return Ok(false);
+    }
+
+    state::erase(&key)?;
+    Ok(true)
+}
+
+// Ledger id of the offer created by `owner` at `sequence`
+fn offer_id(owner: &[u8; 20], sequence: u32) -> Result<[u8; OFFER_ID_LEN], HookError> {
+    let mut keylet = [0u8; KEYLET_LEN];
+    let result = unsafe {
+        util_keylet(keylet.as_mut_ptr(), keylet.len() as i32, KEYLET_NFT_OFFER,
+                    owner.as_ptr(), owner.len() as i32, sequence)
+    };
+
+    if result != KEYLET_LEN as i32 {
+        return Err(HookError::FieldReadFailed);
+    }
+
+    bytes::array(&keylet, 2).ok_or(HookError::FieldReadFailed)
+}
+
+fn offer_key(id: &[u8; OFFER_ID_LEN]) -> [u8; state::KEY_LEN] {
+    state::key(state::NS_NFT_OFFER, &[id])
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use lks_hook_sdk::amount;
+    use lks_hook_sdk::{fields, sim, state};
+    use crate::fixtures::{lks_payment, written_fee, MERCHANT, USER};
+    use crate::{hook, TX_TYPE_NFTOKEN_ACCEPT_OFFER, TX_TYPE_NFTOKEN_CREATE_OFFER};
+
+    #[test]
+    fn sponsors_accepting_an_lks_nft_offer() {
+        lks_payment(25, 12);
+        sim::with(|host| {
+            host.tx_type = TX_TYPE_NFTOKEN_CREATE_OFFER;
+            host.fields.remove(&fields::SF_DESTINATION);
+            host.fields.insert(fields::SF_SEQUENCE, 7u32.to_be_bytes().to_vec());
+        });
+        assert_eq!(sim::run(hook), 0);
+        assert_eq!(written_fee(), 0);
+
+        // The buyer accepts the offer by its id; only the marker says it's LKS
+        let mut offer_id = USER.to_vec();
+        offer_id.extend_from_slice(&7u32.to_be_bytes());
+        offer_id.resize(32, 0);
+        let fee = amount::encode_native(12).to_vec();
+        sim::with(|host| {
+            host.tx_type = TX_TYPE_NFTOKEN_ACCEPT_OFFER;
+            host.otxn_id = [1; 32];
+            host.fields.clear();
+            host.fields.insert(fields::SF_FEE, fee);
+            host.fields.insert(fields::SF_ACCOUNT, MERCHANT.to_vec());
+            host.fields.insert(fields::SF_NFTOKEN_SELL_OFFER, offer_id);
+        });
+        assert_eq!(sim::run(hook), 0);
+        assert_eq!(written_fee(), 0);
+        assert!(sim::with(|host| host.state.keys().all(|key| key[3] != state::NS_NFT_OFFER)));
+    }
+}
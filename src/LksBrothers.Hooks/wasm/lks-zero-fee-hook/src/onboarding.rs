@@ -0,0 +1,84 @@
+// Onboarding sponsorship for the LKS zero-fee hook
+// Every account gets its first FREEFIRST sponsored transactions fully
+// sponsored, whatever their amount tier and stake and without counting
+// against the per-account and per-pair caps. The foundation's safeguards
+// (opt-out memos, the breaker, the fee cap and the budget) still apply.
+// Accounts sponsored before onboarding was switched on start from zero too.
+//
+// State layout:
+//   lifetime counter per account   [transactions sponsored u64]
+// Counters stop being written once onboarding is used up.
+
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::state;
+use crate::config;
+
+// Transactions per account fully sponsored on onboarding, zero to disable
+pub const PARAM_FREE_FIRST: &[u8] = b"FREEFIRST";
+pub const DEFAULT_FREE_FIRST: u64 = 0;
+
+// Transactions sponsored on onboarding so far, None once `source` has used
+// them all up
+pub fn check(source: &[u8; 20]) -> Option<u64> {
+    let free = config::u64_param(PARAM_FREE_FIRST, DEFAULT_FREE_FIRST);
+    if free == 0 {
+        return None;
+    }
+
+    let used = sponsored(source);
+    (used < free).then_some(used)
+}
+
+// Count a transaction sponsored on onboarding
+pub fn record(source: &[u8; 20], used: u64) -> Result<(), HookError> {
+    state::store_entry(&counter_key(source), &(used + 1))
+}
+
+fn sponsored(source: &[u8; 20]) -> u64 {
+    state::load_entry::<u64>(&counter_key(source)).unwrap_or(0)
+}
+
+pub fn counter_key(source: &[u8; 20]) -> [u8; state::KEY_LEN] {
+    state::account_key(state::NS_ONBOARDING, source)
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::amount;
+    use lks_hook_sdk::{fields, sim};
+    use crate::fixtures::{lks_payment, written_fee, USER};
+    use crate::{hook, limits, policy};
+
+    #[test]
+    fn onboarding_sponsors_first_transactions_fully() {
+        lks_payment(25, 100);
+        // Payments of 25 LKS are above the only tier
+        let mut tiers = 10u64.to_be_bytes().to_vec();
+        tiers.push(100);
+        sim::with(|host| {
+            host.hook_params.insert(PARAM_FREE_FIRST.to_vec(), 2u64.to_be_bytes().to_vec());
+            host.hook_params.insert(limits::PARAM_ACCOUNT_CAP.to_vec(), 1u64.to_be_bytes().to_vec());
+            host.hook_params.insert(policy::PARAM_TIERS.to_vec(), tiers);
+        });
+
+        // Tiers and caps don't apply while onboarding, and onboarding
+        // transactions don't count against the caps
+        for otxn in 1..=2 {
+            sim::with(|host| host.otxn_id = [otxn; 32]);
+            assert_eq!(sim::run(hook), 0);
+            assert_eq!(written_fee(), 0);
+            sim::with(|host| host.fields.insert(fields::SF_FEE, amount::encode_native(100).to_vec()));
+        }
+        let account_key = state::account_key(state::NS_ACCOUNT_LIMIT, &USER);
+        assert!(!sim::with(|host| host.state.contains_key(account_key.as_slice())));
+
+        let counter_key = counter_key(&USER);
+        assert_eq!(sim::with(|host| host.value(&counter_key).map(<[u8]>::to_vec)), Some(2u64.to_be_bytes().to_vec()));
+
+        // Afterwards the normal rules are back
+        sim::with(|host| host.otxn_id = [3; 32]);
+        assert_eq!(sim::run(hook), HookError::TierNotSponsored.return_value());
+        assert_eq!(written_fee(), 100);
+    }
+}
@@ -0,0 +1,82 @@
+// SetHook parameters of the LKS zero-fee hook
+// Every parameter the hook reads at SetHook time, besides the SDK's own in
+// config::PARAMS, with the encoding it reads it in. lks-hook-params encodes
+// deployment configs from this list, so a parameter added here can be
+// deployed right away. Admin command parameters arrive on Invoke
+// transactions and aren't listed.
+
+use lks_hook_sdk::state;
+use crate::config::{Encoding, Param};
+use crate::{abuse, analytics, breaker, burst, config, limits, maintenance, maker, onboarding, policy, prune, receipt, referral,
+            sampling, settlement, staking, strict, txtypes, voucher};
+
+pub use crate::policy::{encode_tier, TIER_LEN};
+
+const TIERS: Encoding = Encoding::Records { len: policy::TIER_LEN, max: policy::MAX_TIERS };
+
+pub const PARAMS: &[Param] = &[
+    Param { name: config::PARAM_TRUSTSET_FIRST_ONLY, encoding: Encoding::Flag },
+    Param { name: config::PARAM_KYC_ONLY, encoding: Encoding::Flag },
+    Param { name: config::PARAM_CROSS_CURRENCY, encoding: Encoding::Flag },
+    Param { name: config::PARAM_MAX_FEE, encoding: Encoding::U64 },
+    Param { name: config::PARAM_MIN_AMOUNT, encoding: Encoding::U64 },
+    Param { name: policy::PARAM_TIERS, encoding: TIERS },
+    Param { name: policy::PARAM_STAKE_TIERS, encoding: TIERS },
+    Param { name: policy::PARAM_COPAY_SHARE, encoding: Encoding::U64 },
+    Param { name: policy::PARAM_COPAY_FLOOR, encoding: Encoding::U64 },
+    Param { name: limits::PARAM_EPOCH_LEDGERS, encoding: Encoding::U64 },
+    Param { name: limits::PARAM_ACCOUNT_CAP, encoding: Encoding::U64 },
+    Param { name: limits::PARAM_PAIR_CAP, encoding: Encoding::U64 },
+    Param { name: limits::PARAM_BUDGET, encoding: Encoding::U64 },
+    Param { name: breaker::PARAM_OPEN_BELOW, encoding: Encoding::U64 },
+    Param { name: breaker::PARAM_CLOSE_ABOVE, encoding: Encoding::U64 },
+    Param { name: receipt::PARAM_RECEIPT_SLOTS, encoding: Encoding::U64 },
+    Param { name: prune::PARAM_PRUNE_STEPS, encoding: Encoding::U64 },
+    Param { name: settlement::PARAM_SETTLEMENT_LEDGERS, encoding: Encoding::U64 },
+    Param { name: settlement::PARAM_SETTLEMENT_DESTINATION, encoding: Encoding::Account },
+    Param { name: settlement::PARAM_SETTLEMENT_RETRIES, encoding: Encoding::U64 },
+    Param { name: maintenance::PARAM_MAINTENANCE, encoding: Encoding::Flag },
+    Param { name: maintenance::PARAM_MAINTENANCE_MIN, encoding: Encoding::U64 },
+    Param { name: staking::PARAM_STAKE_ACCOUNT, encoding: Encoding::Account },
+    Param { name: staking::PARAM_STAKE_NAMESPACE, encoding: Encoding::Bytes(state::NAMESPACE_LEN) },
+    Param { name: voucher::PARAM_VOUCHER_KEY, encoding: Encoding::Bytes(voucher::KEY_LEN) },
+    Param { name: referral::PARAM_REFERRALS, encoding: Encoding::Flag },
+    Param { name: analytics::PARAM_ANALYTICS, encoding: Encoding::Flag },
+    Param { name: analytics::PARAM_STATS_EPOCHS, encoding: Encoding::U64 },
+    Param { name: onboarding::PARAM_FREE_FIRST, encoding: Encoding::U64 },
+    Param { name: abuse::PARAM_ABUSE_MAX, encoding: Encoding::U64 },
+    Param { name: abuse::PARAM_ABUSE_AGE, encoding: Encoding::U64 },
+    Param { name: burst::PARAM_BURST_CAP, encoding: Encoding::U64 },
+    Param { name: burst::PARAM_BURST_RATE, encoding: Encoding::U64 },
+    Param { name: burst::PARAM_BURST_HOLD, encoding: Encoding::U64 },
+    Param { name: maker::PARAM_MAKER_ONLY, encoding: Encoding::Flag },
+    Param { name: maker::PARAM_MAKER_REF, encoding: Encoding::U64 },
+    Param { name: maker::PARAM_MAKER_CAP, encoding: Encoding::U64 },
+    Param { name: sampling::PARAM_SAMPLE_RATE, encoding: Encoding::U64 },
+    Param { name: strict::PARAM_STRICT, encoding: Encoding::Flag },
+    Param { name: txtypes::PARAM_TX_TYPES, encoding: Encoding::U64 },
+];
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::sim;
+
+    #[test]
+    fn parameter_catalog_matches_the_readers() {
+        let names: std::vec::Vec<&[u8]> = config::PARAMS.iter().chain(PARAMS).map(|param| param.name).collect();
+        for (i, name) in names.iter().enumerate() {
+            assert!(name.len() <= config::MAX_PARAM_NAME_LEN && !names[i + 1..].contains(name));
+        }
+
+        // Tier tables encoded for deployment read back as written
+        let mut tiers = encode_tier(10_000_000, 100).to_vec();
+        tiers.extend_from_slice(&encode_tier(30_000_000, 50));
+        assert!(config::Encoding::Records { len: TIER_LEN, max: policy::MAX_TIERS }.fits(&tiers));
+        sim::reset();
+        sim::with(|host| host.hook_params.insert(policy::PARAM_TIERS.to_vec(), tiers));
+        assert_eq!(policy::sponsored_share(10_000_000), 100);
+        assert_eq!(policy::sponsored_share(25_000_000), 50);
+        assert_eq!(policy::sponsored_share(30_000_001), 0);
+    }
+}
@@ -0,0 +1,130 @@
+// Payment paths for the LKS zero-fee hook
+// Cross-currency payments can route through LKS order books without either
+// Amount or SendMax being LKS. The Paths field is decoded here so the hook
+// can tell whether a payment is bridged through a sponsored currency.
+//
+// A serialized PathSet is a list of paths separated by 0xFF and terminated by
+// 0x00. Each step starts with a type byte whose bits say which of account,
+// currency and issuer (20 bytes each, in that order) follow.
+
+use lks_hook_sdk::bytes;
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::fields::{self, SF_PATHS};
+use crate::currency;
+
+const PATH_END: u8 = 0x00;
+const PATH_SEPARATOR: u8 = 0xFF;
+
+const STEP_ACCOUNT: u8 = 0x01;
+const STEP_CURRENCY: u8 = 0x10;
+const STEP_ISSUER: u8 = 0x20;
+
+// Payments carry at most 6 paths of 8 steps
+const MAX_STEPS: u32 = 48;
+const MAX_PATHS_LEN: usize = 3072;
+
+#[derive(Clone, Copy, Default)]
+pub struct Step {
+    pub currency: Option<[u8; 20]>,
+    pub issuer: Option<[u8; 20]>,
+}
+
+// Whether any path of the originating payment steps through a sponsored
+// currency from its registered issuer
+pub fn routes_through_lks() -> Result<bool, HookError> {
+    let mut buffer = [0u8; MAX_PATHS_LEN];
+    let paths = match fields::read_raw(SF_PATHS, &mut buffer)? {
+        Some(paths) => paths,
+        None => return Ok(false),
+    };
+
+    let mut found = false;
+    for_each_step(paths, |step| {
+        if let Some(code) = step.currency {
+            if let Some(token) = currency::lookup(&code) {
+                found |= step.issuer.is_none_or(|issuer| token.issuer.matches(&issuer));
+            }
+        }
+    })?;
+
+    Ok(found)
+}
+
+// Walk a serialized PathSet, calling `f` with the currency and issuer of each
+// step of every path
+pub fn for_each_step(paths: &[u8], mut f: impl FnMut(&Step)) -> Result<(), HookError> {
+    let mut pos = 0;
+    let mut ended = false;
+
+    guarded_while!(max MAX_STEPS + 6; !ended; {
+        let step_type = *paths.get(pos).ok_or(HookError::FieldReadFailed)?;
+        pos += 1;
+
+        if step_type == PATH_END {
+            ended = true;
+        } else if step_type != PATH_SEPARATOR {
+            if step_type & !(STEP_ACCOUNT | STEP_CURRENCY | STEP_ISSUER) != 0 {
+                return Err(HookError::FieldReadFailed);
+            }
+
+            let mut step = Step::default();
+            // Rippling through an account doesn't change the currency
+            if step_type & STEP_ACCOUNT != 0 {
+                read_account_id(paths, &mut pos)?;
+            }
+            if step_type & STEP_CURRENCY != 0 {
+                step.currency = Some(read_account_id(paths, &mut pos)?);
+            }
+            if step_type & STEP_ISSUER != 0 {
+                step.issuer = Some(read_account_id(paths, &mut pos)?);
+            }
+            f(&step);
+        }
+    });
+
+    if pos != paths.len() {
+        return Err(HookError::FieldReadFailed);
+    }
+
+    Ok(())
+}
+
+// Accounts, currency codes and issuers in a step are all 20 bytes
+fn read_account_id(paths: &[u8], pos: &mut usize) -> Result<[u8; 20], HookError> {
+    let id = bytes::array(paths, *pos).ok_or(HookError::FieldReadFailed)?;
+    *pos += 20;
+    Ok(id)
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use lks_hook_sdk::amount;
+    use lks_hook_sdk::error::HookError;
+    use lks_hook_sdk::{fields, sim};
+    use crate::fixtures::{lks_payment, written_fee};
+    use crate::{config, hook, LKS_CURRENCY_CODE, LKS_ISSUER};
+
+    #[test]
+    fn sponsors_lks_bridged_payment_only_when_enabled() {
+        // One path: a currency step to LKS from its issuer, then to USD
+        let mut paths = std::vec![0x30];
+        paths.extend_from_slice(&amount::currency_code(&LKS_CURRENCY_CODE));
+        paths.extend_from_slice(LKS_ISSUER.as_bytes());
+        paths.push(0x10);
+        paths.extend_from_slice(&amount::currency_code(b"USD"));
+        paths.push(0x00);
+
+        lks_payment(25, 12);
+        let native = amount::encode_native(5_000).to_vec();
+        sim::with(|host| {
+            host.fields.insert(fields::SF_AMOUNT, native);
+            host.fields.insert(fields::SF_PATHS, paths);
+        });
+        assert_eq!(sim::run(hook), HookError::WrongCurrency.return_value());
+        assert_eq!(written_fee(), 12);
+
+        sim::with(|host| host.hook_params.insert(config::PARAM_CROSS_CURRENCY.to_vec(), std::vec![1]));
+        assert_eq!(sim::run(hook), 0);
+        assert_eq!(written_fee(), 0);
+    }
+}
@@ -0,0 +1,64 @@
+// Foundation pause switch for the LKS zero-fee hook
+// During an incident the foundation halts all sponsorship with an admin
+// command and resumes it with another, without removing the hook. While
+// paused every transaction except admin Invokes passes through with its
+// normal fee before the hook reads or writes anything else, and each one is
+// traced with EV_PAUSED so operators can see the switch is on. The switch
+// is a single hook state entry, present only while paused.
+
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::{log, state};
+
+pub fn is_paused() -> bool {
+    state::load_entry::<bool>(&pause_key()).unwrap_or(false)
+}
+
+pub fn set_paused(paused: bool) -> Result<(), HookError> {
+    if paused {
+        state::store_entry(&pause_key(), &true)?;
+        log::warn(log::EV_PAUSE_SET, b"LKS sponsorship paused by foundation", 1, 0);
+    } else {
+        state::erase(&pause_key())?;
+        log::warn(log::EV_PAUSE_SET, b"LKS sponsorship resumed by foundation", 0, 0);
+    }
+
+    Ok(())
+}
+
+fn pause_key() -> [u8; state::KEY_LEN] {
+    state::key(state::NS_PAUSE, &[])
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use lks_hook_sdk::admin::FOUNDATION_ACCOUNT;
+    use lks_hook_sdk::error::HookError;
+    use lks_hook_sdk::log;
+    use lks_hook_sdk::sim::{self, Outcome};
+    use crate::fixtures::{foundation_invoke, lks_payment, written_fee};
+    use crate::{dispatch, hook, TX_TYPE_ESCROW_CREATE, TX_TYPE_OFFER_CREATE, TX_TYPE_PAYMENT, TX_TYPE_TRUST_SET};
+
+    #[test]
+    fn paused_hook_passes_every_transaction_through_untouched() {
+        foundation_invoke(FOUNDATION_ACCOUNT.as_bytes());
+        sim::with(|host| host.otxn_params.insert(dispatch::PARAM_COMMAND.to_vec(), std::vec![dispatch::OP_PAUSE, 1]));
+        assert_eq!(sim::run(hook), 0);
+        let paused = sim::with(|host| std::mem::take(&mut host.state));
+
+        for tx_type in [TX_TYPE_PAYMENT, TX_TYPE_OFFER_CREATE, TX_TYPE_TRUST_SET, TX_TYPE_ESCROW_CREATE] {
+            lks_payment(25, 12);
+            sim::with(|host| {
+                host.tx_type = tx_type;
+                host.state = paused.clone();
+            });
+            assert_eq!(sim::run(hook), HookError::SponsorshipPaused.return_value());
+            assert_eq!(written_fee(), 12);
+            assert!(matches!(sim::with(|host| host.outcome.clone()), Some(Outcome::Accepted(_))));
+
+            // Nothing but the switch is in state, and the pause is traced
+            assert_eq!(sim::with(|host| host.state.clone()), paused);
+            let event = log::encode(log::Level::Warn, log::EV_PAUSED, tx_type as u64, 0);
+            assert!(sim::with(|host| host.traces.iter().any(|(_, data)| *data == event)));
+        }
+    }
+}
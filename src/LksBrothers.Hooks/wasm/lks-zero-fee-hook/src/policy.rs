@@ -0,0 +1,151 @@
+// Sponsorship policy for the LKS zero-fee hook
+// Instead of all-or-nothing sponsorship, a tier table maps the LKS value
+// moved by a transaction to the share of its fee the foundation covers.
+// A second table maps the sender's LKS stake to a share that scales it, so
+// stakers can be sponsored in full and everyone else in part.
+// A co-pay keeps a symbolic part of every fee with the user for spam
+// resistance, however much the tables sponsor: COPAYPCT percent of the fee,
+// and never less than COPAYMIN drops. The foundation covers what is left,
+// and that is what the budget, receipts and settlements account for.
+
+use lks_hook_sdk::bytes;
+use crate::config;
+
+// Tier table parameter: up to MAX_TIERS entries of
+// [up_to_amount: u64 big-endian micro-LKS, sponsored_share: u8 percent],
+// sorted by ascending amount. Amounts above the last entry are not sponsored.
+// Without the parameter every amount is fully sponsored.
+pub const PARAM_TIERS: &[u8] = b"TIERS";
+
+// Stake tier parameter: up to MAX_TIERS entries of
+// [min_stake: u64 big-endian micro-LKS, sponsored_share: u8 percent],
+// sorted by ascending stake. Senders get the share of the last tier their
+// stake reaches, and none below the first. Without the parameter stake
+// doesn't affect sponsorship.
+pub const PARAM_STAKE_TIERS: &[u8] = b"STKTIERS";
+
+// Share of the fee the user pays in percent, and the least the user pays in
+// drops. Fees at or below the floor aren't sponsored.
+pub const PARAM_COPAY_SHARE: &[u8] = b"COPAYPCT";
+pub const DEFAULT_COPAY_SHARE: u64 = 0;
+pub const PARAM_COPAY_FLOOR: &[u8] = b"COPAYMIN";
+pub const DEFAULT_COPAY_FLOOR: u64 = 0;
+
+pub const TIER_LEN: usize = 9;
+pub const MAX_TIERS: usize = 8;
+
+pub const FULL_SHARE: u8 = 100;
+
+// Percentage of the fee the foundation covers for a transaction moving `amount`
+pub fn sponsored_share(amount: u64) -> u8 {
+    let mut table = [0u8; TIER_LEN * MAX_TIERS];
+    let table_len = config::records_param(PARAM_TIERS, TIER_LEN, &mut table);
+
+    if table_len == 0 {
+        return FULL_SHARE;
+    }
+
+    let mut result = 0;
+    guarded_loop!(i in 0, table_len / TIER_LEN; max MAX_TIERS as u32; {
+        let (up_to, share) = tier(&table, i);
+        if amount <= up_to {
+            result = share.min(FULL_SHARE);
+            break;
+        }
+    });
+
+    result
+}
+
+// Percentage of the amount share kept for a sender with `stake` staked
+pub fn stake_share(stake: u64) -> u8 {
+    let mut table = [0u8; TIER_LEN * MAX_TIERS];
+    let table_len = config::records_param(PARAM_STAKE_TIERS, TIER_LEN, &mut table);
+
+    if table_len == 0 {
+        return FULL_SHARE;
+    }
+
+    let mut result = 0;
+    guarded_loop!(i in 0, table_len / TIER_LEN; max MAX_TIERS as u32; {
+        let (min_stake, share) = tier(&table, i);
+        if stake >= min_stake {
+            result = share.min(FULL_SHARE);
+        }
+    });
+
+    result
+}
+
+// [bound, share] of tier `i`
+fn tier(table: &[u8], i: usize) -> (u64, u8) {
+    let bound = bytes::u64_at(table, i * TIER_LEN).unwrap_or(0);
+    let share = table.get(i * TIER_LEN + 8).copied().unwrap_or(0);
+    (bound, share)
+}
+
+// The record tier() reads back as [bound, share]
+pub fn encode_tier(bound: u64, share: u8) -> [u8; TIER_LEN] {
+    let mut record = [0u8; TIER_LEN];
+    bytes::put(&mut record, 0, &bound.to_be_bytes());
+    record[8] = share;
+    record
+}
+
+// Combine the amount and stake shares
+pub fn scaled_share(share: u8, stake_share: u8) -> u8 {
+    (share.min(FULL_SHARE) as u16 * stake_share.min(FULL_SHARE) as u16 / FULL_SHARE as u16) as u8
+}
+
+// Portion of `original_fee` covered at the given sponsored share. Split by
+// FULL_SHARE so neither product overflows u64: u128 division calls a
+// builtin whose loop has no guard.
+pub fn sponsored_fee(original_fee: u64, share: u8) -> u64 {
+    let share = share.min(FULL_SHARE) as u64;
+    let full = FULL_SHARE as u64;
+    original_fee / full * share + original_fee % full * share / full
+}
+
+// Fee the user pays on `original_fee` under the co-pay: the larger of the
+// co-pay share, rounded up, and the floor, up to the whole fee
+pub fn co_pay(original_fee: u64) -> u64 {
+    let share = config::u64_param(PARAM_COPAY_SHARE, DEFAULT_COPAY_SHARE).min(FULL_SHARE as u64) as u8;
+    let floor = config::u64_param(PARAM_COPAY_FLOOR, DEFAULT_COPAY_FLOOR);
+    let user_fee = original_fee - sponsored_fee(original_fee, FULL_SHARE - share);
+
+    user_fee.max(floor).min(original_fee)
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::error::HookError;
+    use lks_hook_sdk::{sim, state};
+    use crate::fixtures::{lks_payment, written_fee};
+    use crate::{hook, limits, receipt};
+
+    #[test]
+    fn co_pay_keeps_a_floor_with_the_user() {
+        let budget = state::key(state::NS_BUDGET, &[]);
+        let sponsored = |fee: u64| {
+            lks_payment(25, fee);
+            sim::with(|host| {
+                host.hook_params.insert(PARAM_COPAY_SHARE.to_vec(), 10u64.to_be_bytes().to_vec());
+                host.hook_params.insert(PARAM_COPAY_FLOOR.to_vec(), 10u64.to_be_bytes().to_vec());
+            });
+            let result = sim::run(hook);
+            let key = receipt::slot_key(0);
+            let receipt = sim::with(|host| host.value(&key).map(<[u8]>::to_vec));
+            (result, written_fee(), limits::load_counter(&budget, 3), receipt.map(|r| r[28..36].to_vec()))
+        };
+
+        // The floor holds on small fees, the share on larger ones; the budget
+        // and the receipt count the part the foundation covers
+        assert_eq!(sponsored(12), (0, 10, 2, Some(2u64.to_be_bytes().to_vec())));
+        assert_eq!(sponsored(250), (0, 25, 225, Some(225u64.to_be_bytes().to_vec())));
+        assert_eq!(sponsored(255), (0, 26, 229, Some(229u64.to_be_bytes().to_vec())));
+
+        // Fees within the floor are left to the user in full
+        assert_eq!(sponsored(10), (HookError::CoPayCoversFee.return_value(), 10, 0, None));
+    }
+}
@@ -0,0 +1,170 @@
+// Property tests for the sponsorship policy of the LKS zero-fee hook
+// Randomized transaction sequences run through the hook against the
+// simulated host, checking the invariants every sponsorship decision must
+// keep whatever the configuration and order of transactions.
+
+use super::*;
+use lks_hook_sdk::amount::{self, Amount};
+use lks_hook_sdk::sim;
+use lks_hook_sdk::state;
+use lks_hook_sdk::xfl::Xfl;
+use proptest::prelude::*;
+use std::collections::BTreeMap;
+use std::vec::Vec;
+
+const ACCOUNTS: [[u8; 20]; 4] = [[0xA1; 20], [0xA2; 20], [0xA3; 20], [0xA4; 20]];
+
+// One LKS payment in a sequence
+#[derive(Clone, Debug)]
+struct Payment {
+    source: usize,
+    destination: usize,
+    units: u64,
+    fee: u64,
+    // Ledgers elapsed since the previous payment
+    advance: u64,
+}
+
+fn payment() -> impl Strategy<Value = Payment> {
+    (0..ACCOUNTS.len(), 0..ACCOUNTS.len(), 0u64..10_000, 1u64..2_000, 0u64..40).prop_map(
+        |(source, destination, units, fee, advance)| Payment { source, destination, units, fee, advance },
+    )
+}
+
+// Tier tables of [bound: u64, share: u8] entries, shares possibly above 100
+fn tier_table() -> impl Strategy<Value = Vec<u8>> {
+    prop::collection::vec((any::<u64>(), any::<u8>()), 0..=8).prop_map(|tiers| {
+        let mut table = Vec::new();
+        for (bound, share) in tiers {
+            table.extend_from_slice(&bound.to_be_bytes());
+            table.push(share);
+        }
+        table
+    })
+}
+
+fn lks_amount_bytes(units: u64) -> Vec<u8> {
+    let mut amount = Xfl::from_int(units).to_amount_value().to_vec();
+    amount.extend_from_slice(&amount::currency_code(&LKS_CURRENCY_CODE));
+    amount.extend_from_slice(LKS_ISSUER.as_bytes());
+    amount
+}
+
+fn written_fee() -> u64 {
+    let fee = sim::with(|host| host.fields[&fields::SF_FEE].clone());
+    match amount::parse(&fee) {
+        Ok(Amount::Native(drops)) => drops,
+        _ => panic!("fee is not a native amount"),
+    }
+}
+
+// (epoch, value) of the foundation's budget counter
+fn budget_counter() -> Option<(u32, u64)> {
+    let key = state::key(state::NS_BUDGET, &[]);
+    sim::with(|host| host.value(&key).map(<[u8]>::to_vec)).map(|entry| {
+        (u32::from_be_bytes(entry[..4].try_into().unwrap()), u64::from_be_bytes(entry[4..].try_into().unwrap()))
+    })
+}
+
+fn set_param(name: &[u8], value: Vec<u8>) {
+    sim::with(|host| host.hook_params.insert(name.to_vec(), value));
+}
+
+proptest! {
+    #[test]
+    fn shares_never_exceed_the_full_fee(fee in any::<u64>(), share in any::<u8>(), stake_share in any::<u8>()) {
+        let scaled = policy::scaled_share(share, stake_share);
+        prop_assert!(scaled <= share.min(policy::FULL_SHARE));
+        prop_assert!(scaled <= stake_share.min(policy::FULL_SHARE));
+        prop_assert!(policy::sponsored_fee(fee, share) <= fee);
+        prop_assert!(policy::sponsored_fee(fee, scaled) <= policy::sponsored_fee(fee, share));
+
+        let exact = fee as u128 * share.min(policy::FULL_SHARE) as u128 / policy::FULL_SHARE as u128;
+        prop_assert_eq!(policy::sponsored_fee(fee, share) as u128, exact);
+    }
+
+    #[test]
+    fn prices_order_like_their_exact_products(drops in any::<u64>(), units in any::<u64>(), reference in any::<u64>()) {
+        let exact = (drops as u128 * 1_000_000).cmp(&(reference as u128 * units as u128));
+        prop_assert_eq!(maker::price_ordering(drops, units, reference), exact);
+    }
+
+    #[test]
+    fn tier_tables_yield_valid_shares(tiers in tier_table(), stake_tiers in tier_table(), value in any::<u64>()) {
+        sim::reset();
+        set_param(policy::PARAM_TIERS, tiers);
+        set_param(policy::PARAM_STAKE_TIERS, stake_tiers);
+
+        prop_assert!(policy::sponsored_share(value) <= policy::FULL_SHARE);
+        prop_assert!(policy::stake_share(value) <= policy::FULL_SHARE);
+    }
+
+    #[test]
+    fn sponsorship_stays_within_fee_budget_and_caps(
+        payments in prop::collection::vec(payment(), 1..48),
+        tiers in tier_table(),
+        budget in 0u64..20_000,
+        account_cap in 0u64..6,
+        pair_cap in 0u64..4,
+    ) {
+        const EPOCH_LEDGERS: u64 = 64;
+
+        sim::reset();
+        set_param(limits::PARAM_EPOCH_LEDGERS, EPOCH_LEDGERS.to_be_bytes().to_vec());
+        set_param(limits::PARAM_BUDGET, budget.to_be_bytes().to_vec());
+        set_param(limits::PARAM_ACCOUNT_CAP, account_cap.to_be_bytes().to_vec());
+        set_param(limits::PARAM_PAIR_CAP, pair_cap.to_be_bytes().to_vec());
+        set_param(policy::PARAM_TIERS, tiers);
+
+        let mut ledger = 1000;
+        let mut last_budget: Option<(u32, u64)> = None;
+        // Sponsored transactions and drops per epoch
+        let mut per_account: BTreeMap<(u32, usize), u64> = BTreeMap::new();
+        let mut per_pair: BTreeMap<(u32, usize, usize), u64> = BTreeMap::new();
+        let mut spent: BTreeMap<u32, u64> = BTreeMap::new();
+
+        for (i, payment) in payments.iter().enumerate() {
+            ledger += payment.advance;
+            let epoch = (ledger / EPOCH_LEDGERS) as u32;
+            let amount = lks_amount_bytes(payment.units);
+            sim::with(|host| {
+                host.tx_type = TX_TYPE_PAYMENT;
+                host.ledger_seq = ledger;
+                host.otxn_id = [0; 32];
+                host.otxn_id[..8].copy_from_slice(&(i as u64).to_be_bytes());
+                host.fields.clear();
+                host.fields.insert(fields::SF_FEE, amount::encode_native(payment.fee).to_vec());
+                host.fields.insert(fields::SF_ACCOUNT, ACCOUNTS[payment.source].to_vec());
+                host.fields.insert(fields::SF_DESTINATION, ACCOUNTS[payment.destination].to_vec());
+                host.fields.insert(fields::SF_AMOUNT, amount);
+            });
+            sim::run(hook);
+
+            // The user fee only ever goes down
+            let fee = written_fee();
+            prop_assert!(fee <= payment.fee);
+
+            let sponsored = payment.fee - fee;
+            if sponsored > 0 {
+                *per_account.entry((epoch, payment.source)).or_default() += 1;
+                let (low, high) = (payment.source.min(payment.destination), payment.source.max(payment.destination));
+                *per_pair.entry((epoch, low, high)).or_default() += 1;
+                *spent.entry(epoch).or_default() += sponsored;
+            }
+
+            prop_assert!(per_account.get(&(epoch, payment.source)).copied().unwrap_or(0) <= account_cap);
+            prop_assert!(per_pair.values().all(|&count| count <= pair_cap));
+
+            // The budget counter tracks exactly what was sponsored this
+            // epoch, never shrinks within it and never passes the budget
+            if let Some((counter_epoch, value)) = budget_counter() {
+                prop_assert!(value <= budget);
+                prop_assert_eq!(value, spent.get(&counter_epoch).copied().unwrap_or(0));
+                if let Some((last_epoch, last_value)) = last_budget {
+                    prop_assert!(counter_epoch > last_epoch || value >= last_value);
+                }
+                last_budget = Some((counter_epoch, value));
+            }
+        }
+    }
+}
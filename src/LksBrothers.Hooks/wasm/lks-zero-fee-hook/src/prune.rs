@@ -0,0 +1,210 @@
+// Lazy pruning of expired hook state
+// Epoch-keyed entries (limit counters) are dead once their epoch is over but
+// keep holding owner reserve until deleted. Entries are registered in a
+// per-epoch index when first written, and every hook invocation deletes a
+// few entries of the oldest finished epoch so the work per call stays bounded.
+//
+// Prunable entries must start with the epoch they belong to (u32 big-endian).
+// An entry rewritten in a later epoch is left alone when its old index item
+// is pruned.
+//
+// State layout:
+//   cursor             [head epoch u32][next item u32][tail epoch u32]
+//   count per epoch    [items u32][next registered epoch u32]
+//   index item         target key
+// Epochs with registered entries form a list from head to tail through the
+// count entries; the cursor is missing when nothing is waiting to be pruned.
+
+use lks_hook_sdk::bytes;
+use lks_hook_sdk::entry::{Entry, Reader, Writer};
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::state;
+use crate::{config, limits};
+
+// Entries deleted per hook invocation
+pub const PARAM_PRUNE_STEPS: &[u8] = b"PRUNEMAX";
+pub const DEFAULT_PRUNE_STEPS: u64 = 4;
+
+// Upper bound on PRUNEMAX, which also sizes the guard budget
+const MAX_PRUNE_STEPS: u32 = 16;
+
+struct Cursor {
+    head: u32,
+    item: u32,
+    tail: u32,
+}
+
+impl Entry for Cursor {
+    #[inline(always)]
+    fn encode(&self, w: &mut Writer) {
+        w.u32(self.head).u32(self.item).u32(self.tail);
+    }
+
+    #[inline(always)]
+    fn decode(r: &mut Reader) -> Option<Cursor> {
+        Some(Cursor { head: r.u32()?, item: r.u32()?, tail: r.u32()? })
+    }
+}
+
+// Register an entry written in `epoch` for deletion once the epoch is over.
+// Call it once per entry and epoch, when the entry is first written.
+pub fn register(key: &[u8; state::KEY_LEN], epoch: u32) -> Result<(), HookError> {
+    match load_cursor() {
+        None => {
+            store_cursor(&Cursor { head: epoch, item: 0, tail: epoch })?;
+            store_count(epoch, 0, 0)?;
+        }
+        Some(mut cursor) if cursor.tail != epoch => {
+            let (items, _) = load_count(cursor.tail);
+            store_count(cursor.tail, items, epoch)?;
+            store_count(epoch, 0, 0)?;
+            cursor.tail = epoch;
+            store_cursor(&cursor)?;
+        }
+        Some(_) => {}
+    }
+
+    let (items, next) = load_count(epoch);
+    state::store_entry(&item_key(epoch, items), key)?;
+    store_count(epoch, items + 1, next)
+}
+
+// Delete up to PRUNEMAX expired entries, oldest epoch first
+pub fn run() -> Result<(), HookError> {
+    let steps = config::u64_param(PARAM_PRUNE_STEPS, DEFAULT_PRUNE_STEPS)
+        .min(MAX_PRUNE_STEPS as u64) as u32;
+    let current = limits::current_epoch();
+
+    guarded_loop!(_step in 0, steps; max MAX_PRUNE_STEPS; {
+        if !step(current)? {
+            return Ok(());
+        }
+    });
+
+    Ok(())
+}
+
+// Delete one index item, or retire an exhausted epoch. Returns false once
+// nothing expired is left.
+fn step(current: u32) -> Result<bool, HookError> {
+    let mut cursor = match load_cursor() {
+        Some(cursor) if cursor.head < current => cursor,
+        _ => return Ok(false),
+    };
+
+    let (items, next) = load_count(cursor.head);
+    if cursor.item < items {
+        let item_key = item_key(cursor.head, cursor.item);
+        match state::load_entry::<[u8; state::KEY_LEN]>(&item_key) {
+            Some(target) if expired(&target, cursor.head) => state::erase(&target)?,
+            _ => {}
+        }
+        state::erase(&item_key)?;
+
+        cursor.item += 1;
+        store_cursor(&cursor)?;
+        return Ok(true);
+    }
+
+    state::erase(&count_key(cursor.head))?;
+    if cursor.head == cursor.tail {
+        state::erase(&cursor_key())?;
+        return Ok(false);
+    }
+
+    cursor.head = next;
+    cursor.item = 0;
+    store_cursor(&cursor)?;
+    Ok(true)
+}
+
+// Whether the entry at `key` still belongs to `epoch` or an earlier one
+fn expired(key: &[u8; state::KEY_LEN], epoch: u32) -> bool {
+    let mut entry = [0u8; state::MAX_VALUE_LEN];
+    let len = state::load(key, &mut entry);
+    match Reader::new(bytes::head(&entry, len)).u32() {
+        Some(written) => written <= epoch,
+        None => false,
+    }
+}
+
+fn cursor_key() -> [u8; state::KEY_LEN] {
+    state::key(state::NS_PRUNE_CURSOR, &[])
+}
+
+fn count_key(epoch: u32) -> [u8; state::KEY_LEN] {
+    state::key(state::NS_PRUNE_COUNT, &[&epoch.to_be_bytes()])
+}
+
+fn item_key(epoch: u32, item: u32) -> [u8; state::KEY_LEN] {
+    state::key(state::NS_PRUNE_INDEX, &[&epoch.to_be_bytes(), &item.to_be_bytes()])
+}
+
+fn load_cursor() -> Option<Cursor> {
+    state::load_entry(&cursor_key())
+}
+
+fn store_cursor(cursor: &Cursor) -> Result<(), HookError> {
+    state::store_entry(&cursor_key(), cursor)
+}
+
+// Missing count entries read as an empty epoch
+fn load_count(epoch: u32) -> (u32, u32) {
+    state::load_entry(&count_key(epoch)).unwrap_or((0, 0))
+}
+
+fn store_count(epoch: u32, items: u32, next: u32) -> Result<(), HookError> {
+    state::store_entry(&count_key(epoch), &(items, next))
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::amount;
+    use lks_hook_sdk::{fields, sim};
+    use crate::fixtures::{lks_payment, USER};
+    use crate::hook;
+
+    #[test]
+    fn prunes_counters_of_finished_epochs() {
+        lks_payment(25, 12);
+        assert_eq!(sim::run(hook), 0);
+
+        let account_key = state::account_key(state::NS_ACCOUNT_LIMIT, &USER);
+        assert!(sim::with(|host| host.state.contains_key(account_key.as_slice())));
+
+        // Once the epoch is over the next invocation deletes its counters
+        // and the pruning index, leaving the receipts and the settlement
+        // accumulator
+        let ledger = 1000 + limits::DEFAULT_EPOCH_LEDGERS;
+        let native = amount::encode_native(5_000).to_vec();
+        sim::with(|host| {
+            host.ledger_seq = ledger;
+            host.fields.insert(fields::SF_AMOUNT, native);
+            host.hook_params.insert(PARAM_PRUNE_STEPS.to_vec(), 8u64.to_be_bytes().to_vec());
+        });
+        assert_eq!(sim::run(hook), HookError::WrongCurrency.return_value());
+
+        let namespaces: std::vec::Vec<u8> = sim::with(|host| host.state.keys().map(|key| key[3]).collect());
+        let kept = [state::NS_RECEIPT, state::NS_RECEIPT_HEAD, state::NS_SETTLEMENT];
+        assert!(namespaces.iter().all(|ns| kept.contains(ns)));
+    }
+
+    #[test]
+    fn registers_counters_for_pruning_once_per_epoch() {
+        // Sponsorships of no drops leave the budget at zero
+        let epoch = 1000 / limits::DEFAULT_EPOCH_LEDGERS as u32;
+        let count_key = state::key(state::NS_PRUNE_COUNT, &[&epoch.to_be_bytes()]);
+        let registered = || sim::with(|host| host.value(&count_key).and_then(|count| bytes::u32_at(count, 0)));
+
+        lks_payment(25, 0);
+        assert_eq!(sim::run(hook), 0);
+        let first = registered().unwrap();
+
+        // The counters are indexed once each; only the new transaction mark
+        // is added
+        sim::with(|host| host.otxn_id = [1; 32]);
+        assert_eq!(sim::run(hook), 0);
+        assert_eq!(registered(), Some(first + 1));
+    }
+}
@@ -0,0 +1,104 @@
+// Sponsorship receipts for the LKS zero-fee hook
+// Every sponsored transaction leaves a compact receipt in hook state so
+// off-chain services can reconcile what the foundation spent per account.
+// Receipts live in a ring of numbered slots: a head entry holds the next
+// receipt number and slot `number % ring size` is overwritten as it wraps.
+//
+// Receipt layout (37 bytes, big-endian, after the version byte every state
+// value starts with):
+//   [0..4)   receipt number
+//   [4..24)  sponsored account
+//   [24..28) ledger sequence
+//   [28..36) sponsored fee in drops
+//   [36]     transaction category
+
+use lks_hook_sdk::api::ledger_seq;
+use lks_hook_sdk::entry::{Entry, Reader, Writer};
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::state;
+use crate::config;
+use crate::{
+    TX_TYPE_ACCOUNT_SET, TX_TYPE_SET_REGULAR_KEY, TX_TYPE_CHECK_CANCEL, TX_TYPE_CHECK_CASH,
+    TX_TYPE_CHECK_CREATE, TX_TYPE_ESCROW_CANCEL, TX_TYPE_ESCROW_CREATE, TX_TYPE_ESCROW_FINISH, TX_TYPE_NFTOKEN_ACCEPT_OFFER,
+    TX_TYPE_NFTOKEN_CANCEL_OFFER, TX_TYPE_NFTOKEN_CREATE_OFFER, TX_TYPE_NFTOKEN_MINT,
+    TX_TYPE_OFFER_CANCEL, TX_TYPE_OFFER_CREATE, TX_TYPE_PAYCHAN_CLAIM, TX_TYPE_PAYCHAN_CREATE,
+    TX_TYPE_PAYCHAN_FUND, TX_TYPE_PAYMENT, TX_TYPE_TRUST_SET, LKS_TRANSFER_TYPE,
+};
+
+// Number of receipt slots kept before the oldest is overwritten
+pub const PARAM_RECEIPT_SLOTS: &[u8] = b"RCPTSLOTS";
+pub const DEFAULT_RECEIPT_SLOTS: u64 = 1024;
+
+pub struct Receipt {
+    pub number: u32,
+    pub account: [u8; 20],
+    pub ledger: u32,
+    pub fee: u64,
+    pub category: u8,
+}
+
+impl Entry for Receipt {
+    #[inline(always)]
+    fn encode(&self, w: &mut Writer) {
+        w.u32(self.number).account(&self.account).u32(self.ledger).u64(self.fee).u8(self.category);
+    }
+
+    #[inline(always)]
+    fn decode(r: &mut Reader) -> Option<Receipt> {
+        Some(Receipt { number: r.u32()?, account: r.account()?, ledger: r.u32()?, fee: r.u64()?, category: r.u8()? })
+    }
+}
+
+// Transaction categories recorded in receipts
+pub const CATEGORY_OTHER: u8 = 0;
+pub const CATEGORY_PAYMENT: u8 = 1;
+pub const CATEGORY_DEX: u8 = 2;
+pub const CATEGORY_ESCROW: u8 = 3;
+pub const CATEGORY_PAYMENT_CHANNEL: u8 = 4;
+pub const CATEGORY_TRUST_LINE: u8 = 5;
+pub const CATEGORY_NFT: u8 = 6;
+pub const CATEGORY_ACCOUNT: u8 = 7;
+pub const CATEGORY_CHECK: u8 = 8;
+
+pub fn category(tx_type: i32) -> u8 {
+    match tx_type {
+        TX_TYPE_PAYMENT | LKS_TRANSFER_TYPE => CATEGORY_PAYMENT,
+        TX_TYPE_OFFER_CREATE | TX_TYPE_OFFER_CANCEL => CATEGORY_DEX,
+        TX_TYPE_ESCROW_CREATE | TX_TYPE_ESCROW_FINISH | TX_TYPE_ESCROW_CANCEL => CATEGORY_ESCROW,
+        TX_TYPE_PAYCHAN_CREATE | TX_TYPE_PAYCHAN_FUND | TX_TYPE_PAYCHAN_CLAIM => CATEGORY_PAYMENT_CHANNEL,
+        TX_TYPE_TRUST_SET => CATEGORY_TRUST_LINE,
+        TX_TYPE_NFTOKEN_MINT | TX_TYPE_NFTOKEN_CREATE_OFFER | TX_TYPE_NFTOKEN_CANCEL_OFFER
+        | TX_TYPE_NFTOKEN_ACCEPT_OFFER => CATEGORY_NFT,
+        TX_TYPE_ACCOUNT_SET | TX_TYPE_SET_REGULAR_KEY => CATEGORY_ACCOUNT,
+        TX_TYPE_CHECK_CREATE | TX_TYPE_CHECK_CASH | TX_TYPE_CHECK_CANCEL => CATEGORY_CHECK,
+        _ => CATEGORY_OTHER,
+    }
+}
+
+// Append a receipt and advance the head, returning the receipt number
+pub fn write(account: &[u8; 20], fee: u64, category: u8) -> Result<u32, HookError> {
+    let number = next_number();
+    let ledger = unsafe { ledger_seq() } as u32;
+
+    let receipt = Receipt { number, account: *account, ledger, fee, category };
+    state::store_entry(&slot_key(number), &receipt)?;
+    state::store_entry(&head_key(), &number.wrapping_add(1))?;
+
+    Ok(number)
+}
+
+// State key of the slot a receipt number is written to
+pub fn slot_key(number: u32) -> [u8; state::KEY_LEN] {
+    let slots = config::u64_param(PARAM_RECEIPT_SLOTS, DEFAULT_RECEIPT_SLOTS).clamp(1, u32::MAX as u64);
+    let slot = (number as u64 % slots) as u32;
+
+    state::key(state::NS_RECEIPT, &[&slot.to_be_bytes()])
+}
+
+pub fn head_key() -> [u8; state::KEY_LEN] {
+    state::key(state::NS_RECEIPT_HEAD, &[])
+}
+
+fn next_number() -> u32 {
+    state::load_entry::<u32>(&head_key()).unwrap_or(0)
+}
@@ -0,0 +1,120 @@
+// Referral attribution for the LKS zero-fee hook
+// Partners onboarding users have them attach a "lks/referral" memo with the
+// partner's code. With REFERRALS set, the first sponsored transaction of
+// every account settles its attribution: the referrer when it carries a
+// code, organic (an all-zero code) otherwise. Codes on later transactions
+// are ignored, and every referrer has a counter of the accounts it
+// referred, which off-chain tooling reads from the hook state.
+//
+// State layout:
+//   attribution per account   [code(8)][ledger u32]
+//   counter per referrer      [accounts referred u64]
+
+use lks_hook_sdk::api::ledger_seq;
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::memo::REFERRAL_CODE_LEN;
+use lks_hook_sdk::{log, state};
+use crate::config;
+
+// Attribute the accounts the hook sponsors
+pub const PARAM_REFERRALS: &[u8] = b"REFERRALS";
+
+type Attribution = ([u8; REFERRAL_CODE_LEN], u32);
+
+// Settle the attribution of `account` on a sponsored transaction carrying
+// `code`, unless it already has one
+pub fn attribute(account: &[u8; 20], code: Option<[u8; REFERRAL_CODE_LEN]>) -> Result<(), HookError> {
+    if !config::flag(PARAM_REFERRALS, false) {
+        return Ok(());
+    }
+
+    let key = attribution_key(account);
+    if state::load_entry::<Attribution>(&key).is_some() {
+        return Ok(());
+    }
+
+    let code = code.unwrap_or_default();
+    state::store_entry::<Attribution>(&key, &(code, unsafe { ledger_seq() } as u32))?;
+
+    if code == [0; REFERRAL_CODE_LEN] {
+        return Ok(());
+    }
+
+    let referred = referred(&code) + 1;
+    state::store_entry(&referrer_key(&code), &referred)?;
+    log::info(log::EV_REFERRAL, b"LKS account attributed to referrer", u64::from_be_bytes(code), referred);
+    Ok(())
+}
+
+// Accounts attributed to a referrer
+pub fn referred(code: &[u8; REFERRAL_CODE_LEN]) -> u64 {
+    state::load_entry::<u64>(&referrer_key(code)).unwrap_or(0)
+}
+
+pub fn attribution_key(account: &[u8; 20]) -> [u8; state::KEY_LEN] {
+    state::account_key(state::NS_REFERRAL, account)
+}
+
+fn referrer_key(code: &[u8; REFERRAL_CODE_LEN]) -> [u8; state::KEY_LEN] {
+    state::key(state::NS_REFERRER, &[code])
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::{fields, memo, sim};
+    use crate::fixtures::{lks_payment, MERCHANT, USER};
+    use crate::hook;
+
+    // Run a sponsored payment by `account` with referrals on, carrying a
+    // referral memo with `code` if any
+    fn referred_payment(account: &[u8; 20], code: Option<&[u8]>, state: &mut sim::State, otxn: u8) -> i64 {
+        lks_payment(25, 12);
+        let mut memos = std::vec::Vec::new();
+        if let Some(code) = code {
+            memos.extend_from_slice(&[0xEA, 0x7C, memo::DIRECTIVE_REFERRAL.len() as u8]);
+            memos.extend_from_slice(memo::DIRECTIVE_REFERRAL);
+            memos.extend_from_slice(&[0x7D, code.len() as u8]);
+            memos.extend_from_slice(code);
+            memos.push(0xE1);
+        }
+        memos.push(0xF1);
+        sim::with(|host| {
+            host.state = std::mem::take(state);
+            host.otxn_id = [otxn; 32];
+            host.fields.insert(fields::SF_ACCOUNT, account.to_vec());
+            host.fields.insert(fields::SF_MEMOS, memos);
+            host.hook_params.insert(PARAM_REFERRALS.to_vec(), std::vec![1]);
+        });
+        let result = sim::run(hook);
+        *state = sim::with(|host| host.state.clone());
+        result
+    }
+
+    // The code `account` is attributed to, all zero for organic accounts
+    fn referrer(account: &[u8; 20]) -> Option<std::vec::Vec<u8>> {
+        let key = attribution_key(account);
+        sim::with(|host| host.value(&key).map(|entry| entry[..memo::REFERRAL_CODE_LEN].to_vec()))
+    }
+
+    #[test]
+    fn attributes_accounts_to_referrers_on_first_sponsorship() {
+        let mut state = sim::State::new();
+        assert_eq!(referred_payment(&USER, Some(b"ACME"), &mut state, 1), 0);
+        assert_eq!(referrer(&USER).as_deref(), Some(&b"ACME\0\0\0\0"[..]));
+        assert_eq!(referred(b"ACME\0\0\0\0"), 1);
+        let attributed = log::encode(log::Level::Info, log::EV_REFERRAL, u64::from_be_bytes(*b"ACME\0\0\0\0"), 1);
+        assert!(sim::with(|host| host.traces.iter().any(|(_, data)| data[..] == attributed[..])));
+
+        // Codes on later transactions don't move the attribution
+        assert_eq!(referred_payment(&USER, Some(b"OTHER"), &mut state, 2), 0);
+        assert_eq!(referrer(&USER).as_deref(), Some(&b"ACME\0\0\0\0"[..]));
+        assert_eq!(referred(b"OTHER\0\0\0"), 0);
+
+        // Accounts sponsored without a code are organic for good
+        assert_eq!(referred_payment(&MERCHANT, None, &mut state, 3), 0);
+        assert_eq!(referred_payment(&MERCHANT, Some(b"ACME"), &mut state, 4), 0);
+        assert_eq!(referrer(&MERCHANT).as_deref(), Some(&[0; memo::REFERRAL_CODE_LEN][..]));
+        assert_eq!(referred(b"ACME\0\0\0\0"), 1);
+    }
+}
@@ -0,0 +1,170 @@
+// Account registry for the LKS zero-fee hook
+// Stores per-account flags in hook state so abusive accounts can be blocked
+// and partner accounts force-allowed without redeploying the hook
+
+use lks_hook_sdk::codec;
+use lks_hook_sdk::entry::{Entry, Reader, Writer};
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::state;
+
+// Registry flags
+pub const FLAG_BLOCKED: u8 = 0x01;
+pub const FLAG_ALLOWED: u8 = 0x02;
+
+// Admin command operations
+const OP_ADD: u8 = 1;
+const OP_REMOVE: u8 = 2;
+
+// Longest encoded registry command: op, flags, account (raw, hex or
+// r-address)
+pub const MAX_COMMAND_LEN: usize = 2 + codec::MAX_ACCOUNT_TEXT_LEN;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Standing {
+    Normal,
+    // Partner accounts which skip the sponsorship limits
+    Allowed,
+    // Accounts which never get sponsorship
+    Blocked,
+}
+
+// Registry entries: [flags u8], packed as entry flags, so FLAG_BLOCKED is
+// the first flag and FLAG_ALLOWED the second
+#[derive(Clone, Copy, Default)]
+struct Registration {
+    blocked: bool,
+    allowed: bool,
+}
+
+impl Registration {
+    fn new(flags: u8) -> Registration {
+        Registration { blocked: flags & FLAG_BLOCKED != 0, allowed: flags & FLAG_ALLOWED != 0 }
+    }
+
+    fn flags(self) -> u8 {
+        (self.blocked as u8 * FLAG_BLOCKED) | (self.allowed as u8 * FLAG_ALLOWED)
+    }
+}
+
+impl Entry for Registration {
+    #[inline(always)]
+    fn encode(&self, w: &mut Writer) {
+        w.flags(&[self.blocked, self.allowed]);
+    }
+
+    #[inline(always)]
+    fn decode(r: &mut Reader) -> Option<Registration> {
+        let [blocked, allowed] = r.flags()?;
+        Some(Registration { blocked, allowed })
+    }
+}
+
+fn registration(account: &[u8; 20]) -> Registration {
+    let key = state::account_key(state::NS_REGISTRY, account);
+    state::load_entry(&key).unwrap_or_default()
+}
+
+pub fn flags(account: &[u8; 20]) -> u8 {
+    registration(account).flags()
+}
+
+// Blocking always wins over allowing if both flags are set
+pub fn standing(account: &[u8; 20]) -> Standing {
+    let registration = registration(account);
+
+    if registration.blocked {
+        Standing::Blocked
+    } else if registration.allowed {
+        Standing::Allowed
+    } else {
+        Standing::Normal
+    }
+}
+
+pub fn set_flags(account: &[u8; 20], flags: u8) -> Result<(), HookError> {
+    let key = state::account_key(state::NS_REGISTRY, account);
+
+    // Accounts with no flags left are removed to release their state reserve
+    if flags == 0 {
+        return state::erase(&key);
+    }
+
+    state::store_entry(&key, &Registration::new(flags))
+}
+
+// Apply an encoded registry command: [op, flags, account], the account as
+// 20 bytes, 40 hex digits or an r-address
+// Returns the account's resulting flags and the account
+pub fn apply_command(command: &[u8]) -> Result<(u8, [u8; 20]), HookError> {
+    let account = command.get(2..).and_then(codec::parse_account);
+    let (op, change, account) = match (command.first(), command.get(1), account) {
+        (Some(&op), Some(&change), Some(account)) => (op, change & (FLAG_BLOCKED | FLAG_ALLOWED), account),
+        _ => return Err(HookError::AdminCommandInvalid),
+    };
+
+    if change == 0 {
+        return Err(HookError::AdminCommandInvalid);
+    }
+
+    let current = flags(&account);
+    let updated = match op {
+        OP_ADD => current | change,
+        OP_REMOVE => current & !change,
+        _ => return Err(HookError::AdminCommandInvalid),
+    };
+
+    set_flags(&account, updated)?;
+
+    Ok((updated, account))
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::account::AccountId;
+    use lks_hook_sdk::admin::FOUNDATION_ACCOUNT;
+    use lks_hook_sdk::sim::{self, Outcome};
+    use crate::fixtures::{foundation_invoke, lks_payment, written_fee, USER};
+    use crate::{dispatch, hook};
+
+    #[test]
+    fn declines_blocked_account_with_its_code() {
+        lks_payment(25, 12);
+        let key = state::account_key(state::NS_REGISTRY, &USER);
+        sim::with(|host| {
+            host.state.insert(key.to_vec(), std::vec![FLAG_BLOCKED]);
+        });
+
+        assert_eq!(sim::run(hook), HookError::AccountBlocked.return_value());
+        assert_eq!(written_fee(), 12);
+        assert!(matches!(sim::with(|host| host.outcome.clone()), Some(Outcome::Accepted(_))));
+    }
+
+    #[test]
+    fn admin_commands_name_accounts_by_address() {
+        let address = AccountId::new(USER).to_r_address().into_bytes();
+        let mut block = std::vec![dispatch::OP_REGISTRY, 1, FLAG_BLOCKED];
+        block.extend_from_slice(&address);
+
+        foundation_invoke(FOUNDATION_ACCOUNT.as_bytes());
+        sim::with(|host| host.otxn_params.insert(dispatch::PARAM_COMMAND.to_vec(), block.clone()));
+        assert_eq!(sim::run(hook), 0);
+        assert_eq!(flags(&USER), FLAG_BLOCKED);
+
+        // Hex works too, in either case
+        let mut allow = std::vec![2, FLAG_BLOCKED];
+        allow.extend_from_slice(AccountId::new(USER).to_hex().to_lowercase().as_bytes());
+        sim::with(|host| host.otxn_params.insert(dispatch::PARAM_REGISTRY.to_vec(), allow));
+        sim::with(|host| host.otxn_params.remove(dispatch::PARAM_COMMAND));
+        assert_eq!(sim::run(hook), 0);
+        assert_eq!(flags(&USER), 0);
+
+        // A mistyped address fails its checksum rather than naming another
+        // account
+        let last = block.len() - 1;
+        block[last] = if block[last] == b'r' { b'p' } else { b'r' };
+        foundation_invoke(FOUNDATION_ACCOUNT.as_bytes());
+        sim::with(|host| host.otxn_params.insert(dispatch::PARAM_COMMAND.to_vec(), block));
+        assert_eq!(sim::run(hook), HookError::AdminCommandInvalid.return_value());
+    }
+}
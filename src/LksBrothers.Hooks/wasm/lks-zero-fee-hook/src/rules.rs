@@ -0,0 +1,613 @@
+// Sponsorship rules for the LKS zero-fee hook
+// Whether a transaction is sponsored, and how much of its fee, is decided by
+// an ordered list of rules run by one dispatcher. Each rule looks at the
+// transaction and the fee sponsored so far and returns a verdict:
+//   Allow        no objection
+//   Deny(error)  the transaction pays its own fee, declined with `error`
+//   Modify(fee)  sponsor at most `fee` drops
+// The first Deny ends the evaluation. A Modify can only lower the fee.
+//
+// Rules decide; sponsor() applies the decision once every rule allowed it
+// (fee, counters, receipts). Three rules record what they checked: abuse
+// scoring records the account's activity, declined or not, the limit rules
+// keep the counters they read for sponsor() to write back, and the burst
+// limiter, last so no later rule declines a transaction it counted, takes
+// from the account's bucket.
+//
+// The rules run in three stages. ASSET_RULES decide whether an amount field
+// holds a sponsored currency from its registered issuer and price it; the
+// handlers run them on the fields they route on, before the transaction's
+// details are read. FEE_RULES settle the fee and are applied to retried
+// transactions too, so a retry gets the same fee. LIMIT_RULES guard the
+// foundation's limits, which a retried transaction already passed.
+
+use lks_hook_sdk::amount::{self, Amount};
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::fields::{self, FieldId};
+use lks_hook_sdk::memo::{self, Directives};
+use lks_hook_sdk::{denylist, kyc, log};
+use crate::currency::{self, Token};
+use crate::registry::{self, Standing};
+use crate::voucher::Voucher;
+use crate::{abuse, breaker, burst, config, exchange, limits, onboarding, policy, staking, strict};
+
+pub enum Verdict {
+    Allow,
+    Deny(HookError),
+    Modify(u64),
+}
+
+#[derive(Clone, Copy)]
+pub enum Rule {
+    // Only sponsored currencies are sponsored
+    Currency,
+    // A sponsored currency code from any other issuer is not the token
+    Issuer,
+    // Accounts on the emergency denylist are never sponsored
+    Denylist,
+    // Integrators opting out with a memo directive pay their own fees
+    OptOut,
+    // No sponsorship while the foundation reserve runs low
+    Breaker,
+    // Blocked accounts pay their own fees; partners are let through
+    Registry,
+    // KYC-only deployments only sponsor verified accounts
+    Kyc,
+    // Fees above the cap, scaled by the signers, aren't sponsored
+    FeeCap,
+    // The amount and stake tiers set the sponsored share
+    Tier,
+    // The user always pays the co-pay
+    CoPay,
+    // Destinations may require a tag or invoice ID
+    Exchange,
+    // Unvetted transactions are scored for farming
+    Abuse,
+    // The foundation sponsors at most BUDGET drops per epoch
+    Budget,
+    // Per-account and per-pair caps, for accounts not exempt from them
+    RateLimit,
+    // Back-to-back sponsorships per account, for accounts not exempt
+    Burst,
+}
+
+pub const ASSET_RULES: [Rule; 2] = [Rule::Currency, Rule::Issuer];
+pub const FEE_RULES: [Rule; 8] =
+    [Rule::Denylist, Rule::OptOut, Rule::Breaker, Rule::Registry, Rule::Kyc, Rule::FeeCap, Rule::Tier, Rule::CoPay];
+pub const LIMIT_RULES: [Rule; 5] = [Rule::Exchange, Rule::Abuse, Rule::Budget, Rule::RateLimit, Rule::Burst];
+
+// Amount fields run through the asset stage per hook execution: an offer in
+// maker mode has both sides and Amount checked, then both sides priced
+const MAX_ASSETS: usize = 5;
+
+// Rules evaluated per hook execution: every stage, plus the guard check
+// ending each stage's loop but the last
+const MAX_RULES: u32 = (MAX_ASSETS * (ASSET_RULES.len() + 1) + FEE_RULES.len() + 1 + LIMIT_RULES.len()) as u32;
+
+// An amount field of the originating transaction, as the asset rules see it
+pub struct Asset {
+    pub field: FieldId,
+    // None when the field holds no issued amount
+    pub amount: Option<Amount>,
+    // Set by the currency rule
+    pub token: Option<Token>,
+}
+
+impl Asset {
+    // Missing and native amounts hold no sponsored currency. Unparseable ones
+    // don't either, unless strict mode rejects them.
+    pub fn read(field: FieldId) -> Result<Asset, HookError> {
+        let amount = match fields::read_amount(field) {
+            Ok(amount) => amount,
+            Err(_) => {
+                strict::unreadable(field)?;
+                None
+            }
+        };
+
+        Ok(Asset { field, amount, token: None })
+    }
+}
+
+// A transaction up for sponsorship, as the rules see it
+pub struct Sponsorship {
+    // The amount field up for the asset stage
+    pub asset: Option<Asset>,
+    pub source: [u8; 20],
+    // LKS value moved, if any
+    pub amount: Option<u64>,
+    pub original_fee: u64,
+    // Fee sponsored so far
+    pub fee: u64,
+    pub directives: Directives,
+    pub standing: Standing,
+    // Transactions sponsored on onboarding so far, None once used up
+    pub onboarding: Option<u64>,
+    // Read for the limit stage only
+    pub destination: Option<[u8; 20]>,
+    pub voucher: Option<Voucher>,
+    pub usage: limits::Usage,
+}
+
+impl Sponsorship {
+    // The originating transaction moving `amount` LKS, in limit `epoch`, with
+    // its full fee up for sponsorship
+    pub fn read(amount: Option<u64>, epoch: u32) -> Result<Sponsorship, HookError> {
+        // Memos that fail to decode carry no directives
+        let directives = memo::read_directives().unwrap_or_default();
+        let original_fee = fields::read_fee()?;
+        let source = fields::read_account()?;
+
+        Ok(Sponsorship {
+            asset: None,
+            source,
+            amount,
+            original_fee,
+            fee: original_fee,
+            directives,
+            standing: registry::standing(&source),
+            onboarding: onboarding::check(&source),
+            destination: None,
+            voucher: None,
+            usage: limits::Usage::new(epoch),
+        })
+    }
+
+    // The amount in `field`, up for the asset stage alone; nothing else of the
+    // transaction is read
+    pub fn of_asset(field: FieldId) -> Result<Sponsorship, HookError> {
+        Ok(Sponsorship {
+            asset: Some(Asset::read(field)?),
+            source: [0u8; 20],
+            amount: None,
+            original_fee: 0,
+            fee: 0,
+            directives: Directives::default(),
+            standing: Standing::Normal,
+            onboarding: None,
+            destination: None,
+            voucher: None,
+            usage: limits::Usage::new(0),
+        })
+    }
+
+    // Registry partners and transactions carrying a partner voucher
+    pub fn vetted(&self) -> bool {
+        self.standing == Standing::Allowed || self.voucher.is_some()
+    }
+
+    // Vetted transactions and accounts still onboarding skip the caps;
+    // onboarding is what farming accounts are after, so it isn't vetted
+    pub fn exempt(&self) -> bool {
+        self.vetted() || self.onboarding.is_some()
+    }
+}
+
+// Rules doing more than a comparison are kept out of line, so this match only
+// dispatches. Metering charges a wasm block in full on entering it, and a
+// match with every rule inlined would be charged for all of them per rule.
+impl Rule {
+    pub fn evaluate(self, tx: &mut Sponsorship) -> Verdict {
+        match self {
+            Rule::Currency => currency(tx),
+            Rule::Issuer => issuer(tx),
+            Rule::Denylist => denylist(tx),
+            Rule::OptOut => opt_out(tx),
+            Rule::Breaker => breaker(),
+            Rule::Registry => registry(tx),
+            Rule::Kyc => kyc(tx),
+            Rule::FeeCap => fee_cap(tx),
+            Rule::Tier => tier(tx),
+            Rule::CoPay => co_pay(tx),
+            Rule::Exchange => exchange(tx),
+            Rule::Abuse => abuse(tx),
+            Rule::Budget => budget(tx),
+            Rule::RateLimit => rate_limit(tx),
+            Rule::Burst => burst(tx),
+        }
+    }
+}
+
+// Run `rules` in order on `tx`, leaving the fee they agree on in tx.fee
+pub fn evaluate(rules: &[Rule], tx: &mut Sponsorship) -> Result<(), HookError> {
+    guarded_loop!(i in 0, rules.len(); max MAX_RULES; {
+        if let Some(rule) = rules.get(i) {
+            match rule.evaluate(tx) {
+                Verdict::Allow => {}
+                Verdict::Deny(err) => return Err(err),
+                Verdict::Modify(fee) => tx.fee = tx.fee.min(fee),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+// The value of the sponsored token in `field`, in its smallest unit
+// (micro-LKS for LKS COIN). Err with the asset rules' decline when the field
+// holds none, or their rejection in strict mode.
+pub fn asset(field: FieldId) -> Result<u64, HookError> {
+    let mut tx = Sponsorship::of_asset(field)?;
+    evaluate(&ASSET_RULES, &mut tx)?;
+    Ok(tx.amount.unwrap_or(0))
+}
+
+fn verdict(result: Result<(), HookError>) -> Verdict {
+    match result {
+        Ok(()) => Verdict::Allow,
+        Err(err) => Verdict::Deny(err),
+    }
+}
+
+#[inline(never)]
+fn currency(tx: &mut Sponsorship) -> Verdict {
+    let asset = match &mut tx.asset {
+        Some(asset) => asset,
+        None => return Verdict::Allow,
+    };
+    let token = match &asset.amount {
+        Some(Amount::Issued { currency, .. }) => currency::lookup(currency),
+        _ => None,
+    };
+    if token.is_none() {
+        return Verdict::Deny(HookError::WrongCurrency);
+    }
+
+    asset.token = token;
+    Verdict::Allow
+}
+
+// Malformed values of the token price as zero, unless strict mode rejects them
+#[inline(never)]
+fn issuer(tx: &mut Sponsorship) -> Verdict {
+    let (field, value, issuer, token) = match &tx.asset {
+        Some(Asset { field, amount: Some(Amount::Issued { value, issuer, .. }), token: Some(token) }) =>
+            (*field, value, issuer, token),
+        _ => return Verdict::Allow,
+    };
+    if !token.issuer.matches(issuer) {
+        return match strict::malformed(field, amount::ISSUER_OFFSET, HookError::IssuerMismatch) {
+            Ok(()) => Verdict::Deny(HookError::WrongIssuer),
+            Err(err) => Verdict::Deny(err),
+        };
+    }
+
+    tx.amount = match amount::checked_issued_value(value, token.decimals) {
+        Some(units) => Some(units),
+        None => match strict::malformed(field, 0, HookError::AmountValueInvalid) {
+            Ok(()) => Some(0),
+            Err(err) => return Verdict::Deny(err),
+        },
+    };
+    Verdict::Allow
+}
+
+#[inline(never)]
+fn denylist(tx: &Sponsorship) -> Verdict {
+    if denylist::is_banned(&tx.source) {
+        return Verdict::Deny(HookError::Denylisted);
+    }
+    Verdict::Allow
+}
+
+fn opt_out(tx: &Sponsorship) -> Verdict {
+    if tx.directives.no_sponsor {
+        return Verdict::Deny(HookError::OptedOut);
+    }
+    Verdict::Allow
+}
+
+#[inline(never)]
+fn breaker() -> Verdict {
+    verdict(breaker::check())
+}
+
+fn registry(tx: &Sponsorship) -> Verdict {
+    if tx.standing == Standing::Blocked {
+        return Verdict::Deny(HookError::AccountBlocked);
+    }
+    Verdict::Allow
+}
+
+#[inline(never)]
+fn kyc(tx: &Sponsorship) -> Verdict {
+    if config::flag(config::PARAM_KYC_ONLY, false) && !kyc::is_verified(&tx.source) {
+        return Verdict::Deny(HookError::NotVerified);
+    }
+    Verdict::Allow
+}
+
+// During fee escalation the open-ledger fee can spike far above normal;
+// those transactions pay their own fee so the foundation isn't drained.
+// Multi-signed transactions pay the base fee once more per signer, so the
+// cap scales with them.
+#[inline(never)]
+fn fee_cap(tx: &Sponsorship) -> Verdict {
+    let signers = match fields::signer_count() {
+        Ok(signers) => signers as u64,
+        Err(err) => return Verdict::Deny(err),
+    };
+    let max_fee = config::u64_param(config::PARAM_MAX_FEE, config::DEFAULT_MAX_FEE)
+        .saturating_mul(1 + signers);
+    if tx.original_fee > max_fee {
+        log::debug(log::EV_FEE_CAP, b"LKS fee above sponsorship cap", tx.original_fee, max_fee);
+        return Verdict::Deny(HookError::FeeCapExceeded);
+    }
+
+    Verdict::Allow
+}
+
+// Transactions without an amount are fully sponsored before scaling by the
+// sender's stake. Accounts still onboarding are fully sponsored.
+#[inline(never)]
+fn tier(tx: &Sponsorship) -> Verdict {
+    let amount_share = match tx.amount {
+        Some(value) if tx.onboarding.is_none() => policy::sponsored_share(value),
+        _ => policy::FULL_SHARE,
+    };
+    let share = match staking::staked_balance(&tx.source) {
+        Some(stake) if tx.onboarding.is_none() => policy::scaled_share(amount_share, policy::stake_share(stake)),
+        _ => amount_share,
+    };
+    if share == 0 {
+        return Verdict::Deny(HookError::TierNotSponsored);
+    }
+
+    Verdict::Modify(policy::sponsored_fee(tx.fee, share))
+}
+
+// The co-pay is taken from the original fee, so it holds whatever share the
+// tiers left with the user; accounts still onboarding pay it too
+#[inline(never)]
+fn co_pay(tx: &Sponsorship) -> Verdict {
+    let user_fee = policy::co_pay(tx.original_fee);
+    if user_fee == 0 {
+        return Verdict::Allow;
+    }
+    if user_fee >= tx.original_fee {
+        return Verdict::Deny(HookError::CoPayCoversFee);
+    }
+
+    Verdict::Modify(tx.original_fee - user_fee)
+}
+
+#[inline(never)]
+fn exchange(tx: &Sponsorship) -> Verdict {
+    match &tx.destination {
+        Some(destination) => verdict(exchange::check(destination)),
+        None => Verdict::Allow,
+    }
+}
+
+#[inline(never)]
+fn abuse(tx: &Sponsorship) -> Verdict {
+    if tx.vetted() {
+        return Verdict::Allow;
+    }
+    verdict(abuse::check(&tx.source, tx.amount))
+}
+
+#[inline(never)]
+fn budget(tx: &mut Sponsorship) -> Verdict {
+    verdict(limits::check_budget(&mut tx.usage, tx.fee))
+}
+
+#[inline(never)]
+fn rate_limit(tx: &mut Sponsorship) -> Verdict {
+    if tx.exempt() {
+        return Verdict::Allow;
+    }
+    verdict(limits::check_caps(&mut tx.usage, &tx.source, tx.destination.as_ref()))
+}
+
+#[inline(never)]
+fn burst(tx: &Sponsorship) -> Verdict {
+    if tx.exempt() {
+        return Verdict::Allow;
+    }
+    verdict(burst::check(&tx.source))
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::admin::FOUNDATION_ACCOUNT;
+    use lks_hook_sdk::{sim, state};
+    use crate::fixtures::{lks_payment, written_fee, MERCHANT, USER};
+    use crate::{hook, TX_TYPE_INVOKE, TX_TYPE_PAYMENT};
+
+    fn param(name: &[u8], value: &[u8]) {
+        sim::with(|host| host.hook_params.insert(name.to_vec(), value.to_vec()));
+    }
+
+    #[test]
+    fn sponsorship_rules_decide_one_at_a_time() {
+
+        lks_payment(25, 12);
+        let mut tx = Sponsorship::read(Some(25_000_000), 0).unwrap();
+        let rules =
+            [Rule::OptOut, Rule::Registry, Rule::Kyc, Rule::FeeCap, Rule::Tier, Rule::CoPay, Rule::Budget, Rule::RateLimit];
+        for rule in rules {
+            assert!(matches!(rule.evaluate(&mut tx), Verdict::Allow | Verdict::Modify(12)));
+        }
+
+        tx.directives.no_sponsor = true;
+        assert!(matches!(Rule::OptOut.evaluate(&mut tx), Verdict::Deny(HookError::OptedOut)));
+
+        tx.standing = registry::Standing::Blocked;
+        assert!(matches!(Rule::Registry.evaluate(&mut tx), Verdict::Deny(HookError::AccountBlocked)));
+
+        param(config::PARAM_KYC_ONLY, &[1]);
+        assert!(matches!(Rule::Kyc.evaluate(&mut tx), Verdict::Deny(HookError::NotVerified)));
+
+        tx.original_fee = config::DEFAULT_MAX_FEE + 1;
+        assert!(matches!(Rule::FeeCap.evaluate(&mut tx), Verdict::Deny(HookError::FeeCapExceeded)));
+
+        // Half the fee of payments up to 30 LKS
+        let mut tiers = 30_000_000u64.to_be_bytes().to_vec();
+        tiers.push(50);
+        param(policy::PARAM_TIERS, &tiers);
+        assert!(matches!(Rule::Tier.evaluate(&mut tx), Verdict::Modify(6)));
+        tx.amount = Some(40_000_000);
+        assert!(matches!(Rule::Tier.evaluate(&mut tx), Verdict::Deny(HookError::TierNotSponsored)));
+
+        // A quarter of the fee, at least 4 drops, stays with the user
+        param(policy::PARAM_COPAY_SHARE, &25u64.to_be_bytes());
+        param(policy::PARAM_COPAY_FLOOR, &4u64.to_be_bytes());
+        tx.original_fee = 12;
+        assert!(matches!(Rule::CoPay.evaluate(&mut tx), Verdict::Modify(8)));
+        tx.original_fee = 4;
+        assert!(matches!(Rule::CoPay.evaluate(&mut tx), Verdict::Deny(HookError::CoPayCoversFee)));
+
+        param(limits::PARAM_BUDGET, &11u64.to_be_bytes());
+        assert!(matches!(Rule::Budget.evaluate(&mut tx), Verdict::Deny(HookError::BudgetExceeded)));
+
+        // Registry partners are exempt from the caps
+        param(limits::PARAM_ACCOUNT_CAP, &0u64.to_be_bytes());
+        assert!(matches!(Rule::RateLimit.evaluate(&mut tx), Verdict::Deny(HookError::RateLimited)));
+        tx.standing = registry::Standing::Allowed;
+        assert!(matches!(Rule::RateLimit.evaluate(&mut tx), Verdict::Allow));
+    }
+
+    #[test]
+    fn rule_pipeline_stops_at_the_first_denial() {
+        lks_payment(25, 12);
+        let mut tiers = 30_000_000u64.to_be_bytes().to_vec();
+        tiers.push(50);
+        param(policy::PARAM_TIERS, &tiers);
+
+        let mut tx = Sponsorship::read(Some(25_000_000), 0).unwrap();
+        evaluate(&FEE_RULES, &mut tx).unwrap();
+        assert_eq!((tx.original_fee, tx.fee), (12, 6));
+
+        // A rule can't raise the fee another rule lowered
+        evaluate(&[Rule::Tier], &mut tx).unwrap();
+        assert_eq!(tx.fee, 3);
+
+        // Rules after a denial don't run: the blocked account's budget
+        // check never happens
+        param(limits::PARAM_BUDGET, &0u64.to_be_bytes());
+        tx.standing = registry::Standing::Blocked;
+        let pipeline = [Rule::Registry, Rule::Budget];
+        assert_eq!(evaluate(&pipeline, &mut tx), Err(HookError::AccountBlocked));
+    }
+
+    #[test]
+    fn kyc_only_sponsors_verified_accounts() {
+        lks_payment(25, 12);
+        sim::with(|host| host.hook_params.insert(config::PARAM_KYC_ONLY.to_vec(), std::vec![1]));
+        assert_eq!(sim::run(hook), HookError::NotVerified.return_value());
+        assert_eq!(written_fee(), 12);
+
+        // The foundation verifies the account until ledger 1001
+        let mut command = std::vec![kyc::OP_VERIFY];
+        command.extend_from_slice(&USER);
+        command.extend_from_slice(&1001u32.to_be_bytes());
+        sim::with(|host| {
+            host.tx_type = TX_TYPE_INVOKE;
+            host.fields.insert(fields::SF_ACCOUNT, FOUNDATION_ACCOUNT.as_bytes().to_vec());
+            host.otxn_params.insert(kyc::PARAM_COMMAND.to_vec(), command);
+        });
+        assert_eq!(sim::run(hook), 0);
+
+        sim::with(|host| {
+            host.tx_type = TX_TYPE_PAYMENT;
+            host.fields.insert(fields::SF_ACCOUNT, USER.to_vec());
+            host.otxn_params.clear();
+        });
+        assert_eq!(sim::run(hook), 0);
+        assert_eq!(written_fee(), 0);
+
+        // Expired markers no longer count
+        sim::with(|host| {
+            host.ledger_seq = 1001;
+            host.otxn_id = [0x01; 32];
+            host.fields.insert(fields::SF_FEE, amount::encode_native(12).to_vec());
+        });
+        assert_eq!(sim::run(hook), HookError::NotVerified.return_value());
+        assert_eq!(written_fee(), 12);
+    }
+
+    #[test]
+    fn kyc_only_accepts_markers_of_the_compliance_hook() {
+        const COMPLIANCE_HOOK: [u8; 20] = [0xCD; 20];
+        const COMPLIANCE_NAMESPACE: [u8; 32] = [0x4B; 32];
+
+        lks_payment(25, 12);
+        sim::with(|host| {
+            host.hook_params.insert(config::PARAM_KYC_ONLY.to_vec(), std::vec![1]);
+            host.hook_params.insert(kyc::PARAM_KYC_ACCOUNT.to_vec(), COMPLIANCE_HOOK.to_vec());
+            host.hook_params.insert(kyc::PARAM_KYC_NAMESPACE.to_vec(), COMPLIANCE_NAMESPACE.to_vec());
+        });
+        assert_eq!(sim::run(hook), HookError::NotVerified.return_value());
+
+        // The compliance hook verified the account, until ledger 1001
+        let key = state::account_key(state::NS_KYC, &USER).to_vec();
+        sim::with(|host| {
+            let entry = (COMPLIANCE_HOOK.to_vec(), COMPLIANCE_NAMESPACE.to_vec(), key);
+            host.foreign_state.insert(entry, std::vec![state::VERSION, 0, 0, 0x03, 0xE9]);
+            host.otxn_id = [0x01; 32];
+        });
+        assert_eq!(sim::run(hook), 0);
+        assert_eq!(written_fee(), 0);
+
+        sim::with(|host| {
+            host.ledger_seq = 1001;
+            host.otxn_id = [0x02; 32];
+            host.fields.insert(fields::SF_FEE, amount::encode_native(12).to_vec());
+        });
+        assert_eq!(sim::run(hook), HookError::NotVerified.return_value());
+    }
+
+    #[test]
+    fn withholds_sponsorship_from_denylisted_accounts() {
+        const SECURITY_HOOK: [u8; 20] = [0x5E; 20];
+        const NAMESPACE: [u8; 32] = [0x5F; 32];
+
+        lks_payment(25, 12);
+        let ban = (SECURITY_HOOK.to_vec(), NAMESPACE.to_vec(), state::account_key(state::NS_DENYLIST, &USER).to_vec());
+        sim::with(|host| {
+            host.hook_params.insert(denylist::PARAM_DENY_ACCOUNT.to_vec(), SECURITY_HOOK.to_vec());
+            host.hook_params.insert(denylist::PARAM_DENY_NAMESPACE.to_vec(), NAMESPACE.to_vec());
+            host.foreign_state.insert(ban, std::vec![state::VERSION, 0, 0, 3, 0xE7]);
+        });
+
+        // Declined ahead of every other rule, retries included, leaving no
+        // trace in state
+        assert_eq!(sim::run(hook), HookError::Denylisted.return_value());
+        assert_eq!(written_fee(), 12);
+        assert!(sim::with(|host| host.state.is_empty()));
+        let matched = log::encode(log::Level::Warn, log::EV_DENYLISTED, 999, 1);
+        assert!(sim::with(|host| host.traces.iter().any(|(_, data)| data[..] == matched[..])));
+
+        // A list that can't be read only stops sponsorship when failing closed
+        sim::with(|host| host.foreign_fails = true);
+        assert_eq!(sim::run(hook), 0);
+        sim::with(|host| host.hook_params.insert(denylist::PARAM_FAIL_CLOSED.to_vec(), std::vec![1]));
+        assert_eq!(sim::run(hook), HookError::Denylisted.return_value());
+    }
+
+    #[test]
+    fn multi_signed_fee_cap_scales_with_signers() {
+        // Two signers: Account, SigningPubKey and TxnSignature each
+        let mut signers = std::vec::Vec::new();
+        for account in [USER, MERCHANT] {
+            signers.extend_from_slice(&[0xE0, 0x10, 0x81, 0x14]);
+            signers.extend_from_slice(&account);
+            signers.extend_from_slice(&[0x73, 0x21]);
+            signers.extend_from_slice(&[0x02; 33]);
+            signers.extend_from_slice(&[0x74, 0x03, 0x30, 0x01, 0x00]);
+            signers.push(0xE1);
+        }
+        signers.push(0xF1);
+        assert_eq!(fields::count_signers(&signers), Ok(2));
+
+        lks_payment(25, 30);
+        sim::with(|host| {
+            host.hook_params.insert(config::PARAM_MAX_FEE.to_vec(), 10u64.to_be_bytes().to_vec());
+            host.fields.insert(fields::SF_SIGNERS, signers);
+        });
+        assert_eq!(sim::run(hook), 0);
+        assert_eq!(written_fee(), 0);
+    }
+}
@@ -0,0 +1,86 @@
+// Audit sampling for the LKS zero-fee hook
+// Tracing every sponsored transaction in full detail exhausts the trace
+// budget, so only a sample is: AUDITBPS basis points of the sponsored
+// transactions (1% by default) trace the sponsorship message with the
+// account and an EV_AUDIT event with the original fee. The others only
+// trace the fixed EV_SPONSORED event, which is enough to count them.
+//
+// The sample is drawn from the ledger sequence and the transaction hash, so
+// every node traces the same transactions and a retry in the same ledger
+// makes the same draw.
+
+use lks_hook_sdk::api::{ledger_seq, otxn_id};
+use lks_hook_sdk::bytes;
+use crate::config;
+use crate::dedup::TX_ID_LEN;
+
+// Sponsored transactions traced in full, in basis points
+pub const PARAM_SAMPLE_RATE: &[u8] = b"AUDITBPS";
+pub const DEFAULT_SAMPLE_RATE: u64 = 100;
+pub const FULL_RATE: u64 = 10_000;
+
+// Whether the originating transaction is traced in full
+pub fn sampled() -> bool {
+    let rate = config::u64_param(PARAM_SAMPLE_RATE, DEFAULT_SAMPLE_RATE);
+    if rate == 0 {
+        return false;
+    }
+    if rate >= FULL_RATE {
+        return true;
+    }
+
+    let mut id = [0u8; TX_ID_LEN];
+    if unsafe { otxn_id(id.as_mut_ptr(), id.len() as i32, 0) } != TX_ID_LEN as i32 {
+        return false;
+    }
+
+    draw(unsafe { ledger_seq() }, &id) % FULL_RATE < rate
+}
+
+// Mix the ledger sequence into the start of the transaction hash and spread
+// the result over the whole range (the splitmix64 finalizer)
+pub fn draw(ledger: u64, id: &[u8; TX_ID_LEN]) -> u64 {
+    let mut x = bytes::u64_at(id, 0).unwrap_or(0) ^ ledger.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::{log, sim};
+    use crate::fixtures::{lks_payment, traced};
+    use crate::{dedup, hook, TX_TYPE_PAYMENT};
+
+    #[test]
+    fn traces_a_deterministic_sample_in_full() {
+        let audit = log::encode(log::Level::Info, log::EV_AUDIT, 12, u64::from_be_bytes([0xAA; 8]));
+        let sponsored = log::encode(log::Level::Info, log::EV_SPONSORED, 12, TX_TYPE_PAYMENT as u64);
+
+        lks_payment(25, 12);
+        let all = FULL_RATE.to_be_bytes().to_vec();
+        sim::with(|host| host.hook_params.insert(PARAM_SAMPLE_RATE.to_vec(), all));
+        assert_eq!(sim::run(hook), 0);
+        assert!(traced(&audit) && traced(&sponsored));
+
+        // Outside the sample only the fixed event is traced
+        lks_payment(25, 12);
+        sim::with(|host| host.hook_params.insert(PARAM_SAMPLE_RATE.to_vec(), 0u64.to_be_bytes().to_vec()));
+        assert_eq!(sim::run(hook), 0);
+        assert!(!traced(&audit) && traced(&sponsored));
+
+        // By default about 1% of transactions are drawn, the same on every
+        // draw
+        let drawn = (0..10_000u64)
+            .filter(|n| {
+                let mut id = [0u8; dedup::TX_ID_LEN];
+                id[..8].copy_from_slice(&n.to_be_bytes());
+                draw(1000, &id) % FULL_RATE < DEFAULT_SAMPLE_RATE
+            })
+            .count();
+        assert!((80..=120).contains(&drawn), "{drawn} drawn");
+        assert_eq!(draw(1000, &[7; 32]), draw(1000, &[7; 32]));
+        assert_ne!(draw(1000, &[7; 32]), draw(1001, &[7; 32]));
+    }
+}
@@ -0,0 +1,293 @@
+// Batched reimbursement settlement for the LKS zero-fee hook
+// Instead of one reimbursement per sponsored transaction, sponsored fees are
+// summed in hook state and paid out in a single Payment emitted from the hook
+// account every SETTLEN ledgers. A failed emit leaves the sum in the
+// accumulator, so it carries over into the next settlement.
+//
+// Emitted settlements stay in flight until the ledger reports back through
+// cbak. A settlement that failed on ledger is returned to the accumulator
+// and retried with the next sponsored transaction; after SETTLRETRY
+// failures in a row it is flagged and retried only at the normal interval.
+//
+// Accumulator entry: [pending drops u64][last settlement ledger u32]
+//                    [in-flight drops u64][consecutive failures u8]
+
+use lks_hook_sdk::amount;
+use lks_hook_sdk::bytes;
+use lks_hook_sdk::api::{emit, etxn_details, etxn_fee_base, etxn_reserve, hook_account, ledger_seq};
+use lks_hook_sdk::amount::Amount;
+use lks_hook_sdk::entry::{Entry, Reader, Writer};
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::{fields, log, state};
+use crate::config;
+
+// Ledgers between settlements
+pub const PARAM_SETTLEMENT_LEDGERS: &[u8] = b"SETTLEN";
+pub const DEFAULT_SETTLEMENT_LEDGERS: u64 = 256;
+
+// Account receiving the settlement payments; nothing is emitted until set
+pub const PARAM_SETTLEMENT_DESTINATION: &[u8] = b"SETTLDST";
+
+// Failed settlements in a row before they are flagged
+pub const PARAM_SETTLEMENT_RETRIES: &[u8] = b"SETTLRETRY";
+pub const DEFAULT_SETTLEMENT_RETRIES: u64 = 3;
+
+// Emitted transactions may only be applied within this many ledgers
+const LEDGER_WINDOW: u32 = 5;
+
+// Serialized payment without EmitDetails, and the largest EmitDetails object
+const PAYMENT_LEN: usize = 122;
+const MAX_EMIT_DETAILS_LEN: usize = 138;
+
+// Offset of the Fee amount in the serialized payment
+const FEE_OFFSET: usize = 35;
+
+const TX_ID_LEN: usize = 32;
+
+#[derive(Clone, Copy, Default)]
+struct Accumulator {
+    pending: u64,
+    last_ledger: u32,
+    in_flight: u64,
+    failures: u8,
+}
+
+impl Entry for Accumulator {
+    #[inline(always)]
+    fn encode(&self, w: &mut Writer) {
+        w.u64(self.pending).u32(self.last_ledger).u64(self.in_flight).u8(self.failures);
+    }
+
+    #[inline(always)]
+    fn decode(r: &mut Reader) -> Option<Accumulator> {
+        Some(Accumulator { pending: r.u64()?, last_ledger: r.u32()?, in_flight: r.u64()?, failures: r.u8()? })
+    }
+}
+
+// Add a sponsored fee to the accumulator and settle if one is due
+pub fn accumulate(fee: u64) -> Result<(), HookError> {
+    let mut acc = load();
+    acc.pending = acc.pending.saturating_add(fee);
+    let ledger = unsafe { ledger_seq() } as u32;
+
+    let interval = config::u64_param(PARAM_SETTLEMENT_LEDGERS, DEFAULT_SETTLEMENT_LEDGERS);
+    let due = (ledger.saturating_sub(acc.last_ledger) as u64) >= interval;
+
+    let mut destination = [0u8; 20];
+    let configured = config::bytes_param(PARAM_SETTLEMENT_DESTINATION, &mut destination) == 20;
+
+    if due && configured && acc.pending > 0 {
+        match settle(&destination, acc.pending, ledger) {
+            Ok(()) => {
+                log::info(log::EV_SETTLED, b"LKS settlement emitted", acc.pending, 0);
+                acc.in_flight = acc.in_flight.saturating_add(acc.pending);
+                acc.pending = 0;
+                acc.last_ledger = ledger;
+            }
+            Err(err) => {
+                // Keep the sum for the next settlement
+                log::warn(log::EV_SETTLE_FAILED, err.message(), acc.pending, err.code() as u64);
+            }
+        }
+    }
+
+    store(&acc)
+}
+
+// Account for the outcome of an emitted settlement, reported through cbak
+// while the emitted Payment is the originating transaction
+pub fn on_callback(failed: bool) -> Result<(), HookError> {
+    let drops = match fields::read_amount(fields::SF_AMOUNT)? {
+        Some(Amount::Native(drops)) => drops,
+        _ => return Ok(()),
+    };
+
+    let mut acc = load();
+    acc.in_flight = acc.in_flight.saturating_sub(drops);
+
+    if !failed {
+        acc.failures = 0;
+        log::info(log::EV_SETTLE_CONFIRMED, b"LKS settlement confirmed", drops, 0);
+        return store(&acc);
+    }
+
+    acc.pending = acc.pending.saturating_add(drops);
+    acc.failures = acc.failures.saturating_add(1);
+
+    let retries = config::u64_param(PARAM_SETTLEMENT_RETRIES, DEFAULT_SETTLEMENT_RETRIES);
+    if (acc.failures as u64) < retries {
+        // Due again with the next sponsored transaction
+        acc.last_ledger = 0;
+        log::warn(log::EV_SETTLE_RETRY, b"LKS settlement failed, retrying", drops, acc.failures as u64);
+    } else {
+        log::error(log::EV_SETTLE_FLAGGED, b"LKS settlement failing repeatedly", acc.pending,
+                   acc.failures as u64);
+    }
+
+    store(&acc)
+}
+
+// Emit a Payment of `drops` from the hook account to `destination`
+fn settle(destination: &[u8; 20], drops: u64, ledger: u32) -> Result<(), HookError> {
+    let mut account = [0u8; 20];
+    if unsafe { hook_account(account.as_mut_ptr()) } != 20 {
+        return Err(HookError::AccountReadFailed);
+    }
+
+    if unsafe { etxn_reserve(1) } != 1 {
+        return Err(HookError::EmitFailed);
+    }
+
+    let mut tx = [0u8; PAYMENT_LEN + MAX_EMIT_DETAILS_LEN];
+    let mut len = encode_payment(&mut tx, &account, destination, drops, ledger);
+
+    let details_len = unsafe {
+        etxn_details(tx.as_mut_ptr().add(len), MAX_EMIT_DETAILS_LEN as i32)
+    };
+    if details_len <= 0 || details_len as usize > MAX_EMIT_DETAILS_LEN {
+        return Err(HookError::EmitFailed);
+    }
+    len += details_len as usize;
+
+    // The fee depends on the final size, so it is patched in last
+    let fee = unsafe { etxn_fee_base(tx.as_ptr(), len as i32) };
+    if fee <= 0 {
+        return Err(HookError::EmitFailed);
+    }
+    bytes::put(&mut tx, FEE_OFFSET, &amount::encode_native(fee as u64));
+
+    let mut hash = [0u8; TX_ID_LEN];
+    let result = unsafe { emit(hash.as_mut_ptr(), hash.len() as i32, tx.as_ptr(), len as i32) };
+    if result != TX_ID_LEN as i32 {
+        return Err(HookError::EmitFailed);
+    }
+
+    Ok(())
+}
+
+// Canonically ordered Payment fields, with a zero Fee to be patched
+fn encode_payment(out: &mut [u8], account: &[u8; 20], destination: &[u8; 20],
+                  drops: u64, ledger: u32) -> usize {
+    let mut len = 0;
+    let mut put = |field: &[u8]| len += bytes::put(out, len, field);
+
+    // TransactionType Payment, Flags, Sequence (always 0 for emitted transactions)
+    put(&[0x12, 0x00, 0x00]);
+    put(&[0x22]);
+    put(&0x8000_0000u32.to_be_bytes());
+    put(&[0x24]);
+    put(&0u32.to_be_bytes());
+
+    // FirstLedgerSequence and LastLedgerSequence
+    put(&[0x20, 0x1A]);
+    put(&(ledger + 1).to_be_bytes());
+    put(&[0x20, 0x1B]);
+    put(&(ledger + LEDGER_WINDOW).to_be_bytes());
+
+    // Amount and Fee
+    put(&[0x61]);
+    put(&amount::encode_native(drops));
+    put(&[0x68]);
+    put(&amount::encode_native(0));
+
+    // Emitted transactions carry an all-zero SigningPubKey
+    put(&[0x73, 0x21]);
+    put(&[0u8; 33]);
+
+    // Account and Destination
+    put(&[0x81, 0x14]);
+    put(account);
+    put(&[0x83, 0x14]);
+    put(destination);
+
+    len
+}
+
+fn settlement_key() -> [u8; state::KEY_LEN] {
+    state::key(state::NS_SETTLEMENT, &[])
+}
+
+fn load() -> Accumulator {
+    state::load_entry(&settlement_key()).unwrap_or_default()
+}
+
+fn store(acc: &Accumulator) -> Result<(), HookError> {
+    state::store_entry(&settlement_key(), acc)
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::sim;
+    use crate::fixtures::{lks_payment, MERCHANT};
+    use crate::{cbak, hook};
+
+    #[test]
+    fn settles_accumulated_fees_with_carry_over() {
+        lks_payment(25, 12);
+        let destination = MERCHANT.to_vec();
+        sim::with(|host| {
+            host.hook_params.insert(PARAM_SETTLEMENT_DESTINATION.to_vec(), destination);
+            host.emit_fails = true;
+        });
+        assert_eq!(sim::run(hook), 0);
+        assert!(sim::with(|host| host.emitted.is_empty()));
+
+        // The failed settlement carries over into the next one
+        let fee = amount::encode_native(12).to_vec();
+        sim::with(|host| {
+            host.emit_fails = false;
+            host.otxn_id = [2; 32];
+            host.fields.insert(fields::SF_FEE, fee);
+        });
+        assert_eq!(sim::run(hook), 0);
+
+        let emitted = sim::with(|host| host.emitted.clone());
+        assert_eq!(emitted.len(), 1);
+        assert_eq!(&emitted[0][26..34], &amount::encode_native(24));
+        assert_eq!(&emitted[0][35..43], &amount::encode_native(sim::EMIT_FEE as u64));
+        assert_eq!(&emitted[0][102..122], &MERCHANT);
+    }
+
+    #[test]
+    fn settlement_callbacks_confirm_or_retry() {
+        lks_payment(25, 12);
+        let destination = MERCHANT.to_vec();
+        sim::with(|host| {
+            host.hook_params.insert(PARAM_SETTLEMENT_DESTINATION.to_vec(), destination);
+        });
+        assert_eq!(sim::run(hook), 0);
+        let settlement = sim::with(|host| host.emitted[0].clone());
+
+        // The settlement failed on ledger: the fee goes back to pending and
+        // settles again with the next sponsored transaction
+        let payment = sim::with(|host| std::mem::take(&mut host.fields));
+        sim::with(|host| {
+            host.fields.insert(fields::SF_AMOUNT, settlement[26..34].to_vec());
+            host.meta.insert(fields::SF_TRANSACTION_RESULT, std::vec![128]);
+        });
+        assert_eq!(sim::run_cbak(cbak, 0), 0);
+
+        sim::with(|host| {
+            host.fields = payment.clone();
+            host.fields.insert(fields::SF_FEE, amount::encode_native(12).to_vec());
+            host.otxn_id = [2; 32];
+            host.ledger_seq = 1001;
+        });
+        assert_eq!(sim::run(hook), 0);
+        let emitted = sim::with(|host| host.emitted.clone());
+        assert_eq!(emitted.len(), 2);
+        assert_eq!(&emitted[1][26..34], &amount::encode_native(24));
+
+        sim::with(|host| {
+            host.fields.insert(fields::SF_AMOUNT, emitted[1][26..34].to_vec());
+            host.meta.insert(fields::SF_TRANSACTION_RESULT, std::vec![fields::TES_SUCCESS]);
+        });
+        assert_eq!(sim::run_cbak(cbak, 0), 0);
+
+        let key = state::key(state::NS_SETTLEMENT, &[]);
+        let entry = sim::with(|host| host.value(&key).unwrap().to_vec());
+        assert_eq!(&entry[..8], &0u64.to_be_bytes());
+        assert_eq!(&entry[12..], &[0u8; 9]);
+    }
+}
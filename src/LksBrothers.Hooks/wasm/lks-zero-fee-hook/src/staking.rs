@@ -0,0 +1,67 @@
+// LKS staking integration for the LKS zero-fee hook
+// The staking hook records every staker's balance in its own state, which
+// this hook reads as foreign state to scale sponsorship by stake. Staking
+// applies once the staking hook's account and namespace are configured.
+
+use lks_hook_sdk::entry;
+use lks_hook_sdk::foreign::Sibling;
+use lks_hook_sdk::{bytes, state};
+
+// Account the staking hook is installed on
+pub const PARAM_STAKE_ACCOUNT: &[u8] = b"STKACCT";
+
+// HookNamespace the staking hook keeps its state under
+pub const PARAM_STAKE_NAMESPACE: &[u8] = b"STKNS";
+
+const STAKE_LEN: usize = 8;
+
+// Staked balance of `account` in micro-LKS, or None while staking isn't
+// configured. Accounts without a stake entry have staked nothing.
+pub fn staked_balance(account: &[u8; 20]) -> Option<u64> {
+    let staking = Sibling::configured(PARAM_STAKE_ACCOUNT, PARAM_STAKE_NAMESPACE)?;
+
+    let key = state::account_key(state::NS_STAKE, account);
+    let mut stake = [0u8; STAKE_LEN];
+    let len = staking.load_raw(&key, &mut stake);
+    Some(entry::decode(bytes::head(&stake, len)).unwrap_or(0))
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::amount;
+    use lks_hook_sdk::{fields, sim};
+    use crate::fixtures::{lks_payment, written_fee, USER};
+    use crate::{hook, policy};
+
+    #[test]
+    fn stake_tiers_scale_sponsorship() {
+        const STAKING_HOOK: [u8; 20] = [0xCC; 20];
+        const STAKING_NAMESPACE: [u8; 32] = [0x5A; 32];
+
+        lks_payment(25, 100);
+        let mut tiers = 0u64.to_be_bytes().to_vec();
+        tiers.push(50);
+        tiers.extend_from_slice(&1_000_000u64.to_be_bytes());
+        tiers.push(100);
+        sim::with(|host| {
+            host.hook_params.insert(PARAM_STAKE_ACCOUNT.to_vec(), STAKING_HOOK.to_vec());
+            host.hook_params.insert(PARAM_STAKE_NAMESPACE.to_vec(), STAKING_NAMESPACE.to_vec());
+            host.hook_params.insert(policy::PARAM_STAKE_TIERS.to_vec(), tiers);
+        });
+
+        // Without a stake the sender gets the first tier's share
+        assert_eq!(sim::run(hook), 0);
+        assert_eq!(written_fee(), 50);
+
+        let key = state::account_key(state::NS_STAKE, &USER).to_vec();
+        sim::with(|host| {
+            let entry = (STAKING_HOOK.to_vec(), STAKING_NAMESPACE.to_vec(), key);
+            host.foreign_state.insert(entry, 1_000_000u64.to_be_bytes().to_vec());
+            host.otxn_id = [0x01; 32];
+            host.fields.insert(fields::SF_FEE, amount::encode_native(100).to_vec());
+        });
+        assert_eq!(sim::run(hook), 0);
+        assert_eq!(written_fee(), 0);
+    }
+}
@@ -0,0 +1,100 @@
+// Strict mode for the LKS zero-fee hook
+// An amount field that fails to parse normally reads as not LKS, so the
+// transaction goes through unsponsored and nobody learns why. With STRICT
+// set, the amount fields that decide whether a transaction is LKS reject it
+// instead when they are malformed or only look like LKS. An EV_MALFORMED
+// event gives the field code and the byte offset where the field goes
+// wrong, so wallet developers can find the bug:
+//   amount that can't be read or parsed           AmountParseFailed (104)
+//   LKS currency code from another issuer         IssuerMismatch (113)
+//   LKS amount with a negative or malformed value AmountValueInvalid (114)
+// Native amounts and other currencies are never LKS-looking.
+
+use lks_hook_sdk::amount;
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::fields::{self, FieldId};
+use lks_hook_sdk::log;
+use crate::config;
+
+// Reject malformed LKS-looking transactions
+pub const PARAM_STRICT: &[u8] = b"STRICT";
+
+// Report `field`, which failed to read or parse. Err in strict mode.
+#[inline(never)]
+pub fn unreadable(field: FieldId) -> Result<(), HookError> {
+    if !config::flag(PARAM_STRICT, false) {
+        return Ok(());
+    }
+
+    // One byte more than any amount, so oversized fields show as such
+    let mut buffer = [0u8; amount::ISSUED_LEN + 1];
+    let offset = match fields::read_raw(field, &mut buffer) {
+        Ok(Some(data)) => amount::malformed_at(data).unwrap_or(0),
+        _ => 0,
+    };
+    reject(field, offset, HookError::AmountParseFailed)
+}
+
+// Report a problem with the LKS-looking amount `field` at `offset`. Err in
+// strict mode.
+#[inline(never)]
+pub fn malformed(field: FieldId, offset: usize, err: HookError) -> Result<(), HookError> {
+    if !config::flag(PARAM_STRICT, false) {
+        return Ok(());
+    }
+
+    reject(field, offset, err)
+}
+
+fn reject(field: FieldId, offset: usize, err: HookError) -> Result<(), HookError> {
+    log::warn(log::EV_MALFORMED, b"LKS malformed amount field", field as u64, offset as u64);
+    Err(err)
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::sim::{self, Outcome};
+    use lks_hook_sdk::xfl::Xfl;
+    use crate::fixtures::{lks_amount_bytes, lks_payment, traced, written_fee};
+    use crate::hook;
+
+    #[test]
+    fn strict_mode_rejects_malformed_lks_amounts() {
+        let mut foreign_issuer = lks_amount_bytes(25);
+        foreign_issuer[amount::ISSUER_OFFSET..].copy_from_slice(&[0x11; 20]);
+        let mut negative = lks_amount_bytes(25);
+        negative[..8].copy_from_slice(&Xfl::from_int(25).negate().to_amount_value());
+        let cases = [
+            (lks_amount_bytes(25)[..40].to_vec(), HookError::AmountParseFailed, 40),
+            (foreign_issuer, HookError::IssuerMismatch, amount::ISSUER_OFFSET as u64),
+            (negative, HookError::AmountValueInvalid, 0),
+        ];
+
+        for (field, err, offset) in cases {
+            // Without strict mode these go through, unsponsored or declined
+            lks_payment(25, 12);
+            sim::with(|host| host.fields.insert(fields::SF_AMOUNT, field.clone()));
+            assert!(sim::run(hook) >= 0);
+            assert_eq!(written_fee(), 12);
+
+            lks_payment(25, 12);
+            sim::with(|host| {
+                host.fields.insert(fields::SF_AMOUNT, field);
+                host.hook_params.insert(PARAM_STRICT.to_vec(), std::vec![1]);
+            });
+            assert_eq!(sim::run(hook), err.return_value());
+            assert!(matches!(sim::with(|host| host.outcome.clone()), Some(Outcome::Rejected(_))));
+            let malformed = log::encode(log::Level::Warn, log::EV_MALFORMED, fields::SF_AMOUNT as u64, offset);
+            assert!(traced(&malformed));
+        }
+
+        // Well-formed amounts of other currencies aren't LKS-looking
+        lks_payment(25, 12);
+        sim::with(|host| {
+            host.fields.insert(fields::SF_AMOUNT, amount::encode_native(5_000).to_vec());
+            host.hook_params.insert(PARAM_STRICT.to_vec(), std::vec![1]);
+        });
+        assert_eq!(sim::run(hook), HookError::WrongCurrency.return_value());
+    }
+}
@@ -0,0 +1,130 @@
+// TrustSet sponsorship for the LKS zero-fee hook
+// New users must set a trust line to the LKS issuer before they can hold the
+// token; the foundation covers the fee of that transaction. Trust lines to
+// the other sponsored LKS-family tokens are covered the same way.
+
+use lks_hook_sdk::amount::Amount;
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::fields::{self, SF_LIMIT_AMOUNT};
+use crate::{config, maintenance, pass_through, rules, sponsor, strict};
+
+pub fn handle_trustset() -> Result<(), HookError> {
+    // An LKS-named trust line to any other issuer is not the LKS token
+    match rules::asset(SF_LIMIT_AMOUNT) {
+        Ok(_) => {}
+        Err(HookError::WrongCurrency) => {
+            // A trust line limit is never native, so a limit that isn't an
+            // issued amount is left to the ledger to refuse, unless strict
+            // mode is on
+            if !matches!(fields::read_amount(SF_LIMIT_AMOUNT), Ok(Some(Amount::Issued { .. }))) {
+                strict::unreadable(SF_LIMIT_AMOUNT)?;
+            }
+            return Err(HookError::WrongCurrency);
+        }
+        Err(err) => return Err(err),
+    }
+
+    // In first-only mode only the TrustSet creating the line is sponsored;
+    // the hook runs before the transaction applies, so the line exists for
+    // every later one, however it was set up
+    if config::flag(config::PARAM_TRUSTSET_FIRST_ONLY, false) {
+        let (currency, issuer) = match fields::read_amount(SF_LIMIT_AMOUNT)? {
+            Some(Amount::Issued { currency, issuer, .. }) => (currency, issuer),
+            _ => return Err(HookError::FieldReadFailed),
+        };
+        let account = fields::read_account()?;
+        if maintenance::line_balance(&account, &issuer, &currency).is_some() {
+            return pass_through(b"LKS trust line already set for account");
+        }
+    }
+
+    sponsor(None, b"LKS COIN trust line fee sponsored",
+                  b"Zero-fee LKS COIN trust line accepted")
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use lks_hook_sdk::amount;
+    use lks_hook_sdk::error::HookError;
+    use lks_hook_sdk::{fields, log, sim};
+    use crate::fixtures::{lks_amount_bytes, lks_payment, traced, written_fee, USER};
+    use crate::{config, hook, strict, LKS_CURRENCY_CODE, LKS_ISSUER, TX_TYPE_TRUST_SET};
+
+    #[test]
+    fn first_only_trustset_sponsors_until_the_line_exists() {
+        lks_payment(25, 2_000);
+        sim::with(|host| {
+            host.tx_type = TX_TYPE_TRUST_SET;
+            host.fields.remove(&fields::SF_DESTINATION);
+            let limit = host.fields.remove(&fields::SF_AMOUNT).unwrap();
+            host.fields.insert(fields::SF_LIMIT_AMOUNT, limit);
+            host.hook_params.insert(config::PARAM_TRUSTSET_FIRST_ONLY.to_vec(), std::vec![1]);
+        });
+
+        // A TrustSet declined by a later rule leaves the sponsorship unused
+        assert_eq!(sim::run(hook), HookError::FeeCapExceeded.return_value());
+        sim::with(|host| host.fields.insert(fields::SF_FEE, amount::encode_native(12).to_vec()));
+        assert_eq!(sim::run(hook), 0);
+        assert_eq!(written_fee(), 0);
+
+        // Once the line exists, however it was set, changes to it pay their fee
+        let keylet = sim::line_keylet(&USER, LKS_ISSUER.as_bytes(), &amount::currency_code(&LKS_CURRENCY_CODE));
+        let balance = lks_amount_bytes(0);
+        sim::with(|host| {
+            host.ledger.entry(keylet).or_default().insert(fields::SF_BALANCE, balance);
+            host.otxn_id = [1; 32];
+            host.fields.insert(fields::SF_FEE, amount::encode_native(12).to_vec());
+        });
+        assert_eq!(sim::run(hook), 0);
+        assert_eq!(written_fee(), 12);
+    }
+
+    #[test]
+    fn unreadable_trustset_limits_are_only_rejected_in_strict_mode() {
+        for limit in [Some(amount::encode_native(5_000).to_vec()), None] {
+            for strict_mode in [false, true] {
+                lks_payment(25, 12);
+                sim::with(|host| {
+                    host.tx_type = TX_TYPE_TRUST_SET;
+                    host.fields.remove(&fields::SF_DESTINATION);
+                    host.fields.remove(&fields::SF_AMOUNT);
+                    if let Some(limit) = limit.clone() {
+                        host.fields.insert(fields::SF_LIMIT_AMOUNT, limit);
+                    }
+                    if strict_mode {
+                        host.hook_params.insert(strict::PARAM_STRICT.to_vec(), std::vec![1]);
+                    }
+                });
+
+                if strict_mode {
+                    assert_eq!(sim::run(hook), HookError::AmountParseFailed.return_value());
+                    let malformed = log::encode(log::Level::Warn, log::EV_MALFORMED, fields::SF_LIMIT_AMOUNT as u64, 0);
+                    assert!(traced(&malformed));
+                } else {
+                    assert_eq!(sim::run(hook), HookError::WrongCurrency.return_value());
+                    assert_eq!(written_fee(), 12);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn trust_lines_go_through_the_asset_rules() {
+        let mut foreign_issuer = lks_amount_bytes(1_000);
+        foreign_issuer[amount::ISSUER_OFFSET..].copy_from_slice(&[0x11; 20]);
+        let mut other_currency = lks_amount_bytes(1_000);
+        other_currency[8..28].copy_from_slice(&amount::currency_code(b"USD"));
+
+        for (limit, expected) in [(lks_amount_bytes(1_000), 0), (foreign_issuer, HookError::WrongIssuer.return_value()),
+                                  (other_currency, HookError::WrongCurrency.return_value())] {
+            lks_payment(25, 12);
+            sim::with(|host| {
+                host.tx_type = TX_TYPE_TRUST_SET;
+                host.fields.remove(&fields::SF_DESTINATION);
+                host.fields.remove(&fields::SF_AMOUNT);
+                host.fields.insert(fields::SF_LIMIT_AMOUNT, limit);
+            });
+            assert_eq!(sim::run(hook), expected);
+        }
+    }
+}
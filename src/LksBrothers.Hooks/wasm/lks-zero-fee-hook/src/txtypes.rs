@@ -0,0 +1,88 @@
+// Sponsored transaction types of the LKS zero-fee hook
+// TXTYPES is a bitmask of the transaction types the hook sponsors, bit N
+// standing for transaction type N, so operators can run the hook for
+// Payments only, or broaden it to escrows and NFTs, by setting a parameter
+// or a foundation override instead of deploying a new wasm. Transactions of
+// other types pass through with their normal fee before the hook reads or
+// writes anything else, as while paused. The custom LKS transfer type goes
+// with Payments, and admin Invokes are always handled. Unset, every type
+// the hook handles is sponsored.
+
+use lks_hook_sdk::log;
+use crate::{config, LKS_TRANSFER_TYPE, TX_TYPE_PAYMENT};
+
+pub const PARAM_TX_TYPES: &[u8] = b"TXTYPES";
+pub const DEFAULT_TX_TYPES: u64 = u64::MAX;
+
+// Bit of `tx_type` in the mask, zero for types past the last bit
+pub const fn bit(tx_type: i32) -> u64 {
+    let tx_type = if tx_type == LKS_TRANSFER_TYPE { TX_TYPE_PAYMENT } else { tx_type };
+    if tx_type < 0 || tx_type >= u64::BITS as i32 {
+        return 0;
+    }
+    1 << tx_type
+}
+
+// Whether transactions of `tx_type` may be sponsored, tracing those that
+// may not. Types without a bit aren't sponsored anyway and aren't limited.
+pub fn is_sponsored(tx_type: i32) -> bool {
+    let bit = bit(tx_type);
+    if bit == 0 || config::u64_param(PARAM_TX_TYPES, DEFAULT_TX_TYPES) & bit != 0 {
+        return true;
+    }
+
+    log::debug(log::EV_TX_TYPE_OFF, b"LKS transaction type not sponsored, passed through", tx_type as u64, 0);
+    false
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::error::HookError;
+    use lks_hook_sdk::sim::{self, Outcome};
+    use crate::fixtures::{lks_payment, written_fee};
+    use crate::{hook, TX_TYPE_ESCROW_CREATE, TX_TYPE_INVOKE, TX_TYPE_OFFER_CREATE, TX_TYPE_TRUST_SET};
+
+    #[test]
+    fn tx_type_allowlist_passes_other_types_through() {
+        let payments_only = bit(TX_TYPE_PAYMENT).to_be_bytes().to_vec();
+        assert_eq!(bit(LKS_TRANSFER_TYPE), bit(TX_TYPE_PAYMENT));
+        assert_eq!(bit(TX_TYPE_INVOKE), 0);
+
+        for tx_type in [TX_TYPE_PAYMENT, LKS_TRANSFER_TYPE] {
+            lks_payment(25, 12);
+            sim::with(|host| {
+                host.tx_type = tx_type;
+                host.hook_params.insert(PARAM_TX_TYPES.to_vec(), payments_only.clone());
+            });
+            assert_eq!(sim::run(hook), 0);
+            assert_eq!(written_fee(), 0);
+        }
+
+        for tx_type in [TX_TYPE_OFFER_CREATE, TX_TYPE_TRUST_SET, TX_TYPE_ESCROW_CREATE] {
+            lks_payment(25, 12);
+            sim::with(|host| {
+                host.tx_type = tx_type;
+                host.hook_params.insert(PARAM_TX_TYPES.to_vec(), payments_only.clone());
+            });
+            assert_eq!(sim::run(hook), HookError::TxTypeNotSponsored.return_value());
+            assert_eq!(written_fee(), 12);
+            assert!(matches!(sim::with(|host| host.outcome.clone()), Some(Outcome::Accepted(_))));
+
+            // Passed through before anything is written, and traced
+            assert!(sim::with(|host| host.state.is_empty()));
+            let event = log::encode(log::Level::Debug, log::EV_TX_TYPE_OFF, tx_type as u64, 0);
+            let traced = sim::with(|host| host.traces.iter().any(|(_, data)| *data == event));
+            assert_eq!(traced, log::enabled(log::Level::Debug));
+        }
+
+        // Broadened to trust lines without a new wasm
+        let broadened = (bit(TX_TYPE_PAYMENT) | bit(TX_TYPE_TRUST_SET)).to_be_bytes().to_vec();
+        lks_payment(25, 12);
+        sim::with(|host| {
+            host.tx_type = TX_TYPE_TRUST_SET;
+            host.hook_params.insert(PARAM_TX_TYPES.to_vec(), broadened);
+        });
+        assert_ne!(sim::run(hook), HookError::TxTypeNotSponsored.return_value());
+    }
+}
@@ -0,0 +1,198 @@
+// Partner sponsorship vouchers for the LKS zero-fee hook
+// Partners pre-authorize transactions off-chain by signing a voucher for an
+// account; the account attaches it as a memo typed "lks/voucher". A valid
+// voucher lifts the per-account and per-pair caps for its transaction (the
+// budget still applies). Vouchers that are expired, reused, meant for
+// another account or badly signed are traced and ignored, leaving the
+// transaction to the normal limits.
+//
+// MemoData: [account(20)][expiry ledger u32][nonce u64][signature]
+// The partner signs "LKSV" followed by the first 32 bytes with the key set
+// in VOUCHKEY. Nonces only count up per account: redeeming a voucher
+// retires every voucher of that account with a lower or equal nonce, so one
+// state entry per account records the consumed nonces.
+
+use lks_hook_sdk::account::AccountId;
+use lks_hook_sdk::api::{ledger_seq, util_verify};
+use lks_hook_sdk::bytes;
+use lks_hook_sdk::error::HookError;
+use lks_hook_sdk::fields::{self, SF_MEMOS};
+use lks_hook_sdk::{log, memo, state};
+use crate::config;
+
+pub const MEMO_TYPE_VOUCHER: &[u8] = b"lks/voucher";
+
+// Partner public key: 33 bytes, ed25519 keys prefixed with 0xED. Vouchers
+// are ignored while it is unset.
+pub const PARAM_VOUCHER_KEY: &[u8] = b"VOUCHKEY";
+pub const KEY_LEN: usize = 33;
+
+const DOMAIN: &[u8] = b"LKSV";
+const BODY_LEN: usize = 32;
+const MAX_SIGNATURE_LEN: usize = 72;
+
+// Reasons traced with EV_VOUCHER_INVALID
+pub const INVALID_MALFORMED: u64 = 1;
+pub const INVALID_ACCOUNT: u64 = 2;
+pub const INVALID_EXPIRED: u64 = 3;
+pub const INVALID_NONCE: u64 = 4;
+pub const INVALID_SIGNATURE: u64 = 5;
+
+pub struct Voucher {
+    pub expiry: u32,
+    pub nonce: u64,
+}
+
+// The valid voucher `source` attached to the originating transaction, if any
+pub fn read(source: &[u8; 20]) -> Option<Voucher> {
+    let mut key = [0u8; KEY_LEN];
+    if config::bytes_param(PARAM_VOUCHER_KEY, &mut key) != KEY_LEN {
+        return None;
+    }
+
+    let mut memo_buffer = [0u8; memo::MAX_MEMOS_LEN];
+    let memos = match fields::read_raw(SF_MEMOS, &mut memo_buffer) {
+        Ok(Some(memos)) => memos,
+        _ => return None,
+    };
+
+    let mut data: Option<&[u8]> = None;
+    memo::for_each_memo(memos, |memo| {
+        if data.is_none() && bytes::eq(memo.memo_type, MEMO_TYPE_VOUCHER) {
+            data = Some(memo.data);
+        }
+    }).ok()?;
+
+    match verify(source, &key, data?) {
+        Ok(voucher) => Some(voucher),
+        Err((nonce, reason)) => {
+            log::warn(log::EV_VOUCHER_INVALID, b"LKS voucher ignored", nonce, reason);
+            None
+        }
+    }
+}
+
+// Check a voucher for `source`, returning it or its nonce and why it is
+// invalid
+fn verify(source: &[u8; 20], key: &[u8; KEY_LEN], data: &[u8]) -> Result<Voucher, (u64, u64)> {
+    let body: [u8; BODY_LEN] = bytes::array(data, 0).ok_or((0, INVALID_MALFORMED))?;
+    let nonce = bytes::u64_at(&body, 24).unwrap_or(0);
+    let signature = bytes::tail(data, BODY_LEN);
+    if signature.is_empty() || signature.len() > MAX_SIGNATURE_LEN {
+        return Err((nonce, INVALID_MALFORMED));
+    }
+
+    let account = bytes::array(&body, 0).unwrap_or_default();
+    if !AccountId::new(account).matches(source) {
+        return Err((nonce, INVALID_ACCOUNT));
+    }
+
+    let expiry = bytes::u32_at(&body, 20).unwrap_or(0);
+    if (expiry as u64) < unsafe { ledger_seq() } {
+        return Err((nonce, INVALID_EXPIRED));
+    }
+
+    if nonce <= last_nonce(source) {
+        return Err((nonce, INVALID_NONCE));
+    }
+
+    let mut message = [0u8; DOMAIN.len() + BODY_LEN];
+    bytes::put(&mut message, 0, DOMAIN);
+    bytes::put(&mut message, DOMAIN.len(), &body);
+    let valid = unsafe {
+        util_verify(message.as_ptr(), message.len() as i32, signature.as_ptr(), signature.len() as i32,
+                    key.as_ptr(), key.len() as i32)
+    };
+    if valid != 1 {
+        return Err((nonce, INVALID_SIGNATURE));
+    }
+
+    Ok(Voucher { expiry, nonce })
+}
+
+// Record the voucher's nonce as consumed once its transaction is sponsored
+pub fn consume(source: &[u8; 20], voucher: &Voucher) -> Result<(), HookError> {
+    state::store_entry(&nonce_key(source), &voucher.nonce)?;
+    log::info(log::EV_VOUCHER, b"LKS voucher redeemed", voucher.nonce, voucher.expiry as u64);
+    Ok(())
+}
+
+// Highest nonce the account redeemed, zero before its first voucher
+fn last_nonce(source: &[u8; 20]) -> u64 {
+    state::load_entry::<u64>(&nonce_key(source)).unwrap_or(0)
+}
+
+fn nonce_key(source: &[u8; 20]) -> [u8; state::KEY_LEN] {
+    state::account_key(state::NS_VOUCHER, source)
+}
+
+#[cfg(all(test, not(feature = "xahau")))]
+mod tests {
+    use super::*;
+    use lks_hook_sdk::sim;
+    use crate::fixtures::{lks_payment, written_fee, MERCHANT, USER};
+    use crate::{hook, limits};
+
+    const PARTNER_KEY: [u8; KEY_LEN] = [0xED; KEY_LEN];
+
+    // Serialized Memos array holding a voucher for `account`
+    fn voucher_memo(account: &[u8; 20], expiry: u32, nonce: u64, key: &[u8]) -> std::vec::Vec<u8> {
+        let mut body = account.to_vec();
+        body.extend_from_slice(&expiry.to_be_bytes());
+        body.extend_from_slice(&nonce.to_be_bytes());
+        let mut message = b"LKSV".to_vec();
+        message.extend_from_slice(&body);
+        body.extend(sim::sign(key, &message));
+
+        let mut memos = std::vec![0xEA, 0x7C, MEMO_TYPE_VOUCHER.len() as u8];
+        memos.extend_from_slice(MEMO_TYPE_VOUCHER);
+        memos.extend_from_slice(&[0x7D, body.len() as u8]);
+        memos.extend_from_slice(&body);
+        memos.extend_from_slice(&[0xE1, 0xF1]);
+        memos
+    }
+
+    // Run a payment by USER with an account cap of one, after one earlier
+    // sponsored payment, carrying `memos`
+    fn capped_payment(memos: std::vec::Vec<u8>, state: &mut sim::State, otxn: u8) -> i64 {
+        lks_payment(25, 12);
+        sim::with(|host| {
+            host.state = std::mem::take(state);
+            host.otxn_id = [otxn; 32];
+            host.hook_params.insert(limits::PARAM_ACCOUNT_CAP.to_vec(), 1u64.to_be_bytes().to_vec());
+            host.hook_params.insert(PARAM_VOUCHER_KEY.to_vec(), PARTNER_KEY.to_vec());
+            host.fields.insert(fields::SF_MEMOS, memos);
+        });
+        let result = sim::run(hook);
+        *state = sim::with(|host| host.state.clone());
+        result
+    }
+
+    #[test]
+    fn partner_vouchers_lift_rate_limits_once() {
+        let mut state = sim::State::new();
+        assert_eq!(capped_payment(std::vec![0xF1], &mut state, 1), 0);
+        assert_eq!(capped_payment(std::vec![0xF1], &mut state, 2), HookError::RateLimited.return_value());
+
+        // Vouchers that don't hold up leave the cap in place
+        for (memos, reason) in [
+            (voucher_memo(&MERCHANT, 1200, 1, &PARTNER_KEY), INVALID_ACCOUNT),
+            (voucher_memo(&USER, 999, 1, &PARTNER_KEY), INVALID_EXPIRED),
+            (voucher_memo(&USER, 1200, 1, &[0x02; KEY_LEN]), INVALID_SIGNATURE),
+        ] {
+            assert_eq!(capped_payment(memos, &mut state, 3), HookError::RateLimited.return_value());
+            let invalid = log::encode(log::Level::Warn, log::EV_VOUCHER_INVALID, 1, reason);
+            assert!(sim::with(|host| host.traces.iter().any(|(_, data)| data[..] == invalid[..])));
+        }
+
+        let voucher = voucher_memo(&USER, 1200, 7, &PARTNER_KEY);
+        assert_eq!(capped_payment(voucher.clone(), &mut state, 4), 0);
+        assert_eq!(written_fee(), 0);
+
+        // The nonce is consumed, along with every lower one
+        assert_eq!(capped_payment(voucher, &mut state, 5), HookError::RateLimited.return_value());
+        let lower = voucher_memo(&USER, 1200, 6, &PARTNER_KEY);
+        assert_eq!(capped_payment(lower, &mut state, 6), HookError::RateLimited.return_value());
+        assert_eq!(capped_payment(voucher_memo(&USER, 1200, 8, &PARTNER_KEY), &mut state, 7), 0);
+    }
+}
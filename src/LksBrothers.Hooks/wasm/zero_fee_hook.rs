@@ -1,193 +0,0 @@
-// Zero Fee Hook for LKS COIN
-// This WebAssembly module implements the zero transaction fee model
-// by automatically paying network fees on behalf of users
-
-#![no_std]
-#![no_main]
-
-// Hook API functions (these would be provided by the XRPL Hook SDK)
-extern "C" {
-    fn otxn_type() -> i32;
-    fn otxn_slot(slot: i32, data: *mut u8, len: i32) -> i32;
-    fn slot_set(slot: i32, data: *const u8, len: i32) -> i32;
-    fn accept(msg: *const u8, len: i32) -> i32;
-    fn reject(msg: *const u8, len: i32) -> i32;
-    fn trace_u64(msg: *const u8, len: i32, value: u64) -> i32;
-    fn ledger_seq() -> u64;
-    fn hook_account(account: *mut u8) -> i32;
-}
-
-// Transaction types
-const TX_TYPE_PAYMENT: i32 = 0;
-const TX_TYPE_OFFER_CREATE: i32 = 7;
-const TX_TYPE_OFFER_CANCEL: i32 = 8;
-const LKS_TRANSFER_TYPE: i32 = 1234;
-
-// Slot identifiers
-const S_FEE: i32 = 1;
-const S_ACCOUNT: i32 = 2;
-const S_DESTINATION: i32 = 3;
-const S_AMOUNT: i32 = 4;
-
-// Foundation account (this would be configured)
-const FOUNDATION_ACCOUNT: [u8; 20] = [
-    0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0,
-    0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0,
-    0x12, 0x34, 0x56, 0x78
-];
-
-#[no_mangle]
-pub extern "C" fn hook() -> i64 {
-    // Get the transaction type that triggered this hook
-    let tx_type = unsafe { otxn_type() };
-    
-    // Log the transaction type for debugging
-    let msg = b"Processing transaction type";
-    unsafe {
-        trace_u64(msg.as_ptr(), msg.len() as i32, tx_type as u64);
-    }
-
-    // Handle LKS COIN transfers with zero fees
-    if tx_type == LKS_TRANSFER_TYPE || tx_type == TX_TYPE_PAYMENT {
-        return handle_lks_transfer();
-    }
-
-    // Handle DEX operations (OfferCreate/OfferCancel)
-    if tx_type == TX_TYPE_OFFER_CREATE || tx_type == TX_TYPE_OFFER_CANCEL {
-        return handle_dex_operation();
-    }
-
-    // For any other transaction type, let it pass through normally
-    let msg = b"Transaction type not handled by LKS zero-fee hook";
-    unsafe {
-        accept(msg.as_ptr(), msg.len() as i32);
-    }
-    
-    0
-}
-
-fn handle_lks_transfer() -> i64 {
-    // Get the original transaction fee
-    let mut fee_buffer = [0u8; 8];
-    let fee_result = unsafe {
-        otxn_slot(S_FEE, fee_buffer.as_mut_ptr(), 8)
-    };
-    
-    if fee_result != 8 {
-        let msg = b"Failed to read transaction fee";
-        unsafe {
-            reject(msg.as_ptr(), msg.len() as i32);
-        }
-        return -1;
-    }
-
-    // Convert fee bytes to u64
-    let original_fee = u64::from_le_bytes(fee_buffer);
-    
-    // Check if this is an LKS COIN transaction
-    if is_lks_coin_transaction() {
-        // Set the user fee to zero
-        let zero_fee = 0u64.to_le_bytes();
-        unsafe {
-            slot_set(S_FEE, zero_fee.as_ptr(), 8);
-        }
-        
-        // Log that we're sponsoring this transaction
-        let msg = b"LKS COIN transaction fee sponsored by foundation";
-        unsafe {
-            trace_u64(msg.as_ptr(), msg.len() as i32, original_fee);
-        }
-        
-        // The foundation account will pay the network fee separately
-        // This would be handled by the node software
-        
-        let success_msg = b"Zero-fee LKS COIN transaction accepted";
-        unsafe {
-            accept(success_msg.as_ptr(), success_msg.len() as i32);
-        }
-        
-        return 0;
-    }
-
-    // If not an LKS COIN transaction, let it proceed normally
-    let msg = b"Non-LKS transaction processed normally";
-    unsafe {
-        accept(msg.as_ptr(), msg.len() as i32);
-    }
-    
-    0
-}
-
-fn handle_dex_operation() -> i64 {
-    // For DEX operations involving LKS COIN, also apply zero fees
-    if is_lks_coin_dex_operation() {
-        let zero_fee = 0u64.to_le_bytes();
-        unsafe {
-            slot_set(S_FEE, zero_fee.as_ptr(), 8);
-        }
-        
-        let msg = b"LKS COIN DEX operation fee sponsored";
-        unsafe {
-            trace_u64(msg.as_ptr(), msg.len() as i32, 0);
-        }
-        
-        let success_msg = b"Zero-fee LKS COIN DEX operation accepted";
-        unsafe {
-            accept(success_msg.as_ptr(), success_msg.len() as i32);
-        }
-        
-        return 0;
-    }
-
-    // Non-LKS DEX operations proceed normally
-    let msg = b"Non-LKS DEX operation processed normally";
-    unsafe {
-        accept(msg.as_ptr(), msg.len() as i32);
-    }
-    
-    0
-}
-
-fn is_lks_coin_transaction() -> bool {
-    // Check if the transaction involves LKS COIN
-    // This would examine the Amount field to see if it's an LKS currency object
-    
-    let mut amount_buffer = [0u8; 64]; // Larger buffer for currency objects
-    let amount_result = unsafe {
-        otxn_slot(S_AMOUNT, amount_buffer.as_mut_ptr(), 64)
-    };
-    
-    if amount_result <= 0 {
-        return false;
-    }
-    
-    // Simple check: look for "LKS" currency code in the amount data
-    // In a real implementation, this would properly parse the JSON/binary format
-    for i in 0..(amount_result as usize - 2) {
-        if amount_buffer[i] == b'L' && 
-           amount_buffer[i + 1] == b'K' && 
-           amount_buffer[i + 2] == b'S' {
-            return true;
-        }
-    }
-    
-    false
-}
-
-fn is_lks_coin_dex_operation() -> bool {
-    // Similar to is_lks_coin_transaction but checks both TakerGets and TakerPays
-    // For simplicity, we'll use the same logic as above
-    is_lks_coin_transaction()
-}
-
-// Panic handler required for no_std
-#[panic_handler]
-fn panic(_info: &core::panic::PanicInfo) -> ! {
-    loop {}
-}
-
-// Required for no_main
-#[no_mangle]
-pub extern "C" fn _start() {
-    // This function is required but not used in hooks
-}